@@ -0,0 +1,306 @@
+use crate::settings::rmenu_data_dirs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ALIASES_FILE_VERSION: u32 = 1;
+const ALIASES_FILE_NAME: &str = "adaptive_aliases.json";
+const ALIAS_MAX_ENTRIES: usize = 256;
+const ALIAS_MAX_QUERY_CHARS: usize = 64;
+const ALIAS_HALF_LIFE_SECS: f64 = 14.0 * 24.0 * 3600.0;
+const ALIAS_PREFIX_WEIGHT: f64 = 0.5;
+const ALIAS_PIN_MIN_SCORE: f64 = 0.3;
+const ALIAS_PRUNE_SCORE: f64 = 0.05;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct AdaptiveAliasesFile {
+    version: u32,
+    entries: Vec<AdaptiveAlias>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdaptiveAlias {
+    pub query: String,
+    pub target: String,
+    pub hits: u32,
+    pub last_used: u64,
+}
+
+impl AdaptiveAlias {
+    fn score(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_used) as f64;
+        f64::from(self.hits) * 0.5f64.powf(age / ALIAS_HALF_LIFE_SECS)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AdaptiveAliases {
+    path: Option<PathBuf>,
+    entries: Vec<AdaptiveAlias>,
+}
+
+pub fn adaptive_aliases_path(state_dir: &Path) -> PathBuf {
+    state_dir.join(ALIASES_FILE_NAME)
+}
+
+pub fn load_adaptive_aliases(cli_data_dir: Option<&str>) -> AdaptiveAliases {
+    let dirs = rmenu_data_dirs(cli_data_dir);
+    AdaptiveAliases::load(adaptive_aliases_path(&dirs.state_dir))
+}
+
+pub fn normalize_alias_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .chars()
+        .take(ALIAS_MAX_QUERY_CHARS)
+        .collect()
+}
+
+fn is_learnable_target(target: &str) -> bool {
+    let lowered = target.trim_start().to_ascii_lowercase();
+    !lowered.is_empty() && !lowered.starts_with("hidden:") && !lowered.starts_with("copy:")
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+impl AdaptiveAliases {
+    pub fn load(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<AdaptiveAliasesFile>(&raw).ok())
+            .filter(|file| file.version == ALIASES_FILE_VERSION)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+
+        Self {
+            path: Some(path),
+            entries,
+        }
+    }
+
+    pub fn save(&self, silent_mode: bool) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                if !silent_mode {
+                    eprintln!(
+                        "Error creating adaptive aliases directory '{}': {}",
+                        parent.display(),
+                        e
+                    );
+                }
+                return;
+            }
+        }
+
+        let file = AdaptiveAliasesFile {
+            version: ALIASES_FILE_VERSION,
+            entries: self.entries.clone(),
+        };
+        let Ok(payload) = serde_json::to_string(&file) else {
+            return;
+        };
+        if let Err(e) = fs::write(path, payload) {
+            if !silent_mode {
+                eprintln!("Error writing adaptive aliases '{}': {}", path.display(), e);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn record(&mut self, query: &str, target: &str, now: u64) -> bool {
+        let query = normalize_alias_query(query);
+        if query.is_empty() || query.starts_with('/') || !is_learnable_target(target) {
+            return false;
+        }
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.query == query && entry.target.eq_ignore_ascii_case(target))
+        {
+            Some(entry) => {
+                let decayed = entry.score(now);
+                entry.hits = (decayed.round() as u32).saturating_add(1);
+                entry.last_used = now;
+            }
+            None => self.entries.push(AdaptiveAlias {
+                query,
+                target: target.to_string(),
+                hits: 1,
+                last_used: now,
+            }),
+        }
+
+        self.prune(now);
+        true
+    }
+
+    fn prune(&mut self, now: u64) {
+        self.entries
+            .retain(|entry| entry.hits > 0 && entry.score(now) >= ALIAS_PRUNE_SCORE);
+        if self.entries.len() > ALIAS_MAX_ENTRIES {
+            self.entries
+                .sort_by(|left, right| right.score(now).total_cmp(&left.score(now)));
+            self.entries.truncate(ALIAS_MAX_ENTRIES);
+        }
+    }
+
+    pub fn pinned_target(&self, query: &str, now: u64) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let query = normalize_alias_query(query);
+        if query.is_empty() {
+            return None;
+        }
+
+        let mut scores: HashMap<String, (f64, &str)> = HashMap::new();
+        for entry in &self.entries {
+            let weight = if entry.query == query {
+                1.0
+            } else if entry.query.starts_with(&query) {
+                ALIAS_PREFIX_WEIGHT
+            } else {
+                continue;
+            };
+            let slot = scores
+                .entry(entry.target.to_lowercase())
+                .or_insert((0.0, entry.target.as_str()));
+            slot.0 += entry.score(now) * weight;
+        }
+
+        scores
+            .into_values()
+            .filter(|(score, _)| *score >= ALIAS_PIN_MIN_SCORE)
+            .max_by(|(left, _), (right, _)| left.total_cmp(right))
+            .map(|(_, target)| target.to_string())
+    }
+
+    /// Drops every alias learned for exactly `query`.
+    pub fn forget(&mut self, query: &str) -> usize {
+        let query = normalize_alias_query(query);
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.query != query);
+        before - self.entries.len()
+    }
+
+    /// Drops the aliases that pin `target` for `query`: the exact query and the
+    /// longer queries it is a prefix of, as in `pinned_target`.
+    pub fn forget_pin(&mut self, query: &str, target: &str) -> usize {
+        let query = normalize_alias_query(query);
+        if query.is_empty() {
+            return 0;
+        }
+        let before = self.entries.len();
+        self.entries.retain(|entry| {
+            !(entry.query.starts_with(&query) && entry.target.eq_ignore_ascii_case(target))
+        });
+        before - self.entries.len()
+    }
+
+    pub fn forget_all(&mut self) -> usize {
+        let removed = self.entries.len();
+        self.entries.clear();
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_alias_query, AdaptiveAliases, ALIAS_MAX_ENTRIES};
+
+    const DAY: u64 = 24 * 3600;
+
+    #[test]
+    fn recorded_query_pins_target_for_the_same_and_shorter_prefixes() {
+        let mut aliases = AdaptiveAliases::default();
+        let now = 100 * DAY;
+        assert!(aliases.record("  Fi  ", "C:/Apps/firefox.exe", now));
+        assert!(!aliases.record("fi", "copy:42", now));
+        assert!(!aliases.record("/modules.list", "C:/Apps/firefox.exe", now));
+
+        assert_eq!(
+            aliases.pinned_target("FI", now).as_deref(),
+            Some("C:/Apps/firefox.exe")
+        );
+        assert_eq!(
+            aliases.pinned_target("f", now).as_deref(),
+            Some("C:/Apps/firefox.exe")
+        );
+        assert_eq!(aliases.pinned_target("fir", now), None);
+        assert_eq!(aliases.pinned_target("fi", now + 60 * DAY), None);
+        assert_eq!(normalize_alias_query(" Fire   Fox "), "fire fox");
+    }
+
+    #[test]
+    fn strongest_association_wins_and_can_be_forgotten() {
+        let mut aliases = AdaptiveAliases::default();
+        let now = 100 * DAY;
+        aliases.record("term", "wt.exe", now - DAY);
+        aliases.record("term", "wt.exe", now - DAY);
+        aliases.record("term", "cmd.exe", now);
+        assert_eq!(
+            aliases.pinned_target("term", now).as_deref(),
+            Some("wt.exe")
+        );
+
+        assert_eq!(aliases.forget_pin("te", "WT.EXE"), 1);
+        assert_eq!(
+            aliases.pinned_target("term", now).as_deref(),
+            Some("cmd.exe")
+        );
+        assert_eq!(aliases.forget(""), 0);
+        assert_eq!(aliases.forget_all(), 1);
+        assert!(aliases.is_empty());
+    }
+
+    #[test]
+    fn forgetting_a_query_keeps_longer_queries() {
+        let mut aliases = AdaptiveAliases::default();
+        let now = 100 * DAY;
+        aliases.record("n", "notepad.exe", now);
+        aliases.record("no", "notepad.exe", now);
+        aliases.record("not", "notion.exe", now);
+
+        assert_eq!(aliases.forget(" N "), 1);
+        let mut queries = aliases
+            .entries
+            .iter()
+            .map(|entry| entry.query.as_str())
+            .collect::<Vec<_>>();
+        queries.sort();
+        assert_eq!(queries, ["no", "not"]);
+        assert_eq!(aliases.forget("n"), 0);
+    }
+
+    #[test]
+    fn entries_are_capped() {
+        let mut aliases = AdaptiveAliases::default();
+        for index in 0..(ALIAS_MAX_ENTRIES + 10) {
+            aliases.record(
+                &format!("q{index}"),
+                &format!("app{index}.exe"),
+                index as u64,
+            );
+        }
+        assert_eq!(aliases.entries.len(), ALIAS_MAX_ENTRIES);
+        assert!(aliases.pinned_target("q0", 300).is_none());
+    }
+}
@@ -1,10 +1,56 @@
-#[derive(Debug, Default, Clone, Copy)]
+use crate::adaptive_aliases::AdaptiveAliases;
+use crate::fuzzy::{compact_folded, fold_for_match, word_initials};
+use crate::ranking::RankingCache;
+use crate::rules::RuleHit;
+use crate::sources::ShellLink;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum LauncherSource {
     #[default]
     Direct,
     History,
     StartMenu,
     Path,
+    /// A `[Sources]` root; `LauncherItem::extra_source` names it.
+    Extra,
+    /// A browser bookmark read from a `[Bookmarks]` file.
+    Bookmark,
+}
+
+/// Label and ranking boost of the `[Sources]` root an item was indexed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraSource {
+    pub label: String,
+    pub boost: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LauncherItemAction {
+    pub id: String,
+    pub label: String,
+    pub kind: LauncherItemActionKind,
+}
+
+/// What running a secondary action does. Module actions are `Launch` or, with
+/// the `clipboard` capability, `Copy`; the rest are core-only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LauncherItemActionKind {
+    Launch(String),
+    /// Copy the text to the clipboard instead of launching anything.
+    Copy(String),
+    RemoveFromHistory(String),
+    ForgetAlias(String),
+}
+
+impl LauncherItemActionKind {
+    pub fn target(&self) -> &str {
+        match self {
+            Self::Launch(target)
+            | Self::Copy(target)
+            | Self::RemoveFromHistory(target)
+            | Self::ForgetAlias(target) => target,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -19,53 +65,108 @@ pub struct LauncherItem {
     pub label: String,
     pub label_lc: String,
     pub label_compact: String,
+    pub label_initials: String,
     pub target_name: String,
     pub target_name_lc: String,
     pub target_name_compact: String,
+    pub target_name_initials: String,
     pub target: String,
     pub source: LauncherSource,
     pub trailing_hint: Option<String>,
     pub quick_select_key: Option<String>,
     pub trailing_badge: Option<String>,
     pub trailing_badge_tone: Option<LauncherItemTone>,
+    pub secondary_actions: Vec<LauncherItemAction>,
+    pub frecency: f64,
+    pub rule: Option<RuleHit>,
+    pub extra_source: Option<ExtraSource>,
+    pub shortcut: Option<ShellLink>,
+    /// Extra search terms (desktop entry `Keywords`), folded for matching.
+    pub keywords: Vec<String>,
+    /// Submitting copies this text instead of launching `target`.
+    pub copy_text: Option<String>,
 }
 
 impl LauncherItem {
     pub fn new(label: String, target: String, source: LauncherSource) -> Self {
-        let label_lc = label.to_lowercase();
-        let label_compact = label_lc
-            .chars()
-            .filter(|ch| ch.is_ascii_alphanumeric())
-            .collect::<String>();
-
-        let target_name = std::path::Path::new(&target)
-            .file_stem()
-            .and_then(|value| value.to_str())
-            .map(str::trim)
-            .filter(|value| !value.is_empty())
-            .unwrap_or(&target)
-            .to_string();
-        let target_name_lc = target_name.to_lowercase();
-        let target_name_compact = target_name_lc
-            .chars()
-            .filter(|ch| ch.is_ascii_alphanumeric())
-            .collect::<String>();
+        let label_lc = fold_for_match(&label);
+        let label_compact = compact_folded(&label_lc);
+        let label_initials = word_initials(&label, false);
+
+        let target_name = target_file_name(&target);
+        let target_name_lc = fold_for_match(&target_name);
+        let target_name_compact = compact_folded(&target_name_lc);
+        let target_name_initials = word_initials(&target_name, false);
 
         Self {
             label,
             label_lc,
             label_compact,
+            label_initials,
             target_name,
             target_name_lc,
             target_name_compact,
+            target_name_initials,
             target,
             source,
             trailing_hint: None,
             quick_select_key: None,
             trailing_badge: None,
             trailing_badge_tone: None,
+            secondary_actions: Vec::new(),
+            frecency: 0.0,
+            rule: None,
+            extra_source: None,
+            shortcut: None,
+            keywords: Vec::new(),
+            copy_text: None,
+        }
+    }
+
+    pub fn set_label(&mut self, label: String) {
+        self.label_lc = fold_for_match(&label);
+        self.label_compact = compact_folded(&self.label_lc);
+        self.label_initials = word_initials(&label, false);
+        self.label = label;
+    }
+
+    /// Attaches a parsed `.lnk`; its resolved target, when known, replaces the
+    /// shortcut file name for matching.
+    pub fn set_shortcut(&mut self, shortcut: ShellLink) {
+        if let Some(resolved) = shortcut.target.as_deref() {
+            self.target_name = target_file_name(resolved);
+            self.target_name_lc = fold_for_match(&self.target_name);
+            self.target_name_compact = compact_folded(&self.target_name_lc);
+            self.target_name_initials = word_initials(&self.target_name, false);
         }
+        self.shortcut = Some(shortcut);
     }
+
+    pub fn set_keywords(&mut self, keywords: Vec<String>) {
+        self.keywords = keywords
+            .iter()
+            .map(|keyword| fold_for_match(keyword))
+            .collect();
+    }
+
+    /// Target the item launches in the end: the resolved path of a shortcut,
+    /// otherwise `target` itself.
+    pub fn resolved_target(&self) -> &str {
+        self.shortcut
+            .as_ref()
+            .and_then(|shortcut| shortcut.target.as_deref())
+            .unwrap_or(&self.target)
+    }
+}
+
+fn target_file_name(target: &str) -> String {
+    std::path::Path::new(target)
+        .file_stem()
+        .and_then(|value| value.to_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or(target)
+        .to_string()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -123,6 +224,15 @@ pub struct RmodsUiState {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct ActionMenuState {
+    pub item_label: String,
+    pub actions: Vec<LauncherItemAction>,
+    pub parent_items: Vec<LauncherItem>,
+    pub parent_selected_index: usize,
+    pub parent_scroll_offset: usize,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct StartupUpdateNotice {
     pub version: String,
@@ -139,17 +249,33 @@ pub struct AppState {
     pub scroll_offset: usize,
     pub matching_items: Vec<LauncherItem>,
     pub all_items: Vec<LauncherItem>,
+    /// Bumped whenever `all_items` changes, invalidating `ranking_cache`.
+    pub items_generation: u64,
     pub prompt: Option<String>,
     pub launcher_mode: bool,
     pub silent_mode: bool,
     pub history_max_items: usize,
+    pub frecency_half_life_hours: f64,
     pub source_boost_history: i64,
     pub source_boost_start_menu: i64,
     pub source_boost_path: i64,
+    pub source_boost_bookmarks: i64,
+    pub frecency_weight: i64,
+    pub adaptive_aliases: AdaptiveAliases,
     pub rtasks_status: Option<RtasksInputStatus>,
     pub rtasks_priority: Option<RtasksInputPriority>,
     pub rmods: RmodsUiState,
     pub startup_update_notice: Option<StartupUpdateNotice>,
+    pub action_menu: Option<ActionMenuState>,
+    pub ranking_cache: RankingCache,
+}
+
+impl AppState {
+    /// Replaces the launcher items; rankings of the old ones are not reused.
+    pub fn set_all_items(&mut self, items: Vec<LauncherItem>) {
+        self.all_items = items;
+        self.items_generation += 1;
+    }
 }
 
 pub fn ensure_selection_visible(app_state: &mut AppState, max_visible_items: usize) {
@@ -178,18 +304,143 @@ pub fn ensure_selection_visible(app_state: &mut AppState, max_visible_items: usi
     }
 }
 
+pub fn find_quick_select_index(
+    app_state: &AppState,
+    key: char,
+    max_visible_items: usize,
+) -> Option<usize> {
+    let visible_end =
+        (app_state.scroll_offset + max_visible_items).min(app_state.matching_items.len());
+    let key_str = key.to_string();
+
+    for item_index in app_state.scroll_offset..visible_end {
+        let item = &app_state.matching_items[item_index];
+        if item.quick_select_key.as_deref() == Some(key_str.as_str()) {
+            return Some(item_index);
+        }
+    }
+
+    None
+}
+
+/// Clears duplicate quick-select keys, keeping the first item that uses each.
+pub fn normalize_quick_select_items(app_state: &mut AppState) {
+    use std::collections::BTreeSet;
+
+    let mut seen: BTreeSet<String> = BTreeSet::new();
+
+    for item in &mut app_state.matching_items {
+        let Some(key) = item.quick_select_key.clone() else {
+            continue;
+        };
+
+        if key.is_empty() {
+            item.quick_select_key = None;
+            continue;
+        }
+
+        if seen.insert(key.clone()) {
+            continue;
+        }
+
+        if !app_state.silent_mode {
+            eprintln!(
+                "modules quick-select conflict: key '{}' duplicated, keeping first item and clearing duplicate on '{}'",
+                key, item.label
+            );
+        }
+
+        item.quick_select_key = None;
+        if item.trailing_badge.as_deref() == Some(key.as_str()) {
+            item.trailing_badge = None;
+            item.trailing_badge_tone = None;
+        }
+    }
+}
+
+pub fn open_action_menu(app_state: &mut AppState, actions: Vec<LauncherItemAction>) -> bool {
+    let Some(item) = app_state.matching_items.get(app_state.selected_index) else {
+        return false;
+    };
+    if actions.is_empty() {
+        return false;
+    }
+
+    let item_label = item.label.clone();
+    let menu_items = actions
+        .iter()
+        .enumerate()
+        .map(|(index, action)| {
+            let mut menu_item = LauncherItem::new(
+                action.label.clone(),
+                action.kind.target().to_string(),
+                LauncherSource::Direct,
+            );
+            if index < 9 {
+                menu_item.quick_select_key = Some((index + 1).to_string());
+                menu_item.trailing_badge = menu_item.quick_select_key.clone();
+            }
+            menu_item
+        })
+        .collect();
+
+    let parent_items = std::mem::replace(&mut app_state.matching_items, menu_items);
+    app_state.action_menu = Some(ActionMenuState {
+        item_label,
+        actions,
+        parent_items,
+        parent_selected_index: app_state.selected_index,
+        parent_scroll_offset: app_state.scroll_offset,
+    });
+    app_state.selected_index = 0;
+    app_state.scroll_offset = 0;
+    true
+}
+
+pub fn close_action_menu(app_state: &mut AppState) -> bool {
+    let Some(menu) = app_state.action_menu.take() else {
+        return false;
+    };
+
+    app_state.matching_items = menu.parent_items;
+    app_state.selected_index = menu.parent_selected_index;
+    app_state.scroll_offset = menu.parent_scroll_offset;
+    true
+}
+
+pub fn selected_menu_action(app_state: &AppState) -> Option<LauncherItemAction> {
+    app_state
+        .action_menu
+        .as_ref()
+        .and_then(|menu| menu.actions.get(app_state.selected_index))
+        .cloned()
+}
+
 pub fn source_boost(app_state: &AppState, source: LauncherSource) -> i64 {
     match source {
         LauncherSource::History => app_state.source_boost_history,
         LauncherSource::StartMenu => app_state.source_boost_start_menu,
         LauncherSource::Path => app_state.source_boost_path,
-        LauncherSource::Direct => 0,
+        LauncherSource::Bookmark => app_state.source_boost_bookmarks,
+        LauncherSource::Direct | LauncherSource::Extra => 0,
+    }
+}
+
+pub fn frecency_boost(app_state: &AppState, item: &LauncherItem) -> i64 {
+    if item.frecency <= 0.0 {
+        return 0;
     }
+    (app_state.frecency_weight as f64 * item.frecency.ln_1p()).round() as i64
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ensure_selection_visible, source_boost, AppState, LauncherItem, LauncherSource};
+    use super::{
+        close_action_menu, ensure_selection_visible, find_quick_select_index,
+        normalize_quick_select_items, open_action_menu, selected_menu_action, source_boost,
+        AppState, LauncherItem, LauncherItemAction, LauncherItemActionKind, LauncherItemTone,
+        LauncherSource,
+    };
     use crate::fuzzy::fuzzy_score;
 
     #[test]
@@ -232,4 +483,128 @@ mod tests {
 
         assert!(pow_shell_score > powercfg_score);
     }
+
+    #[test]
+    fn action_menu_replaces_and_restores_visible_items() {
+        let mut state = AppState {
+            matching_items: (0..3)
+                .map(|i| {
+                    LauncherItem::new(
+                        format!("item-{i}"),
+                        format!("target-{i}"),
+                        LauncherSource::Path,
+                    )
+                })
+                .collect(),
+            selected_index: 2,
+            scroll_offset: 1,
+            ..Default::default()
+        };
+
+        assert!(!open_action_menu(&mut state, Vec::new()));
+        assert!(state.action_menu.is_none());
+
+        let actions = vec![
+            LauncherItemAction {
+                id: "copy-path".to_string(),
+                label: "Copy path".to_string(),
+                kind: LauncherItemActionKind::Copy("target-2".to_string()),
+            },
+            LauncherItemAction {
+                id: "run-as-admin".to_string(),
+                label: "Run as administrator".to_string(),
+                kind: LauncherItemActionKind::Launch("runas:target-2".to_string()),
+            },
+        ];
+        assert!(open_action_menu(&mut state, actions));
+        assert_eq!(
+            state
+                .action_menu
+                .as_ref()
+                .map(|menu| menu.item_label.as_str()),
+            Some("item-2")
+        );
+        assert_eq!(state.matching_items.len(), 2);
+        assert_eq!(
+            state.matching_items[1].quick_select_key.as_deref(),
+            Some("2")
+        );
+        assert_eq!(state.selected_index, 0);
+
+        state.selected_index = 1;
+        assert_eq!(
+            selected_menu_action(&state).map(|action| action.id),
+            Some("run-as-admin".to_string())
+        );
+
+        assert!(close_action_menu(&mut state));
+        assert!(state.action_menu.is_none());
+        assert_eq!(state.matching_items.len(), 3);
+        assert_eq!(state.selected_index, 2);
+        assert_eq!(state.scroll_offset, 1);
+        assert!(!close_action_menu(&mut state));
+    }
+
+    #[test]
+    fn quick_select_conflicts_keep_first_visible_item() {
+        let mut first = LauncherItem::new(
+            "First".to_string(),
+            "t1".to_string(),
+            LauncherSource::Direct,
+        );
+        first.quick_select_key = Some("1".to_string());
+        first.trailing_badge = Some("1".to_string());
+
+        let mut second = LauncherItem::new(
+            "Second".to_string(),
+            "t2".to_string(),
+            LauncherSource::Direct,
+        );
+        second.quick_select_key = Some("1".to_string());
+        second.trailing_badge = Some("1".to_string());
+        second.trailing_badge_tone = Some(LauncherItemTone::Warning);
+
+        let mut state = AppState {
+            matching_items: vec![first, second],
+            ..Default::default()
+        };
+
+        normalize_quick_select_items(&mut state);
+
+        assert_eq!(
+            state.matching_items[0].quick_select_key.as_deref(),
+            Some("1")
+        );
+        assert_eq!(state.matching_items[1].quick_select_key, None);
+        assert_eq!(state.matching_items[1].trailing_badge, None);
+        assert_eq!(state.matching_items[1].trailing_badge_tone, None);
+    }
+
+    #[test]
+    fn quick_select_index_is_resolved_from_visible_window() {
+        let mut items = Vec::new();
+        for idx in 0..6 {
+            let mut item = LauncherItem::new(
+                format!("Item {idx}"),
+                format!("target-{idx}"),
+                LauncherSource::Direct,
+            );
+            if idx == 4 {
+                item.quick_select_key = Some("5".to_string());
+            }
+            items.push(item);
+        }
+
+        let state = AppState {
+            matching_items: items,
+            scroll_offset: 2,
+            ..Default::default()
+        };
+
+        let index = find_quick_select_index(&state, '5', 3);
+        assert_eq!(index, Some(4));
+
+        let hidden = find_quick_select_index(&state, '5', 2);
+        assert_eq!(hidden, None);
+    }
 }
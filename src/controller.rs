@@ -0,0 +1,931 @@
+//! Frontend-neutral launcher session. Owns the `AppState`, module runtime, and
+//! ranking pipeline, and turns key presses and typed characters into state
+//! changes and outcomes. The Win32 window, the terminal frontend, and the
+//! `--script` driver all feed input through it and only differ in how they
+//! draw and what they do with a `LauncherOutcome`.
+
+use crate::adaptive_aliases::unix_now;
+use crate::app_state::{
+    close_action_menu, ensure_selection_visible, find_quick_select_index,
+    normalize_quick_select_items, open_action_menu, selected_menu_action, AppState, LauncherItem,
+    LauncherItemAction, LauncherItemActionKind, LauncherSource,
+};
+use crate::modules::{
+    types::{InputAccessoryKind, ModuleInputAccessory, ModuleKeyEvent},
+    ModuleRuntime,
+};
+use crate::ranking::{update_matching_items, update_streamed_matching_items};
+use crate::schemes::SchemeRoute;
+use crate::scopes::{split_scope, SearchScope};
+use crate::settings::{QuickSelectMode, RmenuConfig};
+use crate::sources::{core_secondary_actions, remove_history_entry};
+use crate::streaming::StreamingRanker;
+use std::io;
+
+/// Platform services and frontend-owned input modes.
+#[derive(Clone, Copy)]
+pub struct LauncherPlatform {
+    pub copy_text: fn(&str) -> io::Result<()>,
+    /// Fills `matching_items` for inputs the frontend renders itself (the Win32
+    /// update notice, `/rmods` list, and RTasks capture) and returns true to
+    /// skip ranking.
+    pub claim_input: fn(&mut AppState) -> bool,
+    /// Runs after modules saw a key and before the launcher's own handling.
+    /// `Some` consumes the key.
+    pub handle_key: fn(&mut LauncherController, &ModuleKeyEvent) -> Option<LauncherOutcome>,
+}
+
+impl LauncherPlatform {
+    /// No clipboard and no frontend-owned input modes.
+    pub fn headless() -> Self {
+        Self {
+            copy_text: |_| Err(io::Error::new(io::ErrorKind::Unsupported, "no clipboard")),
+            claim_input: |_| false,
+            handle_key: |_, _| None,
+        }
+    }
+}
+
+/// What the frontend should do after a key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LauncherOutcome {
+    Continue,
+    Exit(i32),
+    /// Launch `target` and close; `remember` records it in history.
+    Launch {
+        target: String,
+        remember: bool,
+    },
+    /// Print the selection to stdout (`stdin`/`-e` mode) and close.
+    Print(String),
+    /// Copy the text to the clipboard and close.
+    Copy(String),
+}
+
+pub struct LauncherController {
+    config: RmenuConfig,
+    app_state: AppState,
+    runtime: ModuleRuntime,
+    platform: LauncherPlatform,
+    viewport_rows: usize,
+    streaming: Option<StreamingRanker>,
+    streaming_provider_items: Vec<LauncherItem>,
+    swallow_next_char: bool,
+}
+
+impl LauncherController {
+    pub fn new(
+        config: RmenuConfig,
+        app_state: AppState,
+        runtime: ModuleRuntime,
+        platform: LauncherPlatform,
+    ) -> Self {
+        Self {
+            config,
+            app_state,
+            runtime,
+            platform,
+            viewport_rows: usize::MAX,
+            streaming: None,
+            streaming_provider_items: Vec::new(),
+            swallow_next_char: false,
+        }
+    }
+
+    pub fn config(&self) -> &RmenuConfig {
+        &self.config
+    }
+
+    pub fn app_state(&self) -> &AppState {
+        &self.app_state
+    }
+
+    pub fn app_state_mut(&mut self) -> &mut AppState {
+        &mut self.app_state
+    }
+
+    pub fn runtime(&self) -> &ModuleRuntime {
+        &self.runtime
+    }
+
+    pub fn runtime_mut(&mut self) -> &mut ModuleRuntime {
+        &mut self.runtime
+    }
+
+    /// Hands the module runtime back, e.g. to keep it warm in the daemon.
+    pub fn into_runtime(self) -> ModuleRuntime {
+        self.runtime
+    }
+
+    /// Ranks `stdin` lines on a background thread instead of `all_items`.
+    pub fn attach_streaming_ranker(&mut self, ranker: StreamingRanker) {
+        self.streaming = Some(ranker);
+    }
+
+    /// Runs `on_load` and the initial matching pass.
+    pub fn start(&mut self) {
+        self.run_on_load();
+        self.show_initial_results();
+    }
+
+    pub fn run_on_load(&mut self) {
+        self.runtime.clear_runtime_feedback();
+        self.runtime.run_on_load(&mut self.app_state);
+    }
+
+    /// An empty input shows nothing unless the frontend claims it.
+    pub fn show_initial_results(&mut self) {
+        if !self.app_state.current_input.trim().is_empty() {
+            self.refresh_matches();
+        } else if !(self.platform.claim_input)(&mut self.app_state) {
+            self.app_state.matching_items.clear();
+            self.app_state.selected_index = 0;
+            self.app_state.scroll_offset = 0;
+        }
+    }
+
+    pub fn shutdown(&mut self) {
+        self.runtime.run_on_unload(&mut self.app_state);
+    }
+
+    /// Rows left for results, for frontends smaller than `max_items`.
+    pub fn set_viewport_rows(&mut self, rows: usize) {
+        self.viewport_rows = rows;
+        let max_visible = self.max_visible_items();
+        ensure_selection_visible(&mut self.app_state, max_visible);
+    }
+
+    pub fn max_visible_items(&self) -> usize {
+        (self.config.behavior.max_items.max(1) as usize)
+            .min(self.viewport_rows)
+            .max(1)
+    }
+
+    /// Result rows currently in view.
+    pub fn visible_items(&self) -> &[LauncherItem] {
+        let items = &self.app_state.matching_items;
+        let start = self.app_state.scroll_offset.min(items.len());
+        let end = (start + self.max_visible_items()).min(items.len());
+        &items[start..end]
+    }
+
+    /// The active search scope's label, or else the module runtime's accessory.
+    pub fn input_accessory(&self) -> Option<ModuleInputAccessory> {
+        split_scope(&self.app_state.current_input, &self.config.scopes)
+            .0
+            .map(|scope| ModuleInputAccessory {
+                text: scope.label(),
+                kind: InputAccessoryKind::Info,
+                priority: 0,
+            })
+            .or_else(|| self.runtime.active_input_accessory())
+    }
+
+    /// Re-ranks after a module hot reload. Returns true when it happened.
+    pub fn poll_hot_reload(&mut self) -> bool {
+        if !self.runtime.poll_hot_reload(self.app_state.silent_mode) {
+            return false;
+        }
+        self.refresh_matches();
+        true
+    }
+
+    /// Applies background ranking results and re-ranks the active query when
+    /// new stdin lines arrive. Returns true when the visible results changed.
+    pub fn poll_streaming(&mut self) -> bool {
+        let Some(ranker) = &self.streaming else {
+            return false;
+        };
+        if ranker.take_arrivals() {
+            ranker.refresh();
+        }
+        let Some(result) = ranker
+            .take_result()
+            .filter(|result| result.query == self.app_state.current_input)
+        else {
+            return false;
+        };
+        let provider_items = self.streaming_provider_items.clone();
+        update_streamed_matching_items(
+            &mut self.app_state,
+            result.items,
+            provider_items,
+            self.runtime.providers_first(),
+            &result.query,
+            self.config.behavior.case_sensitive,
+        );
+        self.finish_matching_update();
+        true
+    }
+
+    pub fn refresh_matches(&mut self) {
+        self.app_state.action_menu = None;
+        if let Some(ranker) = &self.streaming {
+            ranker.cancel();
+        }
+        if (self.platform.claim_input)(&mut self.app_state) {
+            return;
+        }
+
+        let input = self.app_state.current_input.clone();
+        let (scope, query) = split_scope(&input, &self.config.scopes);
+        self.runtime.run_on_query_change(&mut self.app_state);
+        self.copy_pending_module_text();
+        if self.runtime.items_replaced_in_cycle() {
+            normalize_quick_select_items(&mut self.app_state);
+            return;
+        }
+        let provider_items = match &scope {
+            Some(SearchScope::Source(_)) => Vec::new(),
+            Some(scope) => {
+                self.runtime
+                    .collect_provider_items_matching(&self.app_state, query, |name| {
+                        scope.includes_module(name)
+                    })
+            }
+            None => self.runtime.collect_provider_items(&self.app_state),
+        };
+
+        let providers_first = self.runtime.providers_first();
+        let case_sensitive = self.config.behavior.case_sensitive;
+        match &self.streaming {
+            // Streamed stdin lines are direct items, which no scope selects.
+            Some(_) if scope.is_some() => update_streamed_matching_items(
+                &mut self.app_state,
+                Vec::new(),
+                provider_items,
+                providers_first,
+                query,
+                case_sensitive,
+            ),
+            Some(ranker) => {
+                ranker.submit(&self.app_state, case_sensitive);
+                self.streaming_provider_items = provider_items;
+                return;
+            }
+            None => update_matching_items(
+                &mut self.app_state,
+                scope.as_ref(),
+                provider_items,
+                providers_first,
+                query,
+                case_sensitive,
+            ),
+        }
+        self.finish_matching_update();
+    }
+
+    /// Decorates the freshly ranked matches and keeps the selection in view.
+    fn finish_matching_update(&mut self) {
+        let max_visible_items = self.max_visible_items();
+        let decorated = self
+            .runtime
+            .decorate_items(&self.app_state, self.app_state.matching_items.clone());
+        self.app_state.matching_items = decorated;
+
+        normalize_quick_select_items(&mut self.app_state);
+        ensure_selection_visible(&mut self.app_state, max_visible_items);
+    }
+
+    pub fn copy_pending_module_text(&mut self) {
+        let Some(text) = self.runtime.take_pending_copy_text() else {
+            return;
+        };
+        match (self.platform.copy_text)(&text) {
+            Ok(()) => self
+                .runtime
+                .set_runtime_feedback("Copied to clipboard", InputAccessoryKind::Success),
+            Err(error) => self.runtime.set_runtime_feedback(
+                format!("Copy to clipboard failed: {error}"),
+                InputAccessoryKind::Error,
+            ),
+        }
+    }
+
+    /// Current input without its scope prefix, the query aliases are learned for.
+    pub fn unscoped_query(&self) -> String {
+        split_scope(&self.app_state.current_input, &self.config.scopes)
+            .1
+            .to_string()
+    }
+
+    fn learn_adaptive_alias(&mut self, target: &str) {
+        let query = self.unscoped_query();
+        if self
+            .app_state
+            .adaptive_aliases
+            .record(&query, target, unix_now())
+        {
+            self.app_state
+                .adaptive_aliases
+                .save(self.app_state.silent_mode);
+        }
+    }
+
+    /// Ignores the next `insert_char`, for keys whose character the key press
+    /// already consumed.
+    pub fn swallow_next_char(&mut self) {
+        self.swallow_next_char = true;
+    }
+
+    /// Handles a key press named like `ModuleKeyEvent::key`. Frontends that
+    /// also deliver a typed character call `insert_char` afterwards.
+    pub fn handle_key(&mut self, key: &ModuleKeyEvent) -> LauncherOutcome {
+        // Tab opens the selected row's actions; Ctrl+Enter is kept as an alias.
+        let action_menu_key = key.key == "tab" || key.ctrl && key.key == "enter";
+        if self.app_state.action_menu.is_some() {
+            return self.handle_action_menu_key(key, action_menu_key);
+        }
+
+        let input_before_modules = self.app_state.current_input.clone();
+        self.runtime.run_on_key(&mut self.app_state, key);
+        self.copy_pending_module_text();
+        if self.app_state.current_input != input_before_modules {
+            self.refresh_matches();
+        }
+
+        if let Some(outcome) = (self.platform.handle_key)(self, key) {
+            return outcome;
+        }
+
+        if action_menu_key {
+            if self.app_state.launcher_mode && !self.app_state.current_input.starts_with('/') {
+                self.open_selected_item_actions();
+            }
+            return LauncherOutcome::Continue;
+        }
+
+        match key.key.as_str() {
+            "escape" => LauncherOutcome::Exit(1),
+            "enter" => self.submit(),
+            "down" => {
+                self.move_selection(true);
+                LauncherOutcome::Continue
+            }
+            "up" => {
+                self.move_selection(false);
+                LauncherOutcome::Continue
+            }
+            "backspace" => {
+                if self.app_state.current_input.pop().is_some() {
+                    self.app_state.selected_index = 0;
+                    self.refresh_matches();
+                }
+                LauncherOutcome::Continue
+            }
+            name if !key.ctrl && !key.alt => match quick_select_digit(name) {
+                Some(digit) => self.quick_select(digit),
+                None => LauncherOutcome::Continue,
+            },
+            _ => LauncherOutcome::Continue,
+        }
+    }
+
+    /// Appends a typed character to the input and re-ranks. Closes an open
+    /// action menu first.
+    pub fn insert_char(&mut self, ch: char) -> LauncherOutcome {
+        if std::mem::take(&mut self.swallow_next_char) || ch.is_control() {
+            return LauncherOutcome::Continue;
+        }
+        self.leave_action_menu();
+        self.app_state.current_input.push(ch);
+        self.app_state.selected_index = 0;
+        self.refresh_matches();
+        LauncherOutcome::Continue
+    }
+
+    fn quick_select(&mut self, digit: char) -> LauncherOutcome {
+        let max_visible = self.max_visible_items();
+        let Some(index) = find_quick_select_index(&self.app_state, digit, max_visible) else {
+            return LauncherOutcome::Continue;
+        };
+        self.swallow_next_char();
+        self.app_state.selected_index = index;
+        ensure_selection_visible(&mut self.app_state, max_visible);
+        if self.config.behavior.quick_select_mode == QuickSelectMode::Submit {
+            return self.submit_selected();
+        }
+        LauncherOutcome::Continue
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        let len = self.app_state.matching_items.len();
+        if len == 0 {
+            return;
+        }
+        self.app_state.selected_index = if down {
+            (self.app_state.selected_index + 1) % len
+        } else {
+            (self.app_state.selected_index + len - 1) % len
+        };
+        let max_visible = self.max_visible_items();
+        ensure_selection_visible(&mut self.app_state, max_visible);
+    }
+
+    fn submit(&mut self) -> LauncherOutcome {
+        let current_input = self.app_state.current_input.clone();
+        if current_input.is_empty() {
+            return LauncherOutcome::Exit(0);
+        }
+
+        if let Some(raw_command) = current_input.strip_prefix('/') {
+            let parts = raw_command.split_whitespace().collect::<Vec<_>>();
+            if let Some((command, rest)) = parts.split_first() {
+                let args = rest.iter().map(|v| (*v).to_string()).collect::<Vec<_>>();
+                if self.launcher_command(command, &args) {
+                    return LauncherOutcome::Continue;
+                }
+                let silent_mode = self.app_state.silent_mode;
+                if self
+                    .runtime
+                    .dispatch_command(&mut self.app_state, command, &args, silent_mode)
+                {
+                    self.copy_pending_module_text();
+                    return LauncherOutcome::Continue;
+                }
+            }
+        } else if self.app_state.selected_index < self.app_state.matching_items.len() {
+            return self.submit_selected();
+        } else if self.app_state.launcher_mode {
+            return self.launch(current_input, true);
+        } else if self.app_state.all_items.is_empty() {
+            return LauncherOutcome::Print(current_input);
+        }
+        LauncherOutcome::Exit(0)
+    }
+
+    /// Slash commands over launcher state that modules must not reach through
+    /// `dispatch_command`.
+    fn launcher_command(&mut self, command: &str, args: &[String]) -> bool {
+        match command {
+            "aliases.forget" => {
+                let aliases = &mut self.app_state.adaptive_aliases;
+                let removed = match args {
+                    [] => {
+                        self.runtime.set_runtime_feedback(
+                            "usage: /aliases.forget <query> | --all",
+                            InputAccessoryKind::Error,
+                        );
+                        return true;
+                    }
+                    [all] if all == "--all" => aliases.forget_all(),
+                    words => aliases.forget(&words.join(" ")),
+                };
+                if removed > 0 {
+                    self.app_state
+                        .adaptive_aliases
+                        .save(self.app_state.silent_mode);
+                }
+                self.runtime.set_runtime_feedback(
+                    format!("adaptive aliases forgotten: {removed}"),
+                    InputAccessoryKind::Info,
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Launches the selected row in launcher mode, prints it otherwise.
+    fn submit_selected(&mut self) -> LauncherOutcome {
+        let selected = self.app_state.matching_items[self.app_state.selected_index].clone();
+        if let Some(text) = selected.copy_text {
+            LauncherOutcome::Copy(text)
+        } else if self.app_state.launcher_mode {
+            self.learn_adaptive_alias(&selected.target);
+            self.launch(selected.target, true)
+        } else {
+            LauncherOutcome::Print(selected.label)
+        }
+    }
+
+    /// Targets with a module-declared scheme run the module's command and keep
+    /// the session open; unknown schemes are reported, anything else is
+    /// launched by the frontend.
+    fn launch(&mut self, target: String, remember: bool) -> LauncherOutcome {
+        let (module, rest) = match self.runtime.schemes().route(&target) {
+            SchemeRoute::Shell | SchemeRoute::Builtin => {
+                return LauncherOutcome::Launch { target, remember };
+            }
+            SchemeRoute::Unknown(scheme) => {
+                self.runtime.set_runtime_feedback(
+                    format!("unknown launch scheme '{scheme}:'"),
+                    InputAccessoryKind::Error,
+                );
+                return LauncherOutcome::Continue;
+            }
+            SchemeRoute::Module { module, rest } => (module.to_string(), rest.to_string()),
+        };
+        let mut parts = rest.split_whitespace();
+        let Some(command) = parts.next() else {
+            self.runtime.set_runtime_feedback(
+                format!("'{}' names no {module} command", target.trim()),
+                InputAccessoryKind::Error,
+            );
+            return LauncherOutcome::Continue;
+        };
+        let args = parts.map(str::to_string).collect::<Vec<_>>();
+        let silent_mode = self.app_state.silent_mode;
+        self.runtime.dispatch_command(
+            &mut self.app_state,
+            &format!("{module}::{command}"),
+            &args,
+            silent_mode,
+        );
+        self.copy_pending_module_text();
+        LauncherOutcome::Continue
+    }
+
+    fn open_selected_item_actions(&mut self) {
+        let Some(item) = self
+            .app_state
+            .matching_items
+            .get(self.app_state.selected_index)
+        else {
+            return;
+        };
+        let mut actions = item.secondary_actions.clone();
+        actions.extend(core_secondary_actions(item));
+        let query = self.unscoped_query();
+        let pinned_target = self
+            .app_state
+            .adaptive_aliases
+            .pinned_target(&query, unix_now());
+        if pinned_target.is_some_and(|target| target.eq_ignore_ascii_case(&item.target)) {
+            actions.push(LauncherItemAction {
+                id: "forget-alias".to_string(),
+                label: format!("Forget for \"{}\"", query.trim()),
+                kind: LauncherItemActionKind::ForgetAlias(item.target.clone()),
+            });
+        }
+
+        if open_action_menu(&mut self.app_state, actions) {
+            let label = self
+                .app_state
+                .action_menu
+                .as_ref()
+                .map(|menu| menu.item_label.clone())
+                .unwrap_or_default();
+            self.runtime
+                .set_runtime_feedback(format!("Actions: {label}"), InputAccessoryKind::Hint);
+        } else {
+            self.runtime
+                .set_runtime_feedback("No actions for this item", InputAccessoryKind::Hint);
+        }
+    }
+
+    fn handle_action_menu_key(
+        &mut self,
+        key: &ModuleKeyEvent,
+        action_menu_key: bool,
+    ) -> LauncherOutcome {
+        match key.key.as_str() {
+            _ if action_menu_key => self.leave_action_menu(),
+            "escape" | "backspace" => self.leave_action_menu(),
+            "enter" => return self.run_selected_menu_action(),
+            "down" => self.move_selection(true),
+            "up" => self.move_selection(false),
+            name => {
+                let max_visible = self.max_visible_items();
+                if let Some(index) = quick_select_digit(name)
+                    .and_then(|digit| find_quick_select_index(&self.app_state, digit, max_visible))
+                {
+                    self.swallow_next_char();
+                    self.app_state.selected_index = index;
+                    return self.run_selected_menu_action();
+                }
+            }
+        }
+        LauncherOutcome::Continue
+    }
+
+    fn leave_action_menu(&mut self) {
+        if close_action_menu(&mut self.app_state) {
+            self.runtime.clear_runtime_feedback();
+        }
+        let max_visible = self.max_visible_items();
+        ensure_selection_visible(&mut self.app_state, max_visible);
+    }
+
+    fn run_selected_menu_action(&mut self) -> LauncherOutcome {
+        let Some(action) = selected_menu_action(&self.app_state) else {
+            return LauncherOutcome::Continue;
+        };
+        close_action_menu(&mut self.app_state);
+        match action.kind {
+            LauncherItemActionKind::Launch(target) => return self.launch(target, false),
+            LauncherItemActionKind::Copy(text) => return LauncherOutcome::Copy(text),
+            LauncherItemActionKind::ForgetAlias(target) => self.forget_alias(&target),
+            LauncherItemActionKind::RemoveFromHistory(target) => self.remove_from_history(&target),
+        }
+        LauncherOutcome::Continue
+    }
+
+    fn forget_alias(&mut self, target: &str) {
+        let query = self.unscoped_query();
+        if self.app_state.adaptive_aliases.forget_pin(&query, target) > 0 {
+            self.app_state
+                .adaptive_aliases
+                .save(self.app_state.silent_mode);
+        }
+        self.refresh_matches();
+        self.runtime.set_runtime_feedback(
+            format!("Forgot \"{}\" for this item", query.trim()),
+            InputAccessoryKind::Success,
+        );
+    }
+
+    fn remove_from_history(&mut self, target: &str) {
+        if !remove_history_entry(target, self.app_state.silent_mode) {
+            self.runtime
+                .set_runtime_feedback("Could not remove history entry", InputAccessoryKind::Error);
+            return;
+        }
+        let items = std::mem::take(&mut self.app_state.all_items)
+            .into_iter()
+            .filter(|item| {
+                !(matches!(item.source, LauncherSource::History)
+                    && item.target.eq_ignore_ascii_case(target))
+            })
+            .collect();
+        self.app_state.set_all_items(items);
+        self.refresh_matches();
+        self.runtime
+            .set_runtime_feedback("Removed from history", InputAccessoryKind::Success);
+    }
+}
+
+fn quick_select_digit(key_name: &str) -> Option<char> {
+    let mut chars = key_name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if ch.is_ascii_digit() => Some(ch),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LauncherController, LauncherOutcome, LauncherPlatform};
+    use crate::app_state::{
+        AppState, LauncherItem, LauncherItemAction, LauncherItemActionKind, LauncherSource,
+    };
+    use crate::modules::context::ModuleCtx;
+    use crate::modules::hooks::RuntimeModule;
+    use crate::modules::types::{
+        InputAccessoryKind, ModuleAction, ModuleItem, ModuleItemCapabilities,
+        ModuleItemDecorations, ModuleKeyEvent, ModuleProviderDef,
+    };
+    use crate::modules::{BuiltinQueryProviderModule, ModuleRuntime};
+    use crate::settings::RmenuConfig;
+
+    fn key(name: &str, ctrl: bool) -> ModuleKeyEvent {
+        ModuleKeyEvent {
+            key: name.to_string(),
+            ctrl,
+            alt: false,
+            shift: false,
+            meta: false,
+        }
+    }
+
+    fn new_controller(items: &[(&str, &str)]) -> LauncherController {
+        new_controller_with_module(items, Box::new(BuiltinQueryProviderModule::default()))
+    }
+
+    fn new_controller_with_module(
+        items: &[(&str, &str)],
+        module: Box<dyn RuntimeModule>,
+    ) -> LauncherController {
+        let mut runtime = ModuleRuntime::new();
+        runtime.register_builtin_module(module);
+        let app_state = AppState {
+            all_items: items
+                .iter()
+                .map(|(label, target)| {
+                    LauncherItem::new(label.to_string(), target.to_string(), LauncherSource::Path)
+                })
+                .collect(),
+            launcher_mode: true,
+            silent_mode: true,
+            ..Default::default()
+        };
+        let mut controller = LauncherController::new(
+            RmenuConfig::default(),
+            app_state,
+            runtime,
+            LauncherPlatform::headless(),
+        );
+        controller.start();
+        controller
+    }
+
+    /// A key press followed by its character, like WM_KEYDOWN then WM_CHAR.
+    fn type_char(controller: &mut LauncherController, ch: char) -> LauncherOutcome {
+        match controller.handle_key(&key(&ch.to_string(), false)) {
+            LauncherOutcome::Continue => controller.insert_char(ch),
+            outcome => outcome,
+        }
+    }
+
+    /// Provides one item with quick-select key `2` for any query.
+    struct QuickSelectModule;
+
+    impl RuntimeModule for QuickSelectModule {
+        fn name(&self) -> &str {
+            "test.quick-select"
+        }
+
+        fn on_load(&mut self, ctx: &mut ModuleCtx) {
+            ctx.register_provider(ModuleProviderDef {
+                name: "quick-select".to_string(),
+                priority: 0,
+            });
+        }
+
+        fn provide_items(&mut self, _query: &str, _ctx: &mut ModuleCtx) -> Vec<ModuleItem> {
+            vec![ModuleItem {
+                id: "quick".to_string(),
+                title: "Quick".to_string(),
+                subtitle: None,
+                source: None,
+                action: ModuleAction::LaunchTarget {
+                    target: "quick.exe".to_string(),
+                },
+                capabilities: ModuleItemCapabilities {
+                    quick_select_key: Some("2".to_string()),
+                },
+                decorations: ModuleItemDecorations::default(),
+                secondary_actions: Vec::new(),
+            }]
+        }
+    }
+
+    #[test]
+    fn digits_extend_calc_expressions_and_enter_copies() {
+        let mut controller = new_controller(&[("Notepad", "notepad")]);
+        for ch in "=6*71".chars() {
+            assert_eq!(type_char(&mut controller, ch), LauncherOutcome::Continue);
+        }
+        assert_eq!(controller.app_state().current_input, "=6*71");
+        assert_eq!(
+            controller.handle_key(&key("enter", false)),
+            LauncherOutcome::Copy("426".to_string())
+        );
+    }
+
+    #[test]
+    fn quick_select_consumes_the_digit_character() {
+        let mut controller =
+            new_controller_with_module(&[("Notepad", "notepad")], Box::new(QuickSelectModule));
+        assert_eq!(type_char(&mut controller, 'q'), LauncherOutcome::Continue);
+        assert_eq!(
+            type_char(&mut controller, '2'),
+            LauncherOutcome::Launch {
+                target: "quick.exe".to_string(),
+                remember: true,
+            }
+        );
+        assert_eq!(controller.insert_char('2'), LauncherOutcome::Continue);
+        assert_eq!(controller.app_state().current_input, "q");
+
+        assert_eq!(controller.insert_char('x'), LauncherOutcome::Continue);
+        assert_eq!(controller.app_state().current_input, "qx");
+    }
+
+    #[test]
+    fn action_menu_opens_runs_by_digit_and_closes_on_typing() {
+        let mut controller = new_controller(&[("Notepad", "notepad")]);
+        for ch in "note".chars() {
+            type_char(&mut controller, ch);
+        }
+
+        assert_eq!(
+            controller.handle_key(&key("enter", true)),
+            LauncherOutcome::Continue
+        );
+        let menu_labels = controller
+            .visible_items()
+            .iter()
+            .map(|item| item.label.clone())
+            .collect::<Vec<_>>();
+        assert!(menu_labels.iter().any(|label| label == "Copy target"));
+
+        assert_eq!(controller.insert_char('s'), LauncherOutcome::Continue);
+        assert!(controller.app_state().action_menu.is_none());
+        assert_eq!(controller.app_state().current_input, "notes");
+
+        controller.handle_key(&key("backspace", false));
+        controller.handle_key(&key("enter", true));
+        let copy_target = controller
+            .visible_items()
+            .iter()
+            .position(|item| item.label == "Copy target")
+            .expect("copy target action");
+        let digit = char::from(b'1' + copy_target as u8);
+        assert_eq!(
+            type_char(&mut controller, digit),
+            LauncherOutcome::Copy("notepad".to_string())
+        );
+        assert_eq!(controller.app_state().current_input, "note");
+    }
+
+    #[test]
+    fn aliases_forget_needs_a_query_or_all() {
+        let mut controller = new_controller(&[("Notepad", "notepad")]);
+        let aliases = &mut controller.app_state_mut().adaptive_aliases;
+        aliases.record("note", "notepad", 1_000);
+        aliases.record("calc", "calc.exe", 1_000);
+
+        let forget = |controller: &mut LauncherController, input: &str| {
+            controller.app_state_mut().current_input = input.to_string();
+            assert_eq!(
+                controller.handle_key(&key("enter", false)),
+                LauncherOutcome::Continue
+            );
+            controller.input_accessory().map(|accessory| accessory.text)
+        };
+
+        let usage = forget(&mut controller, "/aliases.forget").expect("usage feedback");
+        assert!(usage.starts_with("usage:"), "{usage}");
+        assert_eq!(
+            forget(&mut controller, "/aliases.forget no").as_deref(),
+            Some("adaptive aliases forgotten: 0")
+        );
+        assert_eq!(
+            forget(&mut controller, "/aliases.forget note").as_deref(),
+            Some("adaptive aliases forgotten: 1")
+        );
+        assert_eq!(
+            forget(&mut controller, "/aliases.forget --all").as_deref(),
+            Some("adaptive aliases forgotten: 1")
+        );
+    }
+
+    #[test]
+    fn launch_reports_unknown_schemes_and_passes_os_protocols_through() {
+        let mut controller = new_controller(&[
+            ("Store", "ms-windows-store:home"),
+            ("Broken", "nomod:do-thing"),
+        ]);
+        controller
+            .runtime_mut()
+            .schemes_mut()
+            .set_os_protocol_probe(|scheme| scheme == "ms-windows-store");
+
+        let submit = |controller: &mut LauncherController, query: &str| {
+            controller.app_state_mut().current_input.clear();
+            for ch in query.chars() {
+                type_char(controller, ch);
+            }
+            controller.handle_key(&key("enter", false))
+        };
+
+        assert_eq!(
+            submit(&mut controller, "store"),
+            LauncherOutcome::Launch {
+                target: "ms-windows-store:home".to_string(),
+                remember: true,
+            }
+        );
+        assert_eq!(submit(&mut controller, "broken"), LauncherOutcome::Continue);
+        assert_eq!(
+            controller
+                .input_accessory()
+                .map(|accessory| (accessory.kind, accessory.text)),
+            Some((
+                InputAccessoryKind::Error,
+                "unknown launch scheme 'nomod:'".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn module_actions_cannot_reach_core_only_actions() {
+        let mut controller = new_controller(&[("Notepad", "notepad")]);
+        let mut item = LauncherItem::new(
+            "Notepad".to_string(),
+            "notepad".to_string(),
+            LauncherSource::Direct,
+        );
+        item.secondary_actions = vec![LauncherItemAction {
+            id: "sneaky".to_string(),
+            label: "Sneaky".to_string(),
+            kind: LauncherItemActionKind::Launch("history-remove:notepad".to_string()),
+        }];
+        controller.app_state_mut().set_all_items(vec![item]);
+        for ch in "note".chars() {
+            type_char(&mut controller, ch);
+        }
+
+        assert_eq!(
+            controller.handle_key(&key("tab", false)),
+            LauncherOutcome::Continue
+        );
+        assert_eq!(type_char(&mut controller, '1'), LauncherOutcome::Continue);
+        assert_eq!(
+            controller.input_accessory().map(|accessory| accessory.text),
+            Some("unknown launch scheme 'history-remove:'".to_string())
+        );
+        assert_eq!(controller.app_state().all_items.len(), 1);
+    }
+}
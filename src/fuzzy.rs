@@ -1,52 +1,391 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static TRANSLITERATE: AtomicBool = AtomicBool::new(false);
+
+/// Enables Latin transliteration of Cyrillic, Greek, and ligatures in
+/// `fold_for_match`. Set once at startup, before launcher items are built.
+pub fn set_transliteration(enabled: bool) {
+    TRANSLITERATE.store(enabled, Ordering::Relaxed);
+}
+
+fn fold_diacritic(ch: char) -> char {
+    match ch {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' | 'ð' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => 's',
+        'ţ' | 'ť' | 'ŧ' | 'ț' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        'ά' => 'α',
+        'έ' => 'ε',
+        'ή' => 'η',
+        'ί' | 'ϊ' | 'ΐ' => 'ι',
+        'ό' => 'ο',
+        'ύ' | 'ϋ' | 'ΰ' => 'υ',
+        'ώ' => 'ω',
+        'ё' => 'е',
+        _ => ch,
+    }
+}
+
+fn transliterate(ch: char) -> Option<&'static str> {
+    let latin = match ch {
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        'þ' => "th",
+        'ĳ' => "ij",
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' | 'ґ' => "g",
+        'д' => "d",
+        'е' | 'э' => "e",
+        'є' => "ye",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'і' => "i",
+        'й' => "y",
+        'ї' => "yi",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'ю' => "yu",
+        'я' => "ya",
+        'α' => "a",
+        'β' => "v",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' => "e",
+        'ζ' => "z",
+        'η' | 'ι' => "i",
+        'θ' => "th",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' | 'ω' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        _ => return None,
+    };
+    Some(latin)
+}
+
+fn is_combining_mark(ch: char) -> bool {
+    matches!(ch, '\u{0300}'..='\u{036F}')
+}
+
+/// Lowercases `value` and strips accents so "Música" and "musica" compare
+/// equal; with transliteration enabled, non-Latin letters are spelled out.
+pub fn fold_for_match(value: &str) -> String {
+    let transliterate_enabled = TRANSLITERATE.load(Ordering::Relaxed);
+    let mut folded = String::with_capacity(value.len());
+    for ch in value.chars().flat_map(char::to_lowercase) {
+        if is_combining_mark(ch) {
+            continue;
+        }
+        let ch = fold_diacritic(ch);
+        match transliterate(ch).filter(|_| transliterate_enabled) {
+            Some(latin) => folded.push_str(latin),
+            None => folded.push(ch),
+        }
+    }
+    folded
+}
+
 fn normalize_for_match(value: &str, case_sensitive: bool) -> String {
     if case_sensitive {
         value.to_string()
     } else {
-        value.to_lowercase()
+        fold_for_match(value)
     }
 }
 
 fn compact_alnum(value: &str, case_sensitive: bool) -> String {
     if case_sensitive {
-        value
-            .chars()
-            .filter(|ch| ch.is_ascii_alphanumeric())
-            .collect()
+        value.chars().filter(|ch| ch.is_alphanumeric()).collect()
     } else {
-        value
+        fold_for_match(value)
             .chars()
-            .filter(|ch| ch.is_ascii_alphanumeric())
-            .flat_map(char::to_lowercase)
+            .filter(|ch| ch.is_alphanumeric())
             .collect()
     }
 }
 
-pub fn compact_lower_alnum(value: &str) -> String {
-    compact_alnum(value, false)
+/// Compact form of a string already passed through `fold_for_match`.
+pub fn compact_folded(folded: &str) -> String {
+    compact_alnum(folded, true)
 }
 
 fn length_penalty(candidate_len: usize, query_len: usize, cap: i64) -> i64 {
     ((candidate_len.saturating_sub(query_len)) as i64).min(cap)
 }
 
+fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{3040}'..='\u{30FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+    )
+}
+
+/// Splits `value` into words at non-alphanumeric chars and at transitions
+/// between CJK and other scripts, which rarely use spaces between them.
+fn word_tokens(value: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start: Option<(usize, bool)> = None;
+    for (index, ch) in value.char_indices() {
+        if !ch.is_alphanumeric() {
+            if let Some((token_start, _)) = start.take() {
+                tokens.push(&value[token_start..index]);
+            }
+            continue;
+        }
+        let cjk = is_cjk(ch);
+        match start {
+            Some((token_start, token_cjk)) if token_cjk != cjk => {
+                tokens.push(&value[token_start..index]);
+                start = Some((index, cjk));
+            }
+            Some(_) => {}
+            None => start = Some((index, cjk)),
+        }
+    }
+    if let Some((token_start, _)) = start {
+        tokens.push(&value[token_start..]);
+    }
+    tokens
+}
+
 fn token_prefix_score(candidate: &str, query: &str) -> Option<i64> {
-    let mut token_index: i64 = 0;
+    let query_len = query.chars().count();
 
-    for token in candidate
-        .split(|ch: char| !ch.is_ascii_alphanumeric())
-        .filter(|token| !token.is_empty())
-    {
+    for (token_index, token) in word_tokens(candidate).into_iter().enumerate() {
         if token.starts_with(query) {
-            let index_penalty = token_index * 18;
-            let token_len_penalty = length_penalty(token.len(), query.len(), 70);
+            let index_penalty = token_index as i64 * 18;
+            let token_len_penalty = length_penalty(token.chars().count(), query_len, 70);
             return Some(2300 - index_penalty - token_len_penalty);
         }
-        token_index += 1;
     }
 
     None
 }
 
+const ALIGN_SCORE_MATCH: i64 = 16;
+const ALIGN_GAP_START: i64 = -3;
+const ALIGN_GAP_EXTENSION: i64 = -1;
+const ALIGN_BONUS_BOUNDARY: i64 = 8;
+const ALIGN_BONUS_CAMEL: i64 = 7;
+const ALIGN_BONUS_CONSECUTIVE: i64 = 4;
+const ALIGN_BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+const ALIGN_MAX_CANDIDATE_CHARS: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+fn fold_char(ch: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        ch
+    } else {
+        fold_diacritic(ch.to_lowercase().next().unwrap_or(ch))
+    }
+}
+
+fn char_bonus(prev: Option<char>, current: char) -> i64 {
+    let Some(prev) = prev else {
+        return ALIGN_BONUS_BOUNDARY;
+    };
+    if !current.is_alphanumeric() {
+        return 0;
+    }
+    if !prev.is_alphanumeric() {
+        return ALIGN_BONUS_BOUNDARY;
+    }
+    if (prev.is_lowercase() && current.is_uppercase())
+        || (!prev.is_numeric() && current.is_numeric())
+    {
+        return ALIGN_BONUS_CAMEL;
+    }
+    0
+}
+
+/// Smith-Waterman style alignment of `query` against `candidate`: every query
+/// char must match in order; word boundaries, camelCase humps and consecutive
+/// runs earn bonuses while gaps between matches are penalized. Positions are
+/// char indices into `candidate`.
+pub fn fuzzy_align(query: &str, candidate: &str, case_sensitive: bool) -> Option<FuzzyMatch> {
+    let query_chars = query
+        .chars()
+        .map(|ch| fold_char(ch, case_sensitive))
+        .collect::<Vec<_>>();
+    let candidate_raw = candidate
+        .chars()
+        .take(ALIGN_MAX_CANDIDATE_CHARS)
+        .collect::<Vec<_>>();
+    let m = query_chars.len();
+    let n = candidate_raw.len();
+    if m == 0 || n < m {
+        return None;
+    }
+
+    let candidate_chars = candidate_raw
+        .iter()
+        .map(|ch| fold_char(*ch, case_sensitive))
+        .collect::<Vec<_>>();
+    let bonuses = candidate_raw
+        .iter()
+        .enumerate()
+        .map(|(j, ch)| char_bonus(j.checked_sub(1).map(|p| candidate_raw[p]), *ch))
+        .collect::<Vec<_>>();
+
+    const NONE: i64 = i64::MIN / 4;
+    // score[i][j]: best alignment of query[..=i] with query[i] placed at j.
+    // from[i][j]: column of query[i - 1] in that alignment.
+    let mut score = vec![NONE; m * n];
+    let mut run_bonus = vec![0i64; m * n];
+    let mut from = vec![usize::MAX; m * n];
+
+    for j in 0..n {
+        if candidate_chars[j] == query_chars[0] {
+            score[j] = ALIGN_SCORE_MATCH + bonuses[j] * ALIGN_BONUS_FIRST_CHAR_MULTIPLIER;
+            run_bonus[j] = bonuses[j];
+        }
+    }
+
+    for (i, query_char) in query_chars.iter().enumerate().skip(1) {
+        let row = i * n;
+        let prev_row = (i - 1) * n;
+        let mut gap_best = NONE;
+        let mut gap_from = usize::MAX;
+        for j in i..n {
+            if j >= 2 {
+                let extended = gap_best + ALIGN_GAP_EXTENSION;
+                let opened = score[prev_row + j - 2] + ALIGN_GAP_START;
+                if opened >= extended {
+                    gap_best = opened;
+                    gap_from = j - 2;
+                } else {
+                    gap_best = extended;
+                }
+            }
+            if candidate_chars[j] != *query_char {
+                continue;
+            }
+
+            let mut best = NONE;
+            let consecutive = score[prev_row + j - 1];
+            if consecutive > NONE {
+                let bonus = bonuses[j]
+                    .max(run_bonus[prev_row + j - 1])
+                    .max(ALIGN_BONUS_CONSECUTIVE);
+                best = consecutive + ALIGN_SCORE_MATCH + bonus;
+                from[row + j] = j - 1;
+                run_bonus[row + j] = bonus;
+            }
+            if gap_best > NONE / 2 {
+                let gapped = gap_best + ALIGN_SCORE_MATCH + bonuses[j];
+                if gapped > best {
+                    best = gapped;
+                    from[row + j] = gap_from;
+                    run_bonus[row + j] = bonuses[j];
+                }
+            }
+            score[row + j] = best;
+        }
+    }
+
+    let last_row = (m - 1) * n;
+    let (mut column, best) = (0..n)
+        .map(|j| (j, score[last_row + j]))
+        .filter(|(_, value)| *value > NONE / 2)
+        .max_by(|(left_j, left), (right_j, right)| left.cmp(right).then(right_j.cmp(left_j)))?;
+
+    let mut positions = vec![0usize; m];
+    for i in (0..m).rev() {
+        positions[i] = column;
+        if i > 0 {
+            column = from[i * n + column];
+        }
+    }
+
+    Some(FuzzyMatch {
+        score: best,
+        positions,
+    })
+}
+
+/// Char positions in `candidate` to highlight for `query`, or `None` when the
+/// query does not align.
+pub fn fuzzy_match_positions(
+    query: &str,
+    candidate: &str,
+    case_sensitive: bool,
+) -> Option<Vec<usize>> {
+    let query = query.trim();
+    if let Some(found) = fuzzy_align(query, candidate, case_sensitive) {
+        return Some(found.positions);
+    }
+
+    let compact = compact_alnum(query, case_sensitive);
+    if compact.is_empty() || compact == query {
+        return None;
+    }
+    fuzzy_align(&compact, candidate, case_sensitive).map(|found| found.positions)
+}
+
+fn subsequence_tier_score(
+    base: i64,
+    query_len: usize,
+    candidate_len: usize,
+    alignment: i64,
+) -> i64 {
+    let quality = alignment - ALIGN_SCORE_MATCH * query_len as i64;
+    let len_penalty = length_penalty(candidate_len, query_len, 60);
+    (base + quality * 4 - len_penalty).clamp(200, 980)
+}
+
 fn is_subsequence(query: &str, candidate: &str) -> bool {
     let mut candidate_chars = candidate.chars();
 
@@ -64,61 +403,201 @@ fn is_subsequence(query: &str, candidate: &str) -> bool {
     true
 }
 
+/// First letter of every word plus every camelCase hump and digit run, so
+/// "Visual Studio Code" gives "vsc" and "GitHubDesktop" gives "ghd".
+pub fn word_initials(value: &str, case_sensitive: bool) -> String {
+    let mut initials = String::new();
+    for token in word_tokens(value) {
+        let mut prev: Option<char> = None;
+        for ch in token.chars() {
+            let starts_hump = prev.is_none_or(|prev| {
+                (prev.is_lowercase() && ch.is_uppercase())
+                    || (!prev.is_numeric() && ch.is_numeric())
+            });
+            if starts_hump {
+                initials.push(ch);
+            }
+            prev = Some(ch);
+        }
+    }
+
+    if case_sensitive {
+        initials
+    } else {
+        fold_for_match(&initials)
+    }
+}
+
+fn initialism_score(candidate_initials: &str, query: &str) -> Option<i64> {
+    if query.chars().count() < 2 {
+        return None;
+    }
+    if candidate_initials == query {
+        return Some(2200);
+    }
+    if candidate_initials.starts_with(query) {
+        return Some(2100 - length_penalty(candidate_initials.len(), query.len(), 80));
+    }
+    let offset = candidate_initials.find(query)?;
+    let position_penalty = (offset as i64 * 20).min(80);
+    Some(1500 - position_penalty - length_penalty(candidate_initials.len(), query.len(), 60))
+}
+
+/// Tier of `fuzzy_score_core` that produced a match, reported by
+/// `--debug-ranking`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchTier {
+    Exact,
+    CompactExact,
+    Prefix,
+    CompactPrefix,
+    TokenPrefix,
+    Initialism,
+    Contains,
+    CompactContains,
+    Subsequence,
+    CompactSubsequence,
+}
+
+impl MatchTier {
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchTier::Exact => "exact",
+            MatchTier::CompactExact => "compact_exact",
+            MatchTier::Prefix => "prefix",
+            MatchTier::CompactPrefix => "compact_prefix",
+            MatchTier::TokenPrefix => "token_prefix",
+            MatchTier::Initialism => "initialism",
+            MatchTier::Contains => "contains",
+            MatchTier::CompactContains => "compact_contains",
+            MatchTier::Subsequence => "subsequence",
+            MatchTier::CompactSubsequence => "compact_subsequence",
+        }
+    }
+
+    /// Score of a perfect match in this tier, before length and position
+    /// penalties (subsequence tiers can also earn alignment bonuses).
+    pub fn base_score(self) -> i64 {
+        match self {
+            MatchTier::Exact => 3200,
+            MatchTier::CompactExact => 3000,
+            MatchTier::Prefix => 2600,
+            MatchTier::CompactPrefix => 2500,
+            MatchTier::TokenPrefix => 2300,
+            MatchTier::Initialism => 2200,
+            MatchTier::Contains => 1400,
+            MatchTier::CompactContains => 1250,
+            MatchTier::Subsequence => 700,
+            MatchTier::CompactSubsequence => 650,
+        }
+    }
+}
+
 fn fuzzy_score_core(
     query_norm: &str,
     query_compact: &str,
     candidate_norm: &str,
     candidate_compact: &str,
+    candidate_initials: &str,
 ) -> i64 {
+    fuzzy_tier_core(
+        query_norm,
+        query_compact,
+        candidate_norm,
+        candidate_compact,
+        candidate_initials,
+    )
+    .map_or(0, |(_, score)| score)
+}
+
+fn fuzzy_tier_core(
+    query_norm: &str,
+    query_compact: &str,
+    candidate_norm: &str,
+    candidate_compact: &str,
+    candidate_initials: &str,
+) -> Option<(MatchTier, i64)> {
     if query_norm.is_empty() || candidate_norm.is_empty() {
-        return 0;
+        return None;
     }
 
     if query_norm == candidate_norm {
-        return 3200;
+        return Some((MatchTier::Exact, MatchTier::Exact.base_score()));
     }
 
     if !query_compact.is_empty() && query_compact == candidate_compact {
-        return 3000;
+        return Some((
+            MatchTier::CompactExact,
+            MatchTier::CompactExact.base_score(),
+        ));
     }
 
     if candidate_norm.starts_with(query_norm) {
-        return 2600 - length_penalty(candidate_norm.len(), query_norm.len(), 200);
+        let penalty = length_penalty(candidate_norm.len(), query_norm.len(), 200);
+        return Some((MatchTier::Prefix, MatchTier::Prefix.base_score() - penalty));
     }
 
     if !query_compact.is_empty() && candidate_compact.starts_with(query_compact) {
-        return 2500 - length_penalty(candidate_compact.len(), query_compact.len(), 180);
+        let penalty = length_penalty(candidate_compact.len(), query_compact.len(), 180);
+        return Some((
+            MatchTier::CompactPrefix,
+            MatchTier::CompactPrefix.base_score() - penalty,
+        ));
     }
 
     if let Some(score) = token_prefix_score(candidate_norm, query_norm) {
-        return score;
+        return Some((MatchTier::TokenPrefix, score));
+    }
+
+    if let Some(score) = initialism_score(candidate_initials, query_norm) {
+        return Some((MatchTier::Initialism, score));
     }
 
     if candidate_norm.contains(query_norm) {
         let start_index = candidate_norm.find(query_norm).unwrap_or(usize::MAX);
         let position_penalty = (start_index as i64).min(160);
         let len_penalty = length_penalty(candidate_norm.len(), query_norm.len(), 120);
-        return 1400 - position_penalty - len_penalty;
+        return Some((
+            MatchTier::Contains,
+            MatchTier::Contains.base_score() - position_penalty - len_penalty,
+        ));
     }
 
     if !query_compact.is_empty() && candidate_compact.contains(query_compact) {
         let start_index = candidate_compact.find(query_compact).unwrap_or(usize::MAX);
         let position_penalty = (start_index as i64).min(140);
         let len_penalty = length_penalty(candidate_compact.len(), query_compact.len(), 110);
-        return 1250 - position_penalty - len_penalty;
+        return Some((
+            MatchTier::CompactContains,
+            MatchTier::CompactContains.base_score() - position_penalty - len_penalty,
+        ));
     }
 
     if is_subsequence(query_norm, candidate_norm) {
-        let len_penalty = length_penalty(candidate_norm.len(), query_norm.len(), 180);
-        return 700 - len_penalty;
+        if let Some(found) = fuzzy_align(query_norm, candidate_norm, true) {
+            let score = subsequence_tier_score(
+                MatchTier::Subsequence.base_score(),
+                query_norm.chars().count(),
+                candidate_norm.chars().count(),
+                found.score,
+            );
+            return Some((MatchTier::Subsequence, score));
+        }
     }
 
     if !query_compact.is_empty() && is_subsequence(query_compact, candidate_compact) {
-        let len_penalty = length_penalty(candidate_compact.len(), query_compact.len(), 170);
-        return 650 - len_penalty;
+        if let Some(found) = fuzzy_align(query_compact, candidate_norm, true) {
+            let score = subsequence_tier_score(
+                MatchTier::CompactSubsequence.base_score(),
+                query_compact.chars().count(),
+                candidate_norm.chars().count(),
+                found.score,
+            );
+            return Some((MatchTier::CompactSubsequence, score));
+        }
     }
 
-    0
+    None
 }
 
 pub fn fuzzy_score_precomputed_lower(
@@ -126,28 +605,317 @@ pub fn fuzzy_score_precomputed_lower(
     query_compact: &str,
     candidate_norm: &str,
     candidate_compact: &str,
+    candidate_initials: &str,
 ) -> i64 {
-    fuzzy_score_core(query_norm, query_compact, candidate_norm, candidate_compact)
+    fuzzy_score_core(
+        query_norm,
+        query_compact,
+        candidate_norm,
+        candidate_compact,
+        candidate_initials,
+    )
+}
+
+pub fn fuzzy_match_tier_precomputed_lower(
+    query_norm: &str,
+    query_compact: &str,
+    candidate_norm: &str,
+    candidate_compact: &str,
+    candidate_initials: &str,
+) -> Option<(MatchTier, i64)> {
+    fuzzy_tier_core(
+        query_norm,
+        query_compact,
+        candidate_norm,
+        candidate_compact,
+        candidate_initials,
+    )
+}
+
+const TYPO_MIN_QUERY_CHARS: usize = 3;
+const TYPO_MAX_QUERY_CHARS: usize = 32;
+pub const TYPO_TIER_BASE: i64 = 180;
+const TYPO_DISTANCE_PENALTY: i64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypoMatch {
+    pub distance: usize,
+    pub score: i64,
+}
+
+fn max_typo_distance(query_len: usize) -> usize {
+    if query_len < TYPO_MIN_QUERY_CHARS {
+        0
+    } else if query_len <= 4 {
+        1
+    } else {
+        2
+    }
+}
+
+/// Optimal string alignment distance (Damerau-Levenshtein with adjacent
+/// transpositions) between `query` and the closest prefix of `token`, or
+/// `None` once it exceeds `max_distance`.
+fn prefix_edit_distance(query: &[char], token: &[char], max_distance: usize) -> Option<usize> {
+    let m = query.len();
+    let token = &token[..token.len().min(m + max_distance)];
+    let n = token.len();
+    if n + max_distance < m {
+        return None;
+    }
+
+    let mut before_prev: Vec<usize> = Vec::new();
+    let mut prev: Vec<usize> = (0..=n).collect();
+    for i in 1..=m {
+        let mut row = vec![i; n + 1];
+        for j in 1..=n {
+            let cost = usize::from(query[i - 1] != token[j - 1]);
+            let mut best = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && query[i - 1] == token[j - 2] && query[i - 2] == token[j - 1] {
+                best = best.min(before_prev[j - 2] + 1);
+            }
+            row[j] = best;
+        }
+        if row.iter().min().is_some_and(|best| *best > max_distance) {
+            return None;
+        }
+        before_prev = std::mem::replace(&mut prev, row);
+    }
+
+    let first = m.saturating_sub(max_distance);
+    prev[first.min(n)..]
+        .iter()
+        .copied()
+        .min()
+        .filter(|distance| *distance <= max_distance)
+}
+
+/// Typo-tolerant tier for queries that are not a subsequence of `candidate_norm`:
+/// the best bounded edit distance against any word prefix, scored below every
+/// subsequence match. Both inputs must already be normalized.
+pub fn typo_match(query_norm: &str, candidate_norm: &str) -> Option<TypoMatch> {
+    let query = query_norm.chars().collect::<Vec<_>>();
+    if query.len() > TYPO_MAX_QUERY_CHARS {
+        return None;
+    }
+    let max_distance = max_typo_distance(query.len());
+    if max_distance == 0 {
+        return None;
+    }
+
+    word_tokens(candidate_norm)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(token_index, token)| {
+            let token = token.chars().collect::<Vec<_>>();
+            let distance = prefix_edit_distance(&query, &token, max_distance)?;
+            let index_penalty = (token_index as i64 * 6).min(30);
+            Some(TypoMatch {
+                distance,
+                score: TYPO_TIER_BASE
+                    - (distance as i64 - 1) * TYPO_DISTANCE_PENALTY
+                    - index_penalty,
+            })
+        })
+        .max_by_key(|found| found.score)
 }
 
 pub fn fuzzy_score(query: &str, candidate: &str, case_sensitive: bool) -> i64 {
+    fuzzy_match_tier(query, candidate, case_sensitive).map_or(0, |(_, score)| score)
+}
+
+pub fn fuzzy_match_tier(
+    query: &str,
+    candidate: &str,
+    case_sensitive: bool,
+) -> Option<(MatchTier, i64)> {
     let query_norm = normalize_for_match(query, case_sensitive);
     let candidate_norm = normalize_for_match(candidate, case_sensitive);
 
     let query_compact = compact_alnum(&query_norm, true);
     let candidate_compact = compact_alnum(&candidate_norm, true);
+    let candidate_initials = word_initials(candidate, case_sensitive);
 
-    fuzzy_score_core(
+    fuzzy_tier_core(
         &query_norm,
         &query_compact,
         &candidate_norm,
         &candidate_compact,
+        &candidate_initials,
     )
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryTermKind {
+    Fuzzy,
+    Exact,
+    Prefix,
+    Suffix,
+    Equal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTerm {
+    pub kind: QueryTermKind,
+    pub negated: bool,
+    pub raw: String,
+    pub text: String,
+    pub compact: String,
+}
+
+/// A query split fzf-style into AND-ed groups of `|` alternatives.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedQuery {
+    pub groups: Vec<Vec<QueryTerm>>,
+}
+
+fn parse_query_term(token: &str, case_sensitive: bool) -> Option<QueryTerm> {
+    let mut body = token;
+    let negated = body.starts_with('!');
+    if negated {
+        body = &body[1..];
+    }
+
+    let kind = if let Some(rest) = body.strip_prefix('\'') {
+        body = rest;
+        QueryTermKind::Exact
+    } else {
+        let prefix = body.starts_with('^');
+        let suffix = body.len() > usize::from(prefix) && body.ends_with('$');
+        if prefix {
+            body = &body[1..];
+        }
+        if suffix {
+            body = &body[..body.len() - 1];
+        }
+        match (prefix, suffix) {
+            (true, true) => QueryTermKind::Equal,
+            (true, false) => QueryTermKind::Prefix,
+            (false, true) => QueryTermKind::Suffix,
+            (false, false) if negated => QueryTermKind::Exact,
+            (false, false) => QueryTermKind::Fuzzy,
+        }
+    };
+
+    if body.is_empty() {
+        return None;
+    }
+    let text = normalize_for_match(body, case_sensitive);
+    let compact = compact_alnum(&text, true);
+    Some(QueryTerm {
+        kind,
+        negated,
+        raw: token.to_string(),
+        text,
+        compact,
+    })
+}
+
+impl ParsedQuery {
+    pub fn parse(query: &str, case_sensitive: bool) -> Self {
+        let mut groups: Vec<Vec<QueryTerm>> = Vec::new();
+        let mut join_next = false;
+
+        for token in query.split_whitespace() {
+            if token == "|" {
+                join_next = !groups.is_empty();
+                continue;
+            }
+            let Some(term) = parse_query_term(token, case_sensitive) else {
+                continue;
+            };
+            match groups.last_mut() {
+                Some(group) if join_next => group.push(term),
+                _ => groups.push(vec![term]),
+            }
+            join_next = false;
+        }
+
+        Self { groups }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// True when the query is one plain fuzzy term, which ranks exactly like
+    /// the classic single-string matcher.
+    pub fn single_fuzzy_term(&self) -> Option<&QueryTerm> {
+        match self.groups.as_slice() {
+            [group] => match group.as_slice() {
+                [term] if term.kind == QueryTermKind::Fuzzy && !term.negated => Some(term),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn terms(&self) -> impl Iterator<Item = &QueryTerm> {
+        self.groups.iter().flatten()
+    }
+}
+
+/// Literal (non-fuzzy) match of `term` against an already normalized field,
+/// returning a tiered score comparable to `fuzzy_score_core`.
+pub fn literal_term_score(term: &QueryTerm, field_norm: &str, field_compact: &str) -> i64 {
+    let matched = match term.kind {
+        QueryTermKind::Fuzzy | QueryTermKind::Exact => field_norm.contains(&term.text),
+        QueryTermKind::Prefix => field_norm.starts_with(&term.text),
+        QueryTermKind::Suffix => field_norm.ends_with(&term.text),
+        QueryTermKind::Equal => field_norm == term.text,
+    };
+    if !matched {
+        return 0;
+    }
+    fuzzy_score_core(&term.text, &term.compact, field_norm, field_compact, "").max(1)
+}
+
+fn literal_term_positions(term: &QueryTerm, text_norm: &str) -> Option<Vec<usize>> {
+    let byte_start = match term.kind {
+        QueryTermKind::Fuzzy | QueryTermKind::Exact => text_norm.find(&term.text)?,
+        QueryTermKind::Prefix | QueryTermKind::Equal => {
+            text_norm.starts_with(&term.text).then_some(0)?
+        }
+        QueryTermKind::Suffix => {
+            if !text_norm.ends_with(&term.text) {
+                return None;
+            }
+            text_norm.len() - term.text.len()
+        }
+    };
+    let char_start = text_norm[..byte_start].chars().count();
+    Some((char_start..char_start + term.text.chars().count()).collect())
+}
+
+/// Char positions in `text` matched by the positive terms of `query`.
+pub fn query_match_positions(query: &ParsedQuery, text: &str, case_sensitive: bool) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let text_norm = normalize_for_match(text, case_sensitive);
+    let same_shape = text_norm.chars().count() == text.chars().count();
+    let mut positions = Vec::new();
+
+    for term in query.terms().filter(|term| !term.negated) {
+        let found = match term.kind {
+            QueryTermKind::Fuzzy => fuzzy_match_positions(&term.text, text, case_sensitive),
+            _ if same_shape => literal_term_positions(term, &text_norm),
+            _ => None,
+        };
+        positions.extend(found.unwrap_or_default());
+    }
+
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
 #[cfg(test)]
 mod tests {
-    use super::fuzzy_score;
+    use super::{
+        fold_for_match, fuzzy_align, fuzzy_match_positions, fuzzy_score, query_match_positions,
+        transliterate, typo_match, word_initials, word_tokens, ParsedQuery, QueryTermKind,
+    };
 
     #[test]
     fn exact_scores_higher_than_contains() {
@@ -174,4 +942,146 @@ mod tests {
         let contains = fuzzy_score("power", "x_superpower_tool", false);
         assert!(token_prefix > contains);
     }
+
+    #[test]
+    fn alignment_prefers_word_boundaries_and_consecutive_runs() {
+        let found = fuzzy_align("vsc", "Visual Studio Code", false).expect("should align");
+        assert_eq!(found.positions, [0, 7, 14]);
+
+        let found = fuzzy_align("fb", "FooBar", false).expect("camelCase should align");
+        assert_eq!(found.positions, [0, 3]);
+
+        let found = fuzzy_align("pad", "paint padding", false).expect("should align");
+        assert_eq!(found.positions, [6, 7, 8]);
+
+        let boundaries = fuzzy_score("vsc", "visual studio code", false);
+        let scattered = fuzzy_score("vsc", "obvious fiscal", false);
+        assert!(boundaries > scattered);
+        assert!(fuzzy_align("zz", "notepad", false).is_none());
+    }
+
+    #[test]
+    fn match_positions_cover_contiguous_and_compact_queries() {
+        assert_eq!(
+            fuzzy_match_positions("note", "Notepad++", false),
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq!(
+            fuzzy_match_positions("7 zip", "7-Zip", false),
+            Some(vec![0, 2, 3, 4])
+        );
+        assert_eq!(fuzzy_match_positions("xyz", "Notepad", false), None);
+    }
+
+    #[test]
+    fn query_parser_splits_tokens_operators_and_alternatives() {
+        let parsed = ParsedQuery::parse("Code 'proj ^vs ui$ !test | ^pre$ x", false);
+        let shape = parsed
+            .groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|term| (term.kind, term.negated, term.text.as_str()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shape,
+            vec![
+                vec![(QueryTermKind::Fuzzy, false, "code")],
+                vec![(QueryTermKind::Exact, false, "proj")],
+                vec![(QueryTermKind::Prefix, false, "vs")],
+                vec![(QueryTermKind::Suffix, false, "ui")],
+                vec![
+                    (QueryTermKind::Exact, true, "test"),
+                    (QueryTermKind::Equal, false, "pre"),
+                ],
+                vec![(QueryTermKind::Fuzzy, false, "x")],
+            ]
+        );
+
+        assert!(ParsedQuery::parse("  ' ^ ! ", false).is_empty());
+        assert!(ParsedQuery::parse("note", false)
+            .single_fuzzy_term()
+            .is_some());
+        assert!(ParsedQuery::parse("'note", false)
+            .single_fuzzy_term()
+            .is_none());
+    }
+
+    #[test]
+    fn query_positions_union_every_positive_term() {
+        let parsed = ParsedQuery::parse("vis code$ !x", false);
+        assert_eq!(
+            query_match_positions(&parsed, "Visual Studio Code", false),
+            vec![0, 1, 2, 14, 15, 16, 17]
+        );
+    }
+
+    #[test]
+    fn accents_are_folded_for_case_insensitive_matching() {
+        assert_eq!(fold_for_match("Configuración"), "configuracion");
+        assert_eq!(fold_for_match("Cafe\u{301} Ŀøłå"), "cafe lola");
+        assert_eq!(fuzzy_score("configuracion", "Configuración", false), 3200);
+        assert!(fuzzy_score("musica", "Reproductor de Música", false) > 2000);
+        assert_eq!(fuzzy_score("musica", "Música", true), 0);
+        assert_eq!(
+            fuzzy_match_positions("mus", "Música", false),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(transliterate('ж'), Some("zh"));
+        assert_eq!(transliterate('x'), None);
+    }
+
+    #[test]
+    fn unicode_words_split_at_separators_and_cjk_runs() {
+        assert_eq!(
+            word_tokens("Éditeur_vidéo 2"),
+            vec!["Éditeur", "vidéo", "2"]
+        );
+        assert_eq!(word_tokens("VLC播放器"), vec!["VLC", "播放器"]);
+        assert!(fuzzy_score("播放", "VLC播放器", false) > 2000);
+        assert!(fuzzy_score("edit", "Mon Éditeur", false) > 2000);
+    }
+
+    #[test]
+    fn typo_tier_tolerates_transpositions_and_small_edits() {
+        assert_eq!(fuzzy_score("ntoepad", "notepad", false), 0);
+        let transposed = typo_match("ntoepad", "notepad").expect("one transposition");
+        assert_eq!(transposed.distance, 1);
+        let prefix = typo_match("chrmo", "google chrome").expect("typo in word prefix");
+        assert_eq!(prefix.distance, 1);
+        assert!(prefix.score < transposed.score);
+        assert_eq!(
+            typo_match("chrmoe", "chrome").map(|found| found.distance),
+            Some(1)
+        );
+        assert_eq!(
+            typo_match("notpda", "notepad").map(|found| found.distance),
+            Some(2)
+        );
+
+        assert!(typo_match("xyzzy", "notepad").is_none());
+        assert!(typo_match("nt", "notepad").is_none());
+        assert!(typo_match("ntoe", "notepad").is_some());
+        assert!(typo_match("ntxy", "notepad").is_none());
+        assert!(transposed.score > 0 && transposed.score < 200);
+    }
+
+    #[test]
+    fn initialisms_match_word_starts_and_camel_case_humps() {
+        assert_eq!(word_initials("Visual Studio Code", false), "vsc");
+        assert_eq!(word_initials("GitHubDesktop", false), "ghd");
+        assert_eq!(word_initials("7-Zip File Manager", false), "7zfm");
+        assert_eq!(word_initials("Win10 Tools", true), "W1T");
+
+        let initialism = fuzzy_score("vsc", "Visual Studio Code", false);
+        assert!(initialism > fuzzy_score("vsc", "obvious fiscal", false));
+        assert!(initialism > fuzzy_score("vsc", "Services Console", false));
+        assert!(
+            fuzzy_score("gh", "GitHub Desktop", false) > fuzzy_score("gh", "Lighthouse", false)
+        );
+        assert!(fuzzy_score("sc", "Visual Studio Code", false) > 1400);
+    }
 }
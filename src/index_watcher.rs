@@ -0,0 +1,124 @@
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{BOOL, HANDLE, WAIT_FAILED, WAIT_TIMEOUT};
+use windows::Win32::Storage::FileSystem::{
+    FindChangeNotificationHandle, FindCloseChangeNotification, FindFirstChangeNotificationW,
+    FindNextChangeNotification, FILE_NOTIFY_CHANGE, FILE_NOTIFY_CHANGE_DIR_NAME,
+    FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE,
+};
+use windows::Win32::System::Threading::WaitForMultipleObjects;
+
+use crate::app_state::LauncherItem;
+use crate::rules::RankingRulesFile;
+use crate::settings::LauncherConfig;
+use crate::sources::{index_roots, load_launcher_items};
+
+// WaitForMultipleObjects accepts at most MAXIMUM_WAIT_OBJECTS handles; roots
+// past that are still picked up by the periodic refresh.
+const MAX_WATCHED_ROOTS: usize = 64;
+const SETTLE_DELAY: Duration = Duration::from_millis(750);
+const PERIODIC_REFRESH_MS: u32 = 10 * 60 * 1000;
+
+/// Background watcher that keeps the daemon's warm launcher items in sync
+/// with the index roots and `rules.txt`. Changes are coalesced and refreshed
+/// through the per-directory index cache, so only directories that changed are
+/// listed.
+pub struct IndexWatcher {
+    updates: Receiver<Vec<LauncherItem>>,
+}
+
+impl IndexWatcher {
+    pub fn spawn(config: LauncherConfig, rules: RankingRulesFile) -> Self {
+        let (sender, updates) = mpsc::channel();
+        thread::spawn(move || watch_index_roots(&config, rules, &sender));
+        Self { updates }
+    }
+
+    /// Most recent item list published since the last call, if any.
+    pub fn latest_items(&self) -> Option<Vec<LauncherItem>> {
+        self.updates.try_iter().last()
+    }
+}
+
+fn to_wstring(value: &str) -> Vec<u16> {
+    value.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn watch_directory(
+    path: &Path,
+    recursive: bool,
+    filter: FILE_NOTIFY_CHANGE,
+) -> Option<FindChangeNotificationHandle> {
+    let path = to_wstring(&path.to_string_lossy());
+    unsafe { FindFirstChangeNotificationW(PCWSTR(path.as_ptr()), BOOL::from(recursive), filter) }
+        .ok()
+}
+
+fn watch_index_roots(
+    config: &LauncherConfig,
+    mut rules: RankingRulesFile,
+    sender: &Sender<Vec<LauncherItem>>,
+) {
+    // The rules folder goes first so it is watched even when the roots fill
+    // the handle limit.
+    let rules_notification = rules.path().parent().and_then(|dir| {
+        watch_directory(
+            dir,
+            false,
+            FILE_NOTIFY_CHANGE_FILE_NAME | FILE_NOTIFY_CHANGE_LAST_WRITE,
+        )
+    });
+    let notifications = rules_notification
+        .into_iter()
+        .chain(index_roots(config).into_iter().filter_map(|root| {
+            watch_directory(
+                &root.path,
+                root.max_depth > 0,
+                FILE_NOTIFY_CHANGE_FILE_NAME | FILE_NOTIFY_CHANGE_DIR_NAME,
+            )
+        }))
+        .take(MAX_WATCHED_ROOTS)
+        .collect::<Vec<FindChangeNotificationHandle>>();
+    let handles = notifications
+        .iter()
+        .map(|notification| HANDLE(notification.0))
+        .collect::<Vec<_>>();
+
+    loop {
+        let waited = if handles.is_empty() {
+            WAIT_FAILED
+        } else {
+            unsafe { WaitForMultipleObjects(&handles, false, PERIODIC_REFRESH_MS) }
+        };
+        match waited {
+            WAIT_TIMEOUT => {}
+            WAIT_FAILED => thread::sleep(Duration::from_millis(PERIODIC_REFRESH_MS as u64)),
+            _ => {
+                // Installers touch many files at once; let them finish before
+                // listing the changed directories.
+                thread::sleep(SETTLE_DELAY);
+                for notification in &notifications {
+                    unsafe {
+                        FindNextChangeNotification(*notification);
+                    }
+                }
+            }
+        }
+
+        rules.reload_if_changed(true);
+        let items = load_launcher_items(config, rules.rules(), true, false);
+        if sender.send(items).is_err() {
+            break;
+        }
+    }
+
+    for notification in notifications {
+        unsafe {
+            FindCloseChangeNotification(notification);
+        }
+    }
+}
@@ -1,72 +1,53 @@
 use std::io;
+#[cfg(windows)]
 use std::iter::once;
+#[cfg(windows)]
 use std::os::windows::ffi::OsStrExt;
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 use std::path::Path;
 use std::process::{Command, Stdio};
+#[cfg(windows)]
+use std::thread;
+#[cfg(windows)]
+use std::time::Duration;
 
 use crate::rsnip_companion::{RsnipCommand, RsnipCompanion, RsnipIpcResponse};
 use crate::rtasks_companion::{RtasksCommand, RtasksCompanion, RtasksIpcResponse};
+use crate::schemes::SchemeRegistry;
+#[cfg(windows)]
 use windows::core::PCWSTR;
+#[cfg(windows)]
+use windows::Win32::Foundation::{ERROR_SUCCESS, HANDLE, HWND};
+#[cfg(windows)]
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+};
+#[cfg(windows)]
+use windows::Win32::System::Memory::{
+    GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE,
+};
+#[cfg(windows)]
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegOpenKeyExW, RegQueryValueExW, HKEY, HKEY_CLASSES_ROOT, KEY_QUERY_VALUE,
+};
+#[cfg(windows)]
 use windows::Win32::UI::Shell::ShellExecuteW;
+#[cfg(windows)]
 use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
 
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+#[cfg(windows)]
+const CF_UNICODETEXT: u32 = 13;
+#[cfg(windows)]
+const CLIPBOARD_OPEN_ATTEMPTS: u32 = 5;
 
+#[cfg(windows)]
 pub fn centered_text_y(row_top: i32, row_height: i32, font_size: i32) -> i32 {
     row_top + ((row_height - font_size).max(0) / 2)
 }
 
-pub fn abbreviate_target(target: &str, max_len: usize) -> String {
-    if target.len() <= max_len {
-        return target.to_string();
-    }
-
-    let keep = max_len.saturating_sub(3);
-    let tail = target
-        .chars()
-        .rev()
-        .take(keep)
-        .collect::<Vec<char>>()
-        .into_iter()
-        .rev()
-        .collect::<String>();
-    format!("...{}", tail)
-}
-
-pub fn truncate_with_ellipsis_end(value: &str, max_chars: usize) -> String {
-    let count = value.chars().count();
-    if count <= max_chars {
-        return value.to_string();
-    }
-    if max_chars <= 3 {
-        return ".".repeat(max_chars);
-    }
-
-    let keep = max_chars - 3;
-    let prefix: String = value.chars().take(keep).collect();
-    format!("{}...", prefix)
-}
-
-pub fn compact_target_hint(target: &str) -> String {
-    let path = Path::new(target);
-
-    if let Some(file_name) = path.file_name().and_then(|value| value.to_str()) {
-        if let Some(parent_name) = path
-            .parent()
-            .and_then(|parent| parent.file_name())
-            .and_then(|value| value.to_str())
-        {
-            return format!("\\{}\\{}", parent_name, file_name);
-        }
-        return file_name.to_string();
-    }
-
-    abbreviate_target(target, 44)
-}
-
 fn split_executable_and_args(raw: &str) -> (&str, Option<&str>) {
     let input = raw.trim();
     if input.is_empty() {
@@ -95,6 +76,7 @@ fn split_executable_and_args(raw: &str) -> (&str, Option<&str>) {
     (input, None)
 }
 
+#[cfg(windows)]
 fn to_wstring(value: &str) -> Vec<u16> {
     std::ffi::OsStr::new(value)
         .encode_wide()
@@ -114,6 +96,7 @@ fn looks_like_path(value: &str) -> bool {
     value.contains('\\') || value.contains('/') || (value.len() >= 2 && value.as_bytes()[1] == b':')
 }
 
+#[cfg(windows)]
 fn should_fallback_to_cmd(raw_target: &str, file_part: &str) -> bool {
     if looks_like_url(file_part) || looks_like_path(file_part) {
         return false;
@@ -128,6 +111,7 @@ fn should_fallback_to_cmd(raw_target: &str, file_part: &str) -> bool {
         || lowered.starts_with("cmd.exe ")
 }
 
+#[cfg(windows)]
 fn launch_with_shell_execute(verb: &str, file: &str, args: Option<&str>) -> io::Result<()> {
     let operation = to_wstring(verb);
     let file_w = to_wstring(file);
@@ -161,6 +145,7 @@ fn launch_with_shell_execute(verb: &str, file: &str, args: Option<&str>) -> io::
     }
 }
 
+#[cfg(windows)]
 fn launch_with_cmd_start(raw_target: &str) -> io::Result<()> {
     Command::new("cmd")
         .arg("/C")
@@ -234,14 +219,12 @@ fn launch_rsnip_command(command: &str) -> io::Result<()> {
             format!("rsnip companion not available: {err:?}"),
         )
     })?;
-    match companion.ensure_and_send(command).map_err(|err| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("rsnip companion command failed: {err:?}"),
-        )
-    })? {
+    match companion
+        .ensure_and_send(command)
+        .map_err(|err| io::Error::other(format!("rsnip companion command failed: {err:?}")))?
+    {
         RsnipIpcResponse::Ok { .. } => Ok(()),
-        RsnipIpcResponse::Error { message } => Err(io::Error::new(io::ErrorKind::Other, message)),
+        RsnipIpcResponse::Error { message } => Err(io::Error::other(message)),
     }
 }
 
@@ -262,38 +245,125 @@ fn launch_rtasks_command(command: &str) -> io::Result<()> {
             format!("rtasks companion not available: {err:?}"),
         )
     })?;
-    match companion.ensure_and_send(command).map_err(|err| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("rtasks companion command failed: {err:?}"),
-        )
-    })? {
+    match companion
+        .ensure_and_send(command)
+        .map_err(|err| io::Error::other(format!("rtasks companion command failed: {err:?}")))?
+    {
         RtasksIpcResponse::Ok { .. } => Ok(()),
-        RtasksIpcResponse::Error { message } => Err(io::Error::new(io::ErrorKind::Other, message)),
+        RtasksIpcResponse::Error { message } => Err(io::Error::other(message)),
     }
 }
 
-fn launch_verb_and_target(target: &str) -> (&'static str, &str) {
-    let raw_target = target.trim();
-    raw_target
-        .strip_prefix("runas:")
-        .map_or(("open", raw_target), |elevated| ("runas", elevated.trim()))
+/// Registers handlers for `schemes::BUILTIN_SCHEMES` and the OS protocol
+/// probe. `mailto:`, `shell:`, and `ms-settings:` targets go to the shell
+/// unchanged.
+pub fn register_builtin_schemes(registry: &mut SchemeRegistry) {
+    registry.register("copy", copy_text_to_clipboard);
+    registry.register("rsnip", |command: &str| {
+        launch_rsnip_command(command.trim())
+    });
+    registry.register("rtasks", |command: &str| {
+        launch_rtasks_command(command.trim())
+    });
+    registry.register("hidden", |target: &str| launch_hidden_target(target.trim()));
+    registry.register("runas", |target: &str| launch_with_verb("runas", target));
+    for scheme in ["mailto", "shell", "ms-settings"] {
+        registry.register(scheme, move |rest: &str| {
+            launch_with_verb("open", &format!("{scheme}:{rest}"))
+        });
+    }
+    registry.set_os_protocol_probe(is_registered_url_protocol);
 }
 
-pub fn launch_target(target: &str) -> io::Result<()> {
-    if let Some(command) = target.trim().strip_prefix("rsnip:") {
-        return launch_rsnip_command(command.trim());
+/// True when `HKCR\<scheme>` carries a `URL Protocol` value, i.e. an installed
+/// app handles `<scheme>:` targets (`ms-windows-store:`, `zoommtg:`, ...).
+#[cfg(windows)]
+fn is_registered_url_protocol(scheme: &str) -> bool {
+    let key_name = to_wstring(scheme);
+    let value_name = to_wstring("URL Protocol");
+    let mut key = HKEY::default();
+    let status = unsafe {
+        RegOpenKeyExW(
+            HKEY_CLASSES_ROOT,
+            PCWSTR(key_name.as_ptr()),
+            0,
+            KEY_QUERY_VALUE,
+            &mut key,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return false;
     }
+    let status =
+        unsafe { RegQueryValueExW(key, PCWSTR(value_name.as_ptr()), None, None, None, None) };
+    let _ = unsafe { RegCloseKey(key) };
+    status == ERROR_SUCCESS
+}
 
-    if let Some(command) = target.trim().strip_prefix("rtasks:") {
-        return launch_rtasks_command(command.trim());
+/// True when xdg-mime knows a default handler for `x-scheme-handler/<scheme>`.
+#[cfg(not(windows))]
+fn is_registered_url_protocol(scheme: &str) -> bool {
+    Command::new("xdg-mime")
+        .args(["query", "default", &format!("x-scheme-handler/{scheme}")])
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| output.status.success() && !output.stdout.trim_ascii().is_empty())
+        .unwrap_or(false)
+}
+
+#[cfg(windows)]
+pub fn copy_text_to_clipboard(text: &str) -> io::Result<()> {
+    let wide = text.encode_utf16().chain(once(0)).collect::<Vec<u16>>();
+
+    let mut opened = false;
+    for attempt in 0..CLIPBOARD_OPEN_ATTEMPTS {
+        if attempt > 0 {
+            thread::sleep(Duration::from_millis(20));
+        }
+        if unsafe { OpenClipboard(HWND(0)) }.as_bool() {
+            opened = true;
+            break;
+        }
+    }
+    if !opened {
+        return Err(io::Error::other("clipboard is busy in another application"));
     }
 
-    if let Some(hidden_target) = target.trim().strip_prefix("hidden:") {
-        return launch_hidden_target(hidden_target.trim());
+    let result = unsafe { write_clipboard_unicode(&wide) };
+    unsafe {
+        CloseClipboard();
     }
+    result
+}
 
-    let (verb, raw_target) = launch_verb_and_target(target);
+#[cfg(windows)]
+unsafe fn write_clipboard_unicode(wide: &[u16]) -> io::Result<()> {
+    EmptyClipboard();
+    let handle = GlobalAlloc(GMEM_MOVEABLE, std::mem::size_of_val(wide))
+        .map_err(|err| io::Error::other(format!("clipboard allocation failed: {err}")))?;
+
+    let buffer = GlobalLock(handle) as *mut u16;
+    if buffer.is_null() {
+        let _ = GlobalFree(handle);
+        return Err(io::Error::other("clipboard buffer could not be locked"));
+    }
+    std::ptr::copy_nonoverlapping(wide.as_ptr(), buffer, wide.len());
+    GlobalUnlock(handle);
+
+    if let Err(err) = SetClipboardData(CF_UNICODETEXT, HANDLE(handle.0)) {
+        let _ = GlobalFree(handle);
+        return Err(io::Error::other(format!("clipboard write failed: {err}")));
+    }
+    Ok(())
+}
+
+pub fn launch_target(schemes: &SchemeRegistry, target: &str) -> io::Result<()> {
+    schemes.launch(target, |target| launch_with_verb("open", target))
+}
+
+#[cfg(windows)]
+fn launch_with_verb(verb: &str, target: &str) -> io::Result<()> {
+    let raw_target = target.trim();
     if raw_target.is_empty() {
         return Err(io::Error::new(
             io::ErrorKind::InvalidInput,
@@ -331,12 +401,119 @@ pub fn launch_target(target: &str) -> io::Result<()> {
     }
 }
 
+/// Pipes `text` into the first clipboard tool found on `PATH`.
+#[cfg(not(windows))]
+pub fn copy_text_to_clipboard(text: &str) -> io::Result<()> {
+    use std::io::Write;
+
+    const CLIPBOARD_TOOLS: [&[&str]; 4] = [
+        &["wl-copy"],
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+        &["pbcopy"],
+    ];
+    for tool in CLIPBOARD_TOOLS {
+        let Ok(mut child) = Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "{} exited with {status}",
+                tool[0]
+            )))
+        };
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard tool found (wl-copy, xclip, xsel, or pbcopy)",
+    ))
+}
+
+/// URLs and existing documents go to the desktop opener; anything else runs
+/// as a shell command.
+#[cfg(not(windows))]
+fn launch_with_verb(verb: &str, target: &str) -> io::Result<()> {
+    let raw_target = target.trim();
+    if raw_target.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "target is empty",
+        ));
+    }
+    if verb != "open" {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("launch verb '{verb}' is only supported on Windows"),
+        ));
+    }
+
+    let path = Path::new(raw_target);
+    let mut command = if looks_like_url(raw_target) || path.exists() && !is_executable(path) {
+        let opener = if cfg!(target_os = "macos") {
+            "open"
+        } else {
+            "xdg-open"
+        };
+        let mut command = Command::new(opener);
+        command.arg(raw_target);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(raw_target);
+        command
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("failed to launch '{raw_target}': {err}"),
+            )
+        })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        launch_hidden_target, launch_rsnip_command, launch_verb_and_target, looks_like_path,
-        looks_like_url, should_fallback_to_cmd, split_executable_and_args,
+        launch_hidden_target, launch_rsnip_command, launch_target, looks_like_path, looks_like_url,
+        register_builtin_schemes, split_executable_and_args,
     };
+    use crate::schemes::{SchemeRegistry, BUILTIN_SCHEMES};
+
+    fn builtin_schemes() -> SchemeRegistry {
+        let mut registry = SchemeRegistry::new();
+        register_builtin_schemes(&mut registry);
+        registry
+    }
 
     #[test]
     fn split_executable_and_args_parses_quoted_targets() {
@@ -372,17 +549,21 @@ mod tests {
     }
 
     #[test]
-    fn launch_verb_and_target_detects_runas_prefix() {
+    fn builtin_schemes_cover_the_reserved_scheme_list() {
         assert_eq!(
-            launch_verb_and_target("notepad.exe"),
-            ("open", "notepad.exe")
-        );
-        assert_eq!(
-            launch_verb_and_target("runas:wt.exe powershell -NoExit -Command pi"),
-            ("runas", "wt.exe powershell -NoExit -Command pi")
+            builtin_schemes().schemes().collect::<Vec<_>>(),
+            BUILTIN_SCHEMES
         );
     }
 
+    #[test]
+    fn unknown_scheme_is_rejected_before_the_shell() {
+        let error = launch_target(&builtin_schemes(), "rmenu-test-nomod:do-thing")
+            .expect_err("unknown scheme");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("'rmenu-test-nomod:'"));
+    }
+
     #[test]
     fn hidden_launch_rejects_missing_path() {
         assert!(launch_hidden_target("C:\\definitely-missing-rsnip.exe snip").is_err());
@@ -393,8 +574,11 @@ mod tests {
         assert!(launch_rsnip_command("unknown").is_err());
     }
 
+    #[cfg(windows)]
     #[test]
     fn cmd_fallback_only_for_shell_like_commands() {
+        use super::should_fallback_to_cmd;
+
         assert!(should_fallback_to_cmd("cmd /k dir", "cmd"));
         assert!(should_fallback_to_cmd("echo hello && pause", "echo"));
         assert!(!should_fallback_to_cmd("notepad.exe", "notepad.exe"));
@@ -0,0 +1,24 @@
+//! Platform-neutral core of rMenu: configuration, ranking, launcher sources,
+//! the module runtime and registry, and the terminal frontend. The Win32
+//! frontend, daemon, module host, and updater binaries link it; it builds and
+//! tests without the `windows` crate outside Windows.
+
+pub mod adaptive_aliases;
+pub mod app_state;
+pub mod controller;
+pub mod fuzzy;
+pub mod modules;
+pub mod ranking;
+pub mod rmods_registry;
+pub mod row_layout;
+pub mod rsnip_companion;
+pub mod rtasks_companion;
+pub mod rules;
+pub mod schemes;
+pub mod scopes;
+pub mod script;
+pub mod settings;
+pub mod sources;
+pub mod streaming;
+pub mod tui;
+pub mod update_check;
@@ -1,33 +1,34 @@
-mod app_state;
-mod fuzzy;
 mod launcher;
-mod modules;
-mod ranking;
-#[cfg(test)]
+#[cfg(all(windows, test))]
 mod resident_helpers;
-mod rmods_registry;
-#[allow(dead_code)]
-mod rsnip_companion;
-mod rtasks_companion;
-mod settings;
-mod sources;
+#[cfg(windows)]
 mod ui_win32;
-#[allow(dead_code)]
-mod update_check;
 
+use rmenu_core::{
+    adaptive_aliases, app_state, fuzzy, modules, ranking, rsnip_companion, rtasks_companion, rules,
+    schemes, script, settings, sources, streaming, tui, update_check,
+};
+#[cfg(windows)]
+use rmenu_core::{controller, rmods_registry, row_layout};
+
+use adaptive_aliases::load_adaptive_aliases;
 use app_state::{AppState, LauncherItem, LauncherSource};
-use atty;
-use ranking::{rank_items, source_name};
+use ranking::{
+    explain_ranked_item, item_source_name, rank_items, rank_items_incremental, RankingCache,
+};
 use rsnip_companion::install_rsnip_latest;
 use rtasks_companion::install_rtasks_latest;
-use settings::{parse_args, resolve_modules_dir, CmdOptions, RmenuConfig};
+use rules::load_ranking_rules;
+use script::{run_script, Script};
+use settings::{parse_args, resolve_modules_dir, CmdOptions, DebugRankingFormat, RmenuConfig};
 use sources::{index_cache_size_bytes, load_launcher_items};
 use std::{
     io::{self, Read},
     path::Path,
     time::Instant,
 };
-use ui_win32::{measure_ui_latencies, UiLatencyMetrics};
+use streaming::StreamingRanker;
+use tui::{run_tui, TuiPlatform};
 use update_check::{is_newer_version, read_updates_cache};
 
 fn p95_duration_ms(samples: &mut [u128]) -> u128 {
@@ -41,6 +42,111 @@ fn p95_duration_ms(samples: &mut [u128]) -> u128 {
     samples[idx]
 }
 
+const DEBUG_RANKING_DEFAULT_LIMIT: usize = 20;
+
+fn print_debug_ranking(app_state: &AppState, query: &str, case_sensitive: bool, limit: usize) {
+    let ranked = rank_items(app_state, query, case_sensitive);
+
+    println!("Debug ranking for query: '{}'", query);
+    println!(
+        "Dataset size: {} | case_sensitive={} | launcher_mode={}",
+        app_state.all_items.len(),
+        case_sensitive,
+        app_state.launcher_mode
+    );
+
+    for (i, entry) in ranked.iter().take(limit).enumerate() {
+        println!(
+            "{:>2}. total={:<5} fuzzy={:<5} typo={:<2} boost={:<5} frecency={:<5} alias={:<6} rule={:<6} source={:<10} label={} target={}",
+            i + 1,
+            entry.total_score,
+            entry.fuzzy_score,
+            entry
+                .typo_distance
+                .map_or_else(|| "-".to_string(), |distance| distance.to_string()),
+            entry.source_boost,
+            entry.frecency_boost,
+            entry.alias_boost,
+            entry.rule_boost,
+            item_source_name(&entry.item),
+            entry.item.label,
+            entry.item.target
+        );
+        let explanation = explain_ranked_item(entry, query, case_sensitive);
+        match explanation.matched_field {
+            Some(field) => println!(
+                "    match: {} on {} (base={} penalty={})",
+                explanation.tier, field, explanation.tier_base, explanation.penalty
+            ),
+            None if !explanation.terms.is_empty() => {
+                let terms = explanation
+                    .terms
+                    .iter()
+                    .map(|(term, score)| format!("{term}={score}"))
+                    .collect::<Vec<_>>();
+                println!("    terms: {}", terms.join(" "));
+            }
+            None => {}
+        }
+        if let Some(hit) = entry.item.rule {
+            println!("    rule: {} (rules.txt line {})", hit.action, hit.line);
+        }
+    }
+}
+
+/// Machine-readable `--debug-ranking` output, stable enough to diff between
+/// ranking changes.
+fn print_debug_ranking_json(app_state: &AppState, query: &str, case_sensitive: bool, limit: usize) {
+    let results = rank_items(app_state, query, case_sensitive)
+        .iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, entry)| {
+            let explanation = explain_ranked_item(entry, query, case_sensitive);
+            serde_json::json!({
+                "rank": i + 1,
+                "label": entry.item.label,
+                "target": entry.item.target,
+                "shortcut": entry.item.shortcut,
+                "source": item_source_name(&entry.item),
+                "total_score": entry.total_score,
+                "fuzzy_score": entry.fuzzy_score,
+                "tier": explanation.tier,
+                "matched_field": explanation.matched_field,
+                "tier_base": explanation.tier_base,
+                "penalty": explanation.penalty,
+                "typo_distance": entry.typo_distance,
+                "terms": explanation
+                    .terms
+                    .iter()
+                    .map(|(term, score)| serde_json::json!({ "term": term, "score": score }))
+                    .collect::<Vec<_>>(),
+                "source_boost": entry.source_boost,
+                "frecency": entry.item.frecency,
+                "frecency_boost": entry.frecency_boost,
+                "alias_boost": entry.alias_boost,
+                "rule_boost": entry.rule_boost,
+                "rule": entry.item.rule.map(|hit| serde_json::json!({
+                    "action": hit.action,
+                    "line": hit.line,
+                })),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let report = serde_json::json!({
+        "query": query,
+        "case_sensitive": case_sensitive,
+        "launcher_mode": app_state.launcher_mode,
+        "dataset_size": app_state.all_items.len(),
+        "results": results,
+    });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+    );
+}
+
 fn estimated_dataset_bytes(items: &[LauncherItem]) -> usize {
     items
         .iter()
@@ -62,11 +168,65 @@ fn startup_update_notice(cli_data_dir: Option<&str>) -> Option<app_state::Startu
     })
 }
 
+/// Piped input is streamed into an open window unless a diagnostic mode needs
+/// the complete dataset before printing, or a script takes over. The terminal
+/// UI streams too where keys come from `/dev/tty` rather than stdin.
+fn streams_stdin(cmd_options: &CmdOptions) -> bool {
+    !cmd_options.metrics
+        && cmd_options.debug_ranking.is_none()
+        && !cmd_options.modules_debug
+        && (!cmd_options.tui || cfg!(unix))
+        && cmd_options.script.is_none()
+}
+
+/// Runs a `--script` file headlessly. Prints the passed-expectation count, or
+/// the parse errors / first failure on stderr, and returns the exit code.
+fn run_script_file(
+    path: &str,
+    config: &RmenuConfig,
+    app_state: AppState,
+    module_runtime: modules::ModuleRuntime,
+    silent_mode: bool,
+) -> i32 {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            if !silent_mode {
+                eprintln!("Error reading script '{}': {}", path, err);
+            }
+            return 2;
+        }
+    };
+    let script = match Script::parse(&content) {
+        Ok(script) => script,
+        Err(errors) => {
+            if !silent_mode {
+                for error in errors {
+                    eprintln!("{}: {}", path, error);
+                }
+            }
+            return 2;
+        }
+    };
+    match run_script(config, app_state, module_runtime, &script) {
+        Ok(passed) => {
+            println!("{}: {} expectations passed", path, passed);
+            0
+        }
+        Err(failure) => {
+            if !silent_mode {
+                eprintln!("{}: {}", path, failure);
+            }
+            1
+        }
+    }
+}
+
 fn print_metrics(
     app_state: &AppState,
     case_sensitive: bool,
     startup_ms: u128,
-    ui_metrics: Option<UiLatencyMetrics>,
+    ui_metrics: &[(&str, u128)],
 ) {
     let mut queries: Vec<String> = vec![
         "pow".to_string(),
@@ -101,13 +261,8 @@ fn print_metrics(
 
     println!("rmenu metrics");
     println!("- startup_prepare_ms: {}", startup_ms);
-    if let Some(ui) = ui_metrics {
-        println!(
-            "- time_to_window_visible_ms: {}",
-            ui.time_to_window_visible_ms
-        );
-        println!("- time_to_first_paint_ms: {}", ui.time_to_first_paint_ms);
-        println!("- time_to_input_ready_ms: {}", ui.time_to_input_ready_ms);
+    for (name, value) in ui_metrics {
+        println!("- {}: {}", name, value);
     }
     println!("- search_p95_ms: {:.3}", p95_ms);
     println!("- dataset_items: {}", app_state.all_items.len());
@@ -118,10 +273,138 @@ fn print_metrics(
     println!("- index_cache_bytes: {}", cache_size);
 }
 
-fn main() -> windows::core::Result<()> {
+const BENCH_DATASET_SIZES: [usize; 2] = [10_000, 50_000];
+const BENCH_QUERIES: [&str; 4] = ["src main", "projrep", "invoice.pdf", "musplay"];
+
+fn synthetic_dataset(size: usize) -> Vec<LauncherItem> {
+    const DIRS: [&str; 12] = [
+        "src", "docs", "assets", "build", "config", "vendor", "tests", "images", "reports",
+        "music", "projects", "archive",
+    ];
+    const NAMES: [&str; 12] = [
+        "main", "index", "readme", "settings", "player", "invoice", "photo", "notes", "backup",
+        "server", "client", "utils",
+    ];
+    const EXTENSIONS: [&str; 8] = ["rs", "md", "png", "json", "txt", "toml", "mp3", "pdf"];
+
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = |bound: usize| {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((seed >> 33) as usize) % bound
+    };
+
+    (0..size)
+        .map(|index| {
+            let path = format!(
+                "{}/{}/{}_{}.{}",
+                DIRS[next(DIRS.len())],
+                DIRS[next(DIRS.len())],
+                NAMES[next(NAMES.len())],
+                index,
+                EXTENSIONS[next(EXTENSIONS.len())]
+            );
+            LauncherItem::new(path.clone(), path, LauncherSource::Direct)
+        })
+        .collect()
+}
+
+fn print_keystroke_benchmark(case_sensitive: bool) {
+    println!("rmenu keystroke benchmark");
+    for size in BENCH_DATASET_SIZES {
+        let app_state = AppState {
+            all_items: synthetic_dataset(size),
+            ..Default::default()
+        };
+        let mut full_samples_us: Vec<u128> = Vec::new();
+        let mut incremental_samples_us: Vec<u128> = Vec::new();
+
+        for query in BENCH_QUERIES {
+            let mut cache = RankingCache::default();
+            for end in query
+                .char_indices()
+                .map(|(index, ch)| index + ch.len_utf8())
+            {
+                let typed = &query[..end];
+
+                let t0 = Instant::now();
+                let _ = rank_items_incremental(
+                    &app_state,
+                    None,
+                    typed,
+                    case_sensitive,
+                    &mut RankingCache::default(),
+                );
+                full_samples_us.push(t0.elapsed().as_micros());
+
+                let t0 = Instant::now();
+                let _ = rank_items_incremental(&app_state, None, typed, case_sensitive, &mut cache);
+                incremental_samples_us.push(t0.elapsed().as_micros());
+            }
+        }
+
+        let keystrokes = incremental_samples_us.len();
+        let incremental_max_us = incremental_samples_us.iter().copied().max().unwrap_or(0);
+        incremental_samples_us.sort_unstable();
+        let incremental_median_us = incremental_samples_us[keystrokes / 2];
+        println!("- dataset_items: {}", size);
+        println!("  keystrokes: {}", keystrokes);
+        println!(
+            "  keystroke_full_p95_ms: {:.3}",
+            p95_duration_ms(&mut full_samples_us) as f64 / 1000.0
+        );
+        println!(
+            "  keystroke_incremental_median_ms: {:.3}",
+            incremental_median_us as f64 / 1000.0
+        );
+        println!(
+            "  keystroke_incremental_p95_ms: {:.3}",
+            p95_duration_ms(&mut incremental_samples_us) as f64 / 1000.0
+        );
+        println!(
+            "  keystroke_incremental_max_ms: {:.3}",
+            incremental_max_us as f64 / 1000.0
+        );
+    }
+}
+
+/// Window latencies for `--metrics`, measured by opening the Win32 window.
+#[cfg(windows)]
+fn ui_latency_metrics(
+    cmd_options: &CmdOptions,
+    config: &RmenuConfig,
+    app_state: AppState,
+    module_runtime: modules::ModuleRuntime,
+) -> Vec<(&'static str, u128)> {
+    ui_win32::measure_ui_latencies(cmd_options, config, app_state, module_runtime)
+        .map(|ui| {
+            vec![
+                ("time_to_window_visible_ms", ui.time_to_window_visible_ms),
+                ("time_to_first_paint_ms", ui.time_to_first_paint_ms),
+                ("time_to_input_ready_ms", ui.time_to_input_ready_ms),
+            ]
+        })
+        .unwrap_or_default()
+}
+
+/// There is no window to measure outside Windows.
+#[cfg(not(windows))]
+fn ui_latency_metrics(
+    _cmd_options: &CmdOptions,
+    _config: &RmenuConfig,
+    _app_state: AppState,
+    _module_runtime: modules::ModuleRuntime,
+) -> Vec<(&'static str, u128)> {
+    Vec::new()
+}
+
+fn main() -> io::Result<()> {
     let startup_t0 = Instant::now();
 
-    let cmd_options: CmdOptions = parse_args();
+    let mut cmd_options: CmdOptions = parse_args();
+    // The terminal UI is the only frontend outside Windows.
+    cmd_options.tui |= !cfg!(windows);
     if let Some(data_dir) = &cmd_options.data_dir {
         std::env::set_var("RMENU_DATA_DIR", data_dir);
     }
@@ -185,10 +468,17 @@ fn main() -> windows::core::Result<()> {
     };
 
     app_config.apply_cli_overrides(&cmd_options);
+    fuzzy::set_transliteration(app_config.behavior.transliterate);
+
+    if cmd_options.metrics_bench {
+        print_keystroke_benchmark(app_config.behavior.case_sensitive);
+        return Ok(());
+    }
     let launcher_config = app_config.launcher.clone();
 
     let mut initial_items: Vec<LauncherItem> = Vec::new();
     let mut launcher_mode = false;
+    let mut streaming_ranker = None;
 
     if let Some(elements_str) = &cmd_options.elements_str {
         initial_items = elements_str
@@ -197,6 +487,8 @@ fn main() -> windows::core::Result<()> {
             .filter(|s| !s.is_empty())
             .map(|s| LauncherItem::new(s.to_string(), s.to_string(), LauncherSource::Direct))
             .collect();
+    } else if !atty::is(atty::Stream::Stdin) && streams_stdin(&cmd_options) {
+        streaming_ranker = Some(StreamingRanker::from_stdin(silent_mode));
     } else if !atty::is(atty::Stream::Stdin) {
         let mut buffer = String::new();
         match io::stdin().read_to_string(&mut buffer) {
@@ -220,7 +512,9 @@ fn main() -> windows::core::Result<()> {
         }
     } else if launcher_config.launcher_mode_default {
         launcher_mode = true;
-        initial_items = load_launcher_items(&launcher_config, silent_mode, cmd_options.reindex);
+        let rules = load_ranking_rules(cmd_options.data_dir.as_deref(), silent_mode);
+        initial_items =
+            load_launcher_items(&launcher_config, &rules, silent_mode, cmd_options.reindex);
     }
 
     let final_initial_items = initial_items;
@@ -235,21 +529,29 @@ fn main() -> windows::core::Result<()> {
         scroll_offset: 0,
         matching_items: initial_matching_items,
         all_items: final_initial_items,
+        items_generation: 0,
         prompt: cmd_options.prompt.clone(),
         launcher_mode,
         silent_mode,
         history_max_items: launcher_config.history_max_items,
+        frecency_half_life_hours: launcher_config.frecency_half_life_hours,
         source_boost_history: launcher_config.source_boost_history,
         source_boost_start_menu: launcher_config.source_boost_start_menu,
         source_boost_path: launcher_config.source_boost_path,
+        source_boost_bookmarks: launcher_config.source_boost_bookmarks,
+        frecency_weight: launcher_config.frecency_weight,
+        adaptive_aliases: load_adaptive_aliases(cmd_options.data_dir.as_deref()),
         rtasks_status: None,
         rtasks_priority: None,
         rmods: Default::default(),
         startup_update_notice: startup_update_notice(cmd_options.data_dir.as_deref()),
+        action_menu: None,
+        ranking_cache: Default::default(),
     };
 
     let case_sensitive = app_config.behavior.case_sensitive;
     let mut module_runtime = modules::ModuleRuntime::new();
+    launcher::register_builtin_schemes(module_runtime.schemes_mut());
     module_runtime.configure_policy(modules::ModuleRuntimePolicy {
         provider_total_budget_ms: app_config.modules.provider_total_budget_ms,
         provider_timeout_ms: app_config.modules.provider_timeout_ms,
@@ -265,7 +567,8 @@ fn main() -> windows::core::Result<()> {
     });
     let _module_api_version = module_runtime.api_version();
     module_runtime.register_builtin_module(Box::new(modules::BuiltinLifecycleModule));
-    module_runtime.register_builtin_module(Box::new(modules::BuiltinQueryProviderModule));
+    module_runtime
+        .register_builtin_module(Box::new(modules::BuiltinQueryProviderModule::default()));
     module_runtime.register_builtin_module(Box::new(modules::BuiltinRsnipCompanionModule));
     module_runtime.register_builtin_module(Box::new(modules::BuiltinRtasksCompanionModule));
     module_runtime.load_external_descriptors(&modules_dir, silent_mode);
@@ -277,44 +580,71 @@ fn main() -> windows::core::Result<()> {
 
     if cmd_options.metrics {
         let startup_ms = startup_t0.elapsed().as_millis();
-        let ui_metrics = measure_ui_latencies(
+        let ui_metrics = ui_latency_metrics(
             &cmd_options,
             &app_config,
             initial_app_state.clone(),
             module_runtime,
-        )
-        .ok();
-        print_metrics(&initial_app_state, case_sensitive, startup_ms, ui_metrics);
+        );
+        print_metrics(&initial_app_state, case_sensitive, startup_ms, &ui_metrics);
         return Ok(());
     }
 
     if let Some(debug_query) = &cmd_options.debug_ranking {
-        let ranked = rank_items(&initial_app_state, debug_query, case_sensitive);
-
-        println!("Debug ranking for query: '{}'", debug_query);
-        println!(
-            "Dataset size: {} | case_sensitive={} | launcher_mode={}",
-            initial_app_state.all_items.len(),
-            case_sensitive,
-            initial_app_state.launcher_mode
-        );
-
-        for (i, entry) in ranked.iter().take(20).enumerate() {
-            println!(
-                "{:>2}. total={:<5} fuzzy={:<5} boost={:<5} source={:<10} label={} target={}",
-                i + 1,
-                entry.total_score,
-                entry.fuzzy_score,
-                entry.source_boost,
-                source_name(entry.item.source),
-                entry.item.label,
-                entry.item.target
-            );
+        let limit = cmd_options.limit.unwrap_or(DEBUG_RANKING_DEFAULT_LIMIT);
+        match cmd_options.debug_ranking_format {
+            DebugRankingFormat::Text => {
+                print_debug_ranking(&initial_app_state, debug_query, case_sensitive, limit)
+            }
+            DebugRankingFormat::Json => {
+                print_debug_ranking_json(&initial_app_state, debug_query, case_sensitive, limit)
+            }
         }
-
         return Ok(());
     }
 
-    let exit_code = ui_win32::run_ui(&cmd_options, &app_config, initial_app_state, module_runtime)?;
-    std::process::exit(exit_code);
+    if let Some(script_path) = &cmd_options.script {
+        std::process::exit(run_script_file(
+            script_path,
+            &app_config,
+            initial_app_state,
+            module_runtime,
+            silent_mode,
+        ));
+    }
+
+    if cmd_options.tui {
+        let platform = TuiPlatform {
+            launch_target: launcher::launch_target,
+            copy_text: launcher::copy_text_to_clipboard,
+        };
+        let exit_code = run_tui(
+            &app_config,
+            initial_app_state,
+            module_runtime,
+            platform,
+            streaming_ranker,
+        )
+        .unwrap_or_else(|err| {
+            if !silent_mode {
+                eprintln!("Error running terminal UI: {}", err);
+            }
+            1
+        });
+        std::process::exit(exit_code);
+    }
+
+    #[cfg(windows)]
+    {
+        let exit_code = ui_win32::run_ui(
+            &cmd_options,
+            &app_config,
+            initial_app_state,
+            module_runtime,
+            streaming_ranker,
+        )?;
+        std::process::exit(exit_code);
+    }
+    #[cfg(not(windows))]
+    unreachable!("the terminal UI handles every run outside Windows")
 }
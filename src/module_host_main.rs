@@ -1,12 +1,9 @@
-#[path = "modules/ipc.rs"]
-mod ipc;
-
 use std::io::{self, BufRead, BufReader, Write};
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
 
-use ipc::{
+use rmenu_core::modules::ipc::{
     HostRequest, HostRequestPayload, HostResponse, HostResponsePayload, IpcAction, IpcItem,
     IpcKeyEvent, IpcSnapshot, ModuleInitPayload,
 };
@@ -47,17 +44,24 @@ enum WorkerRequest {
     #[serde(rename = "hook")]
     Hook {
         hook: String,
-        query: Option<String>,
-        key_event: Option<IpcKeyEvent>,
-        items: Option<Vec<IpcItem>>,
-        command: Option<String>,
-        args: Option<Vec<String>>,
-        snapshot: Option<IpcSnapshot>,
+        #[serde(flatten)]
+        input: HookInput,
     },
     #[serde(rename = "shutdown")]
     Shutdown,
 }
 
+/// Hook arguments; each hook only fills the ones it takes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HookInput {
+    query: Option<String>,
+    key_event: Option<IpcKeyEvent>,
+    items: Option<Vec<IpcItem>>,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    snapshot: Option<IpcSnapshot>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct WorkerResponse {
     ok: bool,
@@ -126,23 +130,13 @@ impl NodeRuntime {
     fn hook(
         &mut self,
         hook: &str,
-        query: Option<String>,
-        key_event: Option<IpcKeyEvent>,
-        items: Option<Vec<IpcItem>>,
-        command: Option<String>,
-        args: Option<Vec<String>>,
-        snapshot: Option<IpcSnapshot>,
+        input: HookInput,
         max_ipc_payload_bytes: usize,
     ) -> Result<WorkerResponse, String> {
         self.send(
             WorkerRequest::Hook {
                 hook: hook.to_string(),
-                query,
-                key_event,
-                items,
-                command,
-                args,
-                snapshot,
+                input,
             },
             max_ipc_payload_bytes,
         )
@@ -268,8 +262,15 @@ fn handle_request(request: HostRequest, state: &mut HostState) -> HostResponse {
         }
         HostRequestPayload::OnLoad { snapshot } => {
             state.loaded = true;
-            run_hook(state, "onLoad", None, None, None, None, None, snapshot)
-                .unwrap_or(HostResponsePayload::Ack)
+            run_hook(
+                state,
+                "onLoad",
+                HookInput {
+                    snapshot,
+                    ..HookInput::default()
+                },
+            )
+            .unwrap_or(HostResponsePayload::Ack)
         }
         HostRequestPayload::OnQueryChange { query, snapshot } => {
             if !state.loaded {
@@ -281,12 +282,11 @@ fn handle_request(request: HostRequest, state: &mut HostState) -> HostResponse {
                 run_hook(
                     state,
                     "onQueryChange",
-                    Some(query),
-                    None,
-                    None,
-                    None,
-                    None,
-                    Some(snapshot),
+                    HookInput {
+                        query: Some(query),
+                        snapshot: Some(snapshot),
+                        ..HookInput::default()
+                    },
                 )
                 .unwrap_or(HostResponsePayload::Ack)
             }
@@ -301,12 +301,11 @@ fn handle_request(request: HostRequest, state: &mut HostState) -> HostResponse {
                 run_hook(
                     state,
                     "onKey",
-                    None,
-                    Some(event),
-                    None,
-                    None,
-                    None,
-                    Some(snapshot),
+                    HookInput {
+                        key_event: Some(event),
+                        snapshot: Some(snapshot),
+                        ..HookInput::default()
+                    },
                 )
                 .unwrap_or(HostResponsePayload::Ack)
             }
@@ -321,12 +320,11 @@ fn handle_request(request: HostRequest, state: &mut HostState) -> HostResponse {
                 run_hook(
                     state,
                     "provideItems",
-                    Some(query),
-                    None,
-                    None,
-                    None,
-                    None,
-                    Some(snapshot),
+                    HookInput {
+                        query: Some(query),
+                        snapshot: Some(snapshot),
+                        ..HookInput::default()
+                    },
                 )
                 .unwrap_or(HostResponsePayload::ProvideItemsResult { items: Vec::new() })
             }
@@ -341,12 +339,11 @@ fn handle_request(request: HostRequest, state: &mut HostState) -> HostResponse {
                 run_hook(
                     state,
                     "decorateItems",
-                    None,
-                    None,
-                    Some(items),
-                    None,
-                    None,
-                    Some(snapshot),
+                    HookInput {
+                        items: Some(items),
+                        snapshot: Some(snapshot),
+                        ..HookInput::default()
+                    },
                 )
                 .unwrap_or(HostResponsePayload::DecorateItemsResult { items: Vec::new() })
             }
@@ -365,19 +362,26 @@ fn handle_request(request: HostRequest, state: &mut HostState) -> HostResponse {
                 run_hook(
                     state,
                     "onCommand",
-                    None,
-                    None,
-                    None,
-                    Some(command),
-                    Some(args),
-                    Some(snapshot),
+                    HookInput {
+                        command: Some(command),
+                        args: Some(args),
+                        snapshot: Some(snapshot),
+                        ..HookInput::default()
+                    },
                 )
                 .unwrap_or(HostResponsePayload::Ack)
             }
         }
         HostRequestPayload::OnUnload { snapshot } => {
             state.loaded = false;
-            let _ = run_hook(state, "onUnload", None, None, None, None, None, snapshot);
+            let _ = run_hook(
+                state,
+                "onUnload",
+                HookInput {
+                    snapshot,
+                    ..HookInput::default()
+                },
+            );
             HostResponsePayload::Ack
         }
         HostRequestPayload::Shutdown => {
@@ -396,28 +400,10 @@ fn handle_request(request: HostRequest, state: &mut HostState) -> HostResponse {
     }
 }
 
-fn run_hook(
-    state: &mut HostState,
-    hook: &str,
-    query: Option<String>,
-    key_event: Option<IpcKeyEvent>,
-    items: Option<Vec<IpcItem>>,
-    command: Option<String>,
-    args: Option<Vec<String>>,
-    snapshot: Option<IpcSnapshot>,
-) -> Option<HostResponsePayload> {
+fn run_hook(state: &mut HostState, hook: &str, input: HookInput) -> Option<HostResponsePayload> {
     let runtime = state.runtime.as_mut()?;
     let response = runtime
-        .hook(
-            hook,
-            query,
-            key_event,
-            items,
-            command,
-            args,
-            snapshot,
-            state.max_ipc_payload_bytes,
-        )
+        .hook(hook, input, state.max_ipc_payload_bytes)
         .ok()?;
 
     if !response.ok {
@@ -522,6 +508,10 @@ function createCtx(configObj, snapshotObj) {
       });
     },
     clearInputAccessory: () => actions.push({ type: 'ClearInputAccessory' }),
+    copyText: (text) => actions.push({
+      type: 'CopyText',
+      data: { text: typeof text === 'string' ? text : String(text ?? '') }
+    }),
     moduleConfig: () => configObj,
     moduleSourcePath: () => moduleSourcePath,
     moduleDir: () => moduleDir(),
@@ -621,11 +611,13 @@ rl.on('line', async (line) => {
 
 #[cfg(test)]
 mod tests {
-    use super::ipc::{HostRequest, HostRequestPayload, HostResponsePayload, IpcSnapshot};
     use super::{
         handle_request, read_max_ipc_payload_bytes, HostState, NodeRuntime, WorkerRequest,
         DEFAULT_MAX_IPC_PAYLOAD_BYTES,
     };
+    use rmenu_core::modules::ipc::{
+        HostRequest, HostRequestPayload, HostResponsePayload, IpcSnapshot,
+    };
     use std::io::BufReader;
     use std::process::{Command, Stdio};
 
@@ -682,15 +674,21 @@ mod tests {
         }
     }
 
-    #[cfg(windows)]
     #[test]
     fn node_runtime_send_rejects_oversized_worker_response() {
-        let mut child = Command::new("powershell.exe")
-            .args([
-                "-NoProfile",
-                "-Command",
-                "Write-Output 'xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx'; Start-Sleep -Seconds 5",
-            ])
+        #[cfg(windows)]
+        let mut command = Command::new("powershell.exe");
+        #[cfg(windows)]
+        command.args([
+            "-NoProfile",
+            "-Command",
+            "Write-Output 'xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx'; Start-Sleep -Seconds 5",
+        ]);
+        #[cfg(not(windows))]
+        let mut command = Command::new("sh");
+        #[cfg(not(windows))]
+        command.args(["-c", "echo xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx; sleep 5"]);
+        let mut child = command
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
@@ -715,8 +713,10 @@ mod tests {
 
     #[test]
     fn shutdown_clears_loaded_state_without_runtime() {
-        let mut state = HostState::default();
-        state.loaded = true;
+        let mut state = HostState {
+            loaded: true,
+            ..HostState::default()
+        };
 
         let response = handle_request(
             HostRequest {
@@ -21,6 +21,7 @@ pub enum ActionEffect {
     InputAccessoryCleared,
     CommandRegistered(ModuleCommandDef),
     ProviderRegistered(ModuleProviderDef),
+    TextCopyRequested(String),
 }
 
 #[derive(Debug, Default)]
@@ -118,5 +119,9 @@ pub fn apply_action_request(
             state.register_provider(module_name, provider.clone());
             Ok(ActionEffect::ProviderRegistered(provider))
         }
+        ModuleActionRequest::CopyText(text) => {
+            state.pending_copy_text = Some(text.clone());
+            Ok(ActionEffect::TextCopyRequested(text))
+        }
     }
 }
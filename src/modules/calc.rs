@@ -0,0 +1,809 @@
+use std::f64::consts::{E, PI, TAU};
+use std::fmt;
+
+const MAX_NESTING_DEPTH: usize = 64;
+const FLOAT_DISPLAY_DECIMALS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalcValue {
+    Int(i128),
+    Float(f64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalcError {
+    UnexpectedChar(char),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    UnknownName(String),
+    WrongArity {
+        name: String,
+        expected: &'static str,
+    },
+    MissingClosingParen,
+    DivisionByZero,
+    Domain(&'static str),
+    NotFinite,
+    TooDeep,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedChar(ch) => write!(f, "unexpected character '{ch}'"),
+            Self::UnexpectedToken(token) => write!(f, "unexpected '{token}'"),
+            Self::UnexpectedEnd => write!(f, "expression is incomplete"),
+            Self::InvalidNumber(raw) => write!(f, "invalid number '{raw}'"),
+            Self::UnknownName(name) => write!(f, "unknown function or constant '{name}'"),
+            Self::WrongArity { name, expected } => write!(f, "{name} expects {expected}"),
+            Self::MissingClosingParen => write!(f, "missing closing parenthesis"),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::Domain(message) => write!(f, "{message}"),
+            Self::NotFinite => write!(f, "result is too large"),
+            Self::TooDeep => write!(f, "expression is nested too deeply"),
+        }
+    }
+}
+
+impl CalcValue {
+    fn as_f64(self) -> f64 {
+        match self {
+            Self::Int(value) => value as f64,
+            Self::Float(value) => value,
+        }
+    }
+
+    fn as_integer(self) -> Option<i128> {
+        match self {
+            Self::Int(value) => Some(value),
+            Self::Float(value)
+                if value.fract() == 0.0 && value.abs() < i128::MAX as f64 && value.is_finite() =>
+            {
+                Some(value as i128)
+            }
+            Self::Float(_) => None,
+        }
+    }
+
+    fn checked_float(value: f64) -> Result<Self, CalcError> {
+        if value.is_finite() {
+            Ok(Self::Float(value))
+        } else {
+            Err(CalcError::NotFinite)
+        }
+    }
+
+    fn int_or_float(
+        left: Self,
+        right: Self,
+        int_op: fn(i128, i128) -> Option<i128>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Result<Self, CalcError> {
+        if let (Self::Int(l), Self::Int(r)) = (left, right) {
+            if let Some(value) = int_op(l, r) {
+                return Ok(Self::Int(value));
+            }
+        }
+        Self::checked_float(float_op(left.as_f64(), right.as_f64()))
+    }
+
+    fn add(self, other: Self) -> Result<Self, CalcError> {
+        Self::int_or_float(self, other, i128::checked_add, |l, r| l + r)
+    }
+
+    fn sub(self, other: Self) -> Result<Self, CalcError> {
+        Self::int_or_float(self, other, i128::checked_sub, |l, r| l - r)
+    }
+
+    fn mul(self, other: Self) -> Result<Self, CalcError> {
+        Self::int_or_float(self, other, i128::checked_mul, |l, r| l * r)
+    }
+
+    fn div(self, other: Self) -> Result<Self, CalcError> {
+        if other.as_f64() == 0.0 {
+            return Err(CalcError::DivisionByZero);
+        }
+        Self::int_or_float(
+            self,
+            other,
+            |l, r| {
+                (l.checked_rem(r) == Some(0))
+                    .then(|| l.checked_div(r))
+                    .flatten()
+            },
+            |l, r| l / r,
+        )
+    }
+
+    fn rem(self, other: Self) -> Result<Self, CalcError> {
+        if other.as_f64() == 0.0 {
+            return Err(CalcError::DivisionByZero);
+        }
+        Self::int_or_float(self, other, i128::checked_rem, |l, r| l % r)
+    }
+
+    fn pow(self, exponent: Self) -> Result<Self, CalcError> {
+        if let (Self::Int(base), Self::Int(exp)) = (self, exponent) {
+            if let Some(value) = u32::try_from(exp)
+                .ok()
+                .and_then(|exp| base.checked_pow(exp))
+            {
+                return Ok(Self::Int(value));
+            }
+        }
+        let base = self.as_f64();
+        let exp = exponent.as_f64();
+        if base == 0.0 && exp < 0.0 {
+            return Err(CalcError::DivisionByZero);
+        }
+        if base < 0.0 && exp.fract() != 0.0 {
+            return Err(CalcError::Domain("fractional power of a negative number"));
+        }
+        Self::checked_float(base.powf(exp))
+    }
+
+    fn neg(self) -> Result<Self, CalcError> {
+        match self {
+            Self::Int(value) => Ok(value
+                .checked_neg()
+                .map_or(Self::Float(-(value as f64)), Self::Int)),
+            Self::Float(value) => Ok(Self::Float(-value)),
+        }
+    }
+
+    fn factorial(self) -> Result<Self, CalcError> {
+        let Some(n) = self.as_integer().filter(|n| *n >= 0) else {
+            return Err(CalcError::Domain("factorial needs a non-negative integer"));
+        };
+        let mut acc = Self::Int(1);
+        for factor in 2..=n {
+            acc = acc.mul(Self::Int(factor))?;
+        }
+        Ok(acc)
+    }
+}
+
+impl fmt::Display for CalcValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Float(value) => {
+                let magnitude = value.abs();
+                if magnitude != 0.0 && !(1e-9..1e15).contains(&magnitude) {
+                    return write!(f, "{value:e}");
+                }
+                let fixed = format!("{value:.FLOAT_DISPLAY_DECIMALS$}");
+                let trimmed = fixed.trim_end_matches('0').trim_end_matches('.');
+                if trimmed == "-0" {
+                    write!(f, "0")
+                } else {
+                    write!(f, "{trimmed}")
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(CalcValue),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    Percent,
+    Bang,
+    Comma,
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Number(value) => value.to_string(),
+            Self::Ident(name) => name.clone(),
+            Self::Plus => "+".to_string(),
+            Self::Minus => "-".to_string(),
+            Self::Star => "*".to_string(),
+            Self::Slash => "/".to_string(),
+            Self::Caret => "^".to_string(),
+            Self::Percent => "%".to_string(),
+            Self::Bang => "!".to_string(),
+            Self::Comma => ",".to_string(),
+            Self::LParen => "(".to_string(),
+            Self::RParen => ")".to_string(),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut index = 0usize;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        if ch.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        if ch.is_ascii_digit()
+            || (ch == '.' && chars.get(index + 1).is_some_and(char::is_ascii_digit))
+        {
+            let (value, next) = read_number(&chars, index)?;
+            tokens.push(Token::Number(value));
+            index = next;
+            continue;
+        }
+
+        if ch.is_alphabetic() || ch == '_' {
+            let start = index;
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            let name = chars[start..index].iter().collect::<String>();
+            tokens.push(Token::Ident(name.to_lowercase()));
+            continue;
+        }
+
+        let token = match ch {
+            '+' => Token::Plus,
+            '-' | '−' => Token::Minus,
+            '*' if chars.get(index + 1) == Some(&'*') => {
+                index += 1;
+                Token::Caret
+            }
+            '*' | '×' => Token::Star,
+            '/' | '÷' => Token::Slash,
+            '^' => Token::Caret,
+            '%' => Token::Percent,
+            '!' => Token::Bang,
+            ',' => Token::Comma,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            other => return Err(CalcError::UnexpectedChar(other)),
+        };
+        tokens.push(token);
+        index += 1;
+    }
+
+    Ok(tokens)
+}
+
+fn read_number(chars: &[char], start: usize) -> Result<(CalcValue, usize), CalcError> {
+    let radix = match (chars.get(start), chars.get(start + 1)) {
+        (Some('0'), Some('x' | 'X')) => Some(16),
+        (Some('0'), Some('b' | 'B')) => Some(2),
+        (Some('0'), Some('o' | 'O')) => Some(8),
+        _ => None,
+    };
+
+    if let Some(radix) = radix {
+        let mut index = start + 2;
+        while index < chars.len() && (chars[index].is_ascii_alphanumeric() || chars[index] == '_') {
+            index += 1;
+        }
+        let raw = chars[start..index].iter().collect::<String>();
+        let digits = raw[2..].replace('_', "");
+        if digits.is_empty() {
+            return Err(CalcError::InvalidNumber(raw));
+        }
+        return match i128::from_str_radix(&digits, radix) {
+            Ok(value) => Ok((CalcValue::Int(value), index)),
+            Err(_) if digits.chars().all(|ch| ch.is_digit(radix)) => {
+                let value = digits.chars().fold(0f64, |acc, ch| {
+                    acc * radix as f64 + ch.to_digit(radix).unwrap_or(0) as f64
+                });
+                Ok((CalcValue::checked_float(value)?, index))
+            }
+            Err(_) => Err(CalcError::InvalidNumber(raw)),
+        };
+    }
+
+    let mut index = start;
+    let mut is_float = false;
+    while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '_') {
+        index += 1;
+    }
+    if chars.get(index) == Some(&'.') {
+        is_float = true;
+        index += 1;
+        while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '_') {
+            index += 1;
+        }
+    }
+    if matches!(chars.get(index), Some('e' | 'E')) {
+        let after_sign = match chars.get(index + 1) {
+            Some('+' | '-') => index + 2,
+            _ => index + 1,
+        };
+        if chars.get(after_sign).is_some_and(char::is_ascii_digit) {
+            is_float = true;
+            index = after_sign;
+            while index < chars.len() && chars[index].is_ascii_digit() {
+                index += 1;
+            }
+        }
+    }
+
+    let raw = chars[start..index].iter().collect::<String>();
+    let cleaned = raw.replace('_', "");
+    if !is_float {
+        if let Ok(value) = cleaned.parse::<i128>() {
+            return Ok((CalcValue::Int(value), index));
+        }
+    }
+    match cleaned.parse::<f64>() {
+        Ok(value) => Ok((CalcValue::checked_float(value)?, index)),
+        Err(_) => Err(CalcError::InvalidNumber(raw)),
+    }
+}
+
+struct Operand {
+    value: CalcValue,
+    is_percent: bool,
+}
+
+impl Operand {
+    fn plain(value: CalcValue) -> Self {
+        Self {
+            value,
+            is_percent: false,
+        }
+    }
+
+    fn resolve(self) -> Result<CalcValue, CalcError> {
+        if self.is_percent {
+            self.value.div(CalcValue::Int(100))
+        } else {
+            Ok(self.value)
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn consume(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), CalcError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            Err(CalcError::TooDeep)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn expression(&mut self) -> Result<CalcValue, CalcError> {
+        self.enter()?;
+        let mut value = self.term()?.resolve()?;
+        loop {
+            let subtract = if self.consume(&Token::Plus) {
+                false
+            } else if self.consume(&Token::Minus) {
+                true
+            } else {
+                break;
+            };
+            let rhs = self.term()?;
+            let rhs = if rhs.is_percent {
+                value.mul(rhs.value)?.div(CalcValue::Int(100))?
+            } else {
+                rhs.value
+            };
+            value = if subtract {
+                value.sub(rhs)?
+            } else {
+                value.add(rhs)?
+            };
+        }
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<Operand, CalcError> {
+        let mut operand = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => Token::Star,
+                Some(Token::Slash) => Token::Slash,
+                Some(Token::Percent) => Token::Percent,
+                Some(Token::Ident(name)) if name == "mod" => Token::Percent,
+                _ => break,
+            };
+            self.position += 1;
+            let lhs = operand.resolve()?;
+            let rhs = self.unary()?.resolve()?;
+            let value = match op {
+                Token::Star => lhs.mul(rhs)?,
+                Token::Slash => lhs.div(rhs)?,
+                _ => lhs.rem(rhs)?,
+            };
+            operand = Operand::plain(value);
+        }
+        Ok(operand)
+    }
+
+    fn unary(&mut self) -> Result<Operand, CalcError> {
+        // Unary plus is a no-op; looping keeps `++++1` off the stack.
+        while self.consume(&Token::Plus) {}
+        if self.consume(&Token::Minus) {
+            self.enter()?;
+            let operand = self.unary()?;
+            self.depth -= 1;
+            return Ok(Operand {
+                value: operand.value.neg()?,
+                is_percent: operand.is_percent,
+            });
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Operand, CalcError> {
+        let base = self.postfix()?;
+        if !self.consume(&Token::Caret) {
+            return Ok(base);
+        }
+        self.enter()?;
+        let exponent = self.unary()?.resolve()?;
+        self.depth -= 1;
+        Ok(Operand::plain(base.resolve()?.pow(exponent)?))
+    }
+
+    fn postfix(&mut self) -> Result<Operand, CalcError> {
+        let mut operand = Operand::plain(self.primary()?);
+        loop {
+            if self.consume(&Token::Bang) {
+                operand = Operand::plain(operand.resolve()?.factorial()?);
+            } else if self.peek() == Some(&Token::Percent) && !self.operand_follows_percent() {
+                self.position += 1;
+                if operand.is_percent {
+                    operand = Operand::plain(operand.resolve()?);
+                }
+                operand.is_percent = true;
+            } else {
+                return Ok(operand);
+            }
+        }
+    }
+
+    /// `%` followed by an operand (`10 % 3`) is modulo and is left for `term`.
+    fn operand_follows_percent(&self) -> bool {
+        matches!(
+            self.tokens.get(self.position + 1),
+            Some(Token::Number(_) | Token::LParen)
+        ) || matches!(self.tokens.get(self.position + 1), Some(Token::Ident(name)) if name != "mod")
+    }
+
+    fn primary(&mut self) -> Result<CalcValue, CalcError> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::LParen) => {
+                let value = self.expression()?;
+                if !self.consume(&Token::RParen) {
+                    return Err(match self.peek() {
+                        Some(token) => CalcError::UnexpectedToken(token.describe()),
+                        None => CalcError::MissingClosingParen,
+                    });
+                }
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if self.consume(&Token::LParen) {
+                    let args = self.arguments()?;
+                    call_function(&name, &args)
+                } else {
+                    constant(&name).ok_or(CalcError::UnknownName(name))
+                }
+            }
+            Some(token) => Err(CalcError::UnexpectedToken(token.describe())),
+            None => Err(CalcError::UnexpectedEnd),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<CalcValue>, CalcError> {
+        let mut args = Vec::new();
+        if self.consume(&Token::RParen) {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression()?);
+            if self.consume(&Token::Comma) {
+                continue;
+            }
+            if self.consume(&Token::RParen) {
+                return Ok(args);
+            }
+            return Err(match self.peek() {
+                Some(token) => CalcError::UnexpectedToken(token.describe()),
+                None => CalcError::MissingClosingParen,
+            });
+        }
+    }
+}
+
+fn constant(name: &str) -> Option<CalcValue> {
+    match name {
+        "pi" | "π" => Some(CalcValue::Float(PI)),
+        "tau" | "τ" => Some(CalcValue::Float(TAU)),
+        "e" => Some(CalcValue::Float(E)),
+        "phi" => Some(CalcValue::Float((1.0 + 5f64.sqrt()) / 2.0)),
+        _ => None,
+    }
+}
+
+fn expect_args<'a>(
+    name: &str,
+    args: &'a [CalcValue],
+    count: usize,
+) -> Result<&'a [CalcValue], CalcError> {
+    if args.len() == count {
+        return Ok(args);
+    }
+    Err(CalcError::WrongArity {
+        name: name.to_string(),
+        expected: match count {
+            1 => "1 argument",
+            _ => "2 arguments",
+        },
+    })
+}
+
+fn unary_float(name: &str, args: &[CalcValue], op: fn(f64) -> f64) -> Result<CalcValue, CalcError> {
+    let args = expect_args(name, args, 1)?;
+    CalcValue::checked_float(op(args[0].as_f64()))
+}
+
+fn call_function(name: &str, args: &[CalcValue]) -> Result<CalcValue, CalcError> {
+    match name {
+        "sqrt" => {
+            let args = expect_args(name, args, 1)?;
+            if args[0].as_f64() < 0.0 {
+                return Err(CalcError::Domain("square root of a negative number"));
+            }
+            if let Some(value) = args[0].as_integer() {
+                let root = (value as f64).sqrt().round() as i128;
+                if root.checked_mul(root) == Some(value) {
+                    return Ok(CalcValue::Int(root));
+                }
+            }
+            unary_float(name, args, f64::sqrt)
+        }
+        "cbrt" => unary_float(name, args, f64::cbrt),
+        "pow" => {
+            let args = expect_args(name, args, 2)?;
+            args[0].pow(args[1])
+        }
+        "log" | "ln" | "log2" | "log10" => {
+            let (value, base) = match (name, args) {
+                ("log", [value, base]) => (*value, base.as_f64()),
+                ("log" | "log10", [value]) => (*value, 10.0),
+                ("log2", [value]) => (*value, 2.0),
+                ("ln", [value]) => (*value, E),
+                _ => {
+                    return Err(CalcError::WrongArity {
+                        name: name.to_string(),
+                        expected: if name == "log" {
+                            "1 or 2 arguments"
+                        } else {
+                            "1 argument"
+                        },
+                    })
+                }
+            };
+            if value.as_f64() <= 0.0 {
+                return Err(CalcError::Domain("logarithm of a non-positive number"));
+            }
+            if base <= 0.0 || base == 1.0 {
+                return Err(CalcError::Domain("invalid logarithm base"));
+            }
+            CalcValue::checked_float(value.as_f64().ln() / base.ln())
+        }
+        "exp" => unary_float(name, args, f64::exp),
+        "sin" => unary_float(name, args, f64::sin),
+        "cos" => unary_float(name, args, f64::cos),
+        "tan" => unary_float(name, args, f64::tan),
+        "asin" | "acos" => {
+            let args = expect_args(name, args, 1)?;
+            if !(-1.0..=1.0).contains(&args[0].as_f64()) {
+                return Err(CalcError::Domain("argument must be between -1 and 1"));
+            }
+            unary_float(
+                name,
+                args,
+                if name == "asin" { f64::asin } else { f64::acos },
+            )
+        }
+        "atan" => unary_float(name, args, f64::atan),
+        "deg" => unary_float(name, args, f64::to_degrees),
+        "rad" => unary_float(name, args, f64::to_radians),
+        "abs" => {
+            let args = expect_args(name, args, 1)?;
+            match args[0] {
+                CalcValue::Int(value) => Ok(value
+                    .checked_abs()
+                    .map_or(CalcValue::Float((value as f64).abs()), CalcValue::Int)),
+                CalcValue::Float(value) => Ok(CalcValue::Float(value.abs())),
+            }
+        }
+        "floor" | "ceil" | "round" | "trunc" => {
+            let args = expect_args(name, args, 1)?;
+            let CalcValue::Float(value) = args[0] else {
+                return Ok(args[0]);
+            };
+            let rounded = match name {
+                "floor" => value.floor(),
+                "ceil" => value.ceil(),
+                "round" => value.round(),
+                _ => value.trunc(),
+            };
+            Ok(CalcValue::Float(rounded)
+                .as_integer()
+                .map_or(CalcValue::Float(rounded), CalcValue::Int))
+        }
+        "min" | "max" => {
+            let Some((first, rest)) = args.split_first() else {
+                return Err(CalcError::WrongArity {
+                    name: name.to_string(),
+                    expected: "at least 1 argument",
+                });
+            };
+            Ok(rest.iter().fold(*first, |best, value| {
+                let replace = if name == "min" {
+                    value.as_f64() < best.as_f64()
+                } else {
+                    value.as_f64() > best.as_f64()
+                };
+                if replace {
+                    *value
+                } else {
+                    best
+                }
+            }))
+        }
+        _ => Err(CalcError::UnknownName(name.to_string())),
+    }
+}
+
+pub fn evaluate(expression: &str) -> Result<CalcValue, CalcError> {
+    let tokens = tokenize(expression)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        depth: 0,
+    };
+    let value = parser.expression()?;
+    match parser.peek() {
+        None => Ok(value),
+        Some(Token::RParen) => Err(CalcError::UnexpectedToken(")".to_string())),
+        Some(token) => Err(CalcError::UnexpectedToken(token.describe())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, CalcError, CalcValue};
+
+    fn eval_text(expression: &str) -> String {
+        evaluate(expression)
+            .expect("expression evaluates")
+            .to_string()
+    }
+
+    #[test]
+    fn respects_precedence_and_parentheses() {
+        assert_eq!(eval_text("1 + 2 * 3"), "7");
+        assert_eq!(eval_text("(1 + 2) * 3"), "9");
+        assert_eq!(eval_text("2 ^ 3 ^ 2"), "512");
+        assert_eq!(eval_text("-2 ^ 2"), "-4");
+        assert_eq!(eval_text("2 ^ -1"), "0.5");
+        assert_eq!(eval_text("10 mod 4"), "2");
+        assert_eq!(eval_text("7 / 2"), "3.5");
+        assert_eq!(eval_text("8 / 2"), "4");
+    }
+
+    #[test]
+    fn handles_floats_and_big_integers() {
+        assert_eq!(eval_text("0.1 + 0.2"), "0.3");
+        assert_eq!(eval_text("1.5e3"), "1500");
+        assert_eq!(eval_text("2 ^ 100"), "1267650600228229401496703205376");
+        assert_eq!(eval_text("25!"), "15511210043330985984000000");
+        assert_eq!(eval_text("2 ^ 200"), "1.6069380442589903e60");
+    }
+
+    #[test]
+    fn supports_percent_and_radix_literals() {
+        assert_eq!(eval_text("50%"), "0.5");
+        assert_eq!(eval_text("200 + 10%"), "220");
+        assert_eq!(eval_text("200 - 25%"), "150");
+        assert_eq!(eval_text("200 * 10%"), "20");
+        assert_eq!(eval_text("10 % 3"), "1");
+        assert_eq!(eval_text("10%3 + 1"), "2");
+        assert_eq!(eval_text("2 * 7 % (1 + 3)"), "2");
+        assert_eq!(eval_text("50% - 3"), "-2.5");
+        assert_eq!(eval_text("250% mod 2"), "0.5");
+        assert_eq!(eval_text("0xff + 0b1010 + 0o17"), "280");
+        assert_eq!(eval_text("1_000_000 / 4"), "250000");
+    }
+
+    #[test]
+    fn supports_functions_and_constants() {
+        assert_eq!(eval_text("sqrt(144)"), "12");
+        assert_eq!(eval_text("sqrt(2)"), "1.414213562373");
+        assert_eq!(eval_text("pow(2, 10)"), "1024");
+        assert_eq!(eval_text("log(1000)"), "3");
+        assert_eq!(eval_text("log(8, 2)"), "3");
+        assert_eq!(eval_text("ln(e)"), "1");
+        assert_eq!(eval_text("sin(pi / 2)"), "1");
+        assert_eq!(eval_text("cos(0)"), "1");
+        assert_eq!(eval_text("max(3, 9, 4) - min(2, 1)"), "8");
+        assert_eq!(eval_text("round(2.5) + floor(-1.5)"), "1");
+        assert_eq!(
+            evaluate("PI").unwrap(),
+            CalcValue::Float(std::f64::consts::PI)
+        );
+    }
+
+    #[test]
+    fn reports_readable_errors() {
+        assert_eq!(evaluate("1 / 0"), Err(CalcError::DivisionByZero));
+        assert_eq!(evaluate("(1 + 2"), Err(CalcError::MissingClosingParen));
+        assert_eq!(evaluate("1 +"), Err(CalcError::UnexpectedEnd));
+        assert_eq!(
+            evaluate("foo(1)"),
+            Err(CalcError::UnknownName("foo".to_string()))
+        );
+        assert_eq!(
+            evaluate("sqrt(-1)").unwrap_err().to_string(),
+            "square root of a negative number"
+        );
+        assert_eq!(
+            evaluate("pow(2)").unwrap_err().to_string(),
+            "pow expects 2 arguments"
+        );
+        assert_eq!(
+            evaluate("2 $ 3").unwrap_err().to_string(),
+            "unexpected character '$'"
+        );
+        assert_eq!(
+            evaluate("0xZZ"),
+            Err(CalcError::InvalidNumber("0xZZ".to_string()))
+        );
+        assert_eq!(evaluate("10 ^ 400"), Err(CalcError::NotFinite));
+        assert_eq!(evaluate(&"(".repeat(200)), Err(CalcError::TooDeep));
+        assert_eq!(evaluate(&"-".repeat(200)), Err(CalcError::TooDeep));
+        assert_eq!(
+            evaluate(&format!("{}1", "+".repeat(100_000))),
+            Ok(CalcValue::Int(1))
+        );
+    }
+}
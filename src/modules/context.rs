@@ -23,6 +23,7 @@ pub enum ModuleActionRequest {
     ClearInputAccessory,
     RegisterCommand(ModuleCommandDef),
     RegisterProvider(ModuleProviderDef),
+    CopyText(String),
 }
 
 #[derive(Debug, Clone)]
@@ -120,6 +121,11 @@ impl ModuleCtx {
             .push(ModuleActionRequest::ClearInputAccessory);
     }
 
+    pub fn copy_text(&mut self, text: impl Into<String>) {
+        self.action_requests
+            .push(ModuleActionRequest::CopyText(text.into()));
+    }
+
     pub fn register_command(&mut self, command: ModuleCommandDef) {
         self.action_requests
             .push(ModuleActionRequest::RegisterCommand(command));
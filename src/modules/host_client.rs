@@ -15,6 +15,9 @@ use super::types::ModuleDescriptor;
 
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+/// Initialize and the first onLoad include starting Node and importing the
+/// module, so they get more time than a single hook call.
+const HOST_STARTUP_TIMEOUT_MS: u64 = 5_000;
 
 #[derive(Debug)]
 pub enum HostClientError {
@@ -77,7 +80,7 @@ impl ExternalModuleHost {
             stdin,
             response_rx,
             next_id: 1,
-            response_timeout_ms,
+            response_timeout_ms: response_timeout_ms.max(HOST_STARTUP_TIMEOUT_MS),
             max_ipc_payload_bytes,
         };
 
@@ -120,6 +123,7 @@ impl ExternalModuleHost {
             }
         }
 
+        host.response_timeout_ms = response_timeout_ms;
         Ok(host)
     }
 
@@ -323,6 +327,55 @@ where
     rx
 }
 
+fn module_state_dir(descriptor: &ModuleDescriptor) -> Option<String> {
+    let source_path = Path::new(&descriptor.source_path);
+    let modules_dir = match descriptor.source_type {
+        super::types::ModuleSourceType::Directory => source_path.parent(),
+        super::types::ModuleSourceType::Rmod => source_path.parent(),
+    }?;
+    let data_dir = modules_dir.parent()?;
+    Some(
+        data_dir
+            .join("state")
+            .join("modules")
+            .join(&descriptor.name)
+            .to_string_lossy()
+            .to_string(),
+    )
+}
+
+pub(super) fn module_host_binary_path() -> Result<PathBuf, HostClientError> {
+    let current_exe =
+        std::env::current_exe().map_err(|err| HostClientError::Io(err.to_string()))?;
+    let exe_name = current_exe
+        .file_name()
+        .and_then(|value| value.to_str())
+        .ok_or_else(|| HostClientError::Io("invalid current executable name".to_string()))?;
+
+    let host_name = if exe_name.ends_with(".exe") {
+        "rmenu-module-host.exe"
+    } else {
+        "rmenu-module-host"
+    };
+
+    let sibling = current_exe.with_file_name(host_name);
+    if sibling.exists() {
+        return Ok(sibling);
+    }
+
+    let parent_sibling = current_exe
+        .parent()
+        .and_then(|parent| parent.parent())
+        .map(|parent| parent.join(host_name));
+    if let Some(path) = parent_sibling {
+        if path.exists() {
+            return Ok(path);
+        }
+    }
+
+    Ok(sibling)
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
@@ -335,10 +388,16 @@ mod tests {
     use super::{spawn_response_reader_from_reader, ExternalModuleHost, HostClientError};
     use crate::modules::ipc::{HostRequestPayload, IpcSnapshot};
 
-    #[cfg(windows)]
     fn test_host(response_timeout_ms: u64, max_ipc_payload_bytes: usize) -> ExternalModuleHost {
-        let mut child = Command::new("powershell.exe")
-            .args(["-NoProfile", "-Command", "Start-Sleep -Seconds 60"])
+        #[cfg(windows)]
+        let mut command = Command::new("powershell.exe");
+        #[cfg(windows)]
+        command.args(["-NoProfile", "-Command", "Start-Sleep -Seconds 60"]);
+        #[cfg(not(windows))]
+        let mut command = Command::new("sh");
+        #[cfg(not(windows))]
+        command.args(["-c", "sleep 60"]);
+        let mut child = command
             .stdin(Stdio::piped())
             .stdout(Stdio::null())
             .stderr(Stdio::null())
@@ -390,7 +449,6 @@ mod tests {
         assert_eq!(err, RecvTimeoutError::Disconnected);
     }
 
-    #[cfg(windows)]
     #[test]
     fn send_request_times_out_and_kills_unresponsive_host() {
         let mut host = test_host(10, 1024);
@@ -403,7 +461,6 @@ mod tests {
         assert!(host.child.try_wait().expect("child status").is_some());
     }
 
-    #[cfg(windows)]
     #[test]
     fn send_request_rejects_oversized_request_before_waiting_for_response() {
         let mut host = test_host(1_000, 16);
@@ -421,52 +478,3 @@ mod tests {
         host.force_kill();
     }
 }
-
-fn module_state_dir(descriptor: &ModuleDescriptor) -> Option<String> {
-    let source_path = Path::new(&descriptor.source_path);
-    let modules_dir = match descriptor.source_type {
-        super::types::ModuleSourceType::Directory => source_path.parent(),
-        super::types::ModuleSourceType::Rmod => source_path.parent(),
-    }?;
-    let data_dir = modules_dir.parent()?;
-    Some(
-        data_dir
-            .join("state")
-            .join("modules")
-            .join(&descriptor.name)
-            .to_string_lossy()
-            .to_string(),
-    )
-}
-
-fn module_host_binary_path() -> Result<PathBuf, HostClientError> {
-    let current_exe =
-        std::env::current_exe().map_err(|err| HostClientError::Io(err.to_string()))?;
-    let exe_name = current_exe
-        .file_name()
-        .and_then(|value| value.to_str())
-        .ok_or_else(|| HostClientError::Io("invalid current executable name".to_string()))?;
-
-    let host_name = if exe_name.ends_with(".exe") {
-        "rmenu-module-host.exe"
-    } else {
-        "rmenu-module-host"
-    };
-
-    let sibling = current_exe.with_file_name(host_name);
-    if sibling.exists() {
-        return Ok(sibling);
-    }
-
-    let parent_sibling = current_exe
-        .parent()
-        .and_then(|parent| parent.parent())
-        .map(|parent| parent.join(host_name));
-    if let Some(path) = parent_sibling {
-        if path.exists() {
-            return Ok(path);
-        }
-    }
-
-    Ok(sibling)
-}
@@ -78,6 +78,7 @@ pub enum IpcAction {
     ClearInputAccessory,
     ReplaceItems { items: Vec<IpcItem> },
     Toast { text: String },
+    CopyText { text: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -111,7 +112,20 @@ pub struct IpcItem {
     pub subtitle: Option<String>,
     pub source: Option<String>,
     pub target: Option<String>,
+    #[serde(default, alias = "copyText")]
+    pub copy_text: Option<String>,
     pub quick_select_key: Option<String>,
     pub badge: Option<String>,
     pub hint: Option<String>,
+    #[serde(default, alias = "secondaryActions")]
+    pub secondary_actions: Vec<IpcItemAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpcItemAction {
+    pub id: String,
+    pub label: String,
+    pub target: Option<String>,
+    #[serde(default, alias = "copyText")]
+    pub copy_text: Option<String>,
 }
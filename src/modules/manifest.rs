@@ -3,6 +3,7 @@ use std::fs;
 use std::path::{Component, Path};
 
 use super::types::{ModuleDescriptor, ModuleSourceType, ResidentHelperDescriptor};
+use crate::schemes::parse_module_schemes;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ManifestParseError {
@@ -11,6 +12,7 @@ pub enum ManifestParseError {
     InvalidApiVersion(String),
     MissingEntry,
     InvalidResidentCommand(String),
+    InvalidScheme(String),
 }
 
 pub fn load_directory_descriptor(
@@ -73,6 +75,12 @@ pub fn load_directory_descriptor(
         .ok_or(ManifestParseError::MissingRequiredField("kind"))?;
     let entry = string_value(&root_values, "entry").ok_or(ManifestParseError::MissingEntry)?;
     let capabilities = parse_array(&root_values, "capabilities");
+    let schemes = parse_module_schemes(
+        parse_array(&root_values, "schemes")
+            .iter()
+            .map(String::as_str),
+    )
+    .map_err(ManifestParseError::InvalidScheme)?;
 
     let entry_path = module_dir.join(entry);
     let entry_code =
@@ -104,6 +112,7 @@ pub fn load_directory_descriptor(
         api_version,
         kind,
         capabilities,
+        schemes,
         enabled: bool_value(&root_values, "enabled").unwrap_or(true),
         priority: int_value(&root_values, "priority").unwrap_or(0),
         description: string_value(&root_values, "description"),
@@ -234,6 +243,7 @@ api_version = "1"
 kind = "external-js"
 entry = "module.js"
 capabilities = ["providers", "commands"]
+schemes = ["DirMod", "dirmod"]
 enabled = false
 priority = 42
 description = "Directory module"
@@ -257,6 +267,7 @@ file = "config.json"
         assert_eq!(descriptor.api_version, 1);
         assert_eq!(descriptor.kind, "external-js");
         assert_eq!(descriptor.capabilities, vec!["providers", "commands"]);
+        assert_eq!(descriptor.schemes, vec!["dirmod"]);
         assert!(!descriptor.enabled);
         assert_eq!(descriptor.priority, 42);
         assert_eq!(descriptor.description.as_deref(), Some("Directory module"));
@@ -400,6 +411,29 @@ entry = "module.js"
         let err = load_directory_descriptor(&invalid_api).expect_err("invalid api must fail");
         assert_eq!(err, ManifestParseError::InvalidApiVersion("v1".to_string()));
         let _ = fs::remove_dir_all(invalid_api);
+
+        let builtin_scheme = temp_module_dir("builtin-scheme");
+        fs::write(
+            builtin_scheme.join("module.toml"),
+            r#"
+name = "bad-scheme"
+version = "1.0.0"
+api_version = "1"
+kind = "external-js"
+entry = "module.js"
+schemes = ["copy"]
+"#,
+        )
+        .expect("write manifest");
+        fs::write(
+            builtin_scheme.join("module.js"),
+            "export default () => ({})",
+        )
+        .expect("write entry");
+
+        let err = load_directory_descriptor(&builtin_scheme).expect_err("builtin scheme must fail");
+        assert!(matches!(err, ManifestParseError::InvalidScheme(_)));
+        let _ = fs::remove_dir_all(builtin_scheme);
     }
 
     #[test]
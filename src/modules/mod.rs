@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 pub mod actions;
+pub mod calc;
 pub mod context;
 pub mod hooks;
 pub mod host_client;
@@ -20,12 +21,15 @@ use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use host_client::{ExternalModuleHost, HostClientError};
-use ipc::{IpcAction, IpcInputAccessory, IpcItem, IpcKeyEvent, IpcSnapshot};
+use ipc::{IpcAction, IpcInputAccessory, IpcItem, IpcItemAction, IpcKeyEvent, IpcSnapshot};
 use loader::discover_module_descriptors;
 
-use crate::app_state::{AppState, LauncherItem, LauncherSource};
+use crate::app_state::{
+    AppState, LauncherItem, LauncherItemAction, LauncherItemActionKind, LauncherSource,
+};
 use crate::rsnip_companion::{install_rsnip_latest, RsnipCompanion};
 use crate::rtasks_companion::{install_rtasks_latest, RtasksCompanion};
+use crate::schemes::{split_scheme, SchemeRegistry};
 
 use actions::{apply_action_request, ActionRuntimeView};
 use context::{ModuleActionRequest, ModuleCtx, ModuleSnapshot};
@@ -33,8 +37,8 @@ use hooks::RuntimeModule;
 use state::ModuleRuntimeState;
 use types::{
     BadgeKind, InputAccessoryKind, ModuleAction, ModuleCommandDef, ModuleDescriptor,
-    ModuleInputAccessory, ModuleItem, ModuleItemCapabilities, ModuleItemDecorations,
-    ModuleKeyEvent, ModuleMode, ModuleProviderDef, MODULE_API_VERSION,
+    ModuleInputAccessory, ModuleItem, ModuleItemAction, ModuleItemCapabilities,
+    ModuleItemDecorations, ModuleKeyEvent, ModuleMode, ModuleProviderDef, MODULE_API_VERSION,
 };
 
 const MAX_RECENT_HOST_ERRORS: usize = 5;
@@ -48,6 +52,8 @@ const IPC_ITEM_MAX_TITLE_LEN: usize = 256;
 const IPC_ITEM_MAX_SUBTITLE_LEN: usize = 512;
 const IPC_ITEM_MAX_SOURCE_LEN: usize = 64;
 const IPC_ITEM_MAX_TARGET_LEN: usize = 4096;
+const IPC_ITEM_MAX_COPY_TEXT_LEN: usize = 65536;
+const IPC_ITEM_MAX_SECONDARY_ACTIONS: usize = 8;
 const IPC_ITEM_MAX_BADGE_LEN: usize = 32;
 const IPC_ITEM_MAX_HINT_LEN: usize = 512;
 
@@ -145,6 +151,13 @@ pub struct ModuleRuntime {
     last_hot_reload_at: Option<Instant>,
     policy: ModuleRuntimePolicy,
     modules_dir: PathBuf,
+    schemes: SchemeRegistry,
+}
+
+impl Default for ModuleRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ModuleRuntime {
@@ -163,6 +176,7 @@ impl ModuleRuntime {
             last_hot_reload_at: None,
             policy: ModuleRuntimePolicy::default(),
             modules_dir: PathBuf::from("modules"),
+            schemes: SchemeRegistry::new(),
         }
     }
 
@@ -187,6 +201,30 @@ impl ModuleRuntime {
         &self.external_descriptors
     }
 
+    /// Builtin scheme handlers plus the schemes enabled modules declare.
+    pub fn schemes(&self) -> &SchemeRegistry {
+        &self.schemes
+    }
+
+    pub fn schemes_mut(&mut self) -> &mut SchemeRegistry {
+        &mut self.schemes
+    }
+
+    /// Re-claims the declared schemes of enabled modules in priority order; a
+    /// scheme already claimed by an earlier module is skipped with a warning.
+    fn sync_module_schemes(&mut self, silent_mode: bool) {
+        self.schemes.clear_modules();
+        for descriptor in self.external_descriptors.iter().filter(|d| d.enabled) {
+            for scheme in &descriptor.schemes {
+                if let Err(err) = self.schemes.register_module(&descriptor.name, scheme) {
+                    if !silent_mode {
+                        eprintln!("module '{}' scheme ignored: {err}", descriptor.name);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn configure_modules_dir(&mut self, modules_dir: impl Into<PathBuf>) {
         self.modules_dir = modules_dir.into();
     }
@@ -264,6 +302,7 @@ impl ModuleRuntime {
             self.host_health.clear();
             self.last_restart_attempt.clear();
             self.external_descriptors = descriptors;
+            self.sync_module_schemes(silent_mode);
 
             for descriptor in &self.external_descriptors {
                 self.host_capabilities.insert(
@@ -338,6 +377,7 @@ impl ModuleRuntime {
         }
 
         self.external_descriptors = descriptors;
+        self.sync_module_schemes(silent_mode);
 
         for name in changed_or_new {
             if let Some(index) = self
@@ -731,15 +771,29 @@ impl ModuleRuntime {
     }
 
     pub fn collect_provider_items(&mut self, app_state: &AppState) -> Vec<LauncherItem> {
-        let mut provided: Vec<ModuleItem> = Vec::new();
         let query = app_state.current_input.clone();
+        self.collect_provider_items_matching(app_state, &query, |_| true)
+    }
+
+    /// Collects provider items for `query` from the modules and provider hosts
+    /// whose name `include_module` accepts.
+    pub fn collect_provider_items_matching(
+        &mut self,
+        app_state: &AppState,
+        query: &str,
+        include_module: impl Fn(&str) -> bool,
+    ) -> Vec<LauncherItem> {
+        let mut provided: Vec<ModuleItem> = Vec::new();
         let mut runtime_view_state = app_state.clone();
 
         for module in &mut self.modules {
+            if !include_module(module.name()) {
+                continue;
+            }
             let module_name = module.name().to_string();
             let snapshot = snapshot_from_app_state(&runtime_view_state);
             let mut ctx = ModuleCtx::new(module_name, snapshot);
-            let mut items = module.provide_items(&query, &mut ctx);
+            let mut items = module.provide_items(query, &mut ctx);
             provided.append(&mut items);
             Self::apply_ctx_requests(
                 module.name(),
@@ -759,6 +813,9 @@ impl ModuleRuntime {
             if providers_started.elapsed().as_millis() > self.policy.provider_total_budget_ms {
                 break;
             }
+            if !include_module(&host.module_name) {
+                continue;
+            }
 
             let has_capability = host_capabilities
                 .get(&host.module_name)
@@ -770,11 +827,18 @@ impl ModuleRuntime {
 
             let started = Instant::now();
             let snapshot = ipc_snapshot_from_app_state(app_state, false);
-            match host.provide_items(&query, snapshot) {
+            match host.provide_items(query, snapshot) {
                 Ok(items) => {
                     let items = cap_ipc_items(items, self.policy.max_items_per_provider_host);
-                    let sanitized =
-                        sanitize_ipc_items(items, &host.module_name, app_state.silent_mode);
+                    let can_copy = host_capabilities
+                        .get(&host.module_name)
+                        .is_some_and(|caps| caps.contains("clipboard"));
+                    let sanitized = sanitize_ipc_items(
+                        items,
+                        &host.module_name,
+                        can_copy,
+                        app_state.silent_mode,
+                    );
                     provided.extend(sanitized.into_iter().map(module_item_from_ipc_item));
                     telemetry_events.push((
                         host.module_name.clone(),
@@ -821,19 +885,9 @@ impl ModuleRuntime {
             .collect()
     }
 
-    pub fn merge_rank_dataset(
-        &self,
-        core_items: Vec<LauncherItem>,
-        provider_items: Vec<LauncherItem>,
-    ) -> Vec<LauncherItem> {
-        match self.policy.dedupe_source_priority {
-            DedupeSourcePriority::CoreFirst => {
-                dedupe_launcher_items_by_priority(vec![core_items, provider_items])
-            }
-            DedupeSourcePriority::ProviderFirst => {
-                dedupe_launcher_items_by_priority(vec![provider_items, core_items])
-            }
-        }
+    /// Whether provider items win over core items with the same target.
+    pub fn providers_first(&self) -> bool {
+        self.policy.dedupe_source_priority == DedupeSourcePriority::ProviderFirst
     }
 
     pub fn dispatch_command(
@@ -981,8 +1035,15 @@ impl ModuleRuntime {
             let snapshot = ipc_snapshot_from_app_state(app_state, false);
             match host.decorate_items(ipc_items.clone(), snapshot) {
                 Ok(next_items) => {
-                    ipc_items =
-                        sanitize_ipc_items(next_items, &host.module_name, app_state.silent_mode);
+                    let can_copy = host_capabilities
+                        .get(&host.module_name)
+                        .is_some_and(|caps| caps.contains("clipboard"));
+                    ipc_items = sanitize_ipc_items(
+                        next_items,
+                        &host.module_name,
+                        can_copy,
+                        app_state.silent_mode,
+                    );
                     telemetry_events.push((
                         host.module_name.clone(),
                         started.elapsed().as_millis(),
@@ -1051,6 +1112,10 @@ impl ModuleRuntime {
         self.state.active_input_accessory = None;
     }
 
+    pub fn take_pending_copy_text(&mut self) -> Option<String> {
+        self.state.pending_copy_text.take()
+    }
+
     pub fn items_replaced_in_cycle(&self) -> bool {
         self.state.items_replaced_in_cycle
     }
@@ -1478,13 +1543,17 @@ impl ModuleRuntime {
                 }
                 IpcAction::ClearInputAccessory => ctx.clear_input_accessory(),
                 IpcAction::ReplaceItems { items } => {
-                    let sanitized = sanitize_ipc_items(items, module_name, app_state.silent_mode)
-                        .into_iter()
-                        .map(module_item_from_ipc_item)
-                        .collect();
+                    let can_copy =
+                        allowed_capabilities.is_none_or(|caps| caps.contains("clipboard"));
+                    let sanitized =
+                        sanitize_ipc_items(items, module_name, can_copy, app_state.silent_mode)
+                            .into_iter()
+                            .map(module_item_from_ipc_item)
+                            .collect();
                     ctx.replace_items(sanitized);
                 }
                 IpcAction::Toast { text } => ctx.toast(text),
+                IpcAction::CopyText { text } => ctx.copy_text(text),
             }
         }
         Self::apply_ctx_requests(
@@ -1577,6 +1646,7 @@ fn descriptor_signature(descriptor: &ModuleDescriptor) -> u64 {
     descriptor.enabled.hash(&mut hasher);
     descriptor.priority.hash(&mut hasher);
     descriptor.capabilities.hash(&mut hasher);
+    descriptor.schemes.hash(&mut hasher);
     descriptor.entry_code.hash(&mut hasher);
     descriptor.config_json.hash(&mut hasher);
     descriptor.readme.hash(&mut hasher);
@@ -1619,7 +1689,7 @@ fn split_registered_command_key(key: &str) -> Option<(&str, &str)> {
     key.split_once("::")
 }
 
-fn launcher_item_dedupe_key(item: &LauncherItem) -> String {
+pub(crate) fn launcher_item_dedupe_key(item: &LauncherItem) -> String {
     let target = item.target.trim();
     if !target.is_empty() {
         return target.to_ascii_lowercase();
@@ -1649,22 +1719,6 @@ fn should_dispatch_module_key_event(event: &ModuleKeyEvent) -> bool {
     )
 }
 
-fn dedupe_launcher_items_by_priority(groups: Vec<Vec<LauncherItem>>) -> Vec<LauncherItem> {
-    let mut seen: BTreeSet<String> = BTreeSet::new();
-    let mut output = Vec::new();
-
-    for group in groups {
-        for item in group {
-            let key = launcher_item_dedupe_key(&item);
-            if seen.insert(key) {
-                output.push(item);
-            }
-        }
-    }
-
-    output
-}
-
 fn action_name(request: &ModuleActionRequest) -> &'static str {
     match request {
         ModuleActionRequest::SetQuery(_) => "set_query",
@@ -1678,6 +1732,7 @@ fn action_name(request: &ModuleActionRequest) -> &'static str {
         ModuleActionRequest::ClearInputAccessory => "clear_input_accessory",
         ModuleActionRequest::RegisterCommand(_) => "register_command",
         ModuleActionRequest::RegisterProvider(_) => "register_provider",
+        ModuleActionRequest::CopyText(_) => "copy_text",
     }
 }
 
@@ -1685,6 +1740,7 @@ fn required_capability_for_action(request: &ModuleActionRequest) -> Option<&'sta
     match request {
         ModuleActionRequest::RegisterProvider(_) => Some("providers"),
         ModuleActionRequest::RegisterCommand(_) => Some("commands"),
+        ModuleActionRequest::CopyText(_) => Some("clipboard"),
         ModuleActionRequest::SetInputAccessory(_) | ModuleActionRequest::ClearInputAccessory => {
             Some("input-accessory")
         }
@@ -1743,6 +1799,8 @@ fn source_to_name(source: LauncherSource) -> &'static str {
         LauncherSource::History => "history",
         LauncherSource::StartMenu => "start_menu",
         LauncherSource::Path => "path",
+        LauncherSource::Extra => "extra",
+        LauncherSource::Bookmark => "bookmark",
     }
 }
 
@@ -1751,18 +1809,37 @@ fn name_to_source(value: &str) -> LauncherSource {
         "history" => LauncherSource::History,
         "start_menu" => LauncherSource::StartMenu,
         "path" => LauncherSource::Path,
+        "extra" => LauncherSource::Extra,
+        "bookmark" => LauncherSource::Bookmark,
         _ => LauncherSource::Direct,
     }
 }
 
+fn target_from_module_action(action: ModuleAction) -> Option<String> {
+    match action {
+        ModuleAction::LaunchTarget { target } => Some(target),
+        ModuleAction::RunCommand { name, args } => {
+            if args.is_empty() {
+                Some(name)
+            } else {
+                Some(format!("{} {}", name, args.join(" ")))
+            }
+        }
+        ModuleAction::CopyText { .. } | ModuleAction::Noop => None,
+    }
+}
+
 fn module_item_from_launcher_item(item: LauncherItem) -> ModuleItem {
     ModuleItem {
         id: item.target.clone(),
         title: item.label,
         subtitle: Some(item.target.clone()),
         source: Some(source_to_name(item.source).to_string()),
-        action: ModuleAction::LaunchTarget {
-            target: item.target,
+        action: match item.copy_text {
+            Some(text) => ModuleAction::CopyText { text },
+            None => ModuleAction::LaunchTarget {
+                target: item.target,
+            },
         },
         capabilities: ModuleItemCapabilities {
             quick_select_key: item.quick_select_key,
@@ -1773,6 +1850,23 @@ fn module_item_from_launcher_item(item: LauncherItem) -> ModuleItem {
             hint: item.trailing_hint,
             icon: None,
         },
+        // Core-only actions such as history removal are not handed to modules.
+        secondary_actions: item
+            .secondary_actions
+            .into_iter()
+            .filter_map(|action| {
+                let module_action = match action.kind {
+                    LauncherItemActionKind::Launch(target) => ModuleAction::LaunchTarget { target },
+                    LauncherItemActionKind::Copy(text) => ModuleAction::CopyText { text },
+                    _ => return None,
+                };
+                Some(ModuleItemAction {
+                    id: action.id,
+                    label: action.label,
+                    action: module_action,
+                })
+            })
+            .collect(),
     }
 }
 
@@ -1791,16 +1885,9 @@ fn dedupe_module_items(items: Vec<ModuleItem>) -> Vec<ModuleItem> {
 }
 
 fn module_item_to_ipc_item(item: ModuleItem) -> IpcItem {
-    let target = match item.action {
-        ModuleAction::LaunchTarget { target } => Some(target),
-        ModuleAction::RunCommand { name, args } => {
-            if args.is_empty() {
-                Some(name)
-            } else {
-                Some(format!("{} {}", name, args.join(" ")))
-            }
-        }
-        ModuleAction::Noop => None,
+    let (target, copy_text) = match item.action {
+        ModuleAction::CopyText { text } => (None, Some(text)),
+        other => (target_from_module_action(other), None),
     };
 
     IpcItem {
@@ -1809,9 +1896,40 @@ fn module_item_to_ipc_item(item: ModuleItem) -> IpcItem {
         subtitle: item.subtitle,
         source: item.source,
         target,
+        copy_text,
         quick_select_key: item.capabilities.quick_select_key,
         badge: item.decorations.badge,
         hint: item.decorations.hint,
+        secondary_actions: item
+            .secondary_actions
+            .into_iter()
+            .filter_map(ipc_item_action_from_module_action)
+            .collect(),
+    }
+}
+
+fn ipc_item_action_from_module_action(action: ModuleItemAction) -> Option<IpcItemAction> {
+    let (target, copy_text) = match action.action {
+        ModuleAction::CopyText { text } => (None, Some(text)),
+        other => (Some(target_from_module_action(other)?), None),
+    };
+    Some(IpcItemAction {
+        id: action.id,
+        label: action.label,
+        target,
+        copy_text,
+    })
+}
+
+fn module_action_from_ipc_item_action(action: IpcItemAction) -> ModuleItemAction {
+    ModuleItemAction {
+        id: action.id,
+        label: action.label,
+        action: match (action.copy_text, action.target) {
+            (Some(text), _) => ModuleAction::CopyText { text },
+            (None, Some(target)) => ModuleAction::LaunchTarget { target },
+            (None, None) => ModuleAction::Noop,
+        },
     }
 }
 
@@ -1822,11 +1940,18 @@ fn cap_ipc_items(mut items: Vec<IpcItem>, max_items: usize) -> Vec<IpcItem> {
     items
 }
 
-fn sanitize_ipc_items(items: Vec<IpcItem>, module_name: &str, silent_mode: bool) -> Vec<IpcItem> {
+/// `can_copy` is whether the module holds the `clipboard` capability; without
+/// it copy payloads are dropped.
+fn sanitize_ipc_items(
+    items: Vec<IpcItem>,
+    module_name: &str,
+    can_copy: bool,
+    silent_mode: bool,
+) -> Vec<IpcItem> {
     items
         .into_iter()
         .enumerate()
-        .filter_map(|(index, item)| match sanitize_ipc_item(item) {
+        .filter_map(|(index, item)| match sanitize_ipc_item(item, can_copy) {
             Ok(item) => Some(item),
             Err(reason) => {
                 if !silent_mode {
@@ -1841,19 +1966,26 @@ fn sanitize_ipc_items(items: Vec<IpcItem>, module_name: &str, silent_mode: bool)
         .collect()
 }
 
-fn sanitize_ipc_item(item: IpcItem) -> Result<IpcItem, String> {
+fn sanitize_ipc_item(item: IpcItem, can_copy: bool) -> Result<IpcItem, String> {
     let id = sanitize_required_single_line(item.id, IPC_ITEM_MAX_ID_LEN, "id")?;
     let title = sanitize_required_single_line(item.title, IPC_ITEM_MAX_TITLE_LEN, "title")?;
 
     let subtitle = sanitize_optional_single_line(item.subtitle, IPC_ITEM_MAX_SUBTITLE_LEN);
 
     let source = sanitize_optional_single_line(item.source, IPC_ITEM_MAX_SOURCE_LEN)
-        .and_then(|value| sanitize_source(value));
+        .and_then(sanitize_source);
 
-    let target = sanitize_optional_multiline(item.target, IPC_ITEM_MAX_TARGET_LEN);
+    let target = sanitize_ipc_target(item.target);
+    let copy_text = sanitize_ipc_copy_text(item.copy_text, can_copy);
     let quick_select_key = sanitize_quick_select_key(item.quick_select_key);
     let badge = sanitize_optional_single_line(item.badge, IPC_ITEM_MAX_BADGE_LEN);
     let hint = sanitize_optional_single_line(item.hint, IPC_ITEM_MAX_HINT_LEN);
+    let secondary_actions = item
+        .secondary_actions
+        .into_iter()
+        .filter_map(|action| sanitize_ipc_item_action(action, can_copy))
+        .take(IPC_ITEM_MAX_SECONDARY_ACTIONS)
+        .collect();
 
     Ok(IpcItem {
         id,
@@ -1861,12 +1993,46 @@ fn sanitize_ipc_item(item: IpcItem) -> Result<IpcItem, String> {
         subtitle,
         source,
         target,
+        copy_text,
         quick_select_key,
         badge,
         hint,
+        secondary_actions,
     })
 }
 
+fn sanitize_ipc_item_action(action: IpcItemAction, can_copy: bool) -> Option<IpcItemAction> {
+    let id = sanitize_required_single_line(action.id, IPC_ITEM_MAX_ID_LEN, "id").ok()?;
+    let label =
+        sanitize_required_single_line(action.label, IPC_ITEM_MAX_TITLE_LEN, "label").ok()?;
+    let target = sanitize_ipc_target(action.target);
+    let copy_text = sanitize_ipc_copy_text(action.copy_text, can_copy);
+    if target.is_none() && copy_text.is_none() {
+        return None;
+    }
+
+    Some(IpcItemAction {
+        id,
+        label,
+        target,
+        copy_text,
+    })
+}
+
+/// Copying goes through `copyText`, so a `copy:` target from a module is
+/// dropped rather than run by the core's copy scheme.
+fn sanitize_ipc_target(target: Option<String>) -> Option<String> {
+    sanitize_optional_multiline(target, IPC_ITEM_MAX_TARGET_LEN)
+        .filter(|target| split_scheme(target).is_none_or(|(scheme, _)| scheme != "copy"))
+}
+
+fn sanitize_ipc_copy_text(text: Option<String>, can_copy: bool) -> Option<String> {
+    if !can_copy {
+        return None;
+    }
+    sanitize_optional_multiline(text, IPC_ITEM_MAX_COPY_TEXT_LEN)
+}
+
 fn sanitize_required_single_line(
     value: String,
     max_len: usize,
@@ -1964,10 +2130,11 @@ fn sanitize_quick_select_key(value: Option<String>) -> Option<String> {
 }
 
 fn module_item_from_ipc_item(item: IpcItem) -> ModuleItem {
-    let action = item
-        .target
-        .map(|target| ModuleAction::LaunchTarget { target })
-        .unwrap_or(ModuleAction::Noop);
+    let action = match (item.copy_text, item.target) {
+        (Some(text), _) => ModuleAction::CopyText { text },
+        (None, Some(target)) => ModuleAction::LaunchTarget { target },
+        (None, None) => ModuleAction::Noop,
+    };
 
     ModuleItem {
         id: item.id,
@@ -1984,21 +2151,21 @@ fn module_item_from_ipc_item(item: IpcItem) -> ModuleItem {
             hint: item.hint,
             icon: None,
         },
+        secondary_actions: item
+            .secondary_actions
+            .into_iter()
+            .map(module_action_from_ipc_item_action)
+            .collect(),
     }
 }
 
 fn launcher_item_from_module_item(item: ModuleItem) -> LauncherItem {
-    let target = match item.action {
-        ModuleAction::LaunchTarget { target } => target,
-        ModuleAction::RunCommand { name, args } => {
-            if args.is_empty() {
-                name
-            } else {
-                format!("{} {}", name, args.join(" "))
-            }
-        }
-        ModuleAction::Noop => item.subtitle.clone().unwrap_or_else(|| item.title.clone()),
+    let (target, copy_text) = match item.action {
+        ModuleAction::CopyText { text } => (Some(text.clone()), Some(text)),
+        other => (target_from_module_action(other), None),
     };
+    let target =
+        target.unwrap_or_else(|| item.subtitle.clone().unwrap_or_else(|| item.title.clone()));
 
     let source = item
         .source
@@ -2007,9 +2174,25 @@ fn launcher_item_from_module_item(item: ModuleItem) -> LauncherItem {
         .unwrap_or(LauncherSource::Direct);
 
     let mut launcher_item = LauncherItem::new(item.title, target, source);
+    launcher_item.copy_text = copy_text;
     launcher_item.quick_select_key = item.capabilities.quick_select_key;
     launcher_item.trailing_badge = item.decorations.badge;
     launcher_item.trailing_hint = item.decorations.hint;
+    launcher_item.secondary_actions = item
+        .secondary_actions
+        .into_iter()
+        .filter_map(|action| {
+            let kind = match action.action {
+                ModuleAction::CopyText { text } => LauncherItemActionKind::Copy(text),
+                other => LauncherItemActionKind::Launch(target_from_module_action(other)?),
+            };
+            Some(LauncherItemAction {
+                id: action.id,
+                label: action.label,
+                kind,
+            })
+        })
+        .collect();
     launcher_item
 }
 
@@ -2077,7 +2260,11 @@ impl RuntimeModule for BuiltinLifecycleModule {
 }
 
 #[derive(Default)]
-pub struct BuiltinQueryProviderModule;
+pub struct BuiltinQueryProviderModule {
+    /// Last evaluated expression and its item or error text, so
+    /// `on_query_change` and `provide_items` evaluate each query once.
+    last_calc: Option<(String, Result<ModuleItem, String>)>,
+}
 
 #[derive(Default)]
 pub struct BuiltinRsnipCompanionModule;
@@ -2141,6 +2328,7 @@ impl BuiltinRsnipCompanionModule {
                     hint: Some(target.to_string()),
                     icon: None,
                 },
+                secondary_actions: Vec::new(),
             };
         }
 
@@ -2157,6 +2345,7 @@ impl BuiltinRsnipCompanionModule {
                 hint: None,
                 icon: None,
             },
+            secondary_actions: Vec::new(),
         }
     }
 
@@ -2239,6 +2428,7 @@ impl BuiltinRtasksCompanionModule {
                     hint: Some(target.to_string()),
                     icon: None,
                 },
+                secondary_actions: Vec::new(),
             };
         }
 
@@ -2255,6 +2445,7 @@ impl BuiltinRtasksCompanionModule {
                 hint: None,
                 icon: None,
             },
+            secondary_actions: Vec::new(),
         }
     }
 
@@ -2355,6 +2546,7 @@ impl BuiltinRtasksCompanionModule {
                 hint: Some(priority.to_string()),
                 icon: None,
             },
+            secondary_actions: Vec::new(),
         }
     }
 
@@ -2478,6 +2670,47 @@ impl RuntimeModule for BuiltinRsnipCompanionModule {
     }
 }
 
+impl BuiltinQueryProviderModule {
+    fn calc_expression(query: &str) -> Option<&str> {
+        let expr = query.trim().strip_prefix('=')?.trim();
+        (!expr.is_empty()).then_some(expr)
+    }
+
+    fn evaluate_calc(&mut self, query: &str) -> Option<&Result<ModuleItem, String>> {
+        let expr = Self::calc_expression(query)?;
+        let cached = matches!(&self.last_calc, Some((last, _)) if last == expr);
+        if !cached {
+            let result = calc::evaluate(expr)
+                .map(|value| Self::calc_item(expr, value))
+                .map_err(|err| format!("Calc: {err}"));
+            self.last_calc = Some((expr.to_string(), result));
+        }
+        self.last_calc.as_ref().map(|(_, result)| result)
+    }
+
+    fn calc_item(expr: &str, value: calc::CalcValue) -> ModuleItem {
+        let result = value.to_string();
+        ModuleItem {
+            id: format!("calc::{expr}"),
+            title: format!("= {result}"),
+            subtitle: Some(expr.to_string()),
+            source: Some("module_provider".to_string()),
+            action: ModuleAction::CopyText { text: result },
+            // No quick-select key: digits keep extending the expression.
+            capabilities: ModuleItemCapabilities {
+                quick_select_key: None,
+            },
+            decorations: ModuleItemDecorations {
+                badge: None,
+                badge_kind: None,
+                hint: Some("presiona Enter para copiar".to_string()),
+                icon: None,
+            },
+            secondary_actions: Vec::new(),
+        }
+    }
+}
+
 impl RuntimeModule for BuiltinQueryProviderModule {
     fn name(&self) -> &str {
         "builtin.query-provider"
@@ -2490,43 +2723,34 @@ impl RuntimeModule for BuiltinQueryProviderModule {
         });
     }
 
-    fn provide_items(&mut self, query: &str, _ctx: &mut ModuleCtx) -> Vec<ModuleItem> {
-        let trimmed = query.trim();
-        if trimmed.is_empty() {
-            return Vec::new();
-        }
-
-        if let Some(rest) = trimmed.strip_prefix("=") {
-            let expr = rest.trim();
-            if !expr.is_empty() {
-                return vec![ModuleItem {
-                    id: format!("calc::{expr}"),
-                    title: format!("Calc: {expr}"),
-                    subtitle: Some("builtin provider".to_string()),
-                    source: Some("module_provider".to_string()),
-                    action: ModuleAction::Noop,
-                    capabilities: ModuleItemCapabilities {
-                        quick_select_key: Some("1".to_string()),
-                    },
-                    decorations: ModuleItemDecorations {
-                        badge: Some("1".to_string()),
-                        badge_kind: Some(BadgeKind::Shortcut),
-                        hint: Some("presiona Enter para copiar en futuro".to_string()),
-                        icon: None,
-                    },
-                }];
+    fn on_query_change(&mut self, query: &str, ctx: &mut ModuleCtx) {
+        match self.evaluate_calc(query) {
+            None => ctx.clear_input_accessory(),
+            Some(Ok(item)) => {
+                ctx.clear_input_accessory();
+                ctx.replace_items(vec![item.clone()]);
             }
+            Some(Err(text)) => ctx.set_input_accessory(ModuleInputAccessory {
+                text: text.clone(),
+                kind: InputAccessoryKind::Error,
+                priority: 100,
+            }),
         }
+    }
 
-        Vec::new()
+    fn provide_items(&mut self, query: &str, _ctx: &mut ModuleCtx) -> Vec<ModuleItem> {
+        match self.evaluate_calc(query) {
+            Some(Ok(item)) => vec![item.clone()],
+            _ => Vec::new(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        cap_ipc_items, dedupe_launcher_items_by_priority, parse_namespaced_command,
-        sanitize_ipc_item, sanitize_ipc_items, BuiltinLifecycleModule, DedupeSourcePriority,
+        cap_ipc_items, parse_namespaced_command, sanitize_ipc_item, sanitize_ipc_items,
+        BuiltinLifecycleModule, BuiltinQueryProviderModule, DedupeSourcePriority,
         ExternalModuleStatus, HostTelemetry, IpcAction, ModuleRuntime, ResolvedCommandRoute,
         HOT_RELOAD_CHECK_INTERVAL_MS, IPC_ITEM_MAX_BADGE_LEN, IPC_ITEM_MAX_HINT_LEN,
         IPC_ITEM_MAX_ID_LEN, IPC_ITEM_MAX_SOURCE_LEN, IPC_ITEM_MAX_SUBTITLE_LEN,
@@ -2538,12 +2762,13 @@ mod tests {
     use std::process::Command;
     use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-    use crate::app_state::{AppState, LauncherItem, LauncherSource};
-    use crate::modules::ipc::{IpcInputAccessory, IpcItem};
+    use crate::app_state::{AppState, LauncherItem, LauncherItemActionKind, LauncherSource};
+    use crate::modules::ipc::{IpcInputAccessory, IpcItem, IpcItemAction};
     use crate::modules::types::{
-        BadgeKind, InputAccessoryKind, ModuleCommandDef, ModuleInputAccessory,
+        BadgeKind, InputAccessoryKind, ModuleAction, ModuleCommandDef, ModuleInputAccessory,
         ModuleItemCapabilities, ModuleItemDecorations,
     };
+    use crate::schemes::SchemeRoute;
 
     fn temp_modules_dir(name: &str) -> PathBuf {
         let nonce = SystemTime::now()
@@ -2563,11 +2788,13 @@ mod tests {
             subtitle: None,
             source: None,
             target: None,
+            copy_text: None,
             quick_select_key: None,
             badge: None,
             hint: None,
+            secondary_actions: Vec::new(),
         };
-        assert!(sanitize_ipc_item(missing_id).is_err());
+        assert!(sanitize_ipc_item(missing_id, true).is_err());
 
         let missing_title = IpcItem {
             id: "valid".to_string(),
@@ -2575,11 +2802,13 @@ mod tests {
             subtitle: None,
             source: None,
             target: None,
+            copy_text: None,
             quick_select_key: None,
             badge: None,
             hint: None,
+            secondary_actions: Vec::new(),
         };
-        assert!(sanitize_ipc_item(missing_title).is_err());
+        assert!(sanitize_ipc_item(missing_title, true).is_err());
     }
 
     #[test]
@@ -2590,12 +2819,14 @@ mod tests {
             subtitle: Some(format!("{}x", "a".repeat(IPC_ITEM_MAX_SUBTITLE_LEN))),
             source: Some(" Module_Source ".to_string()),
             target: Some(format!("  {}x  ", "b".repeat(IPC_ITEM_MAX_TARGET_LEN))),
+            copy_text: None,
             quick_select_key: Some(" 2 ".to_string()),
             badge: Some(format!("{}x", "c".repeat(IPC_ITEM_MAX_BADGE_LEN))),
             hint: Some(format!("{}x", "d".repeat(IPC_ITEM_MAX_HINT_LEN))),
+            secondary_actions: Vec::new(),
         };
 
-        let sanitized = sanitize_ipc_item(raw).expect("item should sanitize");
+        let sanitized = sanitize_ipc_item(raw, true).expect("item should sanitize");
 
         assert_eq!(sanitized.id, "item id");
         assert_eq!(sanitized.title, "title value");
@@ -2627,12 +2858,14 @@ mod tests {
             subtitle: Some("\n\t".to_string()),
             source: Some("bad source!".to_string()),
             target: Some("\t\n".to_string()),
+            copy_text: None,
             quick_select_key: Some("x".to_string()),
             badge: Some("\n".to_string()),
             hint: Some("\t".to_string()),
+            secondary_actions: Vec::new(),
         };
 
-        let sanitized = sanitize_ipc_item(raw).expect("item should sanitize");
+        let sanitized = sanitize_ipc_item(raw, true).expect("item should sanitize");
 
         assert_eq!(sanitized.subtitle, None);
         assert_eq!(sanitized.source, None);
@@ -2651,9 +2884,11 @@ mod tests {
                 subtitle: None,
                 source: None,
                 target: None,
+                copy_text: None,
                 quick_select_key: None,
                 badge: None,
                 hint: None,
+                secondary_actions: Vec::new(),
             })
             .collect::<Vec<_>>();
 
@@ -2673,9 +2908,11 @@ mod tests {
                 subtitle: None,
                 source: None,
                 target: None,
+                copy_text: None,
                 quick_select_key: None,
                 badge: None,
                 hint: None,
+                secondary_actions: Vec::new(),
             },
             IpcItem {
                 id: "\n".to_string(),
@@ -2683,9 +2920,11 @@ mod tests {
                 subtitle: None,
                 source: None,
                 target: None,
+                copy_text: None,
                 quick_select_key: None,
                 badge: None,
                 hint: None,
+                secondary_actions: Vec::new(),
             },
             IpcItem {
                 id: "ok-3".to_string(),
@@ -2693,13 +2932,15 @@ mod tests {
                 subtitle: None,
                 source: None,
                 target: None,
+                copy_text: None,
                 quick_select_key: None,
                 badge: None,
                 hint: None,
+                secondary_actions: Vec::new(),
             },
         ];
 
-        let sanitized = sanitize_ipc_items(items, "test.module", true);
+        let sanitized = sanitize_ipc_items(items, "test.module", true, true);
         assert_eq!(sanitized.len(), 2);
         assert_eq!(sanitized[0].id, "ok-1");
         assert_eq!(sanitized[1].id, "ok-3");
@@ -2713,12 +2954,14 @@ mod tests {
             subtitle: None,
             source: Some(format!("{}x", "c".repeat(IPC_ITEM_MAX_SOURCE_LEN))),
             target: None,
+            copy_text: None,
             quick_select_key: None,
             badge: None,
             hint: None,
+            secondary_actions: Vec::new(),
         };
 
-        let sanitized = sanitize_ipc_item(raw).expect("item should sanitize");
+        let sanitized = sanitize_ipc_item(raw, true).expect("item should sanitize");
         assert_eq!(sanitized.id.len(), IPC_ITEM_MAX_ID_LEN);
         assert_eq!(sanitized.title.len(), IPC_ITEM_MAX_TITLE_LEN);
         assert_eq!(
@@ -2727,46 +2970,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn dedupe_launcher_items_respects_priority_order() {
-        let core = vec![
-            LauncherItem::new(
-                "Core A".to_string(),
-                "same-target".to_string(),
-                LauncherSource::Direct,
-            ),
-            LauncherItem::new(
-                "Core B".to_string(),
-                "core-only".to_string(),
-                LauncherSource::Direct,
-            ),
-        ];
-        let provider = vec![
-            LauncherItem::new(
-                "Provider A".to_string(),
-                "same-target".to_string(),
-                LauncherSource::Direct,
-            ),
-            LauncherItem::new(
-                "Provider B".to_string(),
-                "provider-only".to_string(),
-                LauncherSource::Direct,
-            ),
-        ];
-
-        let core_first = dedupe_launcher_items_by_priority(vec![core.clone(), provider.clone()]);
-        assert_eq!(core_first.len(), 3);
-        assert_eq!(core_first[0].label, "Core A");
-        assert_eq!(core_first[1].label, "Core B");
-        assert_eq!(core_first[2].label, "Provider B");
-
-        let provider_first = dedupe_launcher_items_by_priority(vec![provider, core]);
-        assert_eq!(provider_first.len(), 3);
-        assert_eq!(provider_first[0].label, "Provider A");
-        assert_eq!(provider_first[1].label, "Provider B");
-        assert_eq!(provider_first[2].label, "Core B");
-    }
-
     #[test]
     fn plain_text_keys_are_not_dispatched_to_modules() {
         assert!(!super::should_dispatch_module_key_event(
@@ -2844,9 +3047,11 @@ mod tests {
                     "powershell.exe -NoProfile -File modules/local-scripts/scripts/build.ps1"
                         .to_string(),
                 ),
+                copy_text: None,
                 quick_select_key: None,
                 badge: Some("ps1".to_string()),
                 hint: Some("modules/local-scripts/scripts/build.ps1".to_string()),
+                secondary_actions: Vec::new(),
             }],
         }];
 
@@ -2868,35 +3073,15 @@ mod tests {
     }
 
     #[test]
-    fn merge_rank_dataset_uses_configurable_source_priority() {
+    fn providers_first_follows_the_dedupe_source_priority() {
         let mut runtime = ModuleRuntime::new();
-
-        let core = vec![LauncherItem::new(
-            "Core Item".to_string(),
-            "same-target".to_string(),
-            LauncherSource::Direct,
-        )];
-        let provider = vec![LauncherItem::new(
-            "Provider Item".to_string(),
-            "same-target".to_string(),
-            LauncherSource::Direct,
-        )];
-
-        runtime.configure_policy(super::ModuleRuntimePolicy {
-            dedupe_source_priority: DedupeSourcePriority::CoreFirst,
-            ..Default::default()
-        });
-        let merged_core_first = runtime.merge_rank_dataset(core.clone(), provider.clone());
-        assert_eq!(merged_core_first.len(), 1);
-        assert_eq!(merged_core_first[0].label, "Core Item");
+        assert!(!runtime.providers_first());
 
         runtime.configure_policy(super::ModuleRuntimePolicy {
             dedupe_source_priority: DedupeSourcePriority::ProviderFirst,
             ..Default::default()
         });
-        let merged_provider_first = runtime.merge_rank_dataset(core, provider);
-        assert_eq!(merged_provider_first.len(), 1);
-        assert_eq!(merged_provider_first[0].label, "Provider Item");
+        assert!(runtime.providers_first());
     }
 
     #[test]
@@ -2957,6 +3142,56 @@ mod tests {
         assert!(text.contains("builtin.lifecycle"));
     }
 
+    #[test]
+    fn query_provider_evaluates_calc_expressions() {
+        let mut runtime = ModuleRuntime::new();
+        runtime.register_builtin_module(Box::new(BuiltinQueryProviderModule::default()));
+        let mut app_state = AppState {
+            current_input: "= (2 + 3) * 10%".to_string(),
+            ..Default::default()
+        };
+
+        runtime.run_on_query_change(&mut app_state);
+        assert!(runtime.items_replaced_in_cycle());
+        assert_eq!(app_state.matching_items.len(), 1);
+        assert_eq!(app_state.matching_items[0].label, "= 0.5");
+        assert_eq!(
+            app_state.matching_items[0].copy_text.as_deref(),
+            Some("0.5")
+        );
+        assert!(runtime.active_input_accessory().is_none());
+
+        app_state.current_input = "= 1 / 0".to_string();
+        runtime.run_on_query_change(&mut app_state);
+        let accessory = runtime
+            .active_input_accessory()
+            .expect("calc error should be shown");
+        assert_eq!(accessory.kind, InputAccessoryKind::Error);
+        assert_eq!(accessory.text, "Calc: division by zero");
+
+        app_state.current_input = "notepad".to_string();
+        runtime.run_on_query_change(&mut app_state);
+        assert!(runtime.active_input_accessory().is_none());
+    }
+
+    #[test]
+    fn scoped_provider_collection_skips_other_modules() {
+        let mut runtime = ModuleRuntime::new();
+        runtime.register_builtin_module(Box::new(BuiltinQueryProviderModule::default()));
+        let app_state = AppState::default();
+
+        let items = runtime.collect_provider_items_matching(&app_state, "= 2 + 2", |name| {
+            name == "builtin.query-provider"
+        });
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "= 4");
+
+        let items = runtime.collect_provider_items_matching(&app_state, "= 2 + 2", |name| {
+            name == "builtin.lifecycle"
+        });
+        assert!(items.is_empty());
+    }
+
     #[test]
     fn runtime_command_telemetry_reset_clears_state() {
         let mut runtime = ModuleRuntime::new();
@@ -3154,6 +3389,197 @@ export default function createModule() {}
         );
     }
 
+    #[test]
+    fn ipc_copy_text_requires_clipboard_capability() {
+        let mut app_state = AppState {
+            silent_mode: true,
+            ..Default::default()
+        };
+        let mut state = super::ModuleRuntimeState::default();
+        let allowed = BTreeSet::new();
+
+        ModuleRuntime::apply_ipc_actions(
+            "restricted.module",
+            vec![IpcAction::CopyText {
+                text: "denied".to_string(),
+            }],
+            &mut app_state,
+            &mut state,
+            Some(&allowed),
+        );
+
+        assert!(state.pending_copy_text.is_none());
+
+        let mut allowed = BTreeSet::new();
+        allowed.insert("clipboard".to_string());
+        ModuleRuntime::apply_ipc_actions(
+            "clipboard.module",
+            vec![IpcAction::CopyText {
+                text: "allowed".to_string(),
+            }],
+            &mut app_state,
+            &mut state,
+            Some(&allowed),
+        );
+
+        assert_eq!(state.pending_copy_text.as_deref(), Some("allowed"));
+    }
+
+    #[test]
+    fn ipc_items_copy_only_with_clipboard_capability() {
+        let item = || IpcItem {
+            id: "secret".to_string(),
+            title: "Secret".to_string(),
+            subtitle: None,
+            source: None,
+            target: Some("COPY:hunter2".to_string()),
+            copy_text: Some("hunter2".to_string()),
+            quick_select_key: None,
+            badge: None,
+            hint: None,
+            secondary_actions: vec![
+                IpcItemAction {
+                    id: "copy".to_string(),
+                    label: "Copy".to_string(),
+                    target: None,
+                    copy_text: Some("hunter2".to_string()),
+                },
+                IpcItemAction {
+                    id: "smuggle".to_string(),
+                    label: "Smuggle".to_string(),
+                    target: Some(" copy:hunter2".to_string()),
+                    copy_text: None,
+                },
+            ],
+        };
+
+        let denied = super::sanitize_ipc_item(item(), false).expect("item should be valid");
+        assert_eq!(denied.target, None);
+        assert_eq!(denied.copy_text, None);
+        assert!(denied.secondary_actions.is_empty());
+
+        let allowed = super::sanitize_ipc_item(item(), true).expect("item should be valid");
+        assert_eq!(allowed.target, None);
+        assert_eq!(allowed.copy_text.as_deref(), Some("hunter2"));
+        assert_eq!(allowed.secondary_actions.len(), 1);
+        assert_eq!(allowed.secondary_actions[0].id, "copy");
+
+        let mut app_state = AppState {
+            silent_mode: true,
+            ..Default::default()
+        };
+        let mut state = super::ModuleRuntimeState::default();
+        ModuleRuntime::apply_ipc_actions(
+            "restricted.module",
+            vec![IpcAction::ReplaceItems {
+                items: vec![item()],
+            }],
+            &mut app_state,
+            &mut state,
+            Some(&BTreeSet::new()),
+        );
+        assert_eq!(app_state.matching_items.len(), 1);
+        assert_eq!(app_state.matching_items[0].copy_text, None);
+        assert_eq!(app_state.matching_items[0].target, "Secret");
+    }
+
+    #[test]
+    fn ipc_copy_text_items_map_to_copy_items() {
+        let item = IpcItem {
+            id: "snippet::sig".to_string(),
+            title: "Signature".to_string(),
+            subtitle: None,
+            source: None,
+            target: Some("ignored.exe".to_string()),
+            copy_text: Some("Best regards,\nTeam".to_string()),
+            quick_select_key: None,
+            badge: None,
+            hint: None,
+            secondary_actions: Vec::new(),
+        };
+
+        let module_item = super::module_item_from_ipc_item(item);
+        assert_eq!(
+            module_item.action,
+            ModuleAction::CopyText {
+                text: "Best regards,\nTeam".to_string()
+            }
+        );
+
+        let launcher_item = super::launcher_item_from_module_item(module_item);
+        assert_eq!(
+            launcher_item.copy_text.as_deref(),
+            Some("Best regards,\nTeam")
+        );
+
+        let round_trip = super::module_item_from_launcher_item(launcher_item);
+        let ipc_item = super::module_item_to_ipc_item(round_trip);
+        assert_eq!(ipc_item.target, None);
+        assert_eq!(ipc_item.copy_text.as_deref(), Some("Best regards,\nTeam"));
+
+        let parsed: IpcItem = serde_json::from_str(r#"{"id":"a","title":"A","copyText":"value"}"#)
+            .expect("camelCase copyText should parse");
+        assert_eq!(parsed.copy_text.as_deref(), Some("value"));
+    }
+
+    #[test]
+    fn ipc_secondary_actions_are_sanitized_and_mapped_to_launcher_actions() {
+        let action = |id: &str, target: Option<&str>, copy_text: Option<&str>| IpcItemAction {
+            id: id.to_string(),
+            label: format!("Label {id}"),
+            target: target.map(str::to_string),
+            copy_text: copy_text.map(str::to_string),
+        };
+        let item = IpcItem {
+            id: "repo::rmenu".to_string(),
+            title: "rmenu".to_string(),
+            subtitle: None,
+            source: None,
+            target: Some("https://example.com/rmenu".to_string()),
+            copy_text: None,
+            quick_select_key: None,
+            badge: None,
+            hint: None,
+            secondary_actions: vec![
+                action("issues", Some("https://example.com/rmenu/issues"), None),
+                action("empty", None, None),
+                action("clone", None, Some("git clone rmenu")),
+            ],
+        };
+
+        let sanitized = super::sanitize_ipc_item(item, true).expect("item should be valid");
+        assert_eq!(sanitized.secondary_actions.len(), 2);
+
+        let launcher_item =
+            super::launcher_item_from_module_item(super::module_item_from_ipc_item(sanitized));
+        let kinds = launcher_item
+            .secondary_actions
+            .iter()
+            .map(|action| action.kind.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                LauncherItemActionKind::Launch("https://example.com/rmenu/issues".to_string()),
+                LauncherItemActionKind::Copy("git clone rmenu".to_string()),
+            ]
+        );
+
+        let round_trip =
+            super::module_item_to_ipc_item(super::module_item_from_launcher_item(launcher_item));
+        assert_eq!(round_trip.secondary_actions[1].target, None);
+        assert_eq!(
+            round_trip.secondary_actions[1].copy_text.as_deref(),
+            Some("git clone rmenu")
+        );
+
+        let parsed: IpcItem = serde_json::from_str(
+            r#"{"id":"a","title":"A","secondaryActions":[{"id":"x","label":"X","target":"x.exe"}]}"#,
+        )
+        .expect("camelCase secondaryActions should parse");
+        assert_eq!(parsed.secondary_actions.len(), 1);
+    }
+
     #[test]
     fn ipc_toast_sets_high_priority_feedback_without_accessory_capability() {
         let mut app_state = AppState {
@@ -3183,12 +3609,17 @@ export default function createModule() {}
         assert_eq!(accessory.priority, 10_000);
     }
 
-    fn node_available() -> bool {
-        Command::new("node")
-            .arg("--version")
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false)
+    /// External-host tests need Node and a built `rmenu-module-host` next to
+    /// the test binary; `cargo test --lib` alone does not build it.
+    fn external_host_available() -> bool {
+        let host_built =
+            super::host_client::module_host_binary_path().is_ok_and(|path| path.exists());
+        host_built
+            && Command::new("node")
+                .arg("--version")
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
     }
 
     fn write_disabled_rmod(dir: &std::path::Path, name: &str, version: &str) {
@@ -3245,7 +3676,7 @@ export default function createModule() {}
 
     #[test]
     fn external_provider_errors_do_not_break_healthy_modules() {
-        if !node_available() {
+        if !external_host_available() {
             return;
         }
 
@@ -3303,7 +3734,7 @@ export default function createModule() {}
 
     #[test]
     fn external_key_hooks_require_keys_capability() {
-        if !node_available() {
+        if !external_host_available() {
             return;
         }
 
@@ -3374,7 +3805,7 @@ export default function createModule() {}
 
     #[test]
     fn external_timeouts_auto_disable_after_threshold() {
-        if !node_available() {
+        if !external_host_available() {
             return;
         }
 
@@ -3422,7 +3853,7 @@ export default function createModule() {}
 
     #[test]
     fn external_restart_backoff_suppresses_repeated_restart_attempts() {
-        if !node_available() {
+        if !external_host_available() {
             return;
         }
 
@@ -3498,6 +3929,50 @@ export default function createModule() {}
         let _ = fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn scheme_targets_route_to_the_declaring_enabled_module() {
+        let rmod = |name: &str, enabled: bool, priority: i32, schemes: &str| {
+            super::rmod::parse_rmod(
+                &format!(
+                    "#!rmod/v1\nname: {name}\nversion: 0.1.0\napi_version: 1\nkind: script\n\
+                     enabled: {enabled}\npriority: {priority}\ncapabilities: commands\n\
+                     schemes: {schemes}\n\n\
+                     ---module.js---\nexport default function createModule() {{}}\n"
+                ),
+                format!("{name}.rmod"),
+            )
+            .expect("rmod should parse")
+        };
+        let mut runtime = ModuleRuntime::new();
+        runtime.external_descriptors = vec![
+            rmod("my-module", true, 0, "MyMod"),
+            rmod("late-module", true, 5, "mymod, late"),
+            rmod("off-module", false, 0, "offmod"),
+        ];
+        runtime.sync_module_schemes(true);
+
+        assert_eq!(
+            runtime.schemes().route("mymod:do-thing  now later"),
+            SchemeRoute::Module {
+                module: "my-module",
+                rest: "do-thing  now later"
+            }
+        );
+        assert_eq!(
+            runtime.schemes().route("late:x"),
+            SchemeRoute::Module {
+                module: "late-module",
+                rest: "x"
+            }
+        );
+        assert_eq!(runtime.schemes().route("copy:42"), SchemeRoute::Builtin);
+        assert_eq!(
+            runtime.schemes().route("offmod:do-thing"),
+            SchemeRoute::Unknown("offmod".to_string())
+        );
+        assert_eq!(runtime.schemes().route("C:\\mymod.exe"), SchemeRoute::Shell);
+    }
+
     #[test]
     fn modules_debug_report_exposes_policy_health_telemetry_and_capabilities() {
         let mut runtime = ModuleRuntime::new();
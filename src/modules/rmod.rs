@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 
 use super::types::{ModuleDescriptor, ModuleSourceType};
+use crate::schemes::parse_module_schemes;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RmodParseError {
@@ -11,6 +12,7 @@ pub enum RmodParseError {
     DuplicateBlock(String),
     MissingModuleJs,
     ConfigNotJson(String),
+    InvalidScheme(String),
 }
 
 impl RmodParseError {
@@ -23,6 +25,7 @@ impl RmodParseError {
             Self::DuplicateBlock(_) => "RMOD_E_DUPLICATE_BLOCK",
             Self::MissingModuleJs => "RMOD_E_MISSING_MODULE_JS",
             Self::ConfigNotJson(_) => "RMOD_E_CONFIG_NOT_JSON",
+            Self::InvalidScheme(_) => "RMOD_E_INVALID_SCHEME",
         }
     }
 
@@ -35,6 +38,7 @@ impl RmodParseError {
             Self::DuplicateBlock(name) => format!("duplicate block: '{name}'"),
             Self::MissingModuleJs => "missing required block: 'module.js'".to_string(),
             Self::ConfigNotJson(error) => format!("invalid config.json: {error}"),
+            Self::InvalidScheme(error) => format!("invalid schemes header: {error}"),
         }
     }
 }
@@ -105,6 +109,15 @@ pub fn parse_rmod(content: &str, source_path: String) -> Result<ModuleDescriptor
         .map(str::to_string)
         .collect::<Vec<_>>();
 
+    let schemes = header
+        .get("schemes")
+        .map(|raw| {
+            parse_module_schemes(raw.split(',').filter(|value| !value.trim().is_empty()))
+                .map_err(RmodParseError::InvalidScheme)
+        })
+        .transpose()?
+        .unwrap_or_default();
+
     let enabled = header
         .get("enabled")
         .map(|value| value.eq_ignore_ascii_case("true"))
@@ -177,6 +190,7 @@ pub fn parse_rmod(content: &str, source_path: String) -> Result<ModuleDescriptor
         api_version,
         kind,
         capabilities,
+        schemes,
         enabled,
         priority,
         description: header.get("description").cloned(),
@@ -201,6 +215,7 @@ version: 0.1.0
 api_version: 1
 kind: script
 capabilities: keys,commands
+schemes: test, test-alt, Test
 
 ---module.js---
 export default function createModule() {}
@@ -212,6 +227,18 @@ export default function createModule() {}
         assert_eq!(parsed.name, "test");
         assert_eq!(parsed.api_version, 1);
         assert_eq!(parsed.capabilities.len(), 2);
+        assert_eq!(parsed.schemes, vec!["test", "test-alt"]);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_and_builtin_schemes() {
+        for schemes in ["my mod", "c", "runas"] {
+            let input = format!(
+                "#!rmod/v1\nname: test\nversion: 0.1.0\napi_version: 1\nkind: script\ncapabilities: commands\nschemes: {schemes}\n\n---module.js---\nexport default function createModule() {{}}\n"
+            );
+            let err = parse_rmod(&input, "x.rmod".to_string()).expect_err("should fail");
+            assert_eq!(err.code(), "RMOD_E_INVALID_SCHEME");
+        }
     }
 
     #[test]
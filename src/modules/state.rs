@@ -16,6 +16,7 @@ pub struct ModuleRuntimeState {
     pub registered_providers: BTreeMap<String, ModuleProviderDef>,
     pub active_input_accessory: Option<(String, ModuleInputAccessory)>,
     pub items_replaced_in_cycle: bool,
+    pub pending_copy_text: Option<String>,
 }
 
 impl ModuleRuntimeState {
@@ -24,20 +24,12 @@ pub enum InputAccessoryKind {
     Hint,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ModuleItemCapabilities {
     pub quick_select_key: Option<String>,
 }
 
-impl Default for ModuleItemCapabilities {
-    fn default() -> Self {
-        Self {
-            quick_select_key: None,
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ModuleItemDecorations {
     pub badge: Option<String>,
     pub badge_kind: Option<BadgeKind>,
@@ -45,24 +37,21 @@ pub struct ModuleItemDecorations {
     pub icon: Option<String>,
 }
 
-impl Default for ModuleItemDecorations {
-    fn default() -> Self {
-        Self {
-            badge: None,
-            badge_kind: None,
-            hint: None,
-            icon: None,
-        }
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ModuleAction {
     LaunchTarget { target: String },
     RunCommand { name: String, args: Vec<String> },
+    CopyText { text: String },
     Noop,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleItemAction {
+    pub id: String,
+    pub label: String,
+    pub action: ModuleAction,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ModuleItem {
     pub id: String,
@@ -72,6 +61,7 @@ pub struct ModuleItem {
     pub action: ModuleAction,
     pub capabilities: ModuleItemCapabilities,
     pub decorations: ModuleItemDecorations,
+    pub secondary_actions: Vec<ModuleItemAction>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -126,6 +116,8 @@ pub struct ModuleDescriptor {
     pub api_version: u32,
     pub kind: String,
     pub capabilities: Vec<String>,
+    /// Launch-target schemes routed to this module's `on_command`.
+    pub schemes: Vec<String>,
     pub enabled: bool,
     pub priority: i32,
     pub description: Option<String>,
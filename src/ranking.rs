@@ -1,24 +1,104 @@
-use crate::app_state::{
-    ensure_selection_visible, source_boost, AppState, LauncherItem, LauncherSource,
+use crate::adaptive_aliases::unix_now;
+use crate::app_state::{frecency_boost, source_boost, AppState, LauncherItem, LauncherSource};
+use crate::fuzzy::{
+    compact_folded, fold_for_match, fuzzy_match_tier, fuzzy_match_tier_precomputed_lower,
+    fuzzy_score, fuzzy_score_precomputed_lower, literal_term_score, typo_match, MatchTier,
+    ParsedQuery, QueryTerm, QueryTermKind, TYPO_TIER_BASE,
 };
-use crate::fuzzy::{compact_lower_alnum, fuzzy_score, fuzzy_score_precomputed_lower};
+use crate::modules::launcher_item_dedupe_key;
+use crate::scopes::SearchScope;
+use std::collections::HashSet;
+use std::thread;
 
 #[derive(Debug, Clone)]
 pub struct RankedItem {
     pub item: LauncherItem,
     pub fuzzy_score: i64,
     pub source_boost: i64,
+    pub frecency_boost: i64,
+    pub alias_boost: i64,
+    pub rule_boost: i64,
+    pub typo_distance: Option<usize>,
     pub total_score: i64,
 }
 
+/// Scores of one dataset entry, referenced by index so ranking never clones
+/// items that end up discarded.
+#[derive(Debug, Clone, Copy)]
+pub struct RankedIndex {
+    pub index: usize,
+    pub fuzzy_score: i64,
+    pub source_boost: i64,
+    pub frecency_boost: i64,
+    pub alias_boost: i64,
+    pub rule_boost: i64,
+    pub typo_distance: Option<usize>,
+    pub total_score: i64,
+}
+
+/// Matches of the previous query, reused when the next keystroke can only
+/// narrow them.
+#[derive(Debug, Clone, Default)]
+pub struct RankingCache {
+    query: String,
+    case_sensitive: bool,
+    items_generation: u64,
+    scope: Option<SearchScope>,
+    matched: Vec<usize>,
+}
+
+impl RankingCache {
+    fn candidates(
+        &self,
+        query: &str,
+        case_sensitive: bool,
+        items_generation: u64,
+        scope: Option<&SearchScope>,
+    ) -> Option<&[usize]> {
+        (self.case_sensitive == case_sensitive
+            && self.items_generation == items_generation
+            && self.scope.as_ref() == scope
+            && query_narrows(&self.query, query))
+        .then_some(self.matched.as_slice())
+    }
+}
+
 const PARTIAL_TOPK_THRESHOLD: usize = 1200;
 const PARTIAL_TOPK_LIMIT: usize = 400;
 const STRONG_LABEL_MATCH_SCORE: i64 = 2300;
+const ADAPTIVE_ALIAS_PIN_BOOST: i64 = 100_000;
+const PARALLEL_MIN_CHUNK: usize = 4096;
+const CANCEL_CHECK_INTERVAL: usize = 1024;
+const TYPO_FALLBACK_MIN_RESULTS: usize = 5;
+// A keyword that starts with the query ranks just below a substring match in
+// the label or executable name.
+const KEYWORD_PREFIX_SCORE: i64 = 1300;
+
+fn rank_compare_desc(
+    dataset: &[LauncherItem],
+    a: &RankedIndex,
+    b: &RankedIndex,
+) -> std::cmp::Ordering {
+    ranked_order(dataset, a, dataset, b)
+}
 
-fn rank_compare_desc(a: &RankedItem, b: &RankedItem) -> std::cmp::Ordering {
-    b.total_score
-        .cmp(&a.total_score)
-        .then_with(|| a.item.label.cmp(&b.item.label))
+/// True when every item matching `next` also matched `previous`, so only the
+/// previous matches need re-scoring. Alternatives and negations can widen the
+/// result set, a finished `suffix$` token can turn back into a fuzzy one, and
+/// a symbol-only token may start matching through its compact form.
+pub fn query_narrows(previous: &str, next: &str) -> bool {
+    if previous.trim().is_empty()
+        || !next.starts_with(previous)
+        || next.contains(['|', '!'])
+        || previous.contains('$')
+    {
+        return false;
+    }
+    previous.ends_with(char::is_whitespace)
+        || previous
+            .split_whitespace()
+            .last()
+            .is_some_and(|token| token.chars().any(char::is_alphanumeric))
 }
 
 pub fn source_name(source: LauncherSource) -> &'static str {
@@ -27,6 +107,16 @@ pub fn source_name(source: LauncherSource) -> &'static str {
         LauncherSource::History => "history",
         LauncherSource::StartMenu => "start_menu",
         LauncherSource::Path => "path",
+        LauncherSource::Extra => "extra",
+        LauncherSource::Bookmark => "bookmark",
+    }
+}
+
+/// Source name for diagnostics; items from `[Sources]` roots use the root's label.
+pub fn item_source_name(item: &LauncherItem) -> &str {
+    match &item.extra_source {
+        Some(extra) => &extra.label,
+        None => source_name(item.source),
     }
 }
 
@@ -43,7 +133,9 @@ fn fuzzy_best_score(
             return label_score;
         }
         let target_score = fuzzy_score(query, &item.target_name, true);
-        return label_score.max(target_score);
+        return label_score
+            .max(target_score)
+            .max(keyword_score(item, query, true));
     }
 
     let label_score = fuzzy_score_precomputed_lower(
@@ -51,6 +143,7 @@ fn fuzzy_best_score(
         query_compact,
         &item.label_lc,
         &item.label_compact,
+        &item.label_initials,
     );
     if label_score >= STRONG_LABEL_MATCH_SCORE {
         return label_score;
@@ -61,76 +154,696 @@ fn fuzzy_best_score(
         query_compact,
         &item.target_name_lc,
         &item.target_name_compact,
+        &item.target_name_initials,
     );
-    label_score.max(target_score)
+    label_score
+        .max(target_score)
+        .max(keyword_score(item, query_norm, false))
 }
 
-pub fn rank_items(app_state: &AppState, query: &str, case_sensitive: bool) -> Vec<RankedItem> {
-    let query_norm = if case_sensitive {
-        String::new()
+fn keyword_score(item: &LauncherItem, query: &str, case_sensitive: bool) -> i64 {
+    if item.keywords.is_empty() || query.is_empty() {
+        return 0;
+    }
+    let query = if case_sensitive {
+        fold_for_match(query)
+    } else {
+        query.to_string()
+    };
+    if item
+        .keywords
+        .iter()
+        .any(|keyword| keyword.starts_with(&query))
+    {
+        KEYWORD_PREFIX_SCORE
+    } else {
+        0
+    }
+}
+
+fn literal_best_score(item: &LauncherItem, term: &QueryTerm, case_sensitive: bool) -> i64 {
+    if case_sensitive {
+        let label_compact = compact_case_sensitive(&item.label);
+        let target_compact = compact_case_sensitive(&item.target_name);
+        return literal_term_score(term, &item.label, &label_compact).max(literal_term_score(
+            term,
+            &item.target_name,
+            &target_compact,
+        ));
+    }
+
+    literal_term_score(term, &item.label_lc, &item.label_compact).max(literal_term_score(
+        term,
+        &item.target_name_lc,
+        &item.target_name_compact,
+    ))
+}
+
+fn compact_case_sensitive(value: &str) -> String {
+    value.chars().filter(|ch| ch.is_alphanumeric()).collect()
+}
+
+fn term_score(item: &LauncherItem, term: &QueryTerm, case_sensitive: bool) -> i64 {
+    let score = match term.kind {
+        QueryTermKind::Fuzzy => {
+            fuzzy_best_score(item, &term.text, &term.text, &term.compact, case_sensitive)
+        }
+        _ => literal_best_score(item, term, case_sensitive),
+    };
+    if term.negated {
+        i64::from(score <= 0)
     } else {
-        query.to_lowercase()
+        score
+    }
+}
+
+/// How an item's fuzzy score came about, as printed by `--debug-ranking`.
+/// Multi-term queries report per-term scores instead of a single tier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankingExplanation {
+    pub tier: &'static str,
+    pub matched_field: Option<&'static str>,
+    pub tier_base: i64,
+    pub penalty: i64,
+    pub terms: Vec<(String, i64)>,
+}
+
+fn field_tiers(
+    item: &LauncherItem,
+    term: &QueryTerm,
+    case_sensitive: bool,
+) -> [(&'static str, Option<(MatchTier, i64)>); 2] {
+    if case_sensitive {
+        return [
+            ("label", fuzzy_match_tier(&term.text, &item.label, true)),
+            (
+                "target_name",
+                fuzzy_match_tier(&term.text, &item.target_name, true),
+            ),
+        ];
+    }
+    [
+        (
+            "label",
+            fuzzy_match_tier_precomputed_lower(
+                &term.text,
+                &term.compact,
+                &item.label_lc,
+                &item.label_compact,
+                &item.label_initials,
+            ),
+        ),
+        (
+            "target_name",
+            fuzzy_match_tier_precomputed_lower(
+                &term.text,
+                &term.compact,
+                &item.target_name_lc,
+                &item.target_name_compact,
+                &item.target_name_initials,
+            ),
+        ),
+    ]
+}
+
+pub fn explain_ranked_item(
+    entry: &RankedItem,
+    query: &str,
+    case_sensitive: bool,
+) -> RankingExplanation {
+    let parsed = ParsedQuery::parse(query, case_sensitive);
+    let item = &entry.item;
+    let Some(term) = parsed.single_fuzzy_term() else {
+        return RankingExplanation {
+            tier: "terms",
+            matched_field: None,
+            tier_base: 0,
+            penalty: 0,
+            terms: term_scores(item, &parsed, case_sensitive),
+        };
     };
-    let query_compact = if case_sensitive {
-        String::new()
+
+    if entry.typo_distance.is_some() {
+        let (label, target_name) = if case_sensitive {
+            (&item.label, &item.target_name)
+        } else {
+            (&item.label_lc, &item.target_name_lc)
+        };
+        let label_score = typo_match(&term.text, label).map_or(0, |found| found.score);
+        let target_score = typo_match(&term.text, target_name).map_or(0, |found| found.score);
+        return RankingExplanation {
+            tier: "typo",
+            matched_field: Some(if label_score >= target_score {
+                "label"
+            } else {
+                "target_name"
+            }),
+            tier_base: TYPO_TIER_BASE,
+            penalty: TYPO_TIER_BASE - entry.fuzzy_score,
+            terms: Vec::new(),
+        };
+    }
+
+    // Same field choice as `fuzzy_best_score`: a strong label match wins
+    // outright, otherwise the better of label and executable name.
+    let [label, target_name] = field_tiers(item, term, case_sensitive);
+    let label_score = label.1.map_or(0, |(_, score)| score);
+    let target_score = target_name.1.map_or(0, |(_, score)| score);
+    if keyword_score(item, &term.text, case_sensitive) > label_score.max(target_score) {
+        return RankingExplanation {
+            tier: "keyword",
+            matched_field: Some("keywords"),
+            tier_base: KEYWORD_PREFIX_SCORE,
+            penalty: 0,
+            terms: Vec::new(),
+        };
+    }
+    let (field, found) = if label_score >= STRONG_LABEL_MATCH_SCORE || label_score >= target_score {
+        label
     } else {
-        compact_lower_alnum(&query_norm)
+        target_name
     };
+    match found {
+        Some((tier, score)) => RankingExplanation {
+            tier: tier.name(),
+            matched_field: Some(field),
+            tier_base: tier.base_score(),
+            penalty: tier.base_score() - score,
+            terms: Vec::new(),
+        },
+        None => RankingExplanation {
+            tier: "none",
+            matched_field: None,
+            tier_base: 0,
+            penalty: 0,
+            terms: Vec::new(),
+        },
+    }
+}
 
-    let mut ranked: Vec<RankedItem> = app_state
-        .all_items
-        .iter()
-        .filter_map(|item| {
-            let fuzzy = fuzzy_best_score(item, query, &query_norm, &query_compact, case_sensitive);
+/// Scores each term of `query` against `item`, as printed by `--debug-ranking`.
+/// Negated terms report 1 when the item passes the exclusion and 0 otherwise.
+pub fn term_scores(
+    item: &LauncherItem,
+    query: &ParsedQuery,
+    case_sensitive: bool,
+) -> Vec<(String, i64)> {
+    query
+        .terms()
+        .map(|term| (term.raw.clone(), term_score(item, term, case_sensitive)))
+        .collect()
+}
 
-            if fuzzy <= 0 {
-                return None;
+fn query_score(item: &LauncherItem, query: &ParsedQuery, case_sensitive: bool) -> i64 {
+    let mut total = 0;
+    let mut scored_groups = 0;
+
+    for group in &query.groups {
+        let mut satisfied = false;
+        let mut best = None;
+        for term in group {
+            let score = term_score(item, term, case_sensitive);
+            if score <= 0 {
+                continue;
             }
-            let boost = source_boost(app_state, item.source);
-            let total = fuzzy + boost;
-            Some(RankedItem {
-                item: item.clone(),
-                fuzzy_score: fuzzy,
-                source_boost: boost,
-                total_score: total,
-            })
-        })
-        .collect();
+            satisfied = true;
+            if !term.negated {
+                best = Some(best.map_or(score, |current: i64| current.max(score)));
+            }
+        }
+        if !satisfied {
+            return 0;
+        }
+        if let Some(score) = best {
+            total += score;
+            scored_groups += 1;
+        }
+    }
+
+    if scored_groups == 0 {
+        1
+    } else {
+        total / scored_groups
+    }
+}
+
+struct QueryScorer<'a> {
+    parsed: ParsedQuery,
+    query_norm: String,
+    query_compact: String,
+    pinned_target: Option<String>,
+    case_sensitive: bool,
+    app_state: &'a AppState,
+}
+
+impl<'a> QueryScorer<'a> {
+    fn new(app_state: &'a AppState, query: &str, case_sensitive: bool) -> Self {
+        let parsed = ParsedQuery::parse(query, case_sensitive);
+        let query_norm = match parsed.single_fuzzy_term() {
+            Some(term) if !case_sensitive => term.text.clone(),
+            _ => String::new(),
+        };
+        let query_compact = if case_sensitive {
+            String::new()
+        } else {
+            compact_folded(&query_norm)
+        };
+
+        Self {
+            parsed,
+            query_norm,
+            query_compact,
+            pinned_target: app_state.adaptive_aliases.pinned_target(query, unix_now()),
+            case_sensitive,
+            app_state,
+        }
+    }
+
+    fn score(&self, index: usize, item: &LauncherItem) -> Option<RankedIndex> {
+        let fuzzy = match self.parsed.single_fuzzy_term() {
+            Some(term) => fuzzy_best_score(
+                item,
+                &term.text,
+                &self.query_norm,
+                &self.query_compact,
+                self.case_sensitive,
+            ),
+            None => query_score(item, &self.parsed, self.case_sensitive),
+        };
+
+        if fuzzy <= 0 {
+            return None;
+        }
+        Some(self.entry(index, item, fuzzy, None))
+    }
+
+    /// Typo-tier score for an item the strict tiers rejected. Only plain
+    /// single-token queries are typo tolerant.
+    fn typo_score(&self, index: usize, item: &LauncherItem) -> Option<RankedIndex> {
+        let term = self.parsed.single_fuzzy_term()?;
+        let (label, target_name) = if self.case_sensitive {
+            (&item.label, &item.target_name)
+        } else {
+            (&item.label_lc, &item.target_name_lc)
+        };
+        let found = [
+            typo_match(&term.text, label),
+            typo_match(&term.text, target_name),
+        ]
+        .into_iter()
+        .flatten()
+        .max_by_key(|found| found.score)?;
+        Some(self.entry(index, item, found.score, Some(found.distance)))
+    }
 
+    fn wants_typo_fallback(&self, strict_matches: usize) -> bool {
+        strict_matches < TYPO_FALLBACK_MIN_RESULTS && self.parsed.single_fuzzy_term().is_some()
+    }
+
+    fn entry(
+        &self,
+        index: usize,
+        item: &LauncherItem,
+        fuzzy: i64,
+        typo_distance: Option<usize>,
+    ) -> RankedIndex {
+        let boost = source_boost(self.app_state, item.source)
+            + item.extra_source.as_ref().map_or(0, |extra| extra.boost);
+        let frecency = frecency_boost(self.app_state, item);
+        let alias = match &self.pinned_target {
+            Some(target) if item.target.eq_ignore_ascii_case(target) => ADAPTIVE_ALIAS_PIN_BOOST,
+            _ => 0,
+        };
+        let rule = item.rule.map_or(0, |hit| hit.boost);
+        RankedIndex {
+            index,
+            fuzzy_score: fuzzy,
+            source_boost: boost,
+            frecency_boost: frecency,
+            alias_boost: alias,
+            rule_boost: rule,
+            typo_distance,
+            total_score: fuzzy + boost + frecency + alias + rule,
+        }
+    }
+}
+
+/// Typo-tier matches for items outside the sorted `matched` indices, used only
+/// when the strict tiers found too few results.
+fn typo_fallback(
+    scorer: &QueryScorer,
+    dataset: &[LauncherItem],
+    in_scope: impl Fn(usize) -> bool,
+    matched: &[usize],
+) -> Vec<RankedIndex> {
+    if !scorer.wants_typo_fallback(matched.len()) {
+        return Vec::new();
+    }
+    dataset
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| in_scope(*index) && matched.binary_search(index).is_err())
+        .filter_map(|(index, item)| scorer.typo_score(index, item))
+        .collect()
+}
+
+fn sort_top_ranked(dataset: &[LauncherItem], ranked: &mut Vec<RankedIndex>) {
     if ranked.len() > PARTIAL_TOPK_THRESHOLD {
         let keep = PARTIAL_TOPK_LIMIT.min(ranked.len());
-        ranked.select_nth_unstable_by(keep - 1, rank_compare_desc);
+        ranked.select_nth_unstable_by(keep - 1, |a, b| rank_compare_desc(dataset, a, b));
         ranked.truncate(keep);
     }
 
-    ranked.sort_unstable_by(rank_compare_desc);
+    ranked.sort_unstable_by(|a, b| rank_compare_desc(dataset, a, b));
+}
+
+/// Copy of the `AppState` fields read while scoring, cheap enough to hand to a
+/// background ranking thread on every keystroke.
+pub fn scoring_state(app_state: &AppState) -> AppState {
+    AppState {
+        source_boost_history: app_state.source_boost_history,
+        source_boost_start_menu: app_state.source_boost_start_menu,
+        source_boost_path: app_state.source_boost_path,
+        source_boost_bookmarks: app_state.source_boost_bookmarks,
+        frecency_weight: app_state.frecency_weight,
+        adaptive_aliases: app_state.adaptive_aliases.clone(),
+        ..Default::default()
+    }
+}
+
+type ScoreFn<'a> = dyn Fn(usize, &LauncherItem) -> Option<RankedIndex> + Sync + 'a;
+
+fn score_parallel(
+    dataset: &[LauncherItem],
+    candidates: Option<&[usize]>,
+    threads: usize,
+    cancelled: &(dyn Fn() -> bool + Sync),
+    score: &ScoreFn,
+) -> Option<Vec<RankedIndex>> {
+    let total = candidates.map_or(dataset.len(), <[usize]>::len);
+    let chunk = total.div_ceil(threads.max(1)).max(PARALLEL_MIN_CHUNK);
+
+    let parts = thread::scope(|scope| {
+        let workers = (0..total)
+            .step_by(chunk)
+            .map(|start| {
+                scope.spawn(move || {
+                    let mut scored = Vec::new();
+                    for position in start..(start + chunk).min(total) {
+                        if (position - start) % CANCEL_CHECK_INTERVAL == 0 && cancelled() {
+                            return None;
+                        }
+                        let index = candidates.map_or(position, |indices| indices[position]);
+                        scored.extend(score(index, &dataset[index]));
+                    }
+                    Some(scored)
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().ok().flatten())
+            .collect::<Vec<_>>()
+    });
+
+    let mut scored = Vec::new();
+    for part in parts {
+        scored.extend(part?);
+    }
+    Some(scored)
+}
+
+/// Ranks `dataset` (or only `candidates`, when given) across `threads` worker
+/// threads. Returns `None` as soon as `cancelled` reports true; otherwise the
+/// top-k ranking plus every strictly matched index, in dataset order.
+pub fn rank_dataset_parallel(
+    scoring: &AppState,
+    dataset: &[LauncherItem],
+    candidates: Option<&[usize]>,
+    query: &str,
+    case_sensitive: bool,
+    threads: usize,
+    cancelled: &(dyn Fn() -> bool + Sync),
+) -> Option<(Vec<RankedIndex>, Vec<usize>)> {
+    let scorer = QueryScorer::new(scoring, query, case_sensitive);
+    let mut ranked = score_parallel(dataset, candidates, threads, cancelled, &|index, item| {
+        scorer.score(index, item)
+    })?;
+    let matched = ranked.iter().map(|entry| entry.index).collect::<Vec<_>>();
+
+    if scorer.wants_typo_fallback(matched.len()) {
+        ranked.extend(score_parallel(
+            dataset,
+            None,
+            threads,
+            cancelled,
+            &|index, item| {
+                if matched.binary_search(&index).is_ok() {
+                    return None;
+                }
+                scorer.typo_score(index, item)
+            },
+        )?);
+    }
+
+    sort_top_ranked(dataset, &mut ranked);
+    Some((ranked, matched))
+}
+
+/// Ranks the `all_items` entries `scope` keeps for `query`, re-scoring only
+/// the matches stored in `cache` when the query extends the cached one and
+/// neither the items nor the scope changed. The cache is updated with the full
+/// match set before the top-k cut.
+pub fn rank_items_incremental(
+    app_state: &AppState,
+    scope: Option<&SearchScope>,
+    query: &str,
+    case_sensitive: bool,
+    cache: &mut RankingCache,
+) -> Vec<RankedIndex> {
+    let dataset = &app_state.all_items;
+    let generation = app_state.items_generation;
+    let in_scope =
+        |index: usize| scope.is_none_or(|scope| scope.includes_source(dataset[index].source));
+    let scorer = QueryScorer::new(app_state, query, case_sensitive);
+    let mut ranked: Vec<RankedIndex> =
+        match cache.candidates(query, case_sensitive, generation, scope) {
+            Some(candidates) => candidates
+                .iter()
+                .filter_map(|&index| scorer.score(index, &dataset[index]))
+                .collect(),
+            None => dataset
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| in_scope(*index))
+                .filter_map(|(index, item)| scorer.score(index, item))
+                .collect(),
+        };
+
+    let matched = ranked.iter().map(|entry| entry.index).collect::<Vec<_>>();
+    ranked.extend(typo_fallback(&scorer, dataset, in_scope, &matched));
+    *cache = RankingCache {
+        query: query.to_string(),
+        case_sensitive,
+        items_generation: generation,
+        scope: scope.cloned(),
+        matched,
+    };
+    sort_top_ranked(dataset, &mut ranked);
+    ranked
+}
+
+fn rank_dataset(
+    app_state: &AppState,
+    dataset: &[LauncherItem],
+    query: &str,
+    case_sensitive: bool,
+) -> Vec<RankedIndex> {
+    let scorer = QueryScorer::new(app_state, query, case_sensitive);
+    let mut ranked: Vec<RankedIndex> = dataset
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| scorer.score(index, item))
+        .collect();
+    let matched = ranked.iter().map(|entry| entry.index).collect::<Vec<_>>();
+    ranked.extend(typo_fallback(&scorer, dataset, |_| true, &matched));
+    sort_top_ranked(dataset, &mut ranked);
     ranked
 }
 
-pub fn update_matching_items_with_dataset(
+pub fn rank_items(app_state: &AppState, query: &str, case_sensitive: bool) -> Vec<RankedItem> {
+    let dataset = &app_state.all_items;
+    rank_dataset(app_state, dataset, query, case_sensitive)
+        .into_iter()
+        .map(|entry| RankedItem {
+            item: dataset[entry.index].clone(),
+            fuzzy_score: entry.fuzzy_score,
+            source_boost: entry.source_boost,
+            frecency_boost: entry.frecency_boost,
+            alias_boost: entry.alias_boost,
+            rule_boost: entry.rule_boost,
+            typo_distance: entry.typo_distance,
+            total_score: entry.total_score,
+        })
+        .collect()
+}
+
+/// Ranks the `all_items` entries `scope` keeps together with `provider_items`
+/// into `matching_items`, where `query` is the current input minus any scope
+/// prefix. When entries share a target, only the first one from the preferred
+/// side is kept.
+pub fn update_matching_items(
     app_state: &mut AppState,
-    dataset: Vec<LauncherItem>,
+    scope: Option<&SearchScope>,
+    provider_items: Vec<LauncherItem>,
+    providers_first: bool,
+    query: &str,
     case_sensitive: bool,
-    max_visible_items: usize,
 ) {
-    if app_state.current_input.is_empty() {
-        app_state.matching_items = dataset;
-        ensure_selection_visible(app_state, max_visible_items);
-        return;
+    let core = if query.is_empty() {
+        app_state.ranking_cache = RankingCache::default();
+        (0..app_state.all_items.len())
+            .filter(|&index| {
+                scope.is_none_or(|scope| scope.includes_source(app_state.all_items[index].source))
+            })
+            .map(unranked)
+            .collect()
+    } else {
+        let mut cache = std::mem::take(&mut app_state.ranking_cache);
+        let core = rank_items_incremental(app_state, scope, query, case_sensitive, &mut cache);
+        app_state.ranking_cache = cache;
+        core
+    };
+    let providers = rank_or_list(app_state, &provider_items, query, case_sensitive);
+    app_state.matching_items = merge_ranked(
+        (&app_state.all_items, core),
+        (&provider_items, providers),
+        providers_first,
+        query.is_empty(),
+    );
+}
+
+/// `update_matching_items` for streamed stdin lines, which the streaming
+/// ranker already narrowed to the top matches.
+pub fn update_streamed_matching_items(
+    app_state: &mut AppState,
+    streamed_items: Vec<LauncherItem>,
+    provider_items: Vec<LauncherItem>,
+    providers_first: bool,
+    query: &str,
+    case_sensitive: bool,
+) {
+    let core = rank_or_list(app_state, &streamed_items, query, case_sensitive);
+    let providers = rank_or_list(app_state, &provider_items, query, case_sensitive);
+    app_state.matching_items = merge_ranked(
+        (&streamed_items, core),
+        (&provider_items, providers),
+        providers_first,
+        query.is_empty(),
+    );
+}
+
+/// Ranks `dataset`, or lists it in order for an empty query.
+fn rank_or_list(
+    app_state: &AppState,
+    dataset: &[LauncherItem],
+    query: &str,
+    case_sensitive: bool,
+) -> Vec<RankedIndex> {
+    if query.is_empty() {
+        (0..dataset.len()).map(unranked).collect()
+    } else {
+        rank_dataset(app_state, dataset, query, case_sensitive)
     }
+}
+
+/// Interleaves two ranked lists, or appends them with the preferred side first
+/// when `unranked`. Entries whose target already came from the preferred side
+/// are dropped.
+fn merge_ranked(
+    (core_items, core): (&[LauncherItem], Vec<RankedIndex>),
+    (provider_items, providers): (&[LauncherItem], Vec<RankedIndex>),
+    providers_first: bool,
+    unranked: bool,
+) -> Vec<LauncherItem> {
+    let mut seen = HashSet::new();
+    let mut keep = |item: &LauncherItem| seen.insert(launcher_item_dedupe_key(item));
+    let (core, providers) = if providers_first {
+        let providers = keep_unique(providers, provider_items, &mut keep);
+        (keep_unique(core, core_items, &mut keep), providers)
+    } else {
+        let core = keep_unique(core, core_items, &mut keep);
+        (core, keep_unique(providers, provider_items, &mut keep))
+    };
 
-    let mut temp_state = app_state.clone();
-    temp_state.all_items = dataset;
+    let mut matching = Vec::with_capacity(core.len() + providers.len());
+    let mut core = core.into_iter().peekable();
+    let mut providers = providers.into_iter().peekable();
+    loop {
+        let take_core = match (core.peek(), providers.peek()) {
+            (None, None) => break,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some(_), Some(_)) if unranked => !providers_first,
+            (Some(a), Some(b)) => ranked_order(core_items, a, provider_items, b).is_le(),
+        };
+        matching.push(if take_core {
+            core_items[core.next().expect("peeked").index].clone()
+        } else {
+            provider_items[providers.next().expect("peeked").index].clone()
+        });
+    }
+    matching
+}
+
+fn unranked(index: usize) -> RankedIndex {
+    RankedIndex {
+        index,
+        fuzzy_score: 0,
+        source_boost: 0,
+        frecency_boost: 0,
+        alias_boost: 0,
+        rule_boost: 0,
+        typo_distance: None,
+        total_score: 0,
+    }
+}
+
+fn keep_unique(
+    ranked: Vec<RankedIndex>,
+    dataset: &[LauncherItem],
+    keep: &mut impl FnMut(&LauncherItem) -> bool,
+) -> Vec<RankedIndex> {
+    ranked
+        .into_iter()
+        .filter(|entry| keep(&dataset[entry.index]))
+        .collect()
+}
 
-    let ranked = rank_items(&temp_state, &app_state.current_input, case_sensitive);
-    app_state.matching_items = ranked.into_iter().map(|entry| entry.item).collect();
-    ensure_selection_visible(app_state, max_visible_items);
+/// `rank_compare_desc` for entries of two different datasets.
+fn ranked_order(
+    a_dataset: &[LauncherItem],
+    a: &RankedIndex,
+    b_dataset: &[LauncherItem],
+    b: &RankedIndex,
+) -> std::cmp::Ordering {
+    a.typo_distance
+        .is_some()
+        .cmp(&b.typo_distance.is_some())
+        .then_with(|| b.total_score.cmp(&a.total_score))
+        .then_with(|| a_dataset[a.index].label.cmp(&b_dataset[b.index].label))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::rank_items;
+    use super::{
+        explain_ranked_item, query_narrows, rank_dataset_parallel, rank_items,
+        rank_items_incremental, update_matching_items, RankingCache,
+    };
+    use crate::adaptive_aliases::unix_now;
     use crate::app_state::{AppState, LauncherItem, LauncherSource};
+    use crate::rules::RankingRules;
+    use crate::scopes::SearchScope;
 
     #[test]
     fn ranking_keeps_executable_name_matching_when_label_is_friendly() {
@@ -148,4 +861,378 @@ mod tests {
         assert_eq!(ranked.len(), 1);
         assert_eq!(ranked[0].item.label, "Paint");
     }
+
+    #[test]
+    fn frecency_lifts_frequently_launched_history_items() {
+        let history_item = |label: &str, frecency: f64| {
+            let mut item = LauncherItem::new(
+                label.to_string(),
+                format!("C:/Tools/{label}.exe"),
+                LauncherSource::History,
+            );
+            item.frecency = frecency;
+            item
+        };
+        let state = AppState {
+            all_items: vec![history_item("codex", 0.02), history_item("coder", 9.0)],
+            frecency_weight: 260,
+            ..Default::default()
+        };
+
+        let ranked = rank_items(&state, "code", false);
+        assert_eq!(ranked[0].item.label, "coder");
+        assert!(ranked[0].frecency_boost > ranked[1].frecency_boost);
+        assert_eq!(ranked[1].frecency_boost, 5);
+    }
+
+    #[test]
+    fn adaptive_alias_pins_learned_target_for_query() {
+        let mut state = AppState {
+            all_items: vec![
+                LauncherItem::new(
+                    "Firefox".to_string(),
+                    "C:/Apps/firefox.exe".to_string(),
+                    LauncherSource::StartMenu,
+                ),
+                LauncherItem::new(
+                    "fi".to_string(),
+                    "C:/Tools/fi.exe".to_string(),
+                    LauncherSource::Path,
+                ),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(rank_items(&state, "fi", false)[0].item.label, "fi");
+
+        state
+            .adaptive_aliases
+            .record("fi", "C:/Apps/firefox.exe", unix_now());
+        let ranked = rank_items(&state, "fi", false);
+        assert_eq!(ranked[0].item.label, "Firefox");
+        assert!(ranked[0].alias_boost > 0);
+        assert_eq!(ranked[1].alias_boost, 0);
+    }
+
+    #[test]
+    fn multi_token_queries_match_tokens_independently_with_operators() {
+        let item = |label: &str, target: &str| {
+            LauncherItem::new(label.to_string(), target.to_string(), LauncherSource::Path)
+        };
+        let state = AppState {
+            all_items: vec![
+                item("Visual Studio Code", "C:/Apps/Code.exe"),
+                item("Project Manager", "C:/Apps/projects.exe"),
+                item("Code Project Tests", "C:/Apps/codetests.exe"),
+                item("Notepad", "C:/Windows/notepad.exe"),
+            ],
+            ..Default::default()
+        };
+        let labels = |query: &str| {
+            rank_items(&state, query, false)
+                .into_iter()
+                .map(|entry| entry.item.label)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels("proj code"), ["Code Project Tests"]);
+        assert_eq!(labels("code !tests"), ["Visual Studio Code"]);
+        assert_eq!(labels("^vis | ^note"), ["Notepad", "Visual Studio Code"]);
+        assert_eq!(labels("'proj ger$"), ["Project Manager"]);
+        assert_eq!(labels("^notepad$"), ["Notepad"]);
+        assert!(labels("'zzz").is_empty());
+    }
+
+    #[test]
+    fn incremental_ranking_narrows_and_matches_full_ranking() {
+        let dataset = (0..3000)
+            .map(|index| {
+                let path = format!("src/mod_{}/file_{index}.rs", index % 7);
+                LauncherItem::new(path.clone(), path, LauncherSource::Direct)
+            })
+            .collect::<Vec<_>>();
+        let state = AppState {
+            all_items: dataset.clone(),
+            ..Default::default()
+        };
+
+        let mut cache = RankingCache::default();
+        for typed in ["f", "fi", "file_1", "file_12", "file_12 mod_5", "file_1"] {
+            let incremental = rank_items_incremental(&state, None, typed, false, &mut cache);
+            let full = rank_items(&state, typed, false);
+            assert_eq!(incremental.len(), full.len(), "query {typed}");
+            for (left, right) in incremental.iter().zip(&full) {
+                assert_eq!(dataset[left.index].label, right.item.label);
+                assert_eq!(left.total_score, right.total_score);
+            }
+        }
+        assert_eq!(cache.query, "file_1");
+        assert!(cache.matched.len() > 400);
+    }
+
+    #[test]
+    fn scope_and_item_changes_invalidate_cached_matches() {
+        let item = |label: &str, source| {
+            LauncherItem::new(label.to_string(), format!("C:/{label}.exe"), source)
+        };
+        let mut state = AppState {
+            all_items: vec![
+                item("code", LauncherSource::History),
+                item("cargo", LauncherSource::Path),
+                item("Calculator", LauncherSource::StartMenu),
+            ],
+            ..Default::default()
+        };
+        let mut labels = |scope: Option<&SearchScope>, query: &str| {
+            update_matching_items(&mut state, scope, Vec::new(), false, query, false);
+            state
+                .matching_items
+                .iter()
+                .map(|item| item.label.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let history = SearchScope::Source(LauncherSource::History);
+        let path = SearchScope::Source(LauncherSource::Path);
+        let module = SearchScope::Module("calc".to_string());
+        assert_eq!(labels(Some(&history), ""), ["code"]);
+        assert_eq!(labels(None, "").len(), 3);
+        assert!(labels(Some(&module), "").is_empty());
+        assert_eq!(labels(Some(&history), "c"), ["code"]);
+        assert_eq!(labels(Some(&path), "ca"), ["cargo"]);
+
+        update_matching_items(&mut state, None, Vec::new(), false, "co", false);
+        let mut items = state.all_items.clone();
+        items.push(item("cobalt", LauncherSource::Path));
+        state.set_all_items(items);
+        update_matching_items(&mut state, None, Vec::new(), false, "cob", false);
+        assert_eq!(state.matching_items[0].label, "cobalt");
+    }
+
+    #[test]
+    fn duplicate_targets_keep_the_preferred_side() {
+        let item = |label: &str, target: &str| {
+            LauncherItem::new(
+                label.to_string(),
+                target.to_string(),
+                LauncherSource::Direct,
+            )
+        };
+        let mut state = AppState {
+            all_items: vec![item("Core A", "same-target"), item("Core B", "core-only")],
+            ..Default::default()
+        };
+        let provider = vec![
+            item("Provider A", "same-target"),
+            item("Provider B", "provider-only"),
+        ];
+        let mut labels = |providers_first: bool, query: &str| {
+            update_matching_items(
+                &mut state,
+                None,
+                provider.clone(),
+                providers_first,
+                query,
+                false,
+            );
+            state
+                .matching_items
+                .iter()
+                .map(|item| item.label.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels(false, ""), ["Core A", "Core B", "Provider B"]);
+        assert_eq!(labels(true, ""), ["Provider A", "Provider B", "Core B"]);
+        assert_eq!(labels(false, "a"), ["Core A"]);
+        assert_eq!(labels(true, "a"), ["Provider A"]);
+    }
+
+    #[test]
+    fn only_extending_queries_reuse_previous_matches() {
+        assert!(query_narrows("fi", "fil"));
+        assert!(query_narrows("code ", "code ^te"));
+        assert!(!query_narrows("", "a"));
+        assert!(!query_narrows("fil", "fi"));
+        assert!(!query_narrows("code", "code | vim"));
+        assert!(!query_narrows("!te", "!tes"));
+        assert!(!query_narrows("code$", "code$x"));
+        assert!(!query_narrows("-", "-a"));
+    }
+
+    #[test]
+    fn parallel_ranking_matches_serial_ranking_and_can_be_cancelled() {
+        let dataset = (0..20_000)
+            .map(|index| {
+                let path = format!("docs/part_{}/note_{index}.md", index % 13);
+                LauncherItem::new(path.clone(), path, LauncherSource::Direct)
+            })
+            .collect::<Vec<_>>();
+        let state = AppState {
+            all_items: dataset.clone(),
+            ..Default::default()
+        };
+
+        let (ranked, matched) =
+            rank_dataset_parallel(&state, &dataset, None, "part_7 note_1", false, 4, &|| false)
+                .expect("ranking is not cancelled");
+        let serial = rank_items(&state, "part_7 note_1", false);
+        assert_eq!(ranked.len(), serial.len());
+        assert_eq!(dataset[ranked[0].index].label, serial[0].item.label);
+        assert!(matched.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let (narrowed, _) = rank_dataset_parallel(
+            &state,
+            &dataset,
+            Some(&matched),
+            "part_7 note_12",
+            false,
+            4,
+            &|| false,
+        )
+        .expect("ranking is not cancelled");
+        assert_eq!(
+            narrowed.len(),
+            rank_items(&state, "part_7 note_12", false).len()
+        );
+
+        assert!(
+            rank_dataset_parallel(&state, &dataset, None, "note", false, 4, &|| true).is_none()
+        );
+    }
+
+    #[test]
+    fn typo_matches_are_a_fallback_below_strict_matches() {
+        let state = AppState {
+            all_items: vec![
+                LauncherItem::new(
+                    "Notepad".to_string(),
+                    "C:/Windows/notepad.exe".to_string(),
+                    LauncherSource::History,
+                ),
+                LauncherItem::new(
+                    "Network Tools Online Editor".to_string(),
+                    "C:/Tools/ntoe.exe".to_string(),
+                    LauncherSource::Path,
+                ),
+            ],
+            source_boost_history: 650,
+            ..Default::default()
+        };
+
+        let ranked = rank_items(&state, "ntoe", false);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].item.label, "Network Tools Online Editor");
+        assert_eq!(ranked[0].typo_distance, None);
+        assert_eq!(ranked[1].item.label, "Notepad");
+        assert_eq!(ranked[1].typo_distance, Some(1));
+
+        let ranked = rank_items(&state, "ntoepad", false);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].item.label, "Notepad");
+        assert!(rank_items(&state, "'ntoepad", false).is_empty());
+    }
+
+    #[test]
+    fn rule_boosts_pin_and_bury_matching_items() {
+        let (rules, errors) = RankingRules::parse(
+            "pin target *\\codium.exe\nbury label code\nboost label coder => 40\n",
+        );
+        assert!(errors.is_empty());
+        let items = ["code", "coder", "codium"]
+            .into_iter()
+            .map(|label| {
+                LauncherItem::new(
+                    label.to_string(),
+                    format!("C:/Tools/{label}.exe"),
+                    LauncherSource::Path,
+                )
+            })
+            .collect();
+        let state = AppState {
+            all_items: rules.apply(items),
+            ..Default::default()
+        };
+
+        let ranked = rank_items(&state, "cod", false);
+        let labels = ranked
+            .iter()
+            .map(|entry| entry.item.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["codium", "coder", "code"]);
+        assert_eq!(ranked[1].rule_boost, 40);
+        assert!(ranked[2].rule_boost < 0);
+    }
+
+    #[test]
+    fn explanations_report_tier_field_and_penalty() {
+        let state = AppState {
+            all_items: vec![
+                LauncherItem::new(
+                    "Paint".to_string(),
+                    "C:/Windows/mspaint.exe".to_string(),
+                    LauncherSource::StartMenu,
+                ),
+                LauncherItem::new(
+                    "Notepad".to_string(),
+                    "C:/Windows/notepad.exe".to_string(),
+                    LauncherSource::Path,
+                ),
+            ],
+            ..Default::default()
+        };
+        let explain = |query: &str| {
+            let ranked = rank_items(&state, query, false);
+            explain_ranked_item(&ranked[0], query, false)
+        };
+
+        let prefix = explain("note");
+        assert_eq!(prefix.tier, "prefix");
+        assert_eq!(prefix.matched_field, Some("label"));
+        assert_eq!((prefix.tier_base, prefix.penalty), (2600, 3));
+
+        let target = explain("mspaint");
+        assert_eq!(target.tier, "exact");
+        assert_eq!(target.matched_field, Some("target_name"));
+        assert_eq!(target.penalty, 0);
+
+        assert_eq!(explain("ntoepad").tier, "typo");
+
+        let terms = explain("note 'pad");
+        assert_eq!(terms.tier, "terms");
+        assert_eq!(terms.terms.len(), 2);
+    }
+    #[test]
+    fn keywords_match_by_prefix_below_label_matches() {
+        let mut files = LauncherItem::new(
+            "Files".to_string(),
+            "nautilus --new-window".to_string(),
+            LauncherSource::StartMenu,
+        );
+        files.set_keywords(vec!["Folder".to_string(), "Explorer".to_string()]);
+        let state = AppState {
+            all_items: vec![
+                files,
+                LauncherItem::new(
+                    "Explorer Tools".to_string(),
+                    "C:/Tools/tools.exe".to_string(),
+                    LauncherSource::Path,
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let ranked = rank_items(&state, "explo", false);
+        let labels = ranked
+            .iter()
+            .map(|entry| entry.item.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["Explorer Tools", "Files"]);
+
+        let ranked = rank_items(&state, "fold", false);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].fuzzy_score, 1300);
+        let explanation = explain_ranked_item(&ranked[0], "fold", false);
+        assert_eq!(explanation.tier, "keyword");
+        assert_eq!(explanation.matched_field, Some("keywords"));
+    }
 }
@@ -284,6 +284,7 @@ mod tests {
             api_version: MODULE_API_VERSION,
             kind: "script".to_string(),
             capabilities: Vec::new(),
+            schemes: Vec::new(),
             enabled: true,
             priority: 0,
             description: None,
@@ -1270,17 +1270,18 @@ mod tests {
 
     #[test]
     fn rmods_state_paths_use_data_root() {
+        let state_dir = Path::new("C:\\rMenuData").join("state");
         assert_eq!(
             rmods_installed_state_path(Some("C:\\rMenuData")),
-            PathBuf::from("C:\\rMenuData\\state\\rmods-installed.json")
+            state_dir.join("rmods-installed.json")
         );
         assert_eq!(
             rmods_registry_cache_path(Some("C:\\rMenuData")),
-            PathBuf::from("C:\\rMenuData\\state\\rmods-registry-cache.json")
+            state_dir.join("rmods-registry-cache.json")
         );
         assert_eq!(
             rmods_downloads_dir(Some("C:\\rMenuData")),
-            PathBuf::from("C:\\rMenuData\\state\\downloads")
+            state_dir.join("downloads")
         );
     }
 
@@ -0,0 +1,218 @@
+//! Frontend-independent layout of result rows: label, hint, and chip zones,
+//! text truncation, and match highlight positions.
+
+use crate::app_state::AppState;
+use crate::fuzzy::{query_match_positions, ParsedQuery};
+use crate::scopes::split_scope;
+use crate::settings::ScopeConfig;
+use std::path::{Path, MAIN_SEPARATOR};
+
+pub fn abbreviate_target(target: &str, max_len: usize) -> String {
+    if target.len() <= max_len {
+        return target.to_string();
+    }
+
+    let keep = max_len.saturating_sub(3);
+    let tail = target
+        .chars()
+        .rev()
+        .take(keep)
+        .collect::<Vec<char>>()
+        .into_iter()
+        .rev()
+        .collect::<String>();
+    format!("...{}", tail)
+}
+
+pub fn truncate_with_ellipsis_end(value: &str, max_chars: usize) -> String {
+    let count = value.chars().count();
+    if count <= max_chars {
+        return value.to_string();
+    }
+    if max_chars <= 3 {
+        return ".".repeat(max_chars);
+    }
+
+    let keep = max_chars - 3;
+    let prefix: String = value.chars().take(keep).collect();
+    format!("{}...", prefix)
+}
+
+/// `<sep>parent<sep>file` for path targets, using the platform's separator.
+pub fn compact_target_hint(target: &str) -> String {
+    let path = Path::new(target);
+
+    if let Some(file_name) = path.file_name().and_then(|value| value.to_str()) {
+        if let Some(parent_name) = path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .and_then(|value| value.to_str())
+        {
+            return format!("{MAIN_SEPARATOR}{parent_name}{MAIN_SEPARATOR}{file_name}");
+        }
+        return file_name.to_string();
+    }
+
+    abbreviate_target(target, 44)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowZones {
+    pub left_text: String,
+    pub right_text: String,
+    pub chip_text: Option<String>,
+    pub right_x: i32,
+    pub chip_x: i32,
+}
+
+/// Splits a result row into label, hint, and chip zones. Positions and widths
+/// are in the caller's units: pixels for Win32, cells for the terminal.
+pub fn compute_row_zones(
+    label: &str,
+    hint: &str,
+    chip: Option<String>,
+    left_x: i32,
+    row_right_bound: i32,
+    char_w: i32,
+) -> RowZones {
+    if row_right_bound <= left_x || char_w <= 0 {
+        return RowZones {
+            left_text: String::new(),
+            right_text: String::new(),
+            chip_text: None,
+            right_x: left_x,
+            chip_x: left_x,
+        };
+    }
+
+    let total_w = row_right_bound - left_x;
+    let min_gap = char_w * 2;
+    let zone_gap = char_w;
+    let min_label_w = char_w * 4;
+    let min_hint_chars = 4;
+
+    let mut chip_text = chip.filter(|value| !value.is_empty());
+    let mut chip_w = chip_text
+        .as_ref()
+        .map(|text| text.chars().count() as i32 * char_w)
+        .unwrap_or(0);
+
+    if chip_text.is_some() && total_w < min_label_w + zone_gap + chip_w {
+        chip_text = None;
+        chip_w = 0;
+    }
+
+    let hint_right_bound = if chip_text.is_some() {
+        row_right_bound - chip_w - zone_gap
+    } else {
+        row_right_bound
+    };
+
+    let mut right_text = String::new();
+    let mut right_w = 0;
+    let available_for_hint = hint_right_bound - left_x - min_label_w - min_gap;
+    if !hint.is_empty() && available_for_hint >= char_w * min_hint_chars {
+        let max_hint_chars = (available_for_hint / char_w).max(1) as usize;
+        right_text = abbreviate_target(hint, max_hint_chars);
+        right_w = right_text.chars().count() as i32 * char_w;
+    }
+
+    let right_x = if right_text.is_empty() {
+        hint_right_bound
+    } else {
+        hint_right_bound - right_w
+    };
+
+    let label_right_limit = if right_text.is_empty() {
+        hint_right_bound
+    } else {
+        right_x - min_gap
+    };
+    let left_max_w = (label_right_limit - left_x).max(char_w);
+    let left_max_chars = (left_max_w / char_w).max(1) as usize;
+    let left_text = truncate_with_ellipsis_end(label, left_max_chars);
+
+    let chip_x = if chip_text.is_some() {
+        row_right_bound - chip_w
+    } else {
+        hint_right_bound
+    };
+
+    RowZones {
+        left_text,
+        right_text,
+        chip_text,
+        right_x,
+        chip_x,
+    }
+}
+
+/// Character positions of `text` matched by the current query, for highlighting.
+pub fn match_highlight_positions(
+    app_state: &AppState,
+    text: &str,
+    case_sensitive: bool,
+    scopes: &ScopeConfig,
+) -> Vec<usize> {
+    let query = split_scope(&app_state.current_input, scopes).1.trim();
+    if query.is_empty() || query.starts_with('/') || app_state.action_menu.is_some() {
+        return Vec::new();
+    }
+
+    let visible_len = text.chars().count();
+    let truncated = text.ends_with('…');
+    let parsed = ParsedQuery::parse(query, case_sensitive);
+    query_match_positions(&parsed, text, case_sensitive)
+        .into_iter()
+        .filter(|index| !(truncated && *index + 1 == visible_len))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compact_target_hint, compute_row_zones};
+    use std::path::MAIN_SEPARATOR;
+
+    #[test]
+    fn target_hint_keeps_parent_and_file_with_the_platform_separator() {
+        assert_eq!(
+            compact_target_hint("/usr/bin/calc"),
+            format!("{MAIN_SEPARATOR}bin{MAIN_SEPARATOR}calc")
+        );
+        assert_eq!(compact_target_hint("calc"), "calc");
+    }
+
+    #[test]
+    fn row_zones_hide_hint_and_chip_on_extreme_width() {
+        let row = compute_row_zones(
+            "Very long label value",
+            "C:/a/very/long/path/hint",
+            Some("[1]".to_string()),
+            0,
+            10,
+            2,
+        );
+
+        assert!(row.right_text.is_empty());
+        assert!(row.chip_text.is_none());
+        assert!(!row.left_text.is_empty());
+    }
+
+    #[test]
+    fn row_zones_keep_all_zones_when_width_is_enough() {
+        let row = compute_row_zones(
+            "Calculator",
+            "\\Windows\\System32\\calc.exe",
+            Some("[1]".to_string()),
+            0,
+            120,
+            2,
+        );
+
+        assert!(!row.left_text.is_empty());
+        assert!(!row.right_text.is_empty());
+        assert_eq!(row.chip_text.as_deref(), Some("[1]"));
+        assert!(row.right_x >= 0);
+        assert!(row.chip_x >= 0);
+    }
+}
@@ -16,6 +16,7 @@ use windows::core::PCWSTR;
 use windows::Win32::System::Com::Urlmon::URLDownloadToFileW;
 
 const DEFAULT_DEV_RSNIP_PATH: &str = "C:\\rSnip\\target\\release\\rsnip.exe";
+#[cfg(windows)]
 const RSNIP_LATEST_EXE_URL: &str =
     "https://github.com/SynrgStudio/rSnip/releases/latest/download/rsnip.exe";
 const RSNIP_PIPE_PATH: &str = r"\\.\pipe\rsnip";
@@ -384,7 +385,10 @@ mod tests {
     fn companion_path_uses_data_dir_layout() {
         assert_eq!(
             companion_rsnip_path_from_data_dir(Some("C:\\rMenuData")),
-            PathBuf::from("C:\\rMenuData\\companions\\rsnip\\rsnip.exe")
+            Path::new("C:\\rMenuData")
+                .join("companions")
+                .join("rsnip")
+                .join("rsnip.exe")
         );
     }
 
@@ -16,6 +16,7 @@ use windows::core::PCWSTR;
 use windows::Win32::System::Com::Urlmon::URLDownloadToFileW;
 
 const DEFAULT_DEV_RTASKS_PATH: &str = "C:\\rTasks\\target\\release\\rtasks.exe";
+#[cfg(windows)]
 const RTASKS_LATEST_EXE_URL: &str =
     "https://github.com/SynrgStudio/rtasks/releases/latest/download/rtasks.exe";
 const RTASKS_PIPE_PATH: &str = r"\\.\pipe\rtasks";
@@ -413,7 +414,10 @@ mod tests {
     fn companion_path_uses_data_dir_layout() {
         assert_eq!(
             companion_rtasks_path_from_data_dir(Some("C:\\rMenuData")),
-            PathBuf::from("C:\\rMenuData\\companions\\rtasks\\rtasks.exe")
+            Path::new("C:\\rMenuData")
+                .join("companions")
+                .join("rtasks")
+                .join("rtasks.exe")
         );
     }
 }
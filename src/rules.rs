@@ -0,0 +1,455 @@
+use crate::app_state::LauncherItem;
+use crate::settings::rmenu_data_dirs;
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const RULES_FILE_NAME: &str = "rules.txt";
+pub const RULE_PIN_BOOST: i64 = 50_000;
+pub const RULE_BURY_PENALTY: i64 = -50_000;
+
+/// Ranking effect of the rule that matched an item, kept on the item so
+/// `--debug-ranking` can report it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleHit {
+    pub line: usize,
+    pub action: &'static str,
+    pub boost: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RuleField {
+    Label,
+    Target,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RuleAction {
+    Pin,
+    Boost(i64),
+    Bury,
+    Hide,
+    Relabel(String),
+}
+
+impl RuleAction {
+    fn name(&self) -> &'static str {
+        match self {
+            RuleAction::Pin => "pin",
+            RuleAction::Boost(_) => "boost",
+            RuleAction::Bury => "bury",
+            RuleAction::Hide => "hide",
+            RuleAction::Relabel(_) => "relabel",
+        }
+    }
+
+    fn boost(&self) -> i64 {
+        match self {
+            RuleAction::Pin => RULE_PIN_BOOST,
+            RuleAction::Boost(value) => *value,
+            RuleAction::Bury => RULE_BURY_PENALTY,
+            RuleAction::Hide | RuleAction::Relabel(_) => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Glob(Vec<char>),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw.strip_prefix("re:") {
+            Some(expr) => RegexBuilder::new(expr)
+                .case_insensitive(true)
+                .build()
+                .map(Pattern::Regex)
+                .map_err(|err| {
+                    // Syntax errors span several lines; the last one names the problem.
+                    let message = err.to_string();
+                    let reason = message.lines().last().unwrap_or_default();
+                    format!("invalid regex: {}", reason.trim_start_matches("error: "))
+                }),
+            None => Ok(Pattern::Glob(normalize_rule_text(raw).chars().collect())),
+        }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        let value = normalize_rule_text(value);
+        match self {
+            Pattern::Glob(glob) => glob_matches(glob, &value.chars().collect::<Vec<_>>()),
+            Pattern::Regex(regex) => regex.is_match(&value),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    line: usize,
+    field: RuleField,
+    pattern: Pattern,
+    action: RuleAction,
+}
+
+/// Pin, boost, bury, hide, and relabel overrides read from `rules.txt`.
+/// Rules are checked top to bottom and the first one matching an item applies.
+#[derive(Debug, Clone, Default)]
+pub struct RankingRules {
+    rules: Vec<Rule>,
+}
+
+pub fn ranking_rules_path(config_dir: &Path) -> PathBuf {
+    config_dir.join(RULES_FILE_NAME)
+}
+
+pub fn load_ranking_rules(cli_data_dir: Option<&str>, silent_mode: bool) -> RankingRules {
+    RankingRulesFile::load(cli_data_dir, silent_mode).rules
+}
+
+fn read_ranking_rules(path: &Path, silent_mode: bool) -> RankingRules {
+    let Ok(content) = fs::read_to_string(path) else {
+        return RankingRules::default();
+    };
+
+    let (rules, errors) = RankingRules::parse(&content);
+    if !silent_mode {
+        for error in errors {
+            eprintln!("{}: {}", path.display(), error);
+        }
+    }
+    rules
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// `rules.txt` together with the modification time it was read at, for
+/// long-running processes that pick up edits without a restart.
+#[derive(Debug, Clone)]
+pub struct RankingRulesFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    rules: RankingRules,
+}
+
+impl RankingRulesFile {
+    pub fn load(cli_data_dir: Option<&str>, silent_mode: bool) -> Self {
+        let dirs = rmenu_data_dirs(cli_data_dir);
+        Self::load_path(ranking_rules_path(&dirs.config_dir), silent_mode)
+    }
+
+    fn load_path(path: PathBuf, silent_mode: bool) -> Self {
+        let modified = modified_time(&path);
+        let rules = read_ranking_rules(&path, silent_mode);
+        Self {
+            path,
+            modified,
+            rules,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn rules(&self) -> &RankingRules {
+        &self.rules
+    }
+
+    /// Re-reads the file when its modification time changed, including when it
+    /// was created or deleted. Returns whether the rules were reloaded.
+    pub fn reload_if_changed(&mut self, silent_mode: bool) -> bool {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        self.rules = read_ranking_rules(&self.path, silent_mode);
+        true
+    }
+}
+
+/// Case-insensitive text with `/` separators, so `C:\Tools\*` and
+/// `c:/tools/*` are the same pattern.
+fn normalize_rule_text(value: &str) -> String {
+    value.to_lowercase().replace('\\', "/")
+}
+
+impl RankingRules {
+    /// Parses one rule per line as `<action> <label|target> <pattern> [=> <argument>]`.
+    /// Invalid lines are skipped and reported.
+    pub fn parse(content: &str) -> (Self, Vec<String>) {
+        let mut rules = Vec::new();
+        let mut errors = Vec::new();
+
+        for (index, line) in content.trim_start_matches('\u{feff}').lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_rule(index + 1, line) {
+                Ok(rule) => rules.push(rule),
+                Err(err) => errors.push(format!("line {}: {err}", index + 1)),
+            }
+        }
+
+        (Self { rules }, errors)
+    }
+
+    fn first_match(&self, item: &LauncherItem) -> Option<&Rule> {
+        self.rules.iter().find(|rule| {
+            let value = match rule.field {
+                RuleField::Label => &item.label,
+                RuleField::Target => &item.target,
+            };
+            rule.pattern.matches(value)
+        })
+    }
+
+    /// Drops hidden items, relabels renamed ones, and tags the rest with the
+    /// rule whose boost ranking applies.
+    pub fn apply(&self, items: Vec<LauncherItem>) -> Vec<LauncherItem> {
+        if self.rules.is_empty() {
+            return items;
+        }
+
+        items
+            .into_iter()
+            .filter_map(|mut item| {
+                let Some(rule) = self.first_match(&item) else {
+                    return Some(item);
+                };
+                match &rule.action {
+                    RuleAction::Hide => return None,
+                    RuleAction::Relabel(label) => item.set_label(label.clone()),
+                    _ => {}
+                }
+                item.rule = Some(RuleHit {
+                    line: rule.line,
+                    action: rule.action.name(),
+                    boost: rule.action.boost(),
+                });
+                Some(item)
+            })
+            .collect()
+    }
+}
+
+fn parse_rule(line_number: usize, line: &str) -> Result<Rule, String> {
+    let (action, rest) = line
+        .split_once(char::is_whitespace)
+        .ok_or("expected `<action> <label|target> <pattern>`")?;
+    let (field, rest) = rest
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .ok_or("missing pattern")?;
+    let field = match field.to_ascii_lowercase().as_str() {
+        "label" => RuleField::Label,
+        "target" => RuleField::Target,
+        other => return Err(format!("unknown field `{other}`, expected label or target")),
+    };
+    let (pattern, argument) = match rest.split_once("=>") {
+        Some((pattern, argument)) => (pattern.trim(), Some(argument.trim())),
+        None => (rest.trim(), None),
+    };
+    if pattern.is_empty() {
+        return Err("missing pattern".to_string());
+    }
+
+    let action = match (action.to_ascii_lowercase().as_str(), argument) {
+        ("pin", None) => RuleAction::Pin,
+        ("bury", None) => RuleAction::Bury,
+        ("hide", None) => RuleAction::Hide,
+        ("boost", Some(value)) => RuleAction::Boost(
+            value
+                .parse()
+                .map_err(|_| format!("boost expects a number, got `{value}`"))?,
+        ),
+        ("relabel", Some(label)) if !label.is_empty() => RuleAction::Relabel(label.to_string()),
+        ("boost" | "relabel", _) => return Err(format!("{action} needs `=> <value>`")),
+        ("pin" | "bury" | "hide", Some(_)) => {
+            return Err(format!("{action} does not take an argument"))
+        }
+        (other, _) => return Err(format!("unknown action `{other}`")),
+    };
+
+    Ok(Rule {
+        line: line_number,
+        field,
+        pattern: Pattern::parse(pattern)?,
+        action,
+    })
+}
+
+/// Case-insensitive whole-string glob match that treats `\` and `/` alike.
+pub fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern = normalize_rule_text(pattern).chars().collect::<Vec<_>>();
+    glob_matches(
+        &pattern,
+        &normalize_rule_text(value).chars().collect::<Vec<_>>(),
+    )
+}
+
+/// Whole-string glob match where `*` spans any run of characters and `?` one.
+fn glob_matches(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        glob_matches, Pattern, RankingRules, RankingRulesFile, RULE_BURY_PENALTY, RULE_PIN_BOOST,
+    };
+    use crate::app_state::{LauncherItem, LauncherSource};
+    use std::time::{Duration, SystemTime};
+
+    fn item(label: &str, target: &str) -> LauncherItem {
+        LauncherItem::new(label.to_string(), target.to_string(), LauncherSource::Path)
+    }
+
+    #[test]
+    fn glob_patterns_match_whole_values() {
+        let glob = |pattern: &str, text: &str| {
+            glob_matches(
+                &pattern.chars().collect::<Vec<_>>(),
+                &text.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert!(glob("*/system32/*", "c:/windows/system32/reg.exe"));
+        assert!(glob("c?de", "code"));
+        assert!(glob("*code*", "vscode.exe"));
+        assert!(!glob("code", "vscode"));
+        assert!(!glob("*.exe", "tool.bat"));
+    }
+
+    #[test]
+    fn regex_patterns_are_case_insensitive_over_normalized_text() {
+        let matches = |pattern: &str, text: &str| {
+            Pattern::parse(&format!("re:{pattern}"))
+                .unwrap()
+                .matches(text)
+        };
+        assert!(matches("^visual (studio|code)$", "Visual Code"));
+        assert!(!matches("^visual (studio|code)$", "visual studio 2022"));
+        assert!(matches("python3\\.\\d+", "C:\\Python\\python3.12.exe"));
+        assert!(matches("^c:/tools/", "C:\\Tools\\x.exe"));
+        assert!(matches("[a-c]+x?$", "abcab"));
+        assert!(!matches("[^a-z]", "ABC"));
+        assert!(matches("^$", ""));
+        assert_eq!(
+            Pattern::parse("re:(unclosed").unwrap_err(),
+            "invalid regex: unclosed group"
+        );
+        assert!(Pattern::parse("re:*bad").is_err());
+        assert!(Pattern::parse("re:[z-a]").is_err());
+    }
+
+    #[test]
+    fn rules_hide_relabel_and_tag_items_with_the_first_match() {
+        let (rules, errors) = RankingRules::parse(
+            "# comment\n\
+             hide target C:\\Windows\\System32\\*\n\
+             relabel target *\\wt.exe => Windows Terminal\n\
+             pin label re:^code$\n\
+             bury label code*\n\
+             boost target *.bat => 120\n\
+             boost label nope\n\
+             frobnicate label x\n",
+        );
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("line 7:"));
+
+        let applied = rules.apply(vec![
+            item("reg", "C:/Windows/System32/reg.exe"),
+            item(
+                "wt",
+                "C:\\Users\\me\\AppData\\Local\\Microsoft\\WindowsApps\\wt.exe",
+            ),
+            item("Code", "C:/Apps/Code.exe"),
+            item("codex", "C:/Tools/codex.exe"),
+            item("build", "C:/Tools/build.bat"),
+            item("notepad", "C:/Windows/notepad.exe"),
+        ]);
+
+        let labels = applied
+            .iter()
+            .map(|item| item.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            ["Windows Terminal", "Code", "codex", "build", "notepad"]
+        );
+        assert_eq!(applied[0].label_lc, "windows terminal");
+        assert_eq!(applied[0].rule.map(|hit| hit.line), Some(3));
+        assert_eq!(applied[1].rule.map(|hit| hit.boost), Some(RULE_PIN_BOOST));
+        assert_eq!(
+            applied[2].rule.map(|hit| hit.boost),
+            Some(RULE_BURY_PENALTY)
+        );
+        assert_eq!(applied[3].rule.map(|hit| hit.action), Some("boost"));
+        assert!(applied[4].rule.is_none());
+    }
+
+    #[test]
+    fn rules_file_reloads_when_its_modification_time_changes() {
+        let dir = std::env::temp_dir().join(format!("rmenu-rules-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rules.txt");
+        let _ = std::fs::remove_file(&path);
+
+        let mut file = RankingRulesFile::load_path(path.clone(), true);
+        assert!(file.rules().rules.is_empty());
+        assert!(!file.reload_if_changed(true));
+
+        std::fs::write(&path, "hide label notepad\n").unwrap();
+        assert!(file.reload_if_changed(true));
+        assert!(file
+            .rules()
+            .apply(vec![item("notepad", "n.exe")])
+            .is_empty());
+        assert!(!file.reload_if_changed(true));
+
+        // Same-second writes can share an mtime; move it forward explicitly.
+        std::fs::write(&path, "pin label notepad\n").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(5)))
+            .unwrap();
+        assert!(file.reload_if_changed(true));
+        assert_eq!(file.rules().apply(vec![item("notepad", "n.exe")]).len(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(file.reload_if_changed(true));
+        assert!(file.rules().rules.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
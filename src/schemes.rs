@@ -0,0 +1,309 @@
+//! Launch-target schemes. A target like `rsnip:snip` or `mymod:do-thing` names
+//! a scheme before its first `:`, and the launcher hands the rest to the handler
+//! registered for that scheme instead of passing the target to the shell.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Arc;
+
+/// Schemes the launcher handles itself. Modules cannot claim these.
+pub const BUILTIN_SCHEMES: &[&str] = &[
+    "copy",
+    "hidden",
+    "mailto",
+    "ms-settings",
+    "rsnip",
+    "rtasks",
+    "runas",
+    "shell",
+];
+
+pub trait SchemeHandler: Send + Sync {
+    /// Launches the part of the target after `<scheme>:`.
+    fn launch(&self, rest: &str) -> io::Result<()>;
+}
+
+impl<F: Fn(&str) -> io::Result<()> + Send + Sync> SchemeHandler for F {
+    fn launch(&self, rest: &str) -> io::Result<()> {
+        self(rest)
+    }
+}
+
+/// Two or more ASCII letters, digits, `-`, or `+`, starting with a letter, so
+/// drive letters like `C:` never count.
+pub fn is_valid_scheme_name(name: &str) -> bool {
+    name.len() >= 2
+        && name.starts_with(|ch: char| ch.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '+')
+}
+
+/// Splits `scheme:rest` and lowercases the scheme. Hierarchical URIs such as
+/// `https://` and `steam://` are left to the shell.
+pub fn split_scheme(target: &str) -> Option<(String, &str)> {
+    let (scheme, rest) = target.trim_start().split_once(':')?;
+    if !is_valid_scheme_name(scheme) || rest.starts_with("//") {
+        return None;
+    }
+    Some((scheme.to_ascii_lowercase(), rest))
+}
+
+/// Lowercases and de-duplicates the schemes a module declares, rejecting
+/// invalid names and builtin schemes.
+pub fn parse_module_schemes<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<String>, String> {
+    let mut schemes = Vec::new();
+    for name in names {
+        let scheme = name.trim().to_ascii_lowercase();
+        if !is_valid_scheme_name(&scheme) {
+            return Err(format!("invalid scheme '{}'", name.trim()));
+        }
+        if BUILTIN_SCHEMES.contains(&scheme.as_str()) {
+            return Err(format!("scheme '{scheme}' is reserved"));
+        }
+        if !schemes.contains(&scheme) {
+            schemes.push(scheme);
+        }
+    }
+    Ok(schemes)
+}
+
+#[derive(Clone)]
+enum SchemeOwner {
+    Handler(Arc<dyn SchemeHandler>),
+    Module(String),
+}
+
+/// Where a launch target goes.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SchemeRoute<'a> {
+    /// No scheme, or one the OS has a protocol handler for.
+    Shell,
+    /// A builtin scheme, launched by its registered handler.
+    Builtin,
+    /// A module-declared scheme; `rest` is the module command and arguments.
+    Module { module: &'a str, rest: &'a str },
+    /// A scheme nobody handles.
+    Unknown(String),
+}
+
+/// Builtin handlers registered by the platform at startup plus the schemes
+/// enabled modules declare. Owned by the module runtime.
+#[derive(Clone)]
+pub struct SchemeRegistry {
+    schemes: BTreeMap<String, SchemeOwner>,
+    os_protocol: fn(&str) -> bool,
+}
+
+impl Default for SchemeRegistry {
+    fn default() -> Self {
+        Self {
+            schemes: BTreeMap::new(),
+            os_protocol: |_| false,
+        }
+    }
+}
+
+impl SchemeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, scheme: &str, handler: impl SchemeHandler + 'static) {
+        self.schemes.insert(
+            scheme.to_ascii_lowercase(),
+            SchemeOwner::Handler(Arc::new(handler)),
+        );
+    }
+
+    /// Asks the OS about schemes nobody registered, e.g. `ms-windows-store:`
+    /// or `zoommtg:`, so installed protocol handlers keep working.
+    pub fn set_os_protocol_probe(&mut self, probe: fn(&str) -> bool) {
+        self.os_protocol = probe;
+    }
+
+    /// Routes `scheme` to `module`. Builtin schemes and schemes another module
+    /// already claimed are refused.
+    pub fn register_module(&mut self, module: &str, scheme: &str) -> Result<(), String> {
+        let scheme = scheme.to_ascii_lowercase();
+        if BUILTIN_SCHEMES.contains(&scheme.as_str()) {
+            return Err(format!("scheme '{scheme}' is reserved"));
+        }
+        match self.schemes.get(&scheme) {
+            Some(SchemeOwner::Module(owner)) if owner != module => {
+                Err(format!("scheme '{scheme}' is already claimed by {owner}"))
+            }
+            Some(SchemeOwner::Handler(_)) => Err(format!("scheme '{scheme}' is reserved")),
+            _ => {
+                self.schemes
+                    .insert(scheme, SchemeOwner::Module(module.to_string()));
+                Ok(())
+            }
+        }
+    }
+
+    pub fn clear_modules(&mut self) {
+        self.schemes
+            .retain(|_, owner| matches!(owner, SchemeOwner::Handler(_)));
+    }
+
+    pub fn schemes(&self) -> impl Iterator<Item = &str> {
+        self.schemes.keys().map(String::as_str)
+    }
+
+    pub fn route<'a>(&'a self, target: &'a str) -> SchemeRoute<'a> {
+        let Some((scheme, rest)) = split_scheme(target) else {
+            return SchemeRoute::Shell;
+        };
+        match self.schemes.get(&scheme) {
+            Some(SchemeOwner::Handler(_)) => SchemeRoute::Builtin,
+            Some(SchemeOwner::Module(module)) => SchemeRoute::Module { module, rest },
+            None if BUILTIN_SCHEMES.contains(&scheme.as_str()) => SchemeRoute::Builtin,
+            None if (self.os_protocol)(&scheme) => SchemeRoute::Shell,
+            None => SchemeRoute::Unknown(scheme),
+        }
+    }
+
+    /// Routes `target` to its scheme's handler, or to `default` when it has no
+    /// scheme or the OS handles it. Module and unknown schemes are an error
+    /// rather than a shell launch.
+    pub fn launch(
+        &self,
+        target: &str,
+        default: impl FnOnce(&str) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let scheme_error = |kind, message: String| Err(io::Error::new(kind, message));
+        match self.route(target) {
+            SchemeRoute::Shell => default(target),
+            SchemeRoute::Builtin => {
+                let Some((scheme, rest)) = split_scheme(target) else {
+                    return default(target);
+                };
+                match self.schemes.get(&scheme) {
+                    Some(SchemeOwner::Handler(handler)) => handler.launch(rest),
+                    _ => scheme_error(
+                        io::ErrorKind::Unsupported,
+                        format!("no handler for '{scheme}:' targets on this platform"),
+                    ),
+                }
+            }
+            SchemeRoute::Module { module, .. } => scheme_error(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "'{}' is a {module} command, not a launch target",
+                    target.trim()
+                ),
+            ),
+            SchemeRoute::Unknown(scheme) => scheme_error(
+                io::ErrorKind::InvalidInput,
+                format!("unknown launch scheme '{scheme}:' in '{}'", target.trim()),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_module_schemes, split_scheme, SchemeRegistry, SchemeRoute};
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn splits_only_opaque_scheme_targets() {
+        assert_eq!(
+            split_scheme(" RSnip:snip"),
+            Some(("rsnip".to_string(), "snip"))
+        );
+        assert_eq!(
+            split_scheme("runas:wt.exe powershell -NoExit -Command pi"),
+            Some(("runas".to_string(), "wt.exe powershell -NoExit -Command pi"))
+        );
+        assert_eq!(
+            split_scheme("copy: keep spaces "),
+            Some(("copy".to_string(), " keep spaces "))
+        );
+        assert_eq!(split_scheme("C:\\Windows\\notepad.exe"), None);
+        assert_eq!(split_scheme("https://example.com"), None);
+        assert_eq!(split_scheme("notepad.exe C:\\notes.txt"), None);
+        assert_eq!(split_scheme("cmd /c echo a:b"), None);
+        assert_eq!(split_scheme("notepad"), None);
+    }
+
+    #[test]
+    fn launch_routes_by_scheme_and_rejects_unknown_ones() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = SchemeRegistry::new();
+        let handler_seen = Arc::clone(&seen);
+        registry.register("Copy", move |rest: &str| {
+            handler_seen.lock().unwrap().push(format!("copy {rest}"));
+            Ok(())
+        });
+        registry
+            .register_module("my-module", "mymod")
+            .expect("free scheme");
+
+        let fallback = |target: &str| -> io::Result<()> {
+            seen.lock().unwrap().push(format!("shell {target}"));
+            Ok(())
+        };
+        registry.launch("copy:42", fallback).unwrap();
+        registry.launch("https://example.com", fallback).unwrap();
+        let error = registry
+            .launch("nomod:do-thing", fallback)
+            .expect_err("unknown scheme");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("'nomod:'"));
+        assert!(registry.launch("mymod:do-thing", fallback).is_err());
+        assert_eq!(
+            registry.launch("runas:cmd", fallback).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec!["copy 42", "shell https://example.com"]
+        );
+        assert_eq!(
+            registry.route("MyMod:do-thing now"),
+            SchemeRoute::Module {
+                module: "my-module",
+                rest: "do-thing now"
+            }
+        );
+        assert_eq!(registry.schemes().collect::<Vec<_>>(), ["copy", "mymod"]);
+    }
+
+    #[test]
+    fn os_protocols_open_in_the_shell() {
+        let mut registry = SchemeRegistry::new();
+        registry.set_os_protocol_probe(|scheme| scheme == "ms-windows-store");
+        assert_eq!(
+            registry.route("ms-windows-store:pdp?productid=9NBLGGH4NNS1"),
+            SchemeRoute::Shell
+        );
+        assert_eq!(
+            registry.route("zoommtg:join"),
+            SchemeRoute::Unknown("zoommtg".to_string())
+        );
+    }
+
+    #[test]
+    fn module_schemes_are_validated_deduplicated_and_exclusive() {
+        assert_eq!(
+            parse_module_schemes(["MyMod", " mymod ", "my-mod"]),
+            Ok(vec!["mymod".to_string(), "my-mod".to_string()])
+        );
+        assert!(parse_module_schemes(["c"]).is_err());
+        assert!(parse_module_schemes(["my mod"]).is_err());
+        assert!(parse_module_schemes(["copy"]).is_err());
+
+        let mut registry = SchemeRegistry::new();
+        assert!(registry.register_module("first", "mymod").is_ok());
+        assert!(registry.register_module("second", "mymod").is_err());
+        assert!(registry.register_module("second", "runas").is_err());
+        registry.clear_modules();
+        assert!(registry.register_module("second", "mymod").is_ok());
+    }
+}
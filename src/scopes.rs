@@ -0,0 +1,121 @@
+use crate::app_state::LauncherSource;
+use crate::ranking::source_name;
+use crate::settings::ScopeConfig;
+
+/// Source a scoped search is restricted to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchScope {
+    Source(LauncherSource),
+    Module(String),
+}
+
+impl SearchScope {
+    pub fn includes_source(&self, source: LauncherSource) -> bool {
+        matches!(self, SearchScope::Source(scoped) if *scoped == source)
+    }
+
+    pub fn includes_module(&self, module_name: &str) -> bool {
+        matches!(self, SearchScope::Module(scoped) if scoped.eq_ignore_ascii_case(module_name))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SearchScope::Source(source) => format!("scope: {}", source_name(*source)),
+            SearchScope::Module(name) => format!("scope: module {name}"),
+        }
+    }
+}
+
+/// Splits a leading scope prefix (`@h `, `@m:calc `, ...) off `input`. A
+/// prefix only counts once it is followed by whitespace, so typing `@h` alone
+/// still searches everything.
+pub fn split_scope<'a>(input: &'a str, scopes: &ScopeConfig) -> (Option<SearchScope>, &'a str) {
+    let source_prefixes = [
+        (&scopes.history, LauncherSource::History),
+        (&scopes.start_menu, LauncherSource::StartMenu),
+        (&scopes.path, LauncherSource::Path),
+    ];
+    for (prefix, source) in source_prefixes {
+        if let Some(rest) = strip_scope_prefix(input, prefix) {
+            return (Some(SearchScope::Source(source)), rest);
+        }
+    }
+
+    if !scopes.module.is_empty() {
+        if let Some(named) = input.strip_prefix(scopes.module.as_str()) {
+            let name_end = named.find(char::is_whitespace).unwrap_or(named.len());
+            let (name, rest) = named.split_at(name_end);
+            if !name.is_empty() && !rest.is_empty() {
+                return (
+                    Some(SearchScope::Module(name.to_string())),
+                    rest.trim_start(),
+                );
+            }
+        }
+    }
+
+    (None, input)
+}
+
+fn strip_scope_prefix<'a>(input: &'a str, prefix: &str) -> Option<&'a str> {
+    if prefix.is_empty() {
+        return None;
+    }
+    let rest = input.strip_prefix(prefix)?;
+    rest.starts_with(char::is_whitespace)
+        .then(|| rest.trim_start())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_scope, SearchScope};
+    use crate::app_state::LauncherSource;
+    use crate::settings::ScopeConfig;
+
+    #[test]
+    fn scope_prefixes_are_split_off_the_query() {
+        let scopes = ScopeConfig::default();
+        assert_eq!(
+            split_scope("@h  note pad", &scopes),
+            (
+                Some(SearchScope::Source(LauncherSource::History)),
+                "note pad"
+            )
+        );
+        assert_eq!(
+            split_scope("@p ", &scopes),
+            (Some(SearchScope::Source(LauncherSource::Path)), "")
+        );
+        assert_eq!(
+            split_scope("@m:calc = 2+2", &scopes),
+            (Some(SearchScope::Module("calc".to_string())), "= 2+2")
+        );
+        assert_eq!(split_scope("@h", &scopes), (None, "@h"));
+        assert_eq!(split_scope("@hist", &scopes), (None, "@hist"));
+        assert_eq!(split_scope("@m:calc", &scopes), (None, "@m:calc"));
+        assert_eq!(split_scope("notepad @h ", &scopes), (None, "notepad @h "));
+
+        let custom = ScopeConfig {
+            history: String::new(),
+            path: "bin:".to_string(),
+            ..ScopeConfig::default()
+        };
+        assert_eq!(split_scope("@h code", &custom), (None, "@h code"));
+        assert_eq!(
+            split_scope("bin: cargo", &custom),
+            (Some(SearchScope::Source(LauncherSource::Path)), "cargo")
+        );
+    }
+
+    #[test]
+    fn scopes_select_a_source_or_a_module() {
+        let history = SearchScope::Source(LauncherSource::History);
+        assert!(history.includes_source(LauncherSource::History));
+        assert!(!history.includes_source(LauncherSource::Path));
+        assert!(!history.includes_module("calc"));
+
+        let module = SearchScope::Module("calc".to_string());
+        assert!(module.includes_module("Calc"));
+        assert!(!module.includes_source(LauncherSource::History));
+    }
+}
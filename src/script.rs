@@ -0,0 +1,625 @@
+//! Headless driver behind `--script`. Replays typed text and key presses
+//! through the shared `LauncherController` the same way the window does (a key
+//! press, then the character it types), so key handling, quick select, the
+//! action menu, and module hooks run exactly as they do interactively, and
+//! checks the visible results, input accessory, and captured
+//! launch/print/exit outcome against `expect` lines. Nothing is launched or
+//! copied.
+
+use crate::adaptive_aliases::AdaptiveAliases;
+use crate::app_state::{AppState, LauncherItem, LauncherSource};
+use crate::controller::{LauncherController, LauncherOutcome, LauncherPlatform};
+use crate::modules::{
+    types::{InputAccessoryKind, ModuleKeyEvent},
+    ModuleRuntime,
+};
+use crate::rules::glob_match;
+use crate::settings::RmenuConfig;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expectation {
+    /// Labels of the visible rows, top to bottom.
+    Items(Vec<String>),
+    Count(usize),
+    Selected(String),
+    Input(String),
+    Accessory(Option<(InputAccessoryKind, String)>),
+    Launch(String),
+    Print(String),
+    Copy(String),
+    Exit(i32),
+}
+
+/// A key press and the character it types, if any, like `WM_KEYDOWN`
+/// followed by `WM_CHAR`.
+#[derive(Debug, Clone, PartialEq)]
+struct ScriptKey {
+    event: ModuleKeyEvent,
+    ch: Option<char>,
+}
+
+impl ScriptKey {
+    fn typed(ch: char) -> Self {
+        let key = if ch == ' ' {
+            "space".to_string()
+        } else {
+            ch.to_lowercase().collect()
+        };
+        Self {
+            event: ModuleKeyEvent {
+                key,
+                ctrl: false,
+                alt: false,
+                shift: ch.is_uppercase(),
+                meta: false,
+            },
+            ch: Some(ch),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum StepKind {
+    Type(String),
+    Key(ScriptKey),
+    Expect(Expectation),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    line: usize,
+    kind: StepKind,
+}
+
+/// A parsed `--script` file.
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    items: Vec<LauncherItem>,
+    steps: Vec<Step>,
+}
+
+impl Script {
+    /// Parses one directive per line:
+    ///
+    /// - `item <label> [=> <target>]` replaces the startup dataset
+    /// - `type <text>` presses each character in turn
+    /// - `key [ctrl+][alt+][shift+][meta+]<name>` presses one key, named like
+    ///   `ModuleKeyEvent::key` (`enter`, `escape`, `down`, `f5`, `a`, ...)
+    /// - `expect items <label> | <label> ...`, `expect count <n>`,
+    ///   `expect selected <label>`, `expect input <text>`,
+    ///   `expect accessory <kind> <text>` or `expect accessory none`,
+    ///   `expect launch <target>`, `expect print <text>`, `expect copy <text>`,
+    ///   `expect exit <code>`
+    ///
+    /// Expected text is a glob compared like ranking rules. Invalid lines are
+    /// reported together.
+    pub fn parse(content: &str) -> Result<Self, Vec<String>> {
+        let mut script = Script::default();
+        let mut errors = Vec::new();
+
+        for (index, line) in content.trim_start_matches('\u{feff}').lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (directive, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            let parsed = match directive.to_ascii_lowercase().as_str() {
+                "item" => parse_item(rest).map(|item| script.items.push(item)),
+                "type" if !rest.is_empty() => {
+                    script.push_step(index + 1, StepKind::Type(rest.to_string()));
+                    Ok(())
+                }
+                "type" => Err("type needs text".to_string()),
+                "key" => parse_key(rest).map(|key| script.push_step(index + 1, StepKind::Key(key))),
+                "expect" => parse_expectation(rest)
+                    .map(|expect| script.push_step(index + 1, StepKind::Expect(expect))),
+                other => Err(format!(
+                    "unknown directive `{other}`, expected item, type, key, or expect"
+                )),
+            };
+            if let Err(err) = parsed {
+                errors.push(format!("line {}: {err}", index + 1));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(script)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn push_step(&mut self, line: usize, kind: StepKind) {
+        self.steps.push(Step { line, kind });
+    }
+}
+
+fn parse_item(rest: &str) -> Result<LauncherItem, String> {
+    let (label, target) = match rest.split_once("=>") {
+        Some((label, target)) => (label.trim(), target.trim()),
+        None => (rest, rest),
+    };
+    if label.is_empty() || target.is_empty() {
+        return Err("expected `item <label> [=> <target>]`".to_string());
+    }
+    Ok(LauncherItem::new(
+        label.to_string(),
+        target.to_string(),
+        LauncherSource::Direct,
+    ))
+}
+
+fn parse_key(spec: &str) -> Result<ScriptKey, String> {
+    if spec.is_empty() {
+        return Err("key needs a key name".to_string());
+    }
+    let mut parts = spec.split('+').collect::<Vec<_>>();
+    // `ctrl++` presses `+` itself.
+    let name = if spec.ends_with("++") {
+        parts.truncate(parts.len() - 2);
+        "+"
+    } else {
+        parts.pop().unwrap_or_default()
+    };
+
+    let mut event = ModuleKeyEvent {
+        key: String::new(),
+        ctrl: false,
+        alt: false,
+        shift: false,
+        meta: false,
+    };
+    for modifier in parts {
+        match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" => event.ctrl = true,
+            "alt" => event.alt = true,
+            "shift" => event.shift = true,
+            "meta" | "win" => event.meta = true,
+            other => return Err(format!("unknown modifier `{other}`")),
+        }
+    }
+
+    let lower = name.to_ascii_lowercase();
+    let mut single = name.chars();
+    let ch = match (single.next(), single.next()) {
+        (Some(ch), None) => Some(ch),
+        _ => None,
+    };
+    event.key = match lower.as_str() {
+        "enter" | "tab" | "backspace" | "up" | "down" | "left" | "right" | "space" => lower.clone(),
+        "escape" | "esc" => "escape".to_string(),
+        _ if ch.is_some() => lower.clone(),
+        _ => match lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            Some(1..=24) => lower.clone(),
+            _ => return Err(format!("unknown key `{name}`")),
+        },
+    };
+    // Only unmodified (or shifted) printable keys type a character.
+    let ch = match ch.or((event.key == "space").then_some(' ')) {
+        Some(ch) if !event.ctrl && !event.alt && !event.meta => Some(if event.shift {
+            ch.to_uppercase().next().unwrap_or(ch)
+        } else {
+            ch
+        }),
+        _ => None,
+    };
+    Ok(ScriptKey { event, ch })
+}
+
+fn parse_accessory_kind(raw: &str) -> Result<InputAccessoryKind, String> {
+    Ok(match raw.to_ascii_lowercase().as_str() {
+        "info" => InputAccessoryKind::Info,
+        "success" => InputAccessoryKind::Success,
+        "warning" => InputAccessoryKind::Warning,
+        "error" => InputAccessoryKind::Error,
+        "hint" => InputAccessoryKind::Hint,
+        other => {
+            return Err(format!(
+                "unknown accessory kind `{other}`, expected info, success, warning, error, hint, or none"
+            ))
+        }
+    })
+}
+
+fn parse_expectation(rest: &str) -> Result<Expectation, String> {
+    let (what, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let value = value.trim();
+    let expectation = match what.to_ascii_lowercase().as_str() {
+        "items" => Expectation::Items(if value.is_empty() {
+            Vec::new()
+        } else {
+            value
+                .split('|')
+                .map(|label| label.trim().to_string())
+                .collect()
+        }),
+        "count" => Expectation::Count(
+            value
+                .parse()
+                .map_err(|_| format!("count expects a number, got `{value}`"))?,
+        ),
+        "exit" => Expectation::Exit(
+            value
+                .parse()
+                .map_err(|_| format!("exit expects a number, got `{value}`"))?,
+        ),
+        "accessory" if value.eq_ignore_ascii_case("none") => Expectation::Accessory(None),
+        "accessory" => {
+            let (kind, text) = value
+                .split_once(char::is_whitespace)
+                .ok_or("expected `expect accessory <kind> <text>` or `expect accessory none`")?;
+            Expectation::Accessory(Some((parse_accessory_kind(kind)?, text.trim().to_string())))
+        }
+        "selected" | "input" | "launch" | "print" | "copy" if value.is_empty() => {
+            return Err(format!("expect {what} needs a value"))
+        }
+        "selected" => Expectation::Selected(value.to_string()),
+        "input" => Expectation::Input(value.to_string()),
+        "launch" => Expectation::Launch(value.to_string()),
+        "print" => Expectation::Print(value.to_string()),
+        "copy" => Expectation::Copy(value.to_string()),
+        other => return Err(format!("unknown expectation `{other}`")),
+    };
+    Ok(expectation)
+}
+
+fn describe_outcome(outcome: Option<&LauncherOutcome>) -> String {
+    match outcome {
+        None | Some(LauncherOutcome::Continue) => "the session still open".to_string(),
+        Some(LauncherOutcome::Exit(code)) => format!("exit {code}"),
+        Some(LauncherOutcome::Launch { target, .. }) => format!("launch `{target}`"),
+        Some(LauncherOutcome::Print(text)) => format!("print `{text}`"),
+        Some(LauncherOutcome::Copy(text)) => format!("copy `{text}`"),
+    }
+}
+
+/// The last key's ending outcome, kept until an `expect` checks it.
+struct ScriptRun {
+    controller: LauncherController,
+    outcome: Option<LauncherOutcome>,
+}
+
+impl ScriptRun {
+    fn press(&mut self, key: &ScriptKey) -> Result<(), String> {
+        if let Some(outcome) = &self.outcome {
+            return Err(format!(
+                "the session already ended with {}; check it with `expect` first",
+                describe_outcome(Some(outcome))
+            ));
+        }
+        let mut outcome = self.controller.handle_key(&key.event);
+        if let (LauncherOutcome::Continue, Some(ch)) = (&outcome, key.ch) {
+            outcome = self.controller.insert_char(ch);
+        }
+        if outcome != LauncherOutcome::Continue {
+            self.outcome = Some(outcome);
+        }
+        Ok(())
+    }
+
+    fn check(&mut self, expectation: &Expectation) -> Result<(), String> {
+        let app_state = self.controller.app_state();
+        match expectation {
+            Expectation::Items(expected) => {
+                let actual = self
+                    .controller
+                    .visible_items()
+                    .iter()
+                    .map(|item| item.label.clone())
+                    .collect::<Vec<_>>();
+                let matches = actual.len() == expected.len()
+                    && expected
+                        .iter()
+                        .zip(&actual)
+                        .all(|(pattern, label)| glob_match(pattern, label));
+                if !matches {
+                    return Err(format!(
+                        "expected items [{}], got [{}]",
+                        expected.join(" | "),
+                        actual.join(" | ")
+                    ));
+                }
+            }
+            Expectation::Count(expected) => {
+                let actual = self.controller.visible_items().len();
+                if actual != *expected {
+                    return Err(format!("expected {expected} visible items, got {actual}"));
+                }
+            }
+            Expectation::Selected(expected) => {
+                let actual = app_state
+                    .matching_items
+                    .get(app_state.selected_index)
+                    .map(|item| item.label.as_str());
+                if !actual.is_some_and(|label| glob_match(expected, label)) {
+                    return Err(format!(
+                        "expected `{expected}` selected, got {}",
+                        actual.map_or("no selection".to_string(), |label| format!("`{label}`"))
+                    ));
+                }
+            }
+            Expectation::Input(expected) => {
+                if !glob_match(expected, &app_state.current_input) {
+                    return Err(format!(
+                        "expected input `{expected}`, got `{}`",
+                        app_state.current_input
+                    ));
+                }
+            }
+            Expectation::Accessory(expected) => {
+                let actual = self
+                    .controller
+                    .input_accessory()
+                    .map(|accessory| (accessory.kind, accessory.text));
+                let matches = match (expected, &actual) {
+                    (None, None) => true,
+                    (Some((kind, pattern)), Some((actual_kind, text))) => {
+                        kind == actual_kind && glob_match(pattern, text)
+                    }
+                    _ => false,
+                };
+                if !matches {
+                    let describe = |accessory: Option<&(InputAccessoryKind, String)>| {
+                        accessory.map_or("none".to_string(), |(kind, text)| {
+                            format!("{kind:?} `{text}`").to_ascii_lowercase()
+                        })
+                    };
+                    return Err(format!(
+                        "expected accessory {}, got {}",
+                        describe(expected.as_ref()),
+                        describe(actual.as_ref())
+                    ));
+                }
+            }
+            Expectation::Launch(_)
+            | Expectation::Print(_)
+            | Expectation::Copy(_)
+            | Expectation::Exit(_) => {
+                let outcome = self.outcome.take();
+                let matches = match (expectation, &outcome) {
+                    (
+                        Expectation::Launch(pattern),
+                        Some(LauncherOutcome::Launch { target, .. }),
+                    ) => glob_match(pattern, target),
+                    (Expectation::Print(pattern), Some(LauncherOutcome::Print(text)))
+                    | (Expectation::Copy(pattern), Some(LauncherOutcome::Copy(text))) => {
+                        glob_match(pattern, text)
+                    }
+                    (Expectation::Exit(code), Some(LauncherOutcome::Exit(actual))) => {
+                        code == actual
+                    }
+                    _ => false,
+                };
+                if !matches {
+                    let expected = match expectation {
+                        Expectation::Launch(target) => format!("launch `{target}`"),
+                        Expectation::Print(text) => format!("print `{text}`"),
+                        Expectation::Copy(text) => format!("copy `{text}`"),
+                        Expectation::Exit(code) => format!("exit {code}"),
+                        _ => unreachable!(),
+                    };
+                    return Err(format!(
+                        "expected {expected}, got {}",
+                        describe_outcome(outcome.as_ref())
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replays `script` against a fresh session and returns how many expectations
+/// passed, or the first failure as `line N: ...`. Adaptive aliases start empty
+/// and are never saved, so runs are repeatable.
+pub fn run_script(
+    config: &RmenuConfig,
+    mut app_state: AppState,
+    module_runtime: ModuleRuntime,
+    script: &Script,
+) -> Result<usize, String> {
+    if !script.items.is_empty() {
+        // Launcher items come from the index, so they get its secondary actions.
+        let source = if app_state.launcher_mode {
+            LauncherSource::Path
+        } else {
+            LauncherSource::Direct
+        };
+        app_state.set_all_items(
+            script
+                .items
+                .iter()
+                .map(|item| LauncherItem {
+                    source,
+                    ..item.clone()
+                })
+                .collect(),
+        );
+    }
+    app_state.adaptive_aliases = AdaptiveAliases::default();
+    let platform = LauncherPlatform {
+        copy_text: |_| Ok(()),
+        ..LauncherPlatform::headless()
+    };
+
+    let mut run = ScriptRun {
+        controller: LauncherController::new(config.clone(), app_state, module_runtime, platform),
+        outcome: None,
+    };
+    run.controller.start();
+
+    let mut passed = 0;
+    let mut result = Ok(());
+    for step in &script.steps {
+        result = match &step.kind {
+            StepKind::Type(text) => text
+                .chars()
+                .try_for_each(|ch| run.press(&ScriptKey::typed(ch))),
+            StepKind::Key(key) => run.press(key),
+            StepKind::Expect(expectation) => run.check(expectation).map(|()| passed += 1),
+        }
+        .map_err(|err| format!("line {}: {err}", step.line));
+        if result.is_err() {
+            break;
+        }
+    }
+    run.controller.shutdown();
+    result.map(|()| passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_key, run_script, Script};
+    use crate::app_state::AppState;
+    use crate::modules::{BuiltinQueryProviderModule, ModuleRuntime};
+    use crate::settings::RmenuConfig;
+
+    fn run(content: &str, launcher_mode: bool) -> Result<usize, String> {
+        let script = Script::parse(content).map_err(|errors| errors.join("\n"))?;
+        let mut runtime = ModuleRuntime::new();
+        runtime.register_builtin_module(Box::new(BuiltinQueryProviderModule::default()));
+        let app_state = AppState {
+            launcher_mode,
+            silent_mode: true,
+            ..Default::default()
+        };
+        run_script(&RmenuConfig::default(), app_state, runtime, &script)
+    }
+
+    #[test]
+    fn parses_keys_with_modifiers() {
+        let key = parse_key("ctrl+enter").expect("ctrl+enter");
+        assert_eq!(
+            (key.event.key.as_str(), key.event.ctrl, key.ch),
+            ("enter", true, None)
+        );
+
+        let key = parse_key("Shift+Alt+F5").expect("shift+alt+f5");
+        assert_eq!(key.event.key, "f5");
+        assert!(key.event.shift && key.event.alt && !key.event.ctrl);
+
+        let key = parse_key("ctrl++").expect("ctrl++");
+        assert_eq!(
+            (key.event.key.as_str(), key.event.ctrl, key.ch),
+            ("+", true, None)
+        );
+
+        assert_eq!(parse_key("space").expect("space").ch, Some(' '));
+        assert_eq!(parse_key("shift+a").expect("shift+a").ch, Some('A'));
+        assert_eq!(parse_key("Esc").expect("esc").event.key, "escape");
+        assert!(parse_key("hyper+a").is_err());
+        assert!(parse_key("pagedown").is_err());
+    }
+
+    #[test]
+    fn reports_every_invalid_line() {
+        let errors = Script::parse("type\nkey nope\nexpect count x\nexpect items a\nwobble")
+            .expect_err("invalid script");
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].starts_with("line 1:"));
+        assert!(errors[3].starts_with("line 5: unknown directive"));
+    }
+
+    #[test]
+    fn replays_typing_navigation_and_captures_the_launch() {
+        let script = "
+            item Paint => mspaint.exe
+            item Calculator => C:\\Windows\\System32\\calc.exe
+            item Clock => clock.exe
+
+            type c
+            expect items Clock | Calculator
+            key down
+            expect selected calc*
+            type a
+            expect input ca
+            expect items Calculator
+            key enter
+            expect launch c:/windows/system32/calc.exe
+        ";
+        assert_eq!(run(script, true), Ok(5));
+    }
+
+    #[test]
+    fn module_accessory_and_calc_copy_are_checked() {
+        let script = "
+            type =6*7
+            expect accessory none
+            expect items = 42
+            key enter
+            expect copy 42
+            key escape
+            expect exit 1
+        ";
+        assert_eq!(run(script, true), Ok(4));
+
+        let script = "
+            type =1+
+            expect accessory error Calc:*
+        ";
+        assert_eq!(run(script, true), Ok(1));
+    }
+
+    #[test]
+    fn digits_extend_calc_expressions() {
+        let script = "
+            type =21
+            expect input =21
+            expect items = 21
+            type *1.5
+            expect items = 31.5
+            key enter
+            expect copy 31.5
+        ";
+        assert_eq!(run(script, true), Ok(4));
+    }
+
+    #[test]
+    fn tab_opens_the_action_menu_and_a_digit_runs_an_action() {
+        // Run as administrator is only offered on Windows.
+        let (menu, copy_key) = if cfg!(windows) {
+            ("Run as administrator | Copy target", 2)
+        } else {
+            ("Copy target", 1)
+        };
+        let script = format!(
+            "
+            item Notepad => notepad
+            item Notes => notes.exe
+            type note
+            expect items Notes | Notepad
+            key down
+            key tab
+            expect items {menu}
+            expect input note
+            key tab
+            expect items Notes | Notepad
+            type pad
+            expect input notepad
+            key ctrl+enter
+            key {copy_key}
+            expect copy notepad
+        "
+        );
+        assert_eq!(run(&script, true), Ok(6));
+    }
+
+    #[test]
+    fn first_failure_is_reported_with_its_line() {
+        let script = "item alpha\nitem beta\ntype a\nexpect print beta\nexpect count 9";
+        assert_eq!(
+            run(script, false),
+            Err("line 4: expected print `beta`, got the session still open".to_string())
+        );
+
+        let script = "item alpha\ntype alpha\nkey enter\ntype b";
+        assert_eq!(
+            run(script, false),
+            Err(
+                "line 4: the session already ended with print `alpha`; check it with `expect` first"
+                    .to_string()
+            )
+        );
+    }
+}
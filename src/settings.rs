@@ -2,7 +2,6 @@ use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
-use windows::Win32::Foundation::COLORREF;
 
 // Estructura principal de configuración
 #[derive(Debug, Clone)]
@@ -12,16 +11,36 @@ pub struct RmenuConfig {
     pub font: FontConfig,
     pub behavior: BehaviorConfig,
     pub launcher: LauncherConfig,
+    pub scopes: ScopeConfig,
     pub modules: ModulesRuntimeConfig,
 }
 
 #[derive(Debug, Clone)]
 pub struct ColorConfig {
-    pub background: COLORREF,
-    pub foreground: COLORREF,
-    pub selected_background: COLORREF,
-    pub selected_foreground: COLORREF,
-    pub border: COLORREF,
+    pub background: RgbColor,
+    pub foreground: RgbColor,
+    pub selected_background: RgbColor,
+    pub selected_foreground: RgbColor,
+    pub border: RgbColor,
+    pub match_highlight: RgbColor,
+}
+
+/// Plain 8-bit RGB color; frontends convert it to their native representation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RgbColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl RgbColor {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        RgbColor { r, g, b }
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +76,7 @@ pub struct BehaviorConfig {
     pub max_items: i32,
     pub element_delimiter: char,
     pub quick_select_mode: QuickSelectMode,
+    pub transliterate: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -65,11 +85,77 @@ pub struct LauncherConfig {
     pub enable_history: bool,
     pub enable_start_menu: bool,
     pub enable_path: bool,
+    pub enable_bookmarks: bool,
     pub history_max_items: usize,
     pub source_boost_history: i64,
     pub source_boost_start_menu: i64,
     pub source_boost_path: i64,
+    pub source_boost_bookmarks: i64,
+    pub frecency_half_life_hours: f64,
+    pub frecency_weight: i64,
     pub blacklist_path_commands: Vec<String>,
+    pub extra_roots: Vec<SourceRootConfig>,
+    pub bookmark_profiles: Vec<BookmarkProfileConfig>,
+}
+
+/// Browser bookmark file declared in `[Bookmarks]` as `<name> = <path>`. The
+/// path is a bookmark file or a Chromium profile folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookmarkProfileConfig {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Additional directory indexed next to the Start Menu and PATH, declared in
+/// `[Sources]` as `<name> = <path>` plus optional `<name>.<setting>` keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRootConfig {
+    pub name: String,
+    pub path: PathBuf,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub max_depth: usize,
+    pub extensions: Vec<String>,
+    pub label: String,
+    pub boost: i64,
+}
+
+const DEFAULT_SOURCE_ROOT_DEPTH: usize = 3;
+
+impl SourceRootConfig {
+    fn new(name: &str, path: &str) -> Self {
+        SourceRootConfig {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: DEFAULT_SOURCE_ROOT_DEPTH,
+            extensions: Vec::new(),
+            label: name.to_string(),
+            boost: 0,
+        }
+    }
+}
+
+/// Input prefixes that restrict a search to one source. An empty prefix
+/// disables that scope.
+#[derive(Debug, Clone)]
+pub struct ScopeConfig {
+    pub history: String,
+    pub start_menu: String,
+    pub path: String,
+    pub module: String,
+}
+
+impl Default for ScopeConfig {
+    fn default() -> Self {
+        ScopeConfig {
+            history: "@h".to_string(),
+            start_menu: "@s".to_string(),
+            path: "@p".to_string(),
+            module: "@m:".to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -117,20 +203,16 @@ fn default_blacklist_path_commands() -> Vec<String> {
     .collect()
 }
 
-// Helper function to create COLORREF from R, G, B components
-fn rgb_to_colorref(r: u8, g: u8, b: u8) -> COLORREF {
-    COLORREF((b as u32) << 16 | (g as u32) << 8 | (r as u32))
-}
-
 impl Default for RmenuConfig {
     fn default() -> Self {
         RmenuConfig {
             colors: ColorConfig {
-                background: rgb_to_colorref(0x28, 0x2C, 0x34), // #282C34 (Gris oscuro azulado)
-                foreground: rgb_to_colorref(0xAB, 0xB2, 0xBF), // #ABB2BF (Gris claro)
-                selected_background: rgb_to_colorref(0x3A, 0x3F, 0x4B), // #3A3F4B (Gris medio)
-                selected_foreground: rgb_to_colorref(0xE6, 0xE6, 0xE6), // #E6E6E6 (Casi blanco)
-                border: rgb_to_colorref(0x21, 0x25, 0x2B),     // #21252B (Gris muy oscuro)
+                background: RgbColor::new(0x28, 0x2C, 0x34), // #282C34 (Gris oscuro azulado)
+                foreground: RgbColor::new(0xAB, 0xB2, 0xBF), // #ABB2BF (Gris claro)
+                selected_background: RgbColor::new(0x3A, 0x3F, 0x4B), // #3A3F4B (Gris medio)
+                selected_foreground: RgbColor::new(0xE6, 0xE6, 0xE6), // #E6E6E6 (Casi blanco)
+                border: RgbColor::new(0x21, 0x25, 0x2B),     // #21252B (Gris muy oscuro)
+                match_highlight: RgbColor::new(0xE5, 0xC0, 0x7B), // #E5C07B (Ámbar)
             },
             dimensions: DimensionConfig {
                 default_layout: Some("custom".to_string()),
@@ -154,18 +236,26 @@ impl Default for RmenuConfig {
                 max_items: 10,
                 element_delimiter: ',',
                 quick_select_mode: QuickSelectMode::Submit,
+                transliterate: false,
             },
             launcher: LauncherConfig {
                 launcher_mode_default: true,
                 enable_history: true,
                 enable_start_menu: true,
                 enable_path: true,
+                enable_bookmarks: true,
                 history_max_items: 300,
                 source_boost_history: 650,
                 source_boost_start_menu: 480,
                 source_boost_path: 0,
+                source_boost_bookmarks: 300,
+                frecency_half_life_hours: 72.0,
+                frecency_weight: 260,
                 blacklist_path_commands: default_blacklist_path_commands(),
+                extra_roots: Vec::new(),
+                bookmark_profiles: Vec::new(),
             },
+            scopes: ScopeConfig::default(),
             modules: ModulesRuntimeConfig {
                 provider_total_budget_ms: 35,
                 provider_timeout_ms: 1500,
@@ -212,7 +302,7 @@ impl RmenuConfig {
             fs::create_dir_all(parent)?;
         }
         let mut file = fs::File::create(&config_path)?;
-        file.write_all(self.to_string().as_bytes())?;
+        file.write_all(self.to_ini_string().as_bytes())?;
         Ok(())
     }
 
@@ -220,48 +310,25 @@ impl RmenuConfig {
         Self::get_home_config_path()
     }
 
-    fn to_string(&self) -> String {
+    fn to_ini_string(&self) -> String {
         let mut s = String::new();
         s.push_str("[Colors]\n");
 
-        let bg_r = (self.colors.background.0 & 0x0000FF) as u8;
-        let bg_g = ((self.colors.background.0 & 0x00FF00) >> 8) as u8;
-        let bg_b = ((self.colors.background.0 & 0xFF0000) >> 16) as u8;
+        let colors = &self.colors;
+        s.push_str(&format!("background = {}\n", colors.background.to_hex()));
+        s.push_str(&format!("foreground = {}\n", colors.foreground.to_hex()));
         s.push_str(&format!(
-            "background = #{:02X}{:02X}{:02X}\n",
-            bg_r, bg_g, bg_b
+            "selected_background = {}\n",
+            colors.selected_background.to_hex()
         ));
-
-        let fg_r = (self.colors.foreground.0 & 0x0000FF) as u8;
-        let fg_g = ((self.colors.foreground.0 & 0x00FF00) >> 8) as u8;
-        let fg_b = ((self.colors.foreground.0 & 0xFF0000) >> 16) as u8;
         s.push_str(&format!(
-            "foreground = #{:02X}{:02X}{:02X}\n",
-            fg_r, fg_g, fg_b
+            "selected_foreground = {}\n",
+            colors.selected_foreground.to_hex()
         ));
-
-        let sel_bg_r = (self.colors.selected_background.0 & 0x0000FF) as u8;
-        let sel_bg_g = ((self.colors.selected_background.0 & 0x00FF00) >> 8) as u8;
-        let sel_bg_b = ((self.colors.selected_background.0 & 0xFF0000) >> 16) as u8;
+        s.push_str(&format!("border = {}\n", colors.border.to_hex()));
         s.push_str(&format!(
-            "selected_background = #{:02X}{:02X}{:02X}\n",
-            sel_bg_r, sel_bg_g, sel_bg_b
-        ));
-
-        let sel_fg_r = (self.colors.selected_foreground.0 & 0x0000FF) as u8;
-        let sel_fg_g = ((self.colors.selected_foreground.0 & 0x00FF00) >> 8) as u8;
-        let sel_fg_b = ((self.colors.selected_foreground.0 & 0xFF0000) >> 16) as u8;
-        s.push_str(&format!(
-            "selected_foreground = #{:02X}{:02X}{:02X}\n",
-            sel_fg_r, sel_fg_g, sel_fg_b
-        ));
-
-        let border_r = (self.colors.border.0 & 0x0000FF) as u8;
-        let border_g = ((self.colors.border.0 & 0x00FF00) >> 8) as u8;
-        let border_b = ((self.colors.border.0 & 0xFF0000) >> 16) as u8;
-        s.push_str(&format!(
-            "border = #{:02X}{:02X}{:02X}\n\n",
-            border_r, border_g, border_b
+            "match_highlight = {}\n\n",
+            colors.match_highlight.to_hex()
         ));
 
         s.push_str("[Dimensions]\n");
@@ -322,7 +389,11 @@ impl RmenuConfig {
             QuickSelectMode::Select => "select",
             QuickSelectMode::Submit => "submit",
         };
-        s.push_str(&format!("quick_select_mode = {}\n\n", quick_select_mode));
+        s.push_str(&format!("quick_select_mode = {}\n", quick_select_mode));
+        s.push_str(&format!(
+            "transliterate = {}\n\n",
+            self.behavior.transliterate
+        ));
 
         s.push_str("[Launcher]\n");
         s.push_str(&format!(
@@ -338,6 +409,10 @@ impl RmenuConfig {
             self.launcher.enable_start_menu
         ));
         s.push_str(&format!("enable_path = {}\n", self.launcher.enable_path));
+        s.push_str(&format!(
+            "enable_bookmarks = {}\n",
+            self.launcher.enable_bookmarks
+        ));
         s.push_str(&format!(
             "history_max_items = {}\n",
             self.launcher.history_max_items
@@ -354,11 +429,65 @@ impl RmenuConfig {
             "source_boost_path = {}\n",
             self.launcher.source_boost_path
         ));
+        s.push_str(&format!(
+            "source_boost_bookmarks = {}\n",
+            self.launcher.source_boost_bookmarks
+        ));
+        s.push_str(&format!(
+            "frecency_half_life_hours = {}\n",
+            self.launcher.frecency_half_life_hours
+        ));
+        s.push_str(&format!(
+            "frecency_weight = {}\n",
+            self.launcher.frecency_weight
+        ));
         s.push_str(&format!(
             "blacklist_path_commands = {}\n\n",
             self.launcher.blacklist_path_commands.join(",")
         ));
 
+        s.push_str("[Sources]\n");
+        for root in &self.launcher.extra_roots {
+            s.push_str(&format!("{} = {}\n", root.name, root.path.display()));
+            if !root.include.is_empty() {
+                s.push_str(&format!(
+                    "{}.include = {}\n",
+                    root.name,
+                    root.include.join(",")
+                ));
+            }
+            if !root.exclude.is_empty() {
+                s.push_str(&format!(
+                    "{}.exclude = {}\n",
+                    root.name,
+                    root.exclude.join(",")
+                ));
+            }
+            s.push_str(&format!("{}.max_depth = {}\n", root.name, root.max_depth));
+            if !root.extensions.is_empty() {
+                s.push_str(&format!(
+                    "{}.extensions = {}\n",
+                    root.name,
+                    root.extensions.join(",")
+                ));
+            }
+            s.push_str(&format!("{}.label = {}\n", root.name, root.label));
+            s.push_str(&format!("{}.boost = {}\n", root.name, root.boost));
+        }
+        s.push('\n');
+
+        s.push_str("[Bookmarks]\n");
+        for profile in &self.launcher.bookmark_profiles {
+            s.push_str(&format!("{} = {}\n", profile.name, profile.path.display()));
+        }
+        s.push('\n');
+
+        s.push_str("[Scopes]\n");
+        s.push_str(&format!("history = {}\n", self.scopes.history));
+        s.push_str(&format!("start_menu = {}\n", self.scopes.start_menu));
+        s.push_str(&format!("path = {}\n", self.scopes.path));
+        s.push_str(&format!("module = {}\n\n", self.scopes.module));
+
         s.push_str("[Modules]\n");
         s.push_str(&format!(
             "provider_total_budget_ms = {}\n",
@@ -405,9 +534,7 @@ impl RmenuConfig {
 
             if line.starts_with('[') && line.ends_with(']') {
                 current_section = line[1..line.len() - 1].to_string();
-                properties
-                    .entry(current_section.clone())
-                    .or_insert_with(HashMap::new);
+                properties.entry(current_section.clone()).or_default();
             } else if let Some(eq_idx) = line.find('=') {
                 let key = line[..eq_idx].trim().to_string();
                 let value = line[eq_idx + 1..].trim().to_string();
@@ -440,6 +567,9 @@ impl RmenuConfig {
             if let Some(val) = colors_props.get("border") {
                 config.colors.border = parse_color_hex(val)?;
             }
+            if let Some(val) = colors_props.get("match_highlight") {
+                config.colors.match_highlight = parse_color_hex(val)?;
+            }
         }
 
         if let Some(dim_props) = properties.get("Dimensions") {
@@ -510,6 +640,10 @@ impl RmenuConfig {
                     _ => QuickSelectMode::Submit,
                 };
             }
+            if let Some(val) = behavior_props.get("transliterate") {
+                config.behavior.transliterate =
+                    val.parse().unwrap_or(config.behavior.transliterate);
+            }
         }
 
         if let Some(launcher_props) = properties.get("Launcher") {
@@ -528,6 +662,10 @@ impl RmenuConfig {
             if let Some(val) = launcher_props.get("enable_path") {
                 config.launcher.enable_path = val.parse().unwrap_or(config.launcher.enable_path);
             }
+            if let Some(val) = launcher_props.get("enable_bookmarks") {
+                config.launcher.enable_bookmarks =
+                    val.parse().unwrap_or(config.launcher.enable_bookmarks);
+            }
             if let Some(val) = launcher_props.get("history_max_items") {
                 config.launcher.history_max_items =
                     val.parse().unwrap_or(config.launcher.history_max_items);
@@ -545,6 +683,22 @@ impl RmenuConfig {
                 config.launcher.source_boost_path =
                     val.parse().unwrap_or(config.launcher.source_boost_path);
             }
+            if let Some(val) = launcher_props.get("source_boost_bookmarks") {
+                config.launcher.source_boost_bookmarks = val
+                    .parse()
+                    .unwrap_or(config.launcher.source_boost_bookmarks);
+            }
+            if let Some(val) = launcher_props.get("frecency_half_life_hours") {
+                config.launcher.frecency_half_life_hours = val
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|hours| hours.is_finite() && *hours > 0.0)
+                    .unwrap_or(config.launcher.frecency_half_life_hours);
+            }
+            if let Some(val) = launcher_props.get("frecency_weight") {
+                config.launcher.frecency_weight =
+                    val.parse().unwrap_or(config.launcher.frecency_weight);
+            }
             if let Some(val) = launcher_props.get("blacklist_path_commands") {
                 let parsed = parse_csv_list(val);
                 if !parsed.is_empty() {
@@ -553,6 +707,38 @@ impl RmenuConfig {
             }
         }
 
+        if let Some(sources_props) = properties.get("Sources") {
+            config.launcher.extra_roots = parse_source_roots(sources_props);
+        }
+
+        if let Some(bookmarks_props) = properties.get("Bookmarks") {
+            let mut profiles = bookmarks_props
+                .iter()
+                .filter(|(_, path)| !path.is_empty())
+                .map(|(name, path)| BookmarkProfileConfig {
+                    name: name.clone(),
+                    path: PathBuf::from(path),
+                })
+                .collect::<Vec<_>>();
+            profiles.sort_by(|a, b| a.name.cmp(&b.name));
+            config.launcher.bookmark_profiles = profiles;
+        }
+
+        if let Some(scopes_props) = properties.get("Scopes") {
+            if let Some(val) = scopes_props.get("history") {
+                config.scopes.history = val.clone();
+            }
+            if let Some(val) = scopes_props.get("start_menu") {
+                config.scopes.start_menu = val.clone();
+            }
+            if let Some(val) = scopes_props.get("path") {
+                config.scopes.path = val.clone();
+            }
+            if let Some(val) = scopes_props.get("module") {
+                config.scopes.module = val.clone();
+            }
+        }
+
         if let Some(modules_props) = properties.get("Modules") {
             if let Some(val) = modules_props.get("provider_total_budget_ms") {
                 config.modules.provider_total_budget_ms = val
@@ -631,6 +817,13 @@ impl RmenuConfig {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DebugRankingFormat {
+    #[default]
+    Text,
+    Json,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct CmdOptions {
     pub elements_str: Option<String>,
@@ -641,9 +834,14 @@ pub struct CmdOptions {
     pub install_companion: Option<String>,
     pub silent: bool,
     pub debug_ranking: Option<String>,
+    pub debug_ranking_format: DebugRankingFormat,
+    pub limit: Option<usize>,
     pub metrics: bool,
+    pub metrics_bench: bool,
     pub modules_debug: bool,
     pub reindex: bool,
+    pub tui: bool,
+    pub script: Option<String>,
     pub layout: Option<String>,
     pub cli_width_percent: Option<f32>,
     pub cli_max_width: Option<i32>,
@@ -663,6 +861,42 @@ fn parse_csv_list(raw: &str) -> Vec<String> {
         .collect()
 }
 
+fn parse_source_roots(props: &HashMap<String, String>) -> Vec<SourceRootConfig> {
+    let mut roots = props
+        .iter()
+        .filter(|(key, path)| !key.contains('.') && !path.is_empty())
+        .map(|(name, path)| SourceRootConfig::new(name, path))
+        .collect::<Vec<_>>();
+    roots.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for root in &mut roots {
+        let setting = |key: &str| props.get(&format!("{}.{key}", root.name));
+        if let Some(val) = setting("include") {
+            root.include = parse_csv_list(val);
+        }
+        if let Some(val) = setting("exclude") {
+            root.exclude = parse_csv_list(val);
+        }
+        if let Some(val) = setting("max_depth") {
+            root.max_depth = val.parse().unwrap_or(root.max_depth);
+        }
+        if let Some(val) = setting("extensions") {
+            root.extensions = parse_csv_list(val)
+                .into_iter()
+                .map(|ext| ext.trim_start_matches('.').to_string())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+        }
+        if let Some(val) = setting("label").filter(|val| !val.is_empty()) {
+            root.label = val.clone();
+        }
+        if let Some(val) = setting("boost") {
+            root.boost = val.parse().unwrap_or(root.boost);
+        }
+    }
+    roots
+}
+
 #[cfg(windows)]
 const DEFAULT_RMENU_DATA_DIR: &str = "C:\\rMenuData";
 #[cfg(not(windows))]
@@ -701,10 +935,7 @@ pub fn rmenu_data_dirs(cli_data_dir: Option<&str>) -> RmenuDataDirs {
 }
 
 pub fn resolve_modules_dir_from_candidates(candidates: ModuleDirCandidates) -> PathBuf {
-    for candidate in [candidates.cli, candidates.env, candidates.data_root]
-        .into_iter()
-        .flatten()
-    {
+    if let Some(candidate) = candidates.cli.or(candidates.env).or(candidates.data_root) {
         return candidate;
     }
 
@@ -754,7 +985,7 @@ pub fn resolve_modules_dir(cli_modules_dir: Option<&str>, cli_data_dir: Option<&
     })
 }
 
-fn parse_color_hex(hex: &str) -> io::Result<COLORREF> {
+fn parse_color_hex(hex: &str) -> io::Result<RgbColor> {
     let hex = hex.trim_start_matches('#');
     if hex.len() != 6 {
         return Err(io::Error::new(
@@ -768,7 +999,7 @@ fn parse_color_hex(hex: &str) -> io::Result<COLORREF> {
         .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Componente G inválido"))?;
     let b = u8::from_str_radix(&hex[4..6], 16)
         .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Componente B inválido"))?;
-    Ok(COLORREF((b as u32) << 16 | (g as u32) << 8 | (r as u32)))
+    Ok(RgbColor::new(r, g, b))
 }
 
 pub fn parse_args() -> CmdOptions {
@@ -779,117 +1010,106 @@ pub fn parse_args() -> CmdOptions {
     while i < args.len() {
         let arg = &args[i];
         match arg.as_str() {
-            "-e" | "--elements" => {
-                if i + 1 < args.len() {
-                    options.elements_str = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "-e" | "--elements" if i + 1 < args.len() => {
+                options.elements_str = Some(args[i + 1].clone());
+                i += 1;
             }
-            "-p" | "--prompt" => {
-                if i + 1 < args.len() {
-                    options.prompt = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "-p" | "--prompt" if i + 1 < args.len() => {
+                options.prompt = Some(args[i + 1].clone());
+                i += 1;
             }
-            "-c" | "--config" => {
-                if i + 1 < args.len() {
-                    options.config_path = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "-c" | "--config" if i + 1 < args.len() => {
+                options.config_path = Some(args[i + 1].clone());
+                i += 1;
             }
-            "--modules-dir" => {
-                if i + 1 < args.len() {
-                    options.modules_dir = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "--modules-dir" if i + 1 < args.len() => {
+                options.modules_dir = Some(args[i + 1].clone());
+                i += 1;
             }
-            "--data-dir" => {
-                if i + 1 < args.len() {
-                    options.data_dir = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "--data-dir" if i + 1 < args.len() => {
+                options.data_dir = Some(args[i + 1].clone());
+                i += 1;
             }
-            "--install" => {
-                if i + 1 < args.len() {
-                    options.install_companion = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "--install" if i + 1 < args.len() => {
+                options.install_companion = Some(args[i + 1].clone());
+                i += 1;
             }
             "-s" | "--silent" => {
                 options.silent = true;
             }
-            "--debug-ranking" => {
-                if i + 1 < args.len() {
-                    options.debug_ranking = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "--debug-ranking" if i + 1 < args.len() => {
+                options.debug_ranking = Some(args[i + 1].clone());
+                i += 1;
+            }
+            "--debug-ranking-format" if i + 1 < args.len() => {
+                options.debug_ranking_format = match args[i + 1].to_ascii_lowercase().as_str() {
+                    "json" => DebugRankingFormat::Json,
+                    _ => DebugRankingFormat::Text,
+                };
+                i += 1;
+            }
+            "--limit" if i + 1 < args.len() => {
+                options.limit = args[i + 1].parse().ok();
+                i += 1;
             }
             "--metrics" => {
                 options.metrics = true;
             }
+            "--metrics-bench" => {
+                options.metrics_bench = true;
+            }
             "--modules-debug" => {
                 options.modules_debug = true;
             }
             "--reindex" => {
                 options.reindex = true;
             }
+            "--tui" => {
+                options.tui = true;
+            }
+            "--script" if i + 1 < args.len() => {
+                options.script = Some(args[i + 1].clone());
+                i += 1;
+            }
             "-h" | "--help" => {
                 print_help();
                 std::process::exit(0);
             }
-            "--layout" => {
-                if i + 1 < args.len() {
-                    options.layout = Some(args[i + 1].to_lowercase().clone());
-                    i += 1;
-                }
+            "--layout" if i + 1 < args.len() => {
+                options.layout = Some(args[i + 1].to_lowercase().clone());
+                i += 1;
             }
-            "--x-pos" => {
-                if i + 1 < args.len() {
-                    options.cli_x_pos = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "--x-pos" if i + 1 < args.len() => {
+                options.cli_x_pos = Some(args[i + 1].clone());
+                i += 1;
             }
-            "--y-pos" => {
-                if i + 1 < args.len() {
-                    options.cli_y_pos = Some(args[i + 1].clone());
-                    i += 1;
-                }
+            "--y-pos" if i + 1 < args.len() => {
+                options.cli_y_pos = Some(args[i + 1].clone());
+                i += 1;
             }
-            "--width-percent" => {
-                if i + 1 < args.len() {
-                    options.cli_width_percent = args[i + 1].parse().ok();
-                    i += 1;
-                }
+            "--width-percent" if i + 1 < args.len() => {
+                options.cli_width_percent = args[i + 1].parse().ok();
+                i += 1;
             }
-            "--max-width" => {
-                if i + 1 < args.len() {
-                    options.cli_max_width = args[i + 1].parse().ok();
-                    i += 1;
-                }
+            "--max-width" if i + 1 < args.len() => {
+                options.cli_max_width = args[i + 1].parse().ok();
+                i += 1;
             }
-            "--height" => {
-                if i + 1 < args.len() {
-                    options.cli_height = args[i + 1].parse().ok();
-                    i += 1;
-                }
+            "--height" if i + 1 < args.len() => {
+                options.cli_height = args[i + 1].parse().ok();
+                i += 1;
             }
-            "--item-height" => {
-                if i + 1 < args.len() {
-                    options.cli_item_height = args[i + 1].parse().ok();
-                    i += 1;
-                }
+            "--item-height" if i + 1 < args.len() => {
+                options.cli_item_height = args[i + 1].parse().ok();
+                i += 1;
             }
-            "--padding" => {
-                if i + 1 < args.len() {
-                    options.cli_padding = args[i + 1].parse().ok();
-                    i += 1;
-                }
+            "--padding" if i + 1 < args.len() => {
+                options.cli_padding = args[i + 1].parse().ok();
+                i += 1;
             }
-            "--border-width" => {
-                if i + 1 < args.len() {
-                    options.cli_border_width = args[i + 1].parse().ok();
-                    i += 1;
-                }
+            "--border-width" if i + 1 < args.len() => {
+                options.cli_border_width = args[i + 1].parse().ok();
+                i += 1;
             }
             _ => { /* Ignorar argumentos desconocidos */ }
         }
@@ -898,6 +1118,65 @@ pub fn parse_args() -> CmdOptions {
     options
 }
 
+pub fn print_help() {
+    println!("rmenu - Un lanzador de menús simple al estilo dmenu para Windows");
+    println!("Uso: rmenu [OPCIONES]");
+    println!();
+    println!("Opciones de Entrada:");
+    println!(
+        "  -e, --elements <LIST>   Lista de elementos (delimitador en config.ini, defecto: ',')."
+    );
+    println!("                            Si no se provee, rmenu lee de stdin (uno por línea).");
+    println!("  -p, --prompt <TEXT>     Texto a mostrar como prompt.");
+    println!();
+    println!("Opciones de Configuración y Comportamiento:");
+    println!("  -c, --config <PATH>     Ruta al archivo de configuración (config.ini).");
+    println!("  -s, --silent            Suprime todos los mensajes de error/diagnóstico (stderr).");
+    println!(
+        "  --debug-ranking <QUERY> Imprime ranking (fuzzy + source boost) para depuración y sale."
+    );
+    println!("  --debug-ranking-format <text|json>");
+    println!("                          Formato de --debug-ranking (json incluye tier y penalizaciones).");
+    println!("  --limit <N>             Filas de --debug-ranking (defecto: 20).");
+    println!("  --metrics               Imprime métricas de startup/UI/search/dataset y sale.");
+    println!("  --metrics-bench         Mide latencia por tecla sobre datasets sintéticos y sale.");
+    println!("  --modules-debug         Imprime diagnóstico de módulos/hosts y sale.");
+    println!("  --modules-dir <PATH>    Sobrescribe el directorio de módulos para esta ejecución.");
+    println!(
+        "  --data-dir <PATH>       Directorio persistente rMenu (modules/companions/config/state)."
+    );
+    println!("  --install <NAME>        Instala companion nativo (rsnip desde GitHub latest).");
+    println!(
+        "  --reindex               Fuerza rebuild del índice (ignora cache en esta ejecución)."
+    );
+    println!("  --tui                   Usa la interfaz de terminal en lugar de la ventana Win32.");
+    println!(
+        "  --script <PATH>         Reproduce un guion de teclas/aserciones sin ventana y sale."
+    );
+    println!("  -h, --help              Muestra esta ayuda.");
+    println!();
+    println!("Opciones de Geometría y Layout (sobrescriben config.ini):");
+    println!("  --layout <NAME>         Aplica un layout predefinido. Opciones:");
+    println!("                            custom, top-fullwidth, bottom-fullwidth, center-dialog,");
+    println!("                            top-left, top-right, bottom-left, bottom-right.");
+    println!(
+        "                            Si es 'custom' o se omite, se usan los valores detallados."
+    );
+    println!("  --x-pos <POS>           Posición X. Ej: '100' (píxeles) o 'r0.5' (relativo).");
+    println!("  --y-pos <POS>           Posición Y. Ej: '0' o 'r0.3'.");
+    println!("  --width-percent <FLOAT> Ancho como porcentaje de pantalla (0.0-1.0).");
+    println!("  --max-width <PX>        Ancho máximo en píxeles.");
+    println!("  --height <PX>           Altura de la barra de entrada en píxeles.");
+    println!("  --item-height <PX>      Altura de cada ítem de la lista en píxeles.");
+    println!("  --padding <PX>          Relleno interno en píxeles.");
+    println!("  --border-width <PX>     Ancho del borde en píxeles.");
+    println!();
+    println!(
+        "Para más detalles sobre colores, fuentes y comportamiento, ver el archivo config.ini."
+    );
+    println!("Ubicación por defecto: %APPDATA%\\rmenu\\config.ini"); // Barras dobles para literal
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
@@ -905,7 +1184,7 @@ mod tests {
         ModuleDirCandidates, RmenuConfig,
     };
     use std::fs;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     #[test]
     fn invalid_module_config_values_fall_back_to_safe_defaults() {
@@ -998,15 +1277,13 @@ max_ipc_payload_bytes = also-bad
 
     #[test]
     fn data_dirs_derive_children_from_root() {
+        let root = Path::new("C:\\rMenuData");
         let dirs = rmenu_data_dirs(Some("C:\\rMenuData"));
-        assert_eq!(dirs.data_dir, PathBuf::from("C:\\rMenuData"));
-        assert_eq!(dirs.modules_dir, PathBuf::from("C:\\rMenuData\\modules"));
-        assert_eq!(
-            dirs.companions_dir,
-            PathBuf::from("C:\\rMenuData\\companions")
-        );
-        assert_eq!(dirs.config_dir, PathBuf::from("C:\\rMenuData\\config"));
-        assert_eq!(dirs.state_dir, PathBuf::from("C:\\rMenuData\\state"));
+        assert_eq!(dirs.data_dir, root);
+        assert_eq!(dirs.modules_dir, root.join("modules"));
+        assert_eq!(dirs.companions_dir, root.join("companions"));
+        assert_eq!(dirs.config_dir, root.join("config"));
+        assert_eq!(dirs.state_dir, root.join("state"));
     }
 
     fn test_dir(name: &str) -> PathBuf {
@@ -1046,55 +1323,75 @@ max_ipc_payload_bytes = 131072
         assert_eq!(parsed.modules.host_restart_backoff_ms, 900);
         assert_eq!(parsed.modules.max_ipc_payload_bytes, 131072);
     }
-}
 
-pub fn print_help() {
-    println!("rmenu - Un lanzador de menús simple al estilo dmenu para Windows");
-    println!("Uso: rmenu [OPCIONES]");
-    println!("");
-    println!("Opciones de Entrada:");
-    println!(
-        "  -e, --elements <LIST>   Lista de elementos (delimitador en config.ini, defecto: ',')."
-    );
-    println!("                            Si no se provee, rmenu lee de stdin (uno por línea).");
-    println!("  -p, --prompt <TEXT>     Texto a mostrar como prompt.");
-    println!("");
-    println!("Opciones de Configuración y Comportamiento:");
-    println!("  -c, --config <PATH>     Ruta al archivo de configuración (config.ini).");
-    println!("  -s, --silent            Suprime todos los mensajes de error/diagnóstico (stderr).");
-    println!(
-        "  --debug-ranking <QUERY> Imprime ranking (fuzzy + source boost) para depuración y sale."
-    );
-    println!("  --metrics               Imprime métricas de startup/UI/search/dataset y sale.");
-    println!("  --modules-debug         Imprime diagnóstico de módulos/hosts y sale.");
-    println!("  --modules-dir <PATH>    Sobrescribe el directorio de módulos para esta ejecución.");
-    println!(
-        "  --data-dir <PATH>       Directorio persistente rMenu (modules/companions/config/state)."
-    );
-    println!("  --install <NAME>        Instala companion nativo (rsnip desde GitHub latest).");
-    println!(
-        "  --reindex               Fuerza rebuild del índice (ignora cache en esta ejecución)."
-    );
-    println!("  -h, --help              Muestra esta ayuda.");
-    println!("");
-    println!("Opciones de Geometría y Layout (sobrescriben config.ini):");
-    println!("  --layout <NAME>         Aplica un layout predefinido. Opciones:");
-    println!("                            custom, top-fullwidth, bottom-fullwidth, center-dialog,");
-    println!("                            top-left, top-right, bottom-left, bottom-right.");
-    println!(
-        "                            Si es 'custom' o se omite, se usan los valores detallados."
-    );
-    println!("  --x-pos <POS>           Posición X. Ej: '100' (píxeles) o 'r0.5' (relativo).");
-    println!("  --y-pos <POS>           Posición Y. Ej: '0' o 'r0.3'.");
-    println!("  --width-percent <FLOAT> Ancho como porcentaje de pantalla (0.0-1.0).");
-    println!("  --max-width <PX>        Ancho máximo en píxeles.");
-    println!("  --height <PX>           Altura de la barra de entrada en píxeles.");
-    println!("  --item-height <PX>      Altura de cada ítem de la lista en píxeles.");
-    println!("  --padding <PX>          Relleno interno en píxeles.");
-    println!("  --border-width <PX>     Ancho del borde en píxeles.");
-    println!("");
-    println!(
-        "Para más detalles sobre colores, fuentes y comportamiento, ver el archivo config.ini."
-    );
-    println!("Ubicación por defecto: %APPDATA%\\rmenu\\config.ini"); // Barras dobles para literal
+    #[test]
+    fn source_roots_parse_with_per_root_settings() {
+        let parsed = RmenuConfig::parse_config(
+            r#"
+[Sources]
+tools = D:\tools
+tools.max_depth = 1
+tools.extensions = .PS1, py
+tools.exclude = *uninst*
+tools.label = Tools
+tools.boost = 150
+share = \\server\apps
+share.boost = nope
+"#,
+        )
+        .expect("source roots should parse");
+
+        let roots = &parsed.launcher.extra_roots;
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].name, "share");
+        assert_eq!(roots[0].label, "share");
+        assert_eq!(roots[0].boost, 0);
+        assert_eq!(roots[0].max_depth, 3);
+        assert_eq!(roots[1].path, PathBuf::from("D:\\tools"));
+        assert_eq!(roots[1].max_depth, 1);
+        assert_eq!(roots[1].extensions, ["ps1", "py"]);
+        assert_eq!(roots[1].exclude, ["*uninst*"]);
+        assert_eq!(roots[1].label, "Tools");
+        assert_eq!(roots[1].boost, 150);
+
+        let reparsed = RmenuConfig::parse_config(&parsed.to_ini_string()).expect("saved config");
+        assert_eq!(reparsed.launcher.extra_roots, parsed.launcher.extra_roots);
+    }
+
+    #[test]
+    fn bookmark_profiles_parse_sorted_by_name() {
+        let parsed = RmenuConfig::parse_config(
+            r#"
+[Launcher]
+enable_bookmarks = false
+source_boost_bookmarks = 120
+
+[Bookmarks]
+firefox = D:\backup\bookmarks.html
+edge = C:\Users\me\AppData\Local\Microsoft\Edge\User Data\Default
+empty =
+"#,
+        )
+        .expect("bookmark profiles should parse");
+
+        assert!(!parsed.launcher.enable_bookmarks);
+        assert_eq!(parsed.launcher.source_boost_bookmarks, 120);
+        let names = parsed
+            .launcher
+            .bookmark_profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["edge", "firefox"]);
+        assert_eq!(
+            parsed.launcher.bookmark_profiles[1].path,
+            PathBuf::from("D:\\backup\\bookmarks.html")
+        );
+
+        let reparsed = RmenuConfig::parse_config(&parsed.to_ini_string()).expect("saved config");
+        assert_eq!(
+            reparsed.launcher.bookmark_profiles,
+            parsed.launcher.bookmark_profiles
+        );
+    }
 }
@@ -0,0 +1,359 @@
+//! Readers for browser bookmark files: Chromium's `Bookmarks` JSON, Firefox
+//! bookmark backups (the JSON written by "Backup..." in the Library window),
+//! and the Netscape bookmark HTML every browser exports.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CHROMIUM_BOOKMARKS_FILE: &str = "Bookmarks";
+const FIREFOX_CONTAINER_TYPE: &str = "text/x-moz-place-container";
+const FIREFOX_BOOKMARK_TYPE: &str = "text/x-moz-place";
+// Bookmark exports with tens of thousands of entries stay well below this.
+const MAX_BOOKMARK_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub title: String,
+    pub url: String,
+    /// Folder names from the root down, joined with `/`.
+    pub folder: String,
+}
+
+/// Bookmark file for a configured path: the path itself, or the Chromium
+/// `Bookmarks` file when it names a profile folder.
+pub fn bookmark_file_path(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.join(CHROMIUM_BOOKMARKS_FILE)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+pub fn read_bookmark_file(path: &Path) -> Option<Vec<Bookmark>> {
+    let meta = fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() > MAX_BOOKMARK_FILE_BYTES {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    let content = String::from_utf8_lossy(bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes));
+    parse_bookmarks(&content)
+}
+
+pub fn parse_bookmarks(content: &str) -> Option<Vec<Bookmark>> {
+    let trimmed = content.trim_start();
+    let mut bookmarks = Vec::new();
+    if trimmed.starts_with('{') {
+        let root = serde_json::from_str::<Value>(trimmed).ok()?;
+        if let Some(roots) = root.get("roots").and_then(Value::as_object) {
+            for node in roots.values() {
+                collect_chromium(node, &mut Vec::new(), &mut bookmarks);
+            }
+        } else {
+            collect_firefox(&root, &mut Vec::new(), &mut bookmarks);
+        }
+    } else if trimmed.starts_with('<') {
+        collect_netscape_html(trimmed, &mut bookmarks);
+    } else {
+        return None;
+    }
+    bookmarks.retain(|bookmark| is_launchable_url(&bookmark.url));
+    Some(bookmarks)
+}
+
+fn is_launchable_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    !url.is_empty()
+        && !["place:", "javascript:", "data:"]
+            .iter()
+            .any(|scheme| lower.starts_with(scheme))
+}
+
+fn bookmark(title: &str, url: &str, folders: &[String]) -> Bookmark {
+    Bookmark {
+        title: title.trim().to_string(),
+        url: url.trim().to_string(),
+        folder: folders.join("/"),
+    }
+}
+
+fn string_field<'a>(node: &'a Value, key: &str) -> &'a str {
+    node.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn children(node: &Value) -> &[Value] {
+    node.get("children")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn collect_chromium(node: &Value, folders: &mut Vec<String>, out: &mut Vec<Bookmark>) {
+    match string_field(node, "type") {
+        "url" => out.push(bookmark(
+            string_field(node, "name"),
+            string_field(node, "url"),
+            folders,
+        )),
+        "folder" => {
+            folders.push(string_field(node, "name").trim().to_string());
+            for child in children(node) {
+                collect_chromium(child, folders, out);
+            }
+            folders.pop();
+        }
+        _ => {}
+    }
+}
+
+fn collect_firefox(node: &Value, folders: &mut Vec<String>, out: &mut Vec<Bookmark>) {
+    match string_field(node, "type") {
+        FIREFOX_BOOKMARK_TYPE => out.push(bookmark(
+            string_field(node, "title"),
+            string_field(node, "uri"),
+            folders,
+        )),
+        FIREFOX_CONTAINER_TYPE => {
+            // The places root has an empty title; it is not a visible folder.
+            let title = string_field(node, "title").trim();
+            if !title.is_empty() {
+                folders.push(title.to_string());
+            }
+            for child in children(node) {
+                collect_firefox(child, folders, out);
+            }
+            if !title.is_empty() {
+                folders.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Walks `<DT><H3>Folder</H3><DL>...</DL>` and `<DT><A HREF="...">Title</A>`
+/// tags. The format is loose HTML (`<p>` and `<DT>` are never closed), so
+/// only the tags that carry structure are looked at.
+fn collect_netscape_html(html: &str, out: &mut Vec<Bookmark>) {
+    let mut folders: Vec<String> = Vec::new();
+    // Folder depth at each open `<DL>`, restored when it closes.
+    let mut open_lists: Vec<usize> = Vec::new();
+    let mut pending_folder: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let tag_end = rest.find('>').map_or(rest.len(), |end| end + 1);
+        let tag = &rest[..tag_end];
+        let name = tag_name(tag);
+        rest = &rest[tag_end..];
+
+        match name.as_str() {
+            "h3" => {
+                let (text, after) = text_until_close(rest, "</h3");
+                pending_folder = Some(decode_html_entities(text).trim().to_string());
+                rest = after;
+            }
+            "a" => {
+                let (text, after) = text_until_close(rest, "</a");
+                if let Some(href) = html_attribute(tag, "href") {
+                    out.push(bookmark(
+                        &decode_html_entities(text),
+                        &decode_html_entities(href),
+                        &folders,
+                    ));
+                }
+                rest = after;
+            }
+            "dl" => {
+                open_lists.push(folders.len());
+                folders.extend(pending_folder.take());
+            }
+            "/dl" => folders.truncate(open_lists.pop().unwrap_or(folders.len())),
+            _ => {}
+        }
+    }
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .chars()
+        .take_while(|ch| ch.is_ascii_alphanumeric() || *ch == '/')
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Text up to the closing tag `close` (matched case-insensitively) and the
+/// remainder after it.
+fn text_until_close<'a>(html: &'a str, close: &str) -> (&'a str, &'a str) {
+    let Some(end) = html
+        .as_bytes()
+        .windows(close.len())
+        .position(|window| window.eq_ignore_ascii_case(close.as_bytes()))
+    else {
+        return (html, "");
+    };
+    let after = &html[end..];
+    let after = after
+        .find('>')
+        .map_or("", |close_end| &after[close_end + 1..]);
+    (&html[..end], after)
+}
+
+fn html_attribute<'a>(tag: &'a str, attribute: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let mut search_from = 0;
+    while let Some(found) = lower[search_from..].find(attribute) {
+        let start = search_from + found;
+        search_from = start + attribute.len();
+        let preceded_by_space = lower[..start].ends_with(char::is_whitespace);
+        let value = lower[search_from..].trim_start();
+        if !preceded_by_space || !value.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - value.len() + 1;
+        let value = tag[value_start..].trim_start();
+        return match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+            _ => value.split([' ', '>']).next(),
+        };
+    }
+    None
+}
+
+fn decode_html_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..=end]);
+        let replacement = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (entity, replacement) {
+            (Some(entity), Some(ch)) => {
+                decoded.push(ch);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_html_entities, parse_bookmarks, Bookmark};
+
+    fn summary(bookmarks: &[Bookmark]) -> Vec<(&str, &str, &str)> {
+        bookmarks
+            .iter()
+            .map(|bookmark| {
+                (
+                    bookmark.title.as_str(),
+                    bookmark.url.as_str(),
+                    bookmark.folder.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chromium_and_firefox_json_keep_folder_paths() {
+        let chromium = r#"{
+            "checksum": "0",
+            "roots": {
+                "bookmark_bar": {
+                    "name": "Bookmarks bar", "type": "folder",
+                    "children": [
+                        {"name": "Intranet", "type": "folder", "children": [
+                            {"name": "Wiki", "type": "url", "url": "https://wiki.corp/"}
+                        ]},
+                        {"name": "Mail", "type": "url", "url": "https://mail.corp/"},
+                        {"name": "Bookmarklet", "type": "url", "url": "javascript:void(0)"}
+                    ]
+                },
+                "other": {"name": "Other bookmarks", "type": "folder", "children": []}
+            },
+            "version": 1
+        }"#;
+        let bookmarks = parse_bookmarks(chromium).expect("chromium bookmarks parse");
+        assert_eq!(
+            summary(&bookmarks),
+            [
+                ("Wiki", "https://wiki.corp/", "Bookmarks bar/Intranet"),
+                ("Mail", "https://mail.corp/", "Bookmarks bar"),
+            ]
+        );
+
+        let firefox = r#"{
+            "title": "", "root": "placesRoot", "type": "text/x-moz-place-container",
+            "children": [
+                {"title": "menu", "root": "bookmarksMenuFolder",
+                 "type": "text/x-moz-place-container", "children": [
+                    {"title": "Builds", "type": "text/x-moz-place", "uri": "https://ci.corp/"},
+                    {"title": "Recent", "type": "text/x-moz-place", "uri": "place:sort=8"}
+                ]}
+            ]
+        }"#;
+        let bookmarks = parse_bookmarks(firefox).expect("firefox bookmarks parse");
+        assert_eq!(
+            summary(&bookmarks),
+            [("Builds", "https://ci.corp/", "menu")]
+        );
+    }
+
+    #[test]
+    fn netscape_html_export_tracks_nested_folders() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1" PERSONAL_TOOLBAR_FOLDER="true">Toolbar</H3>
+    <DL><p>
+        <DT><H3>R&amp;D</H3>
+        <DL><p>
+            <DT><A HREF="https://jira.corp/?a=1&amp;b=2" ADD_DATE="1">Jira &lt;team&gt;</A>
+        </DL><p>
+        <DT><A href='https://hr.corp/'>HR</A>
+    </DL><p>
+    <DT><A HREF="https://example.com/" ICON="data:image/png;base64,AA">Example</A>
+</DL><p>
+"#;
+        let bookmarks = parse_bookmarks(html).expect("html bookmarks parse");
+        assert_eq!(
+            summary(&bookmarks),
+            [
+                ("Jira <team>", "https://jira.corp/?a=1&b=2", "Toolbar/R&D"),
+                ("HR", "https://hr.corp/", "Toolbar"),
+                ("Example", "https://example.com/", ""),
+            ]
+        );
+
+        assert_eq!(
+            decode_html_entities("a &#233; &#x41; & b &bogus;"),
+            "a é A & b &bogus;"
+        );
+        assert_eq!(parse_bookmarks("not a bookmark file"), None);
+    }
+}
@@ -0,0 +1,399 @@
+//! Reader for freedesktop.org Desktop Entry (`.desktop`) files, the installed
+//! applications list of XDG desktops. Only `Type=Application` entries of the
+//! `[Desktop Entry]` group are read; `[Desktop Action ...]` groups are ignored.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DESKTOP_ENTRY_GROUP: &str = "Desktop Entry";
+#[cfg(any(not(windows), test))]
+const DEFAULT_XDG_DATA_DIRS: &str = "/usr/local/share/:/usr/share/";
+// Desktop entries are a few KiB; larger files are not entries.
+const MAX_DESKTOP_ENTRY_BYTES: u64 = 256 * 1024;
+
+/// Locale and desktop names that decide which `Name[xx]` is used and whether
+/// `OnlyShowIn` / `NotShowIn` hide an entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesktopEnvironment {
+    pub locale: Option<String>,
+    pub desktops: Vec<String>,
+}
+
+impl DesktopEnvironment {
+    pub fn current() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|key| std::env::var(key).ok())
+            .map(|value| value.trim().to_string())
+            .find(|value| !value.is_empty());
+        let desktops = std::env::var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .split(':')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+        DesktopEnvironment { locale, desktops }
+    }
+
+    /// Stored in the index cache; cached entries are read again when the
+    /// locale or the desktop changes.
+    pub fn signature(&self) -> String {
+        format!(
+            "locale={};desktop={}",
+            self.locale.as_deref().unwrap_or_default(),
+            self.desktops.join(":")
+        )
+    }
+
+    /// `Name[...]` suffixes to try, most specific first, as the Desktop Entry
+    /// spec orders them for `lang_COUNTRY.ENCODING@MODIFIER`.
+    fn locale_keys(&self) -> Vec<String> {
+        let Some(locale) = self.locale.as_deref() else {
+            return Vec::new();
+        };
+        let (base, modifier) = match locale.split_once('@') {
+            Some((base, modifier)) => (base, Some(modifier)),
+            None => (locale, None),
+        };
+        let base = base.split('.').next().unwrap_or_default();
+        if base.is_empty() || base == "C" || base == "POSIX" {
+            return Vec::new();
+        }
+        let (lang, country) = match base.split_once('_') {
+            Some((lang, country)) => (lang, Some(country)),
+            None => (base, None),
+        };
+
+        let mut keys = Vec::new();
+        if let (Some(country), Some(modifier)) = (country, modifier) {
+            keys.push(format!("{lang}_{country}@{modifier}"));
+        }
+        if let Some(country) = country {
+            keys.push(format!("{lang}_{country}"));
+        }
+        if let Some(modifier) = modifier {
+            keys.push(format!("{lang}@{modifier}"));
+        }
+        keys.push(lang.to_string());
+        keys
+    }
+
+    fn shows(&self, only_show_in: Option<&str>, not_show_in: Option<&str>) -> bool {
+        let listed = |list: &str| {
+            split_list(list)
+                .iter()
+                .any(|name| self.desktops.iter().any(|desktop| desktop == name))
+        };
+        only_show_in.is_none_or(listed) && !not_show_in.is_some_and(listed)
+    }
+}
+
+/// An application entry. Entries marked `Hidden`, `NoDisplay`, or filtered
+/// out by `OnlyShowIn` / `NotShowIn` are still returned with `visible` unset,
+/// because they hide entries with the same desktop file ID in lower-priority
+/// data directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopEntry {
+    pub name: String,
+    /// `Exec` command line with its field codes (`%f`, `%U`, ...) removed.
+    pub exec: String,
+    pub keywords: Vec<String>,
+    pub visible: bool,
+}
+
+pub fn is_desktop_entry(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("desktop"))
+}
+
+/// Desktop file ID of `path`: its path below the `applications` folder with
+/// `/` replaced by `-`, e.g. `kde4/konsole.desktop` -> `kde4-konsole.desktop`.
+pub fn desktop_file_id(applications_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(applications_dir).ok()?;
+    let parts = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    (!parts.is_empty()).then(|| parts.join("-"))
+}
+
+#[cfg(not(windows))]
+pub fn xdg_application_dirs() -> Vec<PathBuf> {
+    application_dirs(
+        std::env::var("XDG_DATA_HOME").ok().as_deref(),
+        dirs::home_dir().as_deref(),
+        std::env::var("XDG_DATA_DIRS").ok().as_deref(),
+    )
+}
+
+/// `applications` folders in XDG lookup order: `$XDG_DATA_HOME` (default
+/// `~/.local/share`) first, then each entry of `$XDG_DATA_DIRS`.
+#[cfg(any(not(windows), test))]
+fn application_dirs(
+    data_home: Option<&str>,
+    home: Option<&Path>,
+    data_dirs: Option<&str>,
+) -> Vec<PathBuf> {
+    let data_home = data_home
+        .filter(|value| !value.trim().is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| home.join(".local").join("share")));
+    let data_dirs = data_dirs
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or(DEFAULT_XDG_DATA_DIRS);
+
+    let mut dirs = Vec::new();
+    for dir in data_home.into_iter().chain(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from),
+    ) {
+        let applications = dir.join("applications");
+        if !dirs.contains(&applications) {
+            dirs.push(applications);
+        }
+    }
+    dirs
+}
+
+pub fn read_desktop_entry(path: &Path, environment: &DesktopEnvironment) -> Option<DesktopEntry> {
+    let meta = fs::metadata(path).ok()?;
+    if meta.len() > MAX_DESKTOP_ENTRY_BYTES {
+        return None;
+    }
+    let content = fs::read(path).ok()?;
+    parse_desktop_entry(&String::from_utf8_lossy(&content), environment)
+}
+
+pub fn parse_desktop_entry(
+    content: &str,
+    environment: &DesktopEnvironment,
+) -> Option<DesktopEntry> {
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    let mut in_group = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(group) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            in_group = group == DESKTOP_ENTRY_GROUP;
+            continue;
+        }
+        if !in_group {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            // The first occurrence of a key wins.
+            fields.entry(key.trim()).or_insert(value.trim());
+        }
+    }
+
+    if fields.get("Type").copied() != Some("Application") {
+        return None;
+    }
+    let localized = |key: &str| {
+        environment
+            .locale_keys()
+            .iter()
+            .find_map(|suffix| fields.get(format!("{key}[{suffix}]").as_str()))
+            .or_else(|| fields.get(key))
+            .map(|value| unescape_value(value))
+    };
+    let name = localized("Name").filter(|name| !name.is_empty())?;
+    let exec = fields
+        .get("Exec")
+        .map(|exec| strip_field_codes(&unescape_value(exec)))
+        .filter(|exec| !exec.is_empty())?;
+    let keywords = localized("Keywords")
+        .map(|keywords| split_list(&keywords))
+        .unwrap_or_default();
+    let flag = |key: &str| fields.get(key).is_some_and(|value| *value == "true");
+    let visible = !flag("Hidden")
+        && !flag("NoDisplay")
+        && environment.shows(
+            fields.get("OnlyShowIn").copied(),
+            fields.get("NotShowIn").copied(),
+        );
+
+    Some(DesktopEntry {
+        name,
+        exec,
+        keywords,
+        visible,
+    })
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Expands the `\s`, `\n`, `\t`, `\r`, and `\\` escapes of string values.
+fn unescape_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') | None => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+        }
+    }
+    unescaped
+}
+
+/// Drops the `Exec` field codes that stand for files, URLs, the icon, or the
+/// entry itself, and turns `%%` into `%`. rMenu launches entries without
+/// arguments, so every code expands to nothing.
+fn strip_field_codes(exec: &str) -> String {
+    let mut stripped = String::with_capacity(exec.len());
+    let mut chars = exec.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            stripped.push(ch);
+            continue;
+        }
+        if chars.next() == Some('%') {
+            stripped.push('%');
+        }
+    }
+    stripped
+        .split_whitespace()
+        .filter(|arg| *arg != "\"\"")
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        application_dirs, desktop_file_id, parse_desktop_entry, strip_field_codes,
+        DesktopEnvironment,
+    };
+    use std::path::{Path, PathBuf};
+
+    fn environment(locale: &str, desktops: &[&str]) -> DesktopEnvironment {
+        DesktopEnvironment {
+            locale: Some(locale.to_string()),
+            desktops: desktops.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn desktop_entry_uses_localized_name_and_strips_field_codes() {
+        let content = "\
+# comment
+[Desktop Entry]
+Type=Application
+Name=Files
+Name[de]=Dateien
+Name[de_CH]=Dateie
+Keywords=folder;manager;explore;
+Keywords[de]=Ordner;Verwaltung;
+Exec=nautilus --new-window %U
+OnlyShowIn=GNOME;Unity;
+
+[Desktop Action new-window]
+Name=New Window
+Exec=nautilus --new-window
+";
+        let entry = parse_desktop_entry(content, &environment("de_DE.UTF-8", &["GNOME"]))
+            .expect("entry should parse");
+        assert_eq!(entry.name, "Dateien");
+        assert_eq!(entry.exec, "nautilus --new-window");
+        assert_eq!(entry.keywords, ["Ordner", "Verwaltung"]);
+        assert!(entry.visible);
+
+        let swiss = parse_desktop_entry(content, &environment("de_CH@euro", &["KDE"]))
+            .expect("entry should parse");
+        assert_eq!(swiss.name, "Dateie");
+        assert!(!swiss.visible);
+
+        let plain = parse_desktop_entry(content, &environment("C", &["Unity"]))
+            .expect("entry should parse");
+        assert_eq!(plain.name, "Files");
+        assert_eq!(plain.keywords, ["folder", "manager", "explore"]);
+
+        assert_eq!(
+            strip_field_codes("env FOO=1 app --icon %i %c %k -- %f 100%%"),
+            "env FOO=1 app --icon -- 100%"
+        );
+    }
+
+    #[test]
+    fn desktop_entry_visibility_and_validity() {
+        let any = DesktopEnvironment::default();
+        let entry = |extra: &str| {
+            parse_desktop_entry(
+                &format!("[Desktop Entry]\nType=Application\nName=App\nExec=app\n{extra}"),
+                &any,
+            )
+        };
+        assert!(entry("").is_some_and(|entry| entry.visible));
+        assert!(entry("NoDisplay=true").is_some_and(|entry| !entry.visible));
+        assert!(entry("Hidden=true").is_some_and(|entry| !entry.visible));
+        assert!(entry("NotShowIn=KDE;").is_some_and(|entry| entry.visible));
+        assert!(entry("OnlyShowIn=KDE;").is_some_and(|entry| !entry.visible));
+        assert_eq!(
+            parse_desktop_entry("[Desktop Entry]\nType=Link\nName=Web\nURL=x", &any),
+            None
+        );
+        assert_eq!(
+            parse_desktop_entry("[Desktop Entry]\nType=Application\nName=NoExec", &any),
+            None
+        );
+    }
+
+    #[test]
+    fn xdg_lookup_order_and_desktop_file_ids() {
+        assert_eq!(
+            application_dirs(
+                None,
+                Some(Path::new("/home/me")),
+                Some("/opt/share:/usr/share")
+            ),
+            [
+                PathBuf::from("/home/me/.local/share/applications"),
+                PathBuf::from("/opt/share/applications"),
+                PathBuf::from("/usr/share/applications"),
+            ]
+        );
+        assert_eq!(
+            application_dirs(Some("/data"), None, None),
+            [
+                PathBuf::from("/data/applications"),
+                PathBuf::from("/usr/local/share/applications"),
+                PathBuf::from("/usr/share/applications"),
+            ]
+        );
+        assert_eq!(
+            desktop_file_id(
+                Path::new("/usr/share/applications"),
+                Path::new("/usr/share/applications/kde4/konsole.desktop")
+            )
+            .as_deref(),
+            Some("kde4-konsole.desktop")
+        );
+    }
+}
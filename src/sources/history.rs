@@ -0,0 +1,402 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HISTORY_FILE_VERSION: u32 = 1;
+const HISTORY_MAX_RECENT_LAUNCHES: usize = 10;
+const SECONDS_PER_HOUR: f64 = 3600.0;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+struct HistoryFile {
+    version: u32,
+    entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    pub target: String,
+    pub launch_count: u32,
+    pub last_launched: u64,
+    #[serde(default)]
+    pub recent_launches: Vec<u64>,
+}
+
+impl HistoryEntry {
+    fn record_launch(&mut self, now: u64) {
+        self.launch_count = self.launch_count.saturating_add(1);
+        self.last_launched = now;
+        self.recent_launches.insert(0, now);
+        self.recent_launches.truncate(HISTORY_MAX_RECENT_LAUNCHES);
+    }
+
+    pub fn frecency(&self, now: u64, half_life_hours: f64) -> f64 {
+        let samples = if self.recent_launches.is_empty() {
+            std::slice::from_ref(&self.last_launched)
+        } else {
+            self.recent_launches.as_slice()
+        };
+        let half_life_secs = (half_life_hours * SECONDS_PER_HOUR).max(1.0);
+        let decayed = samples
+            .iter()
+            .map(|launched| {
+                let age = now.saturating_sub(*launched) as f64;
+                0.5f64.powf(age / half_life_secs)
+            })
+            .sum::<f64>();
+
+        decayed * f64::from(self.launch_count.max(1)) / samples.len() as f64
+    }
+}
+
+fn rmenu_config_file(name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|mut path| {
+        path.push("rmenu");
+        path.push(name);
+        path
+    })
+}
+
+fn history_file_path() -> Option<PathBuf> {
+    rmenu_config_file("history.json")
+}
+
+fn legacy_history_file_path() -> Option<PathBuf> {
+    rmenu_config_file("history.txt")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn is_persistable_target(target: &str) -> bool {
+    let lowered = target.trim_start().to_ascii_lowercase();
+    !lowered.is_empty() && !lowered.starts_with("hidden:") && !lowered.starts_with("copy:")
+}
+
+fn parse_history_file(raw: &str) -> Option<Vec<HistoryEntry>> {
+    let file = serde_json::from_str::<HistoryFile>(raw).ok()?;
+    if file.version != HISTORY_FILE_VERSION {
+        return None;
+    }
+
+    Some(
+        file.entries
+            .into_iter()
+            .filter(|entry| !entry.target.trim().is_empty())
+            .collect(),
+    )
+}
+
+fn entries_from_legacy_history(raw: &str, modified: u64) -> Vec<HistoryEntry> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(index, target)| {
+            let launched = modified.saturating_sub(index as u64);
+            HistoryEntry {
+                target: target.to_string(),
+                launch_count: 1,
+                last_launched: launched,
+                recent_launches: vec![launched],
+            }
+        })
+        .collect()
+}
+
+fn file_modified_secs(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
+
+fn migrate_legacy_history(silent_mode: bool) -> Vec<HistoryEntry> {
+    let Some(legacy_path) = legacy_history_file_path() else {
+        return Vec::new();
+    };
+    let Ok(raw) = fs::read_to_string(&legacy_path) else {
+        return Vec::new();
+    };
+
+    let modified = file_modified_secs(&legacy_path).unwrap_or_else(unix_now);
+    let entries = entries_from_legacy_history(&raw, modified);
+    if write_history_entries(&entries, silent_mode) {
+        let _ = fs::rename(&legacy_path, legacy_path.with_extension("txt.bak"));
+    }
+    entries
+}
+
+/// Entries stored at `path`, or `None` when there is no file. A file that
+/// cannot be parsed is moved to `history.json.bak` so the next launch does not
+/// overwrite it.
+fn read_history_file(path: &Path, silent_mode: bool) -> Option<Vec<HistoryEntry>> {
+    let raw = fs::read_to_string(path).ok()?;
+    Some(parse_history_file(&raw).unwrap_or_else(|| {
+        let backup = path.with_extension("json.bak");
+        let result = fs::rename(path, &backup);
+        if !silent_mode {
+            match result {
+                Ok(()) => eprintln!(
+                    "Moved unreadable history file '{}' to '{}'",
+                    path.display(),
+                    backup.display()
+                ),
+                Err(e) => eprintln!(
+                    "Ignoring unreadable history file '{}' (backup failed: {})",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        Vec::new()
+    }))
+}
+
+pub fn read_history_entries(silent_mode: bool) -> Vec<HistoryEntry> {
+    let Some(path) = history_file_path() else {
+        return Vec::new();
+    };
+
+    read_history_file(&path, silent_mode).unwrap_or_else(|| migrate_legacy_history(silent_mode))
+}
+
+fn write_history_entries(entries: &[HistoryEntry], silent_mode: bool) -> bool {
+    let Some(path) = history_file_path() else {
+        return false;
+    };
+
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            if !silent_mode {
+                eprintln!(
+                    "Error creating history directory '{}': {}",
+                    parent.display(),
+                    e
+                );
+            }
+            return false;
+        }
+    }
+
+    let file = HistoryFile {
+        version: HISTORY_FILE_VERSION,
+        entries: entries.to_vec(),
+    };
+    let payload = match serde_json::to_string(&file) {
+        Ok(payload) => payload,
+        Err(_) => return false,
+    };
+
+    if let Err(e) = fs::write(&path, payload) {
+        if !silent_mode {
+            eprintln!("Error writing history file '{}': {}", path.display(), e);
+        }
+        return false;
+    }
+    true
+}
+
+/// Moves `target` to the front with one more launch. Past `max_items`, the
+/// entries with the lowest frecency are dropped, never the one just launched.
+fn record_launch_in_entries(
+    entries: &mut Vec<HistoryEntry>,
+    target: &str,
+    now: u64,
+    max_items: usize,
+    half_life_hours: f64,
+) {
+    let mut entry = entries
+        .iter()
+        .position(|entry| entry.target.eq_ignore_ascii_case(target))
+        .map(|index| entries.remove(index))
+        .unwrap_or_else(|| HistoryEntry {
+            target: target.to_string(),
+            ..Default::default()
+        });
+    entry.target = target.to_string();
+    entry.record_launch(now);
+    entries.insert(0, entry);
+
+    let excess = entries.len().saturating_sub(max_items);
+    if excess > 0 {
+        let mut evictable = entries
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(index, entry)| {
+                (
+                    entry.frecency(now, half_life_hours),
+                    entry.last_launched,
+                    index,
+                )
+            })
+            .collect::<Vec<_>>();
+        evictable.sort_by(|(left_score, left_time, _), (right_score, right_time, _)| {
+            left_score
+                .total_cmp(right_score)
+                .then_with(|| left_time.cmp(right_time))
+        });
+        let mut evicted = evictable
+            .into_iter()
+            .take(excess)
+            .map(|(_, _, index)| index)
+            .collect::<Vec<_>>();
+        evicted.sort_unstable();
+        for index in evicted.into_iter().rev() {
+            entries.remove(index);
+        }
+    }
+    entries.truncate(max_items);
+}
+
+pub fn persist_history_entry(
+    target: &str,
+    silent_mode: bool,
+    max_items: usize,
+    half_life_hours: f64,
+) {
+    if !is_persistable_target(target) {
+        return;
+    }
+
+    let mut entries = read_history_entries(silent_mode);
+    record_launch_in_entries(&mut entries, target, unix_now(), max_items, half_life_hours);
+    write_history_entries(&entries, silent_mode);
+}
+
+pub fn remove_history_entry(target: &str, silent_mode: bool) -> bool {
+    let mut entries = read_history_entries(silent_mode);
+    let before = entries.len();
+    entries.retain(|entry| !entry.target.eq_ignore_ascii_case(target));
+    if entries.len() == before {
+        return false;
+    }
+
+    write_history_entries(&entries, silent_mode)
+}
+
+pub fn history_entries_by_frecency(
+    silent_mode: bool,
+    half_life_hours: f64,
+) -> Vec<(HistoryEntry, f64)> {
+    let now = unix_now();
+    let mut scored = read_history_entries(silent_mode)
+        .into_iter()
+        .map(|entry| {
+            let frecency = entry.frecency(now, half_life_hours);
+            (entry, frecency)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|(left, left_score), (right, right_score)| {
+        right_score
+            .total_cmp(left_score)
+            .then_with(|| right.last_launched.cmp(&left.last_launched))
+    });
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        entries_from_legacy_history, parse_history_file, read_history_file,
+        record_launch_in_entries,
+    };
+
+    const HOUR: u64 = 3600;
+    const DAY: u64 = 24 * HOUR;
+
+    #[test]
+    fn legacy_history_migrates_in_mru_order() {
+        let entries = entries_from_legacy_history("notepad\n\n  calc.exe  \n", 1_000);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].target, "notepad");
+        assert_eq!(entries[0].launch_count, 1);
+        assert_eq!(entries[1].target, "calc.exe");
+        assert!(entries[0].last_launched > entries[1].last_launched);
+    }
+
+    #[test]
+    fn repeated_recent_launches_outrank_a_single_old_launch() {
+        let now = 90 * DAY;
+        let mut entries = Vec::new();
+        record_launch_in_entries(&mut entries, "old.exe", now - 30 * DAY, 10, 72.0);
+        for hour in 0..6 {
+            record_launch_in_entries(&mut entries, "daily.exe", now - hour * HOUR, 10, 72.0);
+        }
+        record_launch_in_entries(&mut entries, "DAILY.EXE", now, 10, 72.0);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].target, "DAILY.EXE");
+        assert_eq!(entries[0].launch_count, 7);
+
+        let daily = entries[0].frecency(now, 72.0);
+        let old = entries[1].frecency(now, 72.0);
+        assert!(daily > 5.0, "daily frecency was {daily}");
+        assert!(old < 0.01, "old frecency was {old}");
+    }
+
+    #[test]
+    fn history_file_respects_version_and_max_items() {
+        let mut entries = Vec::new();
+        for index in 0..5 {
+            record_launch_in_entries(&mut entries, &format!("app{index}"), index, 3, 72.0);
+        }
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.target.as_str())
+                .collect::<Vec<_>>(),
+            ["app4", "app3", "app2"]
+        );
+
+        let parsed = parse_history_file(
+            r#"{"version":1,"entries":[{"target":"a","launch_count":2,"last_launched":5}]}"#,
+        )
+        .expect("v1 history should parse");
+        assert_eq!(parsed[0].launch_count, 2);
+        assert!(parsed[0].recent_launches.is_empty());
+        assert!(parse_history_file(r#"{"version":99,"entries":[]}"#).is_none());
+    }
+
+    #[test]
+    fn frequent_entries_survive_truncation_over_newer_one_offs() {
+        let now = 10 * DAY;
+        let mut entries = Vec::new();
+        for hour in 0..5 {
+            record_launch_in_entries(&mut entries, "daily.exe", now - DAY - hour * HOUR, 2, 72.0);
+        }
+        record_launch_in_entries(&mut entries, "once.exe", now - HOUR, 2, 72.0);
+        record_launch_in_entries(&mut entries, "new.exe", now, 2, 72.0);
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.target.as_str())
+                .collect::<Vec<_>>(),
+            ["new.exe", "daily.exe"]
+        );
+    }
+
+    #[test]
+    fn unreadable_history_is_backed_up_instead_of_overwritten() {
+        let dir = std::env::temp_dir().join(format!("rmenu-history-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let path = dir.join("history.json");
+        std::fs::write(&path, "{ not json").expect("write history");
+
+        assert_eq!(read_history_file(&path, true), Some(Vec::new()));
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("history.json.bak")).expect("backup"),
+            "{ not json"
+        );
+        assert_eq!(read_history_file(&path, true), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
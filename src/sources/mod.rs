@@ -1,58 +1,190 @@
-use crate::app_state::{LauncherItem, LauncherSource};
-use crate::settings::LauncherConfig;
+mod bookmarks;
+mod desktop_entry;
+mod history;
+mod shell_link;
+
+use crate::app_state::{
+    ExtraSource, LauncherItem, LauncherItemAction, LauncherItemActionKind, LauncherSource,
+};
+use crate::rules::{glob_match, RankingRules};
+use crate::settings::{LauncherConfig, SourceRootConfig};
+use bookmarks::{bookmark_file_path, read_bookmark_file};
+use desktop_entry::{desktop_file_id, is_desktop_entry, read_desktop_entry, DesktopEnvironment};
+use history::history_entries_by_frecency;
+pub use history::{persist_history_entry, remove_history_entry};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use shell_link::read_shell_link;
+pub use shell_link::ShellLink;
+use std::collections::{BTreeMap, HashMap, HashSet};
+#[cfg(windows)]
 use std::ffi::c_void;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(windows)]
 use windows::core::PCWSTR;
+#[cfg(windows)]
 use windows::Win32::Storage::FileSystem::{
     GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW,
 };
 
-const INDEX_CACHE_VERSION: u32 = 5;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum CacheFormat {
-    JsonV3,
-    LegacyTsv,
-}
-
-#[derive(Debug, Clone)]
-struct CacheLoad {
-    items: Vec<LauncherItem>,
-    format: CacheFormat,
-}
+const INDEX_CACHE_VERSION: u32 = 8;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct IndexCacheFile {
     version: u32,
     generated_at_unix_ms: u64,
-    env_signature: CacheEnvironmentSignature,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    root_signatures: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    environment: String,
+    directories: Vec<CachedDirectory>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bookmark_files: Vec<CachedBookmarkFile>,
+}
+
+/// One indexed directory: its mtime when it was listed, the launchable files
+/// directly inside it and, for recursive roots, its subdirectories.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct CachedDirectory {
+    source: String,
+    path: String,
+    mtime_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    root: Option<String>,
+    #[serde(default)]
+    recursive: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    subdirs: Vec<String>,
     items: Vec<CachedLauncherItem>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 struct CachedLauncherItem {
-    source: String,
     label: String,
     target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    shortcut: Option<ShellLink>,
+    /// Desktop file ID of a `.desktop` entry in an XDG `applications` folder.
+    /// Hidden entries are kept with an empty target so they still shadow
+    /// entries with the same ID in later folders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    desktop_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<String>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
-struct CacheEnvironmentSignature {
-    path_hash: u64,
-    start_menu_user_mtime_ms: u64,
-    start_menu_common_mtime_ms: u64,
+/// Bookmarks parsed from one browser bookmark file, reused until the file's
+/// mtime changes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct CachedBookmarkFile {
+    path: String,
+    mtime_ms: u64,
+    items: Vec<CachedBookmark>,
 }
 
-fn history_file_path() -> Option<PathBuf> {
-    dirs::config_dir().map(|mut path| {
-        path.push("rmenu");
-        path.push("history.txt");
-        path
-    })
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct CachedBookmark {
+    label: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    folder: String,
+}
+
+/// Pre-v6 JSON caches stored a flat item list; only their labels are reused.
+#[derive(Debug, Deserialize)]
+struct FlatIndexCacheFile {
+    #[serde(default)]
+    items: Vec<CachedLauncherItem>,
+}
+
+/// What the previous cache offers to a refresh: directories that can be reused
+/// while their mtime is unchanged, and labels of targets seen before, so a
+/// rescanned directory does not re-read version info for every executable.
+/// `[Sources]` directories are only reused while their root's signature matches.
+#[derive(Debug, Default)]
+struct PreviousIndex {
+    directories: HashMap<String, CachedDirectory>,
+    labels: HashMap<String, String>,
+    root_signatures: BTreeMap<String, String>,
+    bookmark_files: HashMap<String, CachedBookmarkFile>,
+}
+
+/// Directory the launcher index is built from. `[Sources]` roots carry their
+/// name and file filters; Start Menu and PATH roots accept every launcher
+/// extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexRoot {
+    pub source: LauncherSource,
+    pub path: PathBuf,
+    pub max_depth: usize,
+    pub name: Option<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    extensions: Vec<String>,
+}
+
+impl IndexRoot {
+    fn builtin(source: LauncherSource, path: PathBuf, max_depth: usize) -> Self {
+        IndexRoot {
+            source,
+            path,
+            max_depth,
+            name: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            extensions: Vec::new(),
+        }
+    }
+
+    fn extra(root: &SourceRootConfig) -> Self {
+        IndexRoot {
+            source: LauncherSource::Extra,
+            path: root.path.clone(),
+            max_depth: root.max_depth,
+            name: Some(root.name.clone()),
+            include: root.include.clone(),
+            exclude: root.exclude.clone(),
+            extensions: root.extensions.clone(),
+        }
+    }
+
+    /// Settings that decide which files a root lists; cached directories of a
+    /// root are rescanned when it changes.
+    fn signature(&self) -> String {
+        format!(
+            "path={};max_depth={};include={};exclude={};extensions={}",
+            directory_key(&self.path),
+            self.max_depth,
+            self.include.join(","),
+            self.exclude.join(","),
+            self.extensions.join(",")
+        )
+    }
+
+    fn excludes(&self, name: &str) -> bool {
+        self.exclude.iter().any(|pattern| glob_match(pattern, name))
+    }
+
+    fn accepts_file(&self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .map(|value| value.to_string_lossy())
+            .unwrap_or_default();
+        let extension_allowed = is_launcher_extension(path)
+            || path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+                .is_some_and(|ext| self.extensions.contains(&ext));
+
+        extension_allowed
+            && (self.include.is_empty()
+                || self
+                    .include
+                    .iter()
+                    .any(|pattern| glob_match(pattern, &name)))
+            && !self.excludes(&name)
+    }
 }
 
 fn index_cache_file_path() -> Option<PathBuf> {
@@ -74,6 +206,8 @@ fn source_to_cache(source: LauncherSource) -> &'static str {
         LauncherSource::Path => "path",
         LauncherSource::History => "history",
         LauncherSource::Direct => "direct",
+        LauncherSource::Extra => "extra",
+        LauncherSource::Bookmark => "bookmark",
     }
 }
 
@@ -83,71 +217,90 @@ fn source_from_cache(value: &str) -> Option<LauncherSource> {
         "path" => Some(LauncherSource::Path),
         "history" => Some(LauncherSource::History),
         "direct" => Some(LauncherSource::Direct),
+        "extra" => Some(LauncherSource::Extra),
+        "bookmark" => Some(LauncherSource::Bookmark),
         _ => None,
     }
 }
 
-pub fn persist_history_entry(target: &str, silent_mode: bool, max_items: usize) {
-    if target
-        .trim_start()
-        .to_ascii_lowercase()
-        .starts_with("hidden:")
-    {
-        return;
+pub fn core_secondary_actions(item: &LauncherItem) -> Vec<LauncherItemAction> {
+    let target = item.target.trim();
+    if target.is_empty() || matches!(item.source, LauncherSource::Direct) {
+        return Vec::new();
     }
 
-    let Some(path) = history_file_path() else {
-        return;
+    let action = |id: &str, label: &str, target: String| LauncherItemAction {
+        id: id.to_string(),
+        label: label.to_string(),
+        kind: LauncherItemActionKind::Launch(target),
     };
 
-    if let Some(parent) = path.parent() {
-        if let Err(e) = fs::create_dir_all(parent) {
-            if !silent_mode {
-                eprintln!(
-                    "Error creating history directory '{}': {}",
-                    parent.display(),
-                    e
-                );
-            }
-            return;
+    let mut actions = Vec::new();
+    let path = Path::new(target);
+    let is_file_path = path.is_absolute();
+    if is_file_path {
+        if let Some(reveal) = reveal_in_folder_target(target) {
+            actions.push(action("open-folder", "Open containing folder", reveal));
         }
     }
-
-    let mut values = read_history_targets();
-    values.retain(|item| !item.eq_ignore_ascii_case(target));
-    values.insert(0, target.to_string());
-    if values.len() > max_items {
-        values.truncate(max_items);
+    let resolved = item.resolved_target();
+    if resolved != target && Path::new(resolved).is_absolute() {
+        if let Some(reveal) = reveal_in_folder_target(resolved) {
+            actions.push(action("open-target-folder", "Open target location", reveal));
+        }
     }
-
-    if let Err(e) = fs::write(&path, values.join("\n")) {
-        if !silent_mode {
-            eprintln!("Error writing history file '{}': {}", path.display(), e);
+    // `runas` is a ShellExecute verb; other platforms have no equivalent.
+    if cfg!(windows)
+        && (is_file_path && is_launcher_extension(path) || !is_file_path && !target.contains("://"))
+    {
+        actions.push(action(
+            "run-as-admin",
+            "Run as administrator",
+            format!("runas:{target}"),
+        ));
+    }
+    actions.push(LauncherItemAction {
+        id: "copy-path".to_string(),
+        label: if is_file_path {
+            "Copy path"
+        } else {
+            "Copy target"
         }
+        .to_string(),
+        kind: LauncherItemActionKind::Copy(target.to_string()),
+    });
+    if matches!(item.source, LauncherSource::History) {
+        actions.push(LauncherItemAction {
+            id: "remove-from-history".to_string(),
+            label: "Remove from history".to_string(),
+            kind: LauncherItemActionKind::RemoveFromHistory(target.to_string()),
+        });
     }
-}
 
-fn read_history_targets() -> Vec<String> {
-    let Some(path) = history_file_path() else {
-        return Vec::new();
-    };
+    actions
+}
 
-    let raw = match fs::read_to_string(path) {
-        Ok(raw) => raw,
-        Err(_) => return Vec::new(),
-    };
+/// Launch target that opens the folder holding `path`; Explorer also selects
+/// the file.
+#[cfg(windows)]
+fn reveal_in_folder_target(path: &str) -> Option<String> {
+    Some(format!("explorer.exe /select,\"{path}\""))
+}
 
-    raw.lines()
-        .map(str::trim)
-        .filter(|line| !line.is_empty())
-        .map(str::to_string)
-        .collect()
+/// Launch target that opens the folder holding `path` in the desktop's file
+/// manager.
+#[cfg(not(windows))]
+fn reveal_in_folder_target(path: &str) -> Option<String> {
+    let parent = Path::new(path).parent()?;
+    (!parent.as_os_str().is_empty()).then(|| parent.display().to_string())
 }
 
+#[cfg(windows)]
 fn to_wstring(value: &str) -> Vec<u16> {
     value.encode_utf16().chain(std::iter::once(0)).collect()
 }
 
+#[cfg(windows)]
 fn read_version_info_string(block: &[u8], sub_block: &str) -> Option<String> {
     let sub_block_w = to_wstring(sub_block);
     let mut value_ptr: *mut c_void = std::ptr::null_mut();
@@ -183,6 +336,7 @@ fn read_version_info_string(block: &[u8], sub_block: &str) -> Option<String> {
     Some(trimmed.to_string())
 }
 
+#[cfg(windows)]
 fn read_exe_file_description(path: &Path) -> Option<String> {
     let ext = path.extension().and_then(|value| value.to_str())?;
     if !ext.eq_ignore_ascii_case("exe") {
@@ -246,6 +400,13 @@ fn read_exe_file_description(path: &Path) -> Option<String> {
         .or_else(|| read_version_info_string(&block, "\\StringFileInfo\\040904E4\\FileDescription"))
 }
 
+/// Version resources are a PE/Win32 concept; other platforms fall back to the
+/// file name.
+#[cfg(not(windows))]
+fn read_exe_file_description(_path: &Path) -> Option<String> {
+    None
+}
+
 fn windowsapps_alias_display_label(path: &Path) -> Option<&'static str> {
     let full_path_lc = path
         .to_string_lossy()
@@ -278,28 +439,15 @@ fn launcher_label_from_target(target: &str) -> String {
         return alias_label.to_string();
     }
 
-    path.file_stem()
-        .and_then(|value| value.to_str())
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .unwrap_or(target)
-        .to_string()
+    file_stem_label(path)
 }
 
-fn collect_files_recursive(root: &Path, out: &mut Vec<PathBuf>) {
-    let entries = match fs::read_dir(root) {
-        Ok(entries) => entries,
-        Err(_) => return,
-    };
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.is_dir() {
-            collect_files_recursive(&path, out);
-        } else {
-            out.push(path);
-        }
-    }
+fn file_stem_label(path: &Path) -> String {
+    path.file_stem()
+        .map(|value| value.to_string_lossy())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
 }
 
 fn is_launcher_extension(path: &Path) -> bool {
@@ -310,10 +458,89 @@ fn is_launcher_extension(path: &Path) -> bool {
 
     matches!(
         ext.as_deref(),
-        Some("exe") | Some("lnk") | Some("cmd") | Some("bat")
+        Some("exe") | Some("lnk") | Some("cmd") | Some("bat") | Some("url") | Some("desktop")
     )
 }
 
+fn is_shortcut(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lnk"))
+}
+
+fn is_internet_shortcut(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("url"))
+}
+
+fn read_internet_shortcut(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    let content = match bytes.as_slice() {
+        [0xFF, 0xFE, rest @ ..] => String::from_utf16_lossy(
+            &rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>(),
+        ),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+    parse_internet_shortcut(&content)
+}
+
+/// Returns the `URL=` value of the `[InternetShortcut]` section of a `.url`
+/// file. Other sections (icon data, `[DEFAULT]`, ...) are ignored.
+fn parse_internet_shortcut(content: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            in_section = section.trim().eq_ignore_ascii_case("InternetShortcut");
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim().eq_ignore_ascii_case("URL") {
+            let value = value.trim();
+            return (!value.is_empty()).then(|| value.to_string());
+        }
+    }
+    None
+}
+
+/// Host part of a `scheme://host/...` URL, used as the row hint for URL items.
+fn url_domain(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|ch| ch.is_ascii_digit()) => name,
+        _ => host,
+    };
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Records the targets `item` launches in `seen`, or returns false when one of
+/// them was already taken by an earlier item. Shortcuts count for both the
+/// `.lnk` path and the file it resolves to, so a Start Menu shortcut and the
+/// PATH executable it points at are listed once.
+fn claim_targets(seen: &mut HashSet<String>, item: &LauncherItem) -> bool {
+    let target = item.target.to_lowercase();
+    let resolved = item.resolved_target().to_lowercase();
+    if seen.contains(&target) || seen.contains(&resolved) {
+        return false;
+    }
+    seen.insert(target);
+    seen.insert(resolved);
+    true
+}
+
 fn build_blacklist_set(config: &LauncherConfig) -> HashSet<String> {
     config
         .blacklist_path_commands
@@ -334,13 +561,6 @@ fn is_blacklisted_command_name(value: &str, blacklist: &HashSet<String>) -> bool
     blacklist.contains(&normalized)
 }
 
-fn is_blacklisted_cli_tool(path: &Path, blacklist: &HashSet<String>) -> bool {
-    let Some(stem) = path.file_stem().and_then(|value| value.to_str()) else {
-        return false;
-    };
-    blacklist.contains(&stem.to_ascii_lowercase())
-}
-
 fn cached_item_to_launcher(
     source_raw: &str,
     label: String,
@@ -404,31 +624,19 @@ fn parse_legacy_index_cache(
     Some(items)
 }
 
-fn map_cached_json_items(
-    parsed: IndexCacheFile,
-    config: &LauncherConfig,
-    blacklist: &HashSet<String>,
-) -> Vec<LauncherItem> {
-    let mut items = Vec::new();
-    for item in parsed.items {
-        if let Some(launcher_item) =
-            cached_item_to_launcher(&item.source, item.label, item.target, config, blacklist)
-        {
-            items.push(launcher_item);
-        }
-    }
-    items
+/// Folders of installed applications: the Start Menu on Windows, the XDG
+/// `applications` folders elsewhere.
+#[cfg(windows)]
+fn installed_app_roots() -> Vec<PathBuf> {
+    start_menu_roots().into_iter().flatten().collect()
 }
 
-fn stable_fnv1a_64(raw: &str) -> u64 {
-    let mut hash: u64 = 0xcbf29ce484222325;
-    for b in raw.as_bytes() {
-        hash ^= *b as u64;
-        hash = hash.wrapping_mul(0x100000001b3);
-    }
-    hash
+#[cfg(not(windows))]
+fn installed_app_roots() -> Vec<PathBuf> {
+    desktop_entry::xdg_application_dirs()
 }
 
+#[cfg(windows)]
 fn start_menu_roots() -> [Option<PathBuf>; 2] {
     let user = std::env::var("APPDATA")
         .ok()
@@ -440,71 +648,322 @@ fn start_menu_roots() -> [Option<PathBuf>; 2] {
     [user, common]
 }
 
-fn dir_mtime_unix_ms(path: Option<&Path>) -> u64 {
-    let Some(path) = path else {
-        return 0;
-    };
+fn dir_mtime_unix_ms(path: &Path) -> Option<u64> {
+    let meta = fs::metadata(path).ok().filter(|meta| meta.is_dir())?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|value| value.duration_since(UNIX_EPOCH).ok());
+    Some(modified.map_or(0, |duration| duration.as_millis() as u64))
+}
 
-    let modified = match fs::metadata(path).and_then(|meta| meta.modified()) {
-        Ok(value) => value,
-        Err(_) => return 0,
-    };
+fn file_mtime_unix_ms(path: &Path) -> Option<u64> {
+    let meta = fs::metadata(path).ok().filter(|meta| meta.is_file())?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|value| value.duration_since(UNIX_EPOCH).ok());
+    Some(modified.map_or(0, |duration| duration.as_millis() as u64))
+}
 
-    match modified.duration_since(UNIX_EPOCH) {
-        Ok(duration) => duration.as_millis() as u64,
-        Err(_) => 0,
-    }
+fn directory_key(path: &Path) -> String {
+    path.to_string_lossy()
+        .trim_end_matches(['\\', '/'])
+        .replace('/', "\\")
+        .to_lowercase()
 }
 
-fn current_env_signature() -> CacheEnvironmentSignature {
-    let path_hash = stable_fnv1a_64(
-        &std::env::var("PATH")
-            .unwrap_or_default()
-            .to_ascii_lowercase(),
-    );
-    let [user_root, common_root] = start_menu_roots();
+pub fn index_roots(config: &LauncherConfig) -> Vec<IndexRoot> {
+    let mut roots = config
+        .extra_roots
+        .iter()
+        .map(IndexRoot::extra)
+        .collect::<Vec<_>>();
+    if config.enable_start_menu {
+        roots.extend(
+            installed_app_roots()
+                .into_iter()
+                .map(|path| IndexRoot::builtin(LauncherSource::StartMenu, path, usize::MAX)),
+        );
+    }
+    if config.enable_path {
+        let path_env = std::env::var("PATH").unwrap_or_default();
+        roots.extend(
+            std::env::split_paths(&path_env)
+                .map(|path| IndexRoot::builtin(LauncherSource::Path, path, 0)),
+        );
+    }
+    roots
+}
 
-    CacheEnvironmentSignature {
-        path_hash,
-        start_menu_user_mtime_ms: dir_mtime_unix_ms(user_root.as_deref()),
-        start_menu_common_mtime_ms: dir_mtime_unix_ms(common_root.as_deref()),
+fn bookmark_file_paths(config: &LauncherConfig) -> Vec<PathBuf> {
+    if !config.enable_bookmarks {
+        return Vec::new();
     }
+    config
+        .bookmark_profiles
+        .iter()
+        .map(|profile| bookmark_file_path(&profile.path))
+        .collect()
 }
 
 fn read_index_cache(
     config: &LauncherConfig,
     blacklist: &HashSet<String>,
     force_reindex: bool,
-) -> Option<CacheLoad> {
-    let path = index_cache_file_path()?;
-    let raw = fs::read_to_string(path).ok()?;
+) -> PreviousIndex {
+    let mut previous = PreviousIndex::default();
+    if force_reindex {
+        return previous;
+    }
+    let Some(raw) = index_cache_file_path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return previous;
+    };
 
-    if let Some(parsed) = parse_index_cache_json(&raw) {
-        if parsed.version == INDEX_CACHE_VERSION {
-            let current_signature = current_env_signature();
-            if force_reindex || parsed.env_signature != current_signature {
-                return None;
+    match parse_index_cache_json(&raw) {
+        Some(parsed) if parsed.version == INDEX_CACHE_VERSION => {
+            previous.root_signatures = parsed.root_signatures;
+            // Desktop entry names and visibility depend on the locale and the
+            // desktop, so a different environment lists every directory again.
+            let same_environment = parsed.environment == DesktopEnvironment::current().signature();
+            for directory in parsed.directories {
+                for item in &directory.items {
+                    previous
+                        .labels
+                        .insert(item.target.to_lowercase(), item.label.clone());
+                }
+                if same_environment {
+                    previous
+                        .directories
+                        .insert(directory_key(Path::new(&directory.path)), directory);
+                }
             }
+            for file in parsed.bookmark_files {
+                previous
+                    .bookmark_files
+                    .insert(directory_key(Path::new(&file.path)), file);
+            }
+        }
+        _ => {
+            let flat_items = serde_json::from_str::<FlatIndexCacheFile>(&raw)
+                .map(|flat| flat.items)
+                .ok()
+                .or_else(|| {
+                    parse_legacy_index_cache(&raw, config, blacklist).map(|items| {
+                        items
+                            .into_iter()
+                            .map(|item| CachedLauncherItem {
+                                label: item.label,
+                                target: item.target,
+                                ..Default::default()
+                            })
+                            .collect()
+                    })
+                })
+                .unwrap_or_default();
+            for item in flat_items {
+                previous
+                    .labels
+                    .insert(item.target.to_lowercase(), item.label);
+            }
+        }
+    }
 
-            return Some(CacheLoad {
-                items: map_cached_json_items(parsed, config, blacklist),
-                format: CacheFormat::JsonV3,
-            });
+    previous
+}
+
+/// Reuses the cached bookmarks of every file whose mtime is unchanged and
+/// parses the rest. Missing files are dropped; unreadable ones are cached
+/// empty so they are not parsed again until they change.
+fn refresh_bookmark_files(
+    paths: &[PathBuf],
+    previous: &PreviousIndex,
+) -> (Vec<CachedBookmarkFile>, usize) {
+    let mut files = Vec::new();
+    let mut reparsed = 0;
+    for path in paths {
+        let Some(mtime_ms) = file_mtime_unix_ms(path) else {
+            continue;
+        };
+        if let Some(cached) = previous
+            .bookmark_files
+            .get(&directory_key(path))
+            .filter(|cached| cached.mtime_ms == mtime_ms)
+        {
+            files.push(cached.clone());
+            continue;
         }
+
+        reparsed += 1;
+        let items = read_bookmark_file(path)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|bookmark| CachedBookmark {
+                label: if bookmark.title.is_empty() {
+                    url_domain(&bookmark.url).unwrap_or_else(|| bookmark.url.clone())
+                } else {
+                    bookmark.title
+                },
+                url: bookmark.url,
+                folder: bookmark.folder,
+            })
+            .collect();
+        files.push(CachedBookmarkFile {
+            path: path.to_string_lossy().to_string(),
+            mtime_ms,
+            items,
+        });
     }
+    (files, reparsed)
+}
 
-    if force_reindex {
-        return None;
+struct IndexWalk<'a> {
+    previous: &'a PreviousIndex,
+    visited: HashSet<String>,
+    directories: Vec<CachedDirectory>,
+    rescanned: usize,
+}
+
+/// Walks `roots`, reusing every cached directory whose mtime is unchanged and
+/// listing the rest again. Returns the directories in root order and how many
+/// had to be listed.
+fn refresh_index_directories(
+    roots: &[IndexRoot],
+    previous: &PreviousIndex,
+) -> (Vec<CachedDirectory>, usize) {
+    let mut walk = IndexWalk {
+        previous,
+        visited: HashSet::new(),
+        directories: Vec::new(),
+        rescanned: 0,
+    };
+    for root in roots {
+        let reusable = match &root.name {
+            Some(name) => previous.root_signatures.get(name) == Some(&root.signature()),
+            None => true,
+        };
+        walk.visit(root, reusable, &root.path, 0);
     }
+    (walk.directories, walk.rescanned)
+}
 
-    if let Some(items) = parse_legacy_index_cache(&raw, config, blacklist) {
-        return Some(CacheLoad {
-            items,
-            format: CacheFormat::LegacyTsv,
+impl IndexWalk<'_> {
+    fn visit(&mut self, root: &IndexRoot, reusable: bool, dir: &Path, depth: usize) {
+        let key = directory_key(dir);
+        if !self.visited.insert(key.clone()) {
+            return;
+        }
+        let Some(mtime_ms) = dir_mtime_unix_ms(dir) else {
+            return;
+        };
+
+        let recursive = depth < root.max_depth;
+        let directory = match self.previous.directories.get(&key) {
+            Some(cached)
+                if reusable
+                    && cached.mtime_ms == mtime_ms
+                    && cached.recursive == recursive
+                    && cached.root == root.name
+                    && cached.source == source_to_cache(root.source) =>
+            {
+                cached.clone()
+            }
+            _ => {
+                self.rescanned += 1;
+                scan_directory(root, dir, recursive, mtime_ms, &self.previous.labels)
+            }
+        };
+
+        let subdirs = directory.subdirs.clone();
+        self.directories.push(directory);
+        for subdir in subdirs {
+            self.visit(root, reusable, Path::new(&subdir), depth + 1);
+        }
+    }
+}
+
+fn scan_directory(
+    root: &IndexRoot,
+    dir: &Path,
+    recursive: bool,
+    mtime_ms: u64,
+    labels: &HashMap<String, String>,
+) -> CachedDirectory {
+    let mut subdirs = Vec::new();
+    let mut items = Vec::new();
+    let environment = DesktopEnvironment::current();
+
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive && !root.excludes(&entry.file_name().to_string_lossy()) {
+                subdirs.push(path.to_string_lossy().to_string());
+            }
+            continue;
+        }
+        if !root.accepts_file(&path) {
+            continue;
+        }
+
+        if is_internet_shortcut(&path) {
+            if let Some(url) = read_internet_shortcut(&path) {
+                items.push(CachedLauncherItem {
+                    label: file_stem_label(&path),
+                    target: url,
+                    ..Default::default()
+                });
+            }
+            continue;
+        }
+
+        if is_desktop_entry(&path) {
+            let Some(entry) = read_desktop_entry(&path, &environment) else {
+                continue;
+            };
+            let desktop_id = root
+                .name
+                .is_none()
+                .then(|| desktop_file_id(&root.path, &path))
+                .flatten();
+            if entry.visible || desktop_id.is_some() {
+                items.push(CachedLauncherItem {
+                    label: entry.name,
+                    target: if entry.visible {
+                        entry.exec
+                    } else {
+                        String::new()
+                    },
+                    desktop_id,
+                    keywords: entry.keywords,
+                    ..Default::default()
+                });
+            }
+            continue;
+        }
+
+        let target = path.to_string_lossy().to_string();
+        let label = labels
+            .get(&target.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| launcher_label_from_target(&target));
+        let shortcut = is_shortcut(&path).then(|| read_shell_link(&path)).flatten();
+        items.push(CachedLauncherItem {
+            label,
+            target,
+            shortcut,
+            ..Default::default()
         });
     }
 
-    None
+    CachedDirectory {
+        source: source_to_cache(root.source).to_string(),
+        path: dir.to_string_lossy().to_string(),
+        mtime_ms,
+        root: root.name.clone(),
+        recursive,
+        subdirs,
+        items,
+    }
 }
 
 fn now_unix_ms() -> u64 {
@@ -514,7 +973,12 @@ fn now_unix_ms() -> u64 {
     }
 }
 
-fn write_index_cache(items: &[LauncherItem], silent_mode: bool) {
+fn write_index_cache(
+    roots: &[IndexRoot],
+    directories: &[CachedDirectory],
+    bookmark_files: &[CachedBookmarkFile],
+    silent_mode: bool,
+) {
     let Some(path) = index_cache_file_path() else {
         return;
     };
@@ -535,21 +999,13 @@ fn write_index_cache(items: &[LauncherItem], silent_mode: bool) {
     let cache = IndexCacheFile {
         version: INDEX_CACHE_VERSION,
         generated_at_unix_ms: now_unix_ms(),
-        env_signature: current_env_signature(),
-        items: items
+        root_signatures: roots
             .iter()
-            .filter(|item| {
-                matches!(
-                    item.source,
-                    LauncherSource::StartMenu | LauncherSource::Path
-                )
-            })
-            .map(|item| CachedLauncherItem {
-                source: source_to_cache(item.source).to_string(),
-                label: item.label.clone(),
-                target: item.target.clone(),
-            })
+            .filter_map(|root| Some((root.name.clone()?, root.signature())))
             .collect(),
+        environment: DesktopEnvironment::current().signature(),
+        directories: directories.to_vec(),
+        bookmark_files: bookmark_files.to_vec(),
     };
 
     let serialized = match serde_json::to_string(&cache) {
@@ -569,146 +1025,131 @@ fn write_index_cache(items: &[LauncherItem], silent_mode: bool) {
     }
 }
 
-fn collect_start_menu_items(items: &mut Vec<LauncherItem>, seen_targets: &mut HashSet<String>) {
-    let roots = start_menu_roots();
-
-    for root in roots.into_iter().flatten() {
-        if !root.exists() {
-            continue;
-        }
-
-        let mut files = Vec::new();
-        collect_files_recursive(&root, &mut files);
-
-        for file in files {
-            if !is_launcher_extension(&file) {
-                continue;
-            }
-
-            let target = file.to_string_lossy().to_string();
-            let dedupe = target.to_lowercase();
-            if !seen_targets.insert(dedupe) {
-                continue;
-            }
-
-            items.push(LauncherItem::new(
-                launcher_label_from_target(&target),
-                target,
-                LauncherSource::StartMenu,
-            ));
-        }
-    }
-}
-
-fn collect_path_items(
-    items: &mut Vec<LauncherItem>,
-    seen_targets: &mut HashSet<String>,
-    blacklist: &HashSet<String>,
-) {
-    let path_env = std::env::var("PATH").unwrap_or_default();
-
-    for dir in std::env::split_paths(&path_env) {
-        let entries = match fs::read_dir(&dir) {
-            Ok(entries) => entries,
-            Err(_) => continue,
-        };
-
-        for entry in entries.flatten() {
-            let file = entry.path();
-            if !file.is_file() || !is_launcher_extension(&file) {
-                continue;
-            }
-            if is_blacklisted_cli_tool(&file, blacklist) {
-                continue;
-            }
-
-            let target = file.to_string_lossy().to_string();
-            let dedupe = target.to_lowercase();
-            if !seen_targets.insert(dedupe) {
-                continue;
-            }
-
-            items.push(LauncherItem::new(
-                launcher_label_from_target(&target),
-                target,
-                LauncherSource::Path,
-            ));
-        }
-    }
-}
-
 pub fn load_launcher_items(
     config: &LauncherConfig,
+    rules: &RankingRules,
     silent_mode: bool,
     force_reindex: bool,
 ) -> Vec<LauncherItem> {
     let mut items: Vec<LauncherItem> = Vec::new();
     let mut seen_targets: HashSet<String> = HashSet::new();
+    // XDG folders come in priority order; an entry hides later ones with the
+    // same desktop file ID.
+    let mut seen_desktop_ids: HashSet<String> = HashSet::new();
     let blacklist = build_blacklist_set(config);
 
     if config.enable_history {
-        for target in read_history_targets()
-            .into_iter()
-            .take(config.history_max_items)
+        for (entry, frecency) in
+            history_entries_by_frecency(silent_mode, config.frecency_half_life_hours)
+                .into_iter()
+                .take(config.history_max_items)
         {
+            let target = entry.target;
             if is_blacklisted_command_name(&target, &blacklist) {
                 continue;
             }
 
-            let dedupe = target.to_lowercase();
-            if !seen_targets.insert(dedupe) {
-                continue;
-            }
-
-            items.push(LauncherItem::new(
+            let mut item = LauncherItem::new(
                 launcher_label_from_target(&target),
                 target,
                 LauncherSource::History,
-            ));
+            );
+            let path = Path::new(&item.target);
+            if is_shortcut(path) {
+                if let Some(shortcut) = read_shell_link(path) {
+                    item.set_shortcut(shortcut);
+                }
+            }
+            if !claim_targets(&mut seen_targets, &item) {
+                continue;
+            }
+            item.frecency = frecency;
+            items.push(item);
         }
     }
 
-    let indexed_items = read_index_cache(config, &blacklist, force_reindex)
-        .map(|cache| {
-            if cache.format == CacheFormat::LegacyTsv {
-                write_index_cache(&cache.items, silent_mode);
+    let previous = read_index_cache(config, &blacklist, force_reindex);
+    let roots = index_roots(config);
+    let (directories, rescanned) = refresh_index_directories(&roots, &previous);
+    let (bookmark_files, reparsed) =
+        refresh_bookmark_files(&bookmark_file_paths(config), &previous);
+    if rescanned + reparsed > 0
+        || directories.len() != previous.directories.len()
+        || bookmark_files.len() != previous.bookmark_files.len()
+    {
+        write_index_cache(&roots, &directories, &bookmark_files, silent_mode);
+    }
+
+    for directory in directories {
+        let extra_source = directory.root.as_ref().and_then(|name| {
+            config
+                .extra_roots
+                .iter()
+                .find(|root| &root.name == name)
+                .map(|root| ExtraSource {
+                    label: root.label.clone(),
+                    boost: root.boost,
+                })
+        });
+        for cached in directory.items {
+            if let Some(desktop_id) = cached.desktop_id {
+                if !seen_desktop_ids.insert(desktop_id) || cached.target.is_empty() {
+                    continue;
+                }
             }
-            cache.items
-        })
-        .unwrap_or_else(|| {
-            let mut built: Vec<LauncherItem> = Vec::new();
-            let mut built_seen: HashSet<String> = HashSet::new();
-
-            if config.enable_start_menu {
-                collect_start_menu_items(&mut built, &mut built_seen);
+            let Some(mut item) = cached_item_to_launcher(
+                &directory.source,
+                cached.label,
+                cached.target,
+                config,
+                &blacklist,
+            ) else {
+                continue;
+            };
+            if let Some(shortcut) = cached.shortcut {
+                item.set_shortcut(shortcut);
             }
-            if config.enable_path {
-                collect_path_items(&mut built, &mut built_seen, &blacklist);
+            item.set_keywords(cached.keywords);
+            if item.trailing_hint.is_none() {
+                item.trailing_hint = url_domain(&item.target);
             }
+            if !claim_targets(&mut seen_targets, &item) {
+                continue;
+            }
+            item.extra_source = extra_source.clone();
+            items.push(item);
+        }
+    }
 
-            write_index_cache(&built, silent_mode);
-            built
-        });
-
-    for item in indexed_items {
-        let dedupe = item.target.to_lowercase();
-        if !seen_targets.insert(dedupe) {
+    for bookmark in bookmark_files.into_iter().flat_map(|file| file.items) {
+        let mut item = LauncherItem::new(bookmark.label, bookmark.url, LauncherSource::Bookmark);
+        item.trailing_hint = Some(bookmark.folder)
+            .filter(|folder| !folder.is_empty())
+            .or_else(|| url_domain(&item.target));
+        if !claim_targets(&mut seen_targets, &item) {
             continue;
         }
         items.push(item);
     }
 
-    items
+    rules.apply(items)
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        build_blacklist_set, is_blacklisted_command_name, parse_legacy_index_cache,
-        source_from_cache, stable_fnv1a_64, windowsapps_alias_display_label,
+        bookmark_file_path, build_blacklist_set, claim_targets, core_secondary_actions,
+        directory_key, is_blacklisted_command_name, parse_internet_shortcut,
+        parse_legacy_index_cache, refresh_bookmark_files, refresh_index_directories,
+        source_from_cache, url_domain, windowsapps_alias_display_label, CachedDirectory, IndexRoot,
+        PreviousIndex, ShellLink,
     };
-    use crate::settings::RmenuConfig;
-    use std::path::Path;
+    use crate::app_state::{LauncherItem, LauncherItemActionKind, LauncherSource};
+    use crate::settings::{RmenuConfig, SourceRootConfig};
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     #[test]
     fn blacklist_detects_plain_and_path_variants() {
@@ -741,14 +1182,257 @@ mod tests {
         assert_eq!(parsed[0].label, "Code");
     }
 
+    fn temp_index_dir(name: &str) -> PathBuf {
+        let nonce = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time must be valid")
+            .as_nanos();
+        std::env::temp_dir().join(format!("rmenu-index-test-{name}-{nonce}"))
+    }
+
+    fn previous_from(directories: Vec<CachedDirectory>) -> PreviousIndex {
+        let mut previous = PreviousIndex::default();
+        for directory in directories {
+            previous
+                .directories
+                .insert(directory_key(Path::new(&directory.path)), directory);
+        }
+        previous
+    }
+
+    #[test]
+    fn index_refresh_rescans_only_changed_directories() {
+        let root = temp_index_dir("refresh");
+        let games = root.join("Games");
+        fs::create_dir_all(&games).expect("create index test dirs");
+        fs::write(root.join("Code.lnk"), "").expect("write shortcut");
+        fs::write(root.join("notes.txt"), "").expect("write text file");
+        fs::write(games.join("Solitaire.lnk"), "").expect("write shortcut");
+        let roots = [IndexRoot::builtin(
+            LauncherSource::StartMenu,
+            root.clone(),
+            usize::MAX,
+        )];
+
+        let (directories, rescanned) = refresh_index_directories(&roots, &PreviousIndex::default());
+        assert_eq!(rescanned, 2);
+        assert_eq!(directories.len(), 2);
+        assert_eq!(directories[0].items.len(), 1);
+        assert_eq!(directories[1].items[0].label, "Solitaire");
+
+        let mut previous = previous_from(directories);
+        let (_, rescanned) = refresh_index_directories(&roots, &previous);
+        assert_eq!(rescanned, 0);
+
+        fs::write(games.join("Chess.lnk"), "").expect("write shortcut");
+        if let Some(cached) = previous.directories.get_mut(&directory_key(&games)) {
+            cached.mtime_ms = 0;
+        }
+        previous.labels.insert(
+            games.join("Solitaire.lnk").to_string_lossy().to_lowercase(),
+            "Microsoft Solitaire".to_string(),
+        );
+        let (directories, rescanned) = refresh_index_directories(&roots, &previous);
+        assert_eq!(rescanned, 1);
+        let mut labels = directories[1]
+            .items
+            .iter()
+            .map(|item| item.label.as_str())
+            .collect::<Vec<_>>();
+        labels.sort_unstable();
+        assert_eq!(labels, ["Chess", "Microsoft Solitaire"]);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn extra_roots_apply_filters_depth_and_signature() {
+        let dir = temp_index_dir("extra");
+        let deep = dir.join("sub").join("deep");
+        fs::create_dir_all(&deep).expect("create index test dirs");
+        fs::create_dir_all(dir.join("node_modules")).expect("create index test dirs");
+        for file in ["a.exe", "b.ps1", "c.py", "setup.exe", "readme.md"] {
+            fs::write(dir.join(file), "").expect("write file");
+        }
+        fs::write(dir.join("sub").join("z.cmd"), "").expect("write file");
+        fs::write(deep.join("x.exe"), "").expect("write file");
+        fs::write(dir.join("node_modules").join("y.exe"), "").expect("write file");
+
+        let mut config = SourceRootConfig {
+            name: "tools".to_string(),
+            path: dir.clone(),
+            include: Vec::new(),
+            exclude: vec!["setup*".to_string(), "node_modules".to_string()],
+            max_depth: 1,
+            extensions: vec!["ps1".to_string()],
+            label: "Tools".to_string(),
+            boost: 100,
+        };
+        let roots = [IndexRoot::extra(&config)];
+        let (directories, _) = refresh_index_directories(&roots, &PreviousIndex::default());
+        let mut names = directories
+            .iter()
+            .flat_map(|directory| &directory.items)
+            .map(|item| item.label.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, ["a", "b", "z"]);
+        assert!(directories
+            .iter()
+            .all(|directory| directory.root.as_deref() == Some("tools")));
+
+        let mut previous = previous_from(directories);
+        previous
+            .root_signatures
+            .insert("tools".to_string(), roots[0].signature());
+        let (_, rescanned) = refresh_index_directories(&roots, &previous);
+        assert_eq!(rescanned, 0);
+
+        config.extensions.push("py".to_string());
+        let roots = [IndexRoot::extra(&config)];
+        let (directories, rescanned) = refresh_index_directories(&roots, &previous);
+        assert_eq!(rescanned, 2);
+        assert_eq!(directories[0].items.len(), 3);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
     #[test]
-    fn stable_path_hash_is_deterministic() {
-        let a = stable_fnv1a_64("c:/tools;c:/windows/system32");
-        let b = stable_fnv1a_64("c:/tools;c:/windows/system32");
-        let c = stable_fnv1a_64("c:/tools;c:/windows/system32;d:/extra");
+    fn internet_shortcuts_index_as_url_items() {
+        assert_eq!(
+            parse_internet_shortcut(
+                "[{000214A0-0000-0000-C000-000000000046}]\r\nProp3=19,11\r\n\
+                 [InternetShortcut]\r\nIDList=\r\nurl = https://wiki.corp.local/Home \r\n"
+            )
+            .as_deref(),
+            Some("https://wiki.corp.local/Home")
+        );
+        assert_eq!(
+            parse_internet_shortcut("[Other]\nURL=https://example.com"),
+            None
+        );
+        assert_eq!(parse_internet_shortcut("[InternetShortcut]\nURL="), None);
+
+        assert_eq!(
+            url_domain("https://user@Intranet.Corp:8443/tools?id=1").as_deref(),
+            Some("intranet.corp")
+        );
+        assert_eq!(url_domain("file:///C:/Docs/index.html"), None);
+        assert_eq!(url_domain("C:/Windows/notepad.exe"), None);
+
+        let dir = temp_index_dir("url");
+        fs::create_dir_all(&dir).expect("create index test dir");
+        fs::write(
+            dir.join("Team Wiki.url"),
+            "[InternetShortcut]\r\nURL=https://wiki.corp.local/\r\n",
+        )
+        .expect("write internet shortcut");
+        fs::write(dir.join("Broken.url"), "[InternetShortcut]\r\n").expect("write file");
+        let config = SourceRootConfig {
+            name: "desktop".to_string(),
+            path: dir.clone(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: 0,
+            extensions: Vec::new(),
+            label: "Desktop".to_string(),
+            boost: 0,
+        };
+        let (directories, _) =
+            refresh_index_directories(&[IndexRoot::extra(&config)], &PreviousIndex::default());
+        let items = &directories[0].items;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "Team Wiki");
+        assert_eq!(items[0].target, "https://wiki.corp.local/");
+
+        let _ = fs::remove_dir_all(dir);
+    }
 
-        assert_eq!(a, b);
-        assert_ne!(a, c);
+    #[test]
+    fn bookmark_files_are_reparsed_only_when_modified() {
+        let dir = temp_index_dir("bookmarks");
+        let profile = dir.join("Default");
+        fs::create_dir_all(&profile).expect("create profile dir");
+        let file = bookmark_file_path(&profile);
+        fs::write(
+            &file,
+            r#"{"roots": {"bookmark_bar": {"name": "Bar", "type": "folder", "children": [
+                {"name": "", "type": "url", "url": "https://ci.corp/builds"}
+            ]}}}"#,
+        )
+        .expect("write bookmarks");
+
+        let paths = [file.clone(), dir.join("missing.html")];
+        let (files, reparsed) = refresh_bookmark_files(&paths, &PreviousIndex::default());
+        assert_eq!(reparsed, 1);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].items[0].label, "ci.corp");
+        assert_eq!(files[0].items[0].folder, "Bar");
+
+        let mut previous = PreviousIndex::default();
+        previous
+            .bookmark_files
+            .insert(directory_key(&file), files[0].clone());
+        let (_, reparsed) = refresh_bookmark_files(&paths, &previous);
+        assert_eq!(reparsed, 0);
+
+        previous
+            .bookmark_files
+            .values_mut()
+            .for_each(|cached| cached.mtime_ms += 1);
+        let (files, reparsed) = refresh_bookmark_files(&paths, &previous);
+        assert_eq!(reparsed, 1);
+        assert_eq!(files[0].items.len(), 1);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn desktop_entries_index_with_ids_and_hidden_placeholders() {
+        let dir = temp_index_dir("xdg");
+        let user = dir.join("home").join("applications");
+        let system = dir.join("usr").join("applications");
+        fs::create_dir_all(&user).expect("create xdg dirs");
+        fs::create_dir_all(system.join("kde4")).expect("create xdg dirs");
+        let entry = |name: &str, extra: &str| {
+            format!("[Desktop Entry]\nType=Application\nName={name}\nExec={name} %U\n{extra}")
+        };
+        fs::write(user.join("editor.desktop"), entry("editor", "Hidden=true")).expect("write");
+        fs::write(system.join("editor.desktop"), entry("editor", "")).expect("write");
+        fs::write(
+            system.join("kde4").join("konsole.desktop"),
+            entry("konsole", "Keywords=terminal;shell;"),
+        )
+        .expect("write");
+
+        let roots = [
+            IndexRoot::builtin(LauncherSource::StartMenu, user.clone(), usize::MAX),
+            IndexRoot::builtin(LauncherSource::StartMenu, system.clone(), usize::MAX),
+        ];
+        let (directories, _) = refresh_index_directories(&roots, &PreviousIndex::default());
+        let mut items = directories
+            .iter()
+            .flat_map(|directory| &directory.items)
+            .map(|item| {
+                (
+                    item.desktop_id.as_deref().unwrap_or_default(),
+                    item.target.as_str(),
+                    item.keywords.len(),
+                )
+            })
+            .collect::<Vec<_>>();
+        items.sort_unstable();
+        assert_eq!(
+            items,
+            [
+                ("editor.desktop", "", 0),
+                ("editor.desktop", "editor", 0),
+                ("kde4-konsole.desktop", "konsole", 2),
+            ]
+        );
+        assert_eq!(directories[0].items[0].target, "");
+
+        let _ = fs::remove_dir_all(dir);
     }
 
     #[test]
@@ -756,4 +1440,107 @@ mod tests {
         let path = Path::new("C:/Users/test/AppData/Local/Microsoft/WindowsApps/mspaint.exe");
         assert_eq!(windowsapps_alias_display_label(path), Some("Paint"));
     }
+
+    #[test]
+    fn core_secondary_actions_depend_on_source_and_target() {
+        let history = LauncherItem::new(
+            "notepad".to_string(),
+            "notepad".to_string(),
+            LauncherSource::History,
+        );
+        let ids = core_secondary_actions(&history)
+            .into_iter()
+            .map(|action| action.id)
+            .collect::<Vec<_>>();
+        let expected: &[&str] = if cfg!(windows) {
+            &["run-as-admin", "copy-path", "remove-from-history"]
+        } else {
+            &["copy-path", "remove-from-history"]
+        };
+        assert_eq!(ids, expected);
+
+        let url = LauncherItem::new(
+            "docs".to_string(),
+            "https://example.com".to_string(),
+            LauncherSource::History,
+        );
+        let actions = core_secondary_actions(&url);
+        assert!(actions.iter().all(|action| action.id != "run-as-admin"));
+        assert_eq!(
+            actions
+                .iter()
+                .find(|action| action.id == "remove-from-history")
+                .map(|action| &action.kind),
+            Some(&LauncherItemActionKind::RemoveFromHistory(
+                "https://example.com".to_string()
+            ))
+        );
+
+        let direct = LauncherItem::new(
+            "line".to_string(),
+            "line".to_string(),
+            LauncherSource::Direct,
+        );
+        assert!(core_secondary_actions(&direct).is_empty());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn core_secondary_actions_open_the_parent_folder_without_runas() {
+        let app = LauncherItem::new(
+            "calc".to_string(),
+            "/usr/bin/calc".to_string(),
+            LauncherSource::Path,
+        );
+        let kinds = core_secondary_actions(&app)
+            .into_iter()
+            .map(|action| (action.id, action.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    "open-folder".to_string(),
+                    LauncherItemActionKind::Launch("/usr/bin".to_string())
+                ),
+                (
+                    "copy-path".to_string(),
+                    LauncherItemActionKind::Copy("/usr/bin/calc".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn shortcuts_dedupe_and_match_by_resolved_target() {
+        let mut shortcut = LauncherItem::new(
+            "Text Editor".to_string(),
+            "C:/Start Menu/Programs/Text Editor.lnk".to_string(),
+            LauncherSource::StartMenu,
+        );
+        shortcut.set_shortcut(ShellLink {
+            target: Some("C:/Windows/System32/notepad.exe".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(shortcut.target_name, "notepad");
+        assert_eq!(
+            shortcut.resolved_target(),
+            "C:/Windows/System32/notepad.exe"
+        );
+
+        let mut seen = HashSet::new();
+        assert!(claim_targets(&mut seen, &shortcut));
+        let path_exe = LauncherItem::new(
+            "notepad".to_string(),
+            "C:/Windows/System32/NOTEPAD.EXE".to_string(),
+            LauncherSource::Path,
+        );
+        assert!(!claim_targets(&mut seen, &path_exe));
+        let same_link = LauncherItem::new(
+            "Text Editor".to_string(),
+            "c:/start menu/programs/text editor.lnk".to_string(),
+            LauncherSource::History,
+        );
+        assert!(!claim_targets(&mut seen, &same_link));
+    }
 }
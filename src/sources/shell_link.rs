@@ -0,0 +1,422 @@
+//! Reader for Windows Shell Link (`.lnk`) files, following [MS-SHLLINK].
+//!
+//! Only the parts needed to resolve a shortcut are decoded: the LinkInfo
+//! local or network path, the string data section, and the environment
+//! variable block. Item ID lists are skipped, so shortcuts that only point
+//! into the shell namespace (Control Panel items, packaged apps) resolve to
+//! no target.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const HEADER_SIZE: usize = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+// Shortcuts are a few KiB at most; anything larger is not worth parsing.
+const MAX_LINK_FILE_BYTES: u64 = 1024 * 1024;
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x0000_0001;
+const HAS_LINK_INFO: u32 = 0x0000_0002;
+const HAS_NAME: u32 = 0x0000_0004;
+const HAS_RELATIVE_PATH: u32 = 0x0000_0008;
+const HAS_WORKING_DIR: u32 = 0x0000_0010;
+const HAS_ARGUMENTS: u32 = 0x0000_0020;
+const HAS_ICON_LOCATION: u32 = 0x0000_0040;
+const IS_UNICODE: u32 = 0x0000_0080;
+const FORCE_NO_LINK_INFO: u32 = 0x0000_0100;
+
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+const COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX: u32 = 0x2;
+
+const ENVIRONMENT_VARIABLE_BLOCK: u32 = 0xA000_0001;
+
+/// What a `.lnk` file points at.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShellLink {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon_location: Option<String>,
+}
+
+pub fn read_shell_link(path: &Path) -> Option<ShellLink> {
+    if fs::metadata(path).ok()?.len() > MAX_LINK_FILE_BYTES {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    let mut link = parse_shell_link(&bytes)?;
+
+    // A target only given relative to the shortcut is resolved against the
+    // folder holding the `.lnk`.
+    if let Some(target) = link.target.as_deref().filter(|t| is_relative(t)) {
+        link.target = path
+            .parent()
+            .map(|dir| normalize_path(&dir.join(target).to_string_lossy()));
+    }
+    Some(link)
+}
+
+pub fn parse_shell_link(bytes: &[u8]) -> Option<ShellLink> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.u32()? as usize != HEADER_SIZE || bytes.get(4..20)? != LINK_CLSID {
+        return None;
+    }
+    let flags = reader.u32_at(20)?;
+    reader.pos = HEADER_SIZE;
+
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        let id_list_size = reader.u16()? as usize;
+        reader.skip(id_list_size)?;
+    }
+
+    let mut target = None;
+    if flags & HAS_LINK_INFO != 0 {
+        let start = reader.pos;
+        let size = reader.u32_at(start)? as usize;
+        let info = bytes.get(start..start.checked_add(size)?)?;
+        if flags & FORCE_NO_LINK_INFO == 0 {
+            target = link_info_target(info);
+        }
+        reader.skip(size)?;
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    let mut string_data = |flag: u32| -> Option<Option<String>> {
+        if flags & flag == 0 {
+            return Some(None);
+        }
+        let value = reader.counted_string(unicode)?;
+        Some(Some(value).filter(|value| !value.is_empty()))
+    };
+    let description = string_data(HAS_NAME)?;
+    let relative_path = string_data(HAS_RELATIVE_PATH)?;
+    let working_dir = string_data(HAS_WORKING_DIR)?;
+    let arguments = string_data(HAS_ARGUMENTS)?;
+    let icon_location = string_data(HAS_ICON_LOCATION)?;
+
+    let environment_target = environment_variable_target(&bytes[reader.pos..]);
+    let target = target
+        .or(environment_target)
+        .or(relative_path)
+        .map(|value| normalize_path(&value));
+
+    Some(ShellLink {
+        target,
+        arguments,
+        working_dir: working_dir.map(|value| expand_environment_strings(&value)),
+        description,
+        icon_location: icon_location.map(|value| expand_environment_strings(&value)),
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn u16(&mut self) -> Option<u16> {
+        let value = u16::from_le_bytes(self.bytes.get(self.pos..self.pos + 2)?.try_into().ok()?);
+        self.pos += 2;
+        Some(value)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let value = self.u32_at(self.pos)?;
+        self.pos += 4;
+        Some(value)
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        Some(u32::from_le_bytes(
+            self.bytes
+                .get(offset..offset.checked_add(4)?)?
+                .try_into()
+                .ok()?,
+        ))
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        let end = self.pos.checked_add(len)?;
+        (end <= self.bytes.len()).then(|| self.pos = end)
+    }
+
+    /// StringData entry: a character count followed by UTF-16 or ANSI text.
+    fn counted_string(&mut self, unicode: bool) -> Option<String> {
+        let count = self.u16()? as usize;
+        let len = if unicode { count * 2 } else { count };
+        let raw = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(if unicode {
+            decode_utf16(raw)
+        } else {
+            decode_ansi(raw)
+        })
+    }
+}
+
+fn link_info_target(info: &[u8]) -> Option<String> {
+    let field = |offset: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(info.get(offset..offset + 4)?.try_into().ok()?) as usize)
+    };
+    let header_size = field(4)?;
+    let link_flags = field(8)? as u32;
+    let suffix = if header_size >= 0x24 {
+        nul_terminated_utf16(info, field(0x20)?)
+    } else {
+        None
+    }
+    .or_else(|| nul_terminated_ansi(info, field(0x18)?))
+    .unwrap_or_default();
+
+    if link_flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        let base = if header_size >= 0x24 {
+            nul_terminated_utf16(info, field(0x1C)?)
+        } else {
+            None
+        }
+        .or_else(|| nul_terminated_ansi(info, field(0x10)?))?;
+        return Some(join_link_path(&base, &suffix));
+    }
+
+    if link_flags & COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX != 0 {
+        let network = info.get(field(0x14)?..)?;
+        let net_name_offset = u32::from_le_bytes(network.get(8..12)?.try_into().ok()?) as usize;
+        let net_name = if net_name_offset > 0x14 {
+            let unicode_offset =
+                u32::from_le_bytes(network.get(0x14..0x18)?.try_into().ok()?) as usize;
+            nul_terminated_utf16(network, unicode_offset)
+        } else {
+            None
+        }
+        .or_else(|| nul_terminated_ansi(network, net_name_offset))?;
+        return Some(join_link_path(&net_name, &suffix));
+    }
+
+    None
+}
+
+fn join_link_path(base: &str, suffix: &str) -> String {
+    if suffix.is_empty() || base.ends_with('\\') {
+        format!("{base}{suffix}")
+    } else {
+        format!("{base}\\{suffix}")
+    }
+}
+
+/// Target from the EnvironmentVariableDataBlock in the extra data section,
+/// used by shortcuts such as `%windir%\system32\notepad.exe`.
+fn environment_variable_target(mut extra: &[u8]) -> Option<String> {
+    while extra.len() >= 8 {
+        let size = u32::from_le_bytes(extra[0..4].try_into().ok()?) as usize;
+        if size < 8 || size > extra.len() {
+            return None;
+        }
+        let signature = u32::from_le_bytes(extra[4..8].try_into().ok()?);
+        if signature == ENVIRONMENT_VARIABLE_BLOCK {
+            let block = &extra[..size];
+            let target = nul_terminated_utf16(block, 8 + 260)
+                .filter(|value| !value.is_empty())
+                .or_else(|| nul_terminated_ansi(block, 8))?;
+            return Some(expand_environment_strings(&target));
+        }
+        extra = &extra[size..];
+    }
+    None
+}
+
+fn nul_terminated_ansi(bytes: &[u8], offset: usize) -> Option<String> {
+    let raw = bytes.get(offset..)?;
+    let end = raw.iter().position(|&b| b == 0)?;
+    Some(decode_ansi(&raw[..end]))
+}
+
+fn nul_terminated_utf16(bytes: &[u8], offset: usize) -> Option<String> {
+    let raw = bytes.get(offset..)?;
+    let end = raw
+        .chunks_exact(2)
+        .position(|pair| pair == [0, 0])
+        .map(|units| units * 2)?;
+    Some(decode_utf16(&raw[..end]))
+}
+
+fn decode_utf16(raw: &[u8]) -> String {
+    let units = raw
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units)
+}
+
+/// ANSI strings use the system code page; Latin-1 is a close enough reading
+/// for paths, which are almost always ASCII.
+fn decode_ansi(raw: &[u8]) -> String {
+    raw.iter().map(|&b| char::from(b)).collect()
+}
+
+fn is_relative(path: &str) -> bool {
+    !(path.starts_with("\\\\") || path.get(1..3) == Some(":\\") || path.get(1..3) == Some(":/"))
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.replace('/', "\\");
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('\\') {
+        match part {
+            "." => {}
+            ".." if parts
+                .last()
+                .is_some_and(|last| !last.is_empty() && *last != ".." && !last.ends_with(':')) =>
+            {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("\\")
+}
+
+/// Expands `%NAME%` references from the process environment; unknown names
+/// are kept as written.
+fn expand_environment_strings(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) if end > 0 => {
+                let name = &after[..end];
+                match std::env::var(name) {
+                    Ok(expanded) => out.push_str(&expanded),
+                    Err(_) => {
+                        out.push('%');
+                        out.push_str(name);
+                        out.push('%');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                out.push('%');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_shell_link, ShellLink, HEADER_SIZE, LINK_CLSID};
+
+    fn header(flags: u32) -> Vec<u8> {
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        bytes[4..20].copy_from_slice(&LINK_CLSID);
+        bytes[20..24].copy_from_slice(&flags.to_le_bytes());
+        bytes
+    }
+
+    fn counted_utf16(value: &str) -> Vec<u8> {
+        let units = value.encode_utf16().collect::<Vec<_>>();
+        let mut bytes = (units.len() as u16).to_le_bytes().to_vec();
+        bytes.extend(units.iter().flat_map(|unit| unit.to_le_bytes()));
+        bytes
+    }
+
+    fn local_link_info(base: &str) -> Vec<u8> {
+        // LinkInfo header (0x1C bytes), an empty VolumeID, the base path and
+        // an empty common path suffix.
+        let volume_offset = 0x1C;
+        let base_offset = volume_offset + 0x10;
+        let suffix_offset = base_offset + base.len() + 1;
+        let size = suffix_offset + 1;
+        let mut info = Vec::new();
+        for value in [
+            size,
+            0x1C,
+            0x1,
+            volume_offset,
+            base_offset,
+            0,
+            suffix_offset,
+        ] {
+            info.extend((value as u32).to_le_bytes());
+        }
+        info.extend((0x10u32).to_le_bytes());
+        info.extend([0u8; 12]);
+        info.extend(base.as_bytes());
+        info.extend([0, 0]);
+        info
+    }
+
+    #[test]
+    fn link_info_target_and_string_data_are_read() {
+        // HasLinkTargetIDList | HasLinkInfo | HasName | HasWorkingDir |
+        // HasArguments | HasIconLocation | IsUnicode
+        let mut bytes = header(0x1 | 0x2 | 0x4 | 0x10 | 0x20 | 0x40 | 0x80);
+        bytes.extend(4u16.to_le_bytes());
+        bytes.extend([0xAA; 4]);
+        bytes.extend(local_link_info("C:\\Program Files\\Editor\\editor.exe"));
+        bytes.extend(counted_utf16("Edit text files"));
+        bytes.extend(counted_utf16("C:\\Users\\me"));
+        bytes.extend(counted_utf16("--new-window"));
+        bytes.extend(counted_utf16("C:\\Program Files\\Editor\\editor.exe,0"));
+        bytes.extend([0u8; 4]);
+
+        assert_eq!(
+            parse_shell_link(&bytes),
+            Some(ShellLink {
+                target: Some("C:\\Program Files\\Editor\\editor.exe".to_string()),
+                arguments: Some("--new-window".to_string()),
+                working_dir: Some("C:\\Users\\me".to_string()),
+                description: Some("Edit text files".to_string()),
+                icon_location: Some("C:\\Program Files\\Editor\\editor.exe,0".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn environment_block_and_relative_paths_are_fallbacks() {
+        std::env::set_var("RMENU_LNK_TEST_ROOT", "C:\\Windows");
+        // HasRelativePath | IsUnicode, then an EnvironmentVariableDataBlock.
+        let mut bytes = header(0x8 | 0x80);
+        bytes.extend(counted_utf16("..\\..\\notepad.exe"));
+        let mut block = vec![0u8; 0x314];
+        block[0..4].copy_from_slice(&0x314u32.to_le_bytes());
+        block[4..8].copy_from_slice(&0xA000_0001u32.to_le_bytes());
+        let target = "%RMENU_LNK_TEST_ROOT%\\system32\\notepad.exe";
+        for (index, unit) in target.encode_utf16().enumerate() {
+            block[8 + 260 + index * 2..8 + 260 + index * 2 + 2]
+                .copy_from_slice(&unit.to_le_bytes());
+        }
+        bytes.extend(block);
+        bytes.extend([0u8; 4]);
+
+        let link = parse_shell_link(&bytes).expect("valid link");
+        assert_eq!(
+            link.target.as_deref(),
+            Some("C:\\Windows\\system32\\notepad.exe")
+        );
+
+        let mut relative_only = header(0x8 | 0x80);
+        relative_only.extend(counted_utf16("..\\tools\\.\\run.cmd"));
+        let link = parse_shell_link(&relative_only).expect("valid link");
+        assert_eq!(link.target.as_deref(), Some("..\\tools\\run.cmd"));
+
+        assert_eq!(parse_shell_link(b"not a shortcut"), None);
+        let mut truncated = header(0x2);
+        truncated.extend(100u32.to_le_bytes());
+        assert_eq!(parse_shell_link(&truncated), None);
+    }
+}
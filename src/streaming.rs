@@ -0,0 +1,327 @@
+use crate::app_state::{AppState, LauncherItem, LauncherSource};
+use crate::ranking::{query_narrows, rank_dataset_parallel, scoring_state};
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const STREAM_BATCH_MAX_LINES: usize = 4096;
+const RANK_MAX_THREADS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct StreamingResult {
+    pub generation: u64,
+    pub query: String,
+    pub items: Vec<LauncherItem>,
+}
+
+struct RankJob {
+    generation: u64,
+    query: String,
+    case_sensitive: bool,
+    scoring: AppState,
+}
+
+#[derive(Default)]
+struct MatchCache {
+    query: String,
+    case_sensitive: bool,
+    scanned: usize,
+    matched: Vec<usize>,
+}
+
+impl MatchCache {
+    /// Previous matches plus every line appended since they were computed.
+    fn candidates(&self, job: &RankJob, len: usize) -> Option<Vec<usize>> {
+        if self.case_sensitive != job.case_sensitive || !query_narrows(&self.query, &job.query) {
+            return None;
+        }
+        let mut candidates = self.matched.clone();
+        candidates.extend(self.scanned..len);
+        Some(candidates)
+    }
+}
+
+struct Shared {
+    /// Lines read but not yet taken over by the ranking worker, which owns the
+    /// full list so scoring never holds a lock the reader needs.
+    pending: Mutex<Vec<LauncherItem>>,
+    received: AtomicUsize,
+    generation: AtomicU64,
+    result: Mutex<Option<StreamingResult>>,
+}
+
+/// Items streamed from a reader thread plus a background ranking worker, so
+/// the window can open before input ends and typing never waits on scoring.
+pub struct StreamingRanker {
+    shared: Arc<Shared>,
+    jobs: Sender<RankJob>,
+    seen: AtomicUsize,
+    active_query: Mutex<Option<(String, bool, AppState)>>,
+}
+
+fn rank_threads() -> usize {
+    thread::available_parallelism()
+        .map_or(2, |count| count.get())
+        .min(RANK_MAX_THREADS)
+}
+
+fn read_lines<R: Read>(reader: R, shared: &Shared, silent_mode: bool) {
+    let mut reader = BufReader::new(reader);
+    let mut batch = Vec::new();
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let done = match reader.read_until(b'\n', &mut line) {
+            Ok(0) => true,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim();
+                if !text.is_empty() {
+                    batch.push(LauncherItem::new(
+                        text.to_string(),
+                        text.to_string(),
+                        LauncherSource::Direct,
+                    ));
+                }
+                false
+            }
+            Err(e) => {
+                if !silent_mode {
+                    eprintln!("Error reading from stdin: {}", e);
+                }
+                true
+            }
+        };
+
+        // Publish whenever the next read could block, so slow producers show up
+        // line by line while fast ones are appended in large batches.
+        if !batch.is_empty()
+            && (done || batch.len() >= STREAM_BATCH_MAX_LINES || reader.buffer().is_empty())
+        {
+            let appended = batch.len();
+            shared.pending.lock().unwrap().append(&mut batch);
+            shared.received.fetch_add(appended, Ordering::AcqRel);
+        }
+        if done {
+            return;
+        }
+    }
+}
+
+fn run_rank_worker(shared: &Shared, jobs: Receiver<RankJob>) {
+    let threads = rank_threads();
+    let mut cache = MatchCache::default();
+    let mut items = Vec::new();
+
+    while let Ok(mut job) = jobs.recv() {
+        while let Ok(next) = jobs.try_recv() {
+            job = next;
+        }
+        if shared.generation.load(Ordering::Acquire) != job.generation {
+            continue;
+        }
+
+        items.append(&mut std::mem::take(&mut *shared.pending.lock().unwrap()));
+        let len = items.len();
+        if job.query.is_empty() {
+            // Every line in arrival order, like a non-streamed stdin list.
+            cache = MatchCache::default();
+            *shared.result.lock().unwrap() = Some(StreamingResult {
+                generation: job.generation,
+                query: job.query,
+                items: items.clone(),
+            });
+            continue;
+        }
+
+        let candidates = cache.candidates(&job, len);
+        let cancelled = || shared.generation.load(Ordering::Relaxed) != job.generation;
+        let Some((ranked, matched)) = rank_dataset_parallel(
+            &job.scoring,
+            &items,
+            candidates.as_deref(),
+            &job.query,
+            job.case_sensitive,
+            threads,
+            &cancelled,
+        ) else {
+            continue;
+        };
+
+        let top = ranked
+            .iter()
+            .map(|entry| items[entry.index].clone())
+            .collect();
+
+        cache = MatchCache {
+            query: job.query.clone(),
+            case_sensitive: job.case_sensitive,
+            scanned: len,
+            matched,
+        };
+        *shared.result.lock().unwrap() = Some(StreamingResult {
+            generation: job.generation,
+            query: job.query,
+            items: top,
+        });
+    }
+}
+
+impl StreamingRanker {
+    pub fn from_stdin(silent_mode: bool) -> Self {
+        Self::spawn(io::stdin(), silent_mode)
+    }
+
+    pub fn spawn<R: Read + Send + 'static>(reader: R, silent_mode: bool) -> Self {
+        let shared = Arc::new(Shared {
+            pending: Mutex::new(Vec::new()),
+            received: AtomicUsize::new(0),
+            generation: AtomicU64::new(0),
+            result: Mutex::new(None),
+        });
+        let (jobs, job_receiver) = mpsc::channel();
+
+        let reader_shared = Arc::clone(&shared);
+        thread::spawn(move || read_lines(reader, &reader_shared, silent_mode));
+        let worker_shared = Arc::clone(&shared);
+        thread::spawn(move || run_rank_worker(&worker_shared, job_receiver));
+
+        Self {
+            shared,
+            jobs,
+            seen: AtomicUsize::new(0),
+            active_query: Mutex::new(None),
+        }
+    }
+
+    /// Starts ranking the current input, cancelling any job still in flight. An
+    /// empty input lists every line received so far.
+    pub fn submit(&self, app_state: &AppState, case_sensitive: bool) {
+        let generation = self.shared.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        let query = app_state.current_input.clone();
+        let scoring = scoring_state(app_state);
+        *self.active_query.lock().unwrap() = Some((query.clone(), case_sensitive, scoring.clone()));
+        let _ = self.jobs.send(RankJob {
+            generation,
+            query,
+            case_sensitive,
+            scoring,
+        });
+    }
+
+    /// Re-ranks the active query to pick up newly streamed lines without
+    /// cancelling the job in flight.
+    pub fn refresh(&self) {
+        let active = self.active_query.lock().unwrap();
+        let Some((query, case_sensitive, scoring)) = active.as_ref() else {
+            return;
+        };
+        let _ = self.jobs.send(RankJob {
+            generation: self.shared.generation.load(Ordering::Acquire),
+            query: query.clone(),
+            case_sensitive: *case_sensitive,
+            scoring: scoring.clone(),
+        });
+    }
+
+    pub fn cancel(&self) {
+        self.shared.generation.fetch_add(1, Ordering::AcqRel);
+        *self.active_query.lock().unwrap() = None;
+    }
+
+    /// True once per batch of lines appended since the previous call.
+    pub fn take_arrivals(&self) -> bool {
+        let received = self.shared.received.load(Ordering::Acquire);
+        self.seen.swap(received, Ordering::AcqRel) != received
+    }
+
+    /// Latest finished ranking for the most recent submitted query.
+    pub fn take_result(&self) -> Option<StreamingResult> {
+        let result = self.shared.result.lock().unwrap().take()?;
+        (result.generation == self.shared.generation.load(Ordering::Acquire)).then_some(result)
+    }
+
+    /// Number of lines read so far.
+    pub fn received(&self) -> usize {
+        self.shared.received.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamingRanker;
+    use crate::app_state::AppState;
+    use std::io::{Cursor, Write};
+    use std::thread::sleep;
+    use std::time::{Duration, Instant};
+
+    fn wait_for<T>(mut poll: impl FnMut() -> Option<T>) -> T {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let Some(value) = poll() {
+                return value;
+            }
+            assert!(Instant::now() < deadline, "timed out waiting for stream");
+            sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn streamed_lines_are_ranked_in_the_background() {
+        let input = (0..10_000)
+            .map(|index| format!("logs/{}/entry_{index}.txt\n", index % 9))
+            .collect::<String>();
+        let ranker = StreamingRanker::spawn(Cursor::new(input.into_bytes()), true);
+        wait_for(|| (ranker.received() == 10_000).then_some(()));
+        assert!(ranker.take_arrivals());
+        assert!(!ranker.take_arrivals());
+
+        let mut state = AppState {
+            current_input: "entry_99".to_string(),
+            ..Default::default()
+        };
+        ranker.submit(&state, false);
+        state.current_input = "entry_999".to_string();
+        ranker.submit(&state, false);
+
+        let result = wait_for(|| ranker.take_result());
+        assert_eq!(result.query, "entry_999");
+        assert_eq!(result.items[0].label, "logs/0/entry_999.txt");
+
+        ranker.refresh();
+        let refreshed = wait_for(|| ranker.take_result());
+        assert_eq!(refreshed.items.len(), result.items.len());
+        ranker.cancel();
+        assert!(ranker.take_result().is_none());
+    }
+
+    #[test]
+    fn empty_query_lists_lines_that_arrive_later() {
+        let (reader, mut writer) = std::io::pipe().expect("pipe");
+        let ranker = StreamingRanker::spawn(reader, true);
+        writeln!(writer, "first").expect("write");
+        wait_for(|| (ranker.received() == 1).then_some(()));
+
+        ranker.submit(&AppState::default(), false);
+        let result = wait_for(|| ranker.take_result());
+        assert_eq!(result.items.len(), 1);
+
+        writeln!(writer, "second").expect("write");
+        let labels = wait_for(|| {
+            if ranker.take_arrivals() {
+                ranker.refresh();
+            }
+            ranker
+                .take_result()
+                .filter(|result| result.items.len() == 2)
+        })
+        .items
+        .into_iter()
+        .map(|item| item.label)
+        .collect::<Vec<_>>();
+        assert_eq!(labels, ["first", "second"]);
+    }
+}
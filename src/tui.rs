@@ -0,0 +1,625 @@
+//! Terminal frontend. Feeds keys through the same `LauncherController` as the
+//! Win32 window, and renders the input bar and result rows as character cells
+//! so it works over SSH, in WSL, on Linux, and in tests.
+
+use crate::app_state::{AppState, LauncherItem, LauncherItemTone};
+use crate::controller::{LauncherController, LauncherOutcome, LauncherPlatform};
+use crate::modules::{
+    input_accessory_text,
+    types::{InputAccessoryKind, ModuleInputAccessory, ModuleKeyEvent},
+    ModuleRuntime,
+};
+use crate::row_layout::{
+    compact_target_hint, compute_row_zones, match_highlight_positions, truncate_with_ellipsis_end,
+};
+use crate::schemes::SchemeRegistry;
+use crate::settings::{RgbColor, RmenuConfig};
+use crate::sources::persist_history_entry;
+use crate::streaming::StreamingRanker;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use std::io::{self, Write};
+use std::time::Duration;
+
+const INPUT_PLACEHOLDER_TEXT: &str = concat!("rMenu ", env!("CARGO_PKG_VERSION"));
+const PADDING: usize = 1;
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Platform services the terminal frontend cannot provide itself.
+#[derive(Clone, Copy)]
+pub struct TuiPlatform {
+    pub launch_target: fn(&SchemeRegistry, &str) -> io::Result<()>,
+    pub copy_text: fn(&str) -> io::Result<()>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuiStyle {
+    Normal,
+    Placeholder,
+    Highlight,
+    Accessory(InputAccessoryKind),
+    Tone(LauncherItemTone),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TuiSpan {
+    pub col: usize,
+    pub text: String,
+    pub style: TuiStyle,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TuiLine {
+    pub selected: bool,
+    pub spans: Vec<TuiSpan>,
+}
+
+impl TuiLine {
+    /// The line as plain text, `width` cells wide.
+    pub fn text(&self, width: usize) -> String {
+        let mut cells = vec![' '; width];
+        for span in &self.spans {
+            for (offset, ch) in span.text.chars().enumerate() {
+                if let Some(cell) = cells.get_mut(span.col + offset) {
+                    *cell = ch;
+                }
+            }
+        }
+        cells.into_iter().collect::<String>().trim_end().to_string()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TuiFrame {
+    pub lines: Vec<TuiLine>,
+    pub cursor_col: usize,
+}
+
+pub struct TuiSession {
+    controller: LauncherController,
+}
+
+impl TuiSession {
+    pub fn new(
+        config: RmenuConfig,
+        app_state: AppState,
+        runtime: ModuleRuntime,
+        platform: TuiPlatform,
+    ) -> Self {
+        let platform = LauncherPlatform {
+            copy_text: platform.copy_text,
+            ..LauncherPlatform::headless()
+        };
+        Self {
+            controller: LauncherController::new(config, app_state, runtime, platform),
+        }
+    }
+
+    pub fn app_state(&self) -> &AppState {
+        self.controller.app_state()
+    }
+
+    /// Runs `on_load` and the initial matching pass, like the window does
+    /// before it is shown.
+    pub fn start(&mut self) {
+        self.controller.start();
+    }
+
+    pub fn shutdown(&mut self) {
+        self.controller.shutdown();
+    }
+
+    /// Rows left for results below the input bar.
+    pub fn set_viewport_rows(&mut self, rows: usize) {
+        self.controller.set_viewport_rows(rows);
+    }
+
+    /// Re-ranks after a module hot reload. Returns true when it happened.
+    pub fn poll_hot_reload(&mut self) -> bool {
+        self.controller.poll_hot_reload()
+    }
+
+    /// Applies streamed stdin results. Returns true when they changed.
+    pub fn poll_streaming(&mut self) -> bool {
+        self.controller.poll_streaming()
+    }
+
+    /// Result rows currently in view.
+    pub fn visible_items(&self) -> &[LauncherItem] {
+        self.controller.visible_items()
+    }
+
+    pub fn input_accessory(&self) -> Option<ModuleInputAccessory> {
+        self.controller.input_accessory()
+    }
+
+    /// Feeds a terminal key press to the controller, then its character when
+    /// it typed one.
+    pub fn handle_key(&mut self, key: KeyEvent) -> LauncherOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && key.code == KeyCode::Char('c') {
+            return LauncherOutcome::Exit(1);
+        }
+        let mut event = module_key_event(&key);
+        // Terminals without keyboard enhancements send Ctrl+Enter as Ctrl+J.
+        if ctrl && key.code == KeyCode::Char('j') {
+            event.key = "enter".to_string();
+        }
+
+        match (self.controller.handle_key(&event), key.code) {
+            (LauncherOutcome::Continue, KeyCode::Char(ch))
+                if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.controller.insert_char(ch)
+            }
+            (outcome, _) => outcome,
+        }
+    }
+
+    /// Lays out the input bar and visible result rows in `width` x `height`
+    /// cells.
+    pub fn render(&self, width: usize, height: usize) -> TuiFrame {
+        let mut frame = TuiFrame::default();
+        if width == 0 || height == 0 {
+            return frame;
+        }
+        let app_state = self.controller.app_state();
+        let config = self.controller.config();
+
+        let mut input_line = TuiLine::default();
+        let mut x_offset = PADDING;
+        if let Some(prompt) = &app_state.prompt {
+            let prompt_text = format!("{prompt}: ");
+            let prompt_w = prompt_text.chars().count();
+            input_line.spans.push(TuiSpan {
+                col: x_offset,
+                text: prompt_text,
+                style: TuiStyle::Normal,
+            });
+            x_offset += prompt_w;
+        }
+
+        let input_max = width.saturating_sub(x_offset + PADDING);
+        if app_state.current_input.is_empty() {
+            input_line.spans.push(TuiSpan {
+                col: x_offset,
+                text: truncate_with_ellipsis_end(INPUT_PLACEHOLDER_TEXT, input_max),
+                style: TuiStyle::Placeholder,
+            });
+            frame.cursor_col = x_offset;
+        } else {
+            let input_chars = app_state.current_input.chars().collect::<Vec<_>>();
+            let shown = &input_chars[input_chars.len().saturating_sub(input_max)..];
+            input_line.spans.push(TuiSpan {
+                col: x_offset,
+                text: shown.iter().collect(),
+                style: TuiStyle::Normal,
+            });
+            frame.cursor_col = x_offset + shown.len();
+        }
+
+        if let Some(accessory) = self.input_accessory() {
+            let input_end = frame.cursor_col + PADDING;
+            let max_chars = width.saturating_sub(input_end + PADDING);
+            if max_chars >= 6 {
+                let text = truncate_with_ellipsis_end(&input_accessory_text(&accessory), max_chars);
+                let col = (width - PADDING - text.chars().count()).max(input_end);
+                input_line.spans.push(TuiSpan {
+                    col,
+                    text,
+                    style: TuiStyle::Accessory(accessory.kind),
+                });
+            }
+        }
+        frame.lines.push(input_line);
+
+        let visible_rows = self.controller.max_visible_items().min(height - 1);
+        let visible_end =
+            (app_state.scroll_offset + visible_rows).min(app_state.matching_items.len());
+        for item_index in app_state.scroll_offset..visible_end {
+            let item = &app_state.matching_items[item_index];
+            let chip = item
+                .quick_select_key
+                .as_ref()
+                .map(|key| format!("[{key}]"))
+                .or_else(|| item.trailing_badge.clone());
+            let default_hint = compact_target_hint(&item.target);
+            let row = compute_row_zones(
+                &item.label,
+                item.trailing_hint
+                    .as_deref()
+                    .unwrap_or(default_hint.as_str()),
+                chip,
+                PADDING as i32,
+                width.saturating_sub(PADDING) as i32,
+                1,
+            );
+
+            let mut line = TuiLine {
+                selected: item_index == app_state.selected_index,
+                spans: Vec::new(),
+            };
+            let highlights = match_highlight_positions(
+                app_state,
+                &row.left_text,
+                config.behavior.case_sensitive,
+                &config.scopes,
+            );
+            push_highlighted_spans(&mut line, PADDING, &row.left_text, &highlights);
+            if !row.right_text.is_empty() {
+                line.spans.push(TuiSpan {
+                    col: row.right_x.max(0) as usize,
+                    text: row.right_text,
+                    style: TuiStyle::Normal,
+                });
+            }
+            if let Some(chip) = row.chip_text {
+                line.spans.push(TuiSpan {
+                    col: row.chip_x.max(PADDING as i32 + 1) as usize,
+                    text: chip,
+                    style: item
+                        .trailing_badge_tone
+                        .map_or(TuiStyle::Normal, TuiStyle::Tone),
+                });
+            }
+            frame.lines.push(line);
+        }
+
+        frame
+    }
+}
+
+fn push_highlighted_spans(line: &mut TuiLine, col: usize, text: &str, positions: &[usize]) {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut start = 0;
+    while start < chars.len() {
+        let highlighted = positions.contains(&start);
+        let mut end = start + 1;
+        while end < chars.len() && positions.contains(&end) == highlighted {
+            end += 1;
+        }
+        line.spans.push(TuiSpan {
+            col: col + start,
+            text: chars[start..end].iter().collect(),
+            style: if highlighted {
+                TuiStyle::Highlight
+            } else {
+                TuiStyle::Normal
+            },
+        });
+        start = end;
+    }
+}
+
+/// Key names follow the Win32 frontend so modules see the same events.
+fn module_key_event(key: &KeyEvent) -> ModuleKeyEvent {
+    let name = match key.code {
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "escape".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::F(number) => format!("f{number}"),
+        KeyCode::Char(ch) => ch.to_lowercase().to_string(),
+        other => format!("{other:?}").to_ascii_lowercase(),
+    };
+    ModuleKeyEvent {
+        key: name,
+        ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+        alt: key.modifiers.contains(KeyModifiers::ALT),
+        shift: key.modifiers.contains(KeyModifiers::SHIFT),
+        meta: key.modifiers.contains(KeyModifiers::SUPER),
+    }
+}
+
+fn terminal_color(color: RgbColor) -> Color {
+    Color::Rgb {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+fn blend(left: RgbColor, right: RgbColor, right_weight: u16) -> RgbColor {
+    let mix =
+        |l: u8, r: u8| ((l as u16 * (100 - right_weight) + r as u16 * right_weight) / 100) as u8;
+    RgbColor::new(
+        mix(left.r, right.r),
+        mix(left.g, right.g),
+        mix(left.b, right.b),
+    )
+}
+
+fn tone_color(tone: LauncherItemTone) -> RgbColor {
+    match tone {
+        LauncherItemTone::Success => RgbColor::new(0x90, 0xD8, 0x90),
+        LauncherItemTone::Warning => RgbColor::new(0xE0, 0xB0, 0x30),
+        LauncherItemTone::Danger => RgbColor::new(0xE8, 0x50, 0x40),
+    }
+}
+
+fn style_color(style: TuiStyle, foreground: RgbColor, config: &RmenuConfig) -> RgbColor {
+    match style {
+        TuiStyle::Normal => foreground,
+        TuiStyle::Placeholder => blend(config.colors.background, config.colors.foreground, 45),
+        TuiStyle::Highlight => config.colors.match_highlight,
+        TuiStyle::Accessory(InputAccessoryKind::Info | InputAccessoryKind::Hint) => foreground,
+        TuiStyle::Accessory(InputAccessoryKind::Success) => tone_color(LauncherItemTone::Success),
+        TuiStyle::Accessory(InputAccessoryKind::Warning) => tone_color(LauncherItemTone::Warning),
+        TuiStyle::Accessory(InputAccessoryKind::Error) => tone_color(LauncherItemTone::Danger),
+        TuiStyle::Tone(tone) => tone_color(tone),
+    }
+}
+
+/// Unselected rows keep the terminal's own background; text and the selected
+/// row use the `[Colors]` theme.
+fn draw_frame(out: &mut impl Write, frame: &TuiFrame, config: &RmenuConfig) -> io::Result<()> {
+    let (width, _) = terminal::size()?;
+    queue!(out, cursor::Hide)?;
+    for (row, line) in frame.lines.iter().enumerate() {
+        let row = row as u16;
+        queue!(
+            out,
+            cursor::MoveTo(0, row),
+            ResetColor,
+            Clear(ClearType::CurrentLine)
+        )?;
+        let foreground = if line.selected {
+            queue!(
+                out,
+                SetBackgroundColor(terminal_color(config.colors.selected_background)),
+                Print(" ".repeat(width as usize))
+            )?;
+            config.colors.selected_foreground
+        } else {
+            config.colors.foreground
+        };
+        for span in &line.spans {
+            queue!(
+                out,
+                cursor::MoveTo(span.col as u16, row),
+                SetForegroundColor(terminal_color(style_color(span.style, foreground, config))),
+                Print(&span.text)
+            )?;
+        }
+    }
+    queue!(
+        out,
+        ResetColor,
+        cursor::MoveTo(0, frame.lines.len() as u16),
+        Clear(ClearType::FromCursorDown),
+        cursor::MoveTo(frame.cursor_col as u16, 0),
+        cursor::Show
+    )?;
+    out.flush()
+}
+
+fn event_loop(session: &mut TuiSession, out: &mut impl Write) -> io::Result<LauncherOutcome> {
+    let mut dirty = true;
+    loop {
+        if dirty {
+            let (width, height) = terminal::size()?;
+            session.set_viewport_rows((height as usize).saturating_sub(1));
+            let frame = session.render(width as usize, height as usize);
+            draw_frame(out, &frame, session.controller.config())?;
+        }
+
+        if !event::poll(POLL_INTERVAL)? {
+            dirty = session.poll_hot_reload() || session.poll_streaming();
+            continue;
+        }
+        dirty = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => match session.handle_key(key) {
+                LauncherOutcome::Continue => true,
+                outcome => return Ok(outcome),
+            },
+            Event::Resize(..) => true,
+            _ => false,
+        };
+    }
+}
+
+/// Runs the terminal frontend on stderr, leaving stdout free for the printed
+/// selection. Returns the process exit code.
+pub fn run_tui(
+    config: &RmenuConfig,
+    initial_app_state: AppState,
+    module_runtime: ModuleRuntime,
+    platform: TuiPlatform,
+    streaming_ranker: Option<StreamingRanker>,
+) -> io::Result<i32> {
+    let mut session = TuiSession::new(config.clone(), initial_app_state, module_runtime, platform);
+    if let Some(ranker) = streaming_ranker {
+        session.controller.attach_streaming_ranker(ranker);
+    }
+    session.start();
+
+    let mut out = io::stderr();
+    terminal::enable_raw_mode()?;
+    let outcome =
+        execute!(out, EnterAlternateScreen).and_then(|()| event_loop(&mut session, &mut out));
+    let _ = execute!(out, ResetColor, LeaveAlternateScreen, cursor::Show);
+    let _ = terminal::disable_raw_mode();
+    session.shutdown();
+
+    let silent_mode = session.app_state().silent_mode;
+    match outcome? {
+        LauncherOutcome::Continue => Ok(0),
+        LauncherOutcome::Exit(code) => Ok(code),
+        LauncherOutcome::Print(text) => {
+            println!("{text}");
+            Ok(0)
+        }
+        LauncherOutcome::Copy(text) => {
+            if let Err(error) = (platform.copy_text)(&text) {
+                if !silent_mode {
+                    eprintln!("Copy to clipboard failed: {error}");
+                }
+            }
+            Ok(0)
+        }
+        LauncherOutcome::Launch { target, remember } => {
+            match (platform.launch_target)(session.controller.runtime().schemes(), &target) {
+                Ok(()) if remember => persist_history_entry(
+                    &target,
+                    silent_mode,
+                    session.app_state().history_max_items,
+                    session.app_state().frecency_half_life_hours,
+                ),
+                Ok(()) => {}
+                Err(error) => {
+                    if !silent_mode {
+                        eprintln!("Error launching target '{}': {}", target, error);
+                    }
+                }
+            }
+            Ok(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TuiPlatform, TuiSession, TuiStyle};
+    use crate::app_state::{AppState, LauncherItem, LauncherSource};
+    use crate::controller::LauncherOutcome;
+    use crate::modules::types::InputAccessoryKind;
+    use crate::modules::{BuiltinQueryProviderModule, ModuleRuntime};
+    use crate::settings::RmenuConfig;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::io;
+    use std::path::MAIN_SEPARATOR;
+
+    fn no_platform() -> TuiPlatform {
+        TuiPlatform {
+            launch_target: |_, _| Ok(()),
+            copy_text: |_| Err(io::Error::new(io::ErrorKind::Unsupported, "no clipboard")),
+        }
+    }
+
+    fn new_session(items: &[(&str, &str)], launcher_mode: bool) -> TuiSession {
+        let mut runtime = ModuleRuntime::new();
+        runtime.register_builtin_module(Box::new(BuiltinQueryProviderModule::default()));
+        let app_state = AppState {
+            all_items: items
+                .iter()
+                .map(|(label, target)| {
+                    LauncherItem::new(label.to_string(), target.to_string(), LauncherSource::Path)
+                })
+                .collect(),
+            launcher_mode,
+            silent_mode: true,
+            ..Default::default()
+        };
+        let mut session =
+            TuiSession::new(RmenuConfig::default(), app_state, runtime, no_platform());
+        session.start();
+        session
+    }
+
+    fn type_text(session: &mut TuiSession, text: &str) {
+        for ch in text.chars() {
+            assert_eq!(
+                session.handle_key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE)),
+                LauncherOutcome::Continue
+            );
+        }
+    }
+
+    fn press(session: &mut TuiSession, code: KeyCode) -> LauncherOutcome {
+        session.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn typing_ranks_items_and_renders_label_and_hint_zones() {
+        let mut session = new_session(
+            &[
+                ("Notepad", "/usr/bin/notepad"),
+                ("Calculator", "/usr/bin/calc"),
+            ],
+            true,
+        );
+        type_text(&mut session, "calc");
+
+        let frame = session.render(40, 10);
+        assert_eq!(frame.lines.len(), 2);
+        assert_eq!(frame.lines[0].text(40), " calc");
+        assert_eq!(frame.cursor_col, 5);
+        assert!(frame.lines[1].selected);
+        assert_eq!(
+            frame.lines[1].text(40),
+            format!(
+                " Calculator{}{MAIN_SEPARATOR}bin{MAIN_SEPARATOR}calc",
+                " ".repeat(19)
+            )
+        );
+        assert!(frame.lines[1]
+            .spans
+            .iter()
+            .any(|span| span.text == "Calc" && span.style == TuiStyle::Highlight));
+
+        assert_eq!(
+            press(&mut session, KeyCode::Enter),
+            LauncherOutcome::Launch {
+                target: "/usr/bin/calc".to_string(),
+                remember: true,
+            }
+        );
+    }
+
+    #[test]
+    fn calc_digits_extend_the_expression_and_enter_copies() {
+        let mut session = new_session(&[("Notepad", "/usr/bin/notepad")], true);
+        type_text(&mut session, "=6*71");
+        assert_eq!(session.app_state().current_input, "=6*71");
+
+        let frame = session.render(40, 10);
+        assert_eq!(frame.lines.len(), 2);
+        let row = frame.lines[1].text(40);
+        assert!(row.starts_with(" = 426"), "{row}");
+        assert!(!row.contains("[1]"), "{row}");
+
+        assert_eq!(
+            press(&mut session, KeyCode::Enter),
+            LauncherOutcome::Copy("426".to_string())
+        );
+    }
+
+    #[test]
+    fn module_input_accessory_is_right_aligned_in_the_input_bar() {
+        let mut session = new_session(&[], true);
+        type_text(&mut session, "=1+");
+
+        let frame = session.render(60, 5);
+        let accessory = frame.lines[0]
+            .spans
+            .iter()
+            .find(|span| span.style == TuiStyle::Accessory(InputAccessoryKind::Error))
+            .expect("calc error accessory");
+        assert!(accessory.text.starts_with("Calc:"));
+        assert_eq!(accessory.col + accessory.text.chars().count(), 59);
+    }
+
+    #[test]
+    fn stdin_mode_prints_selection_and_escape_cancels() {
+        let mut session = new_session(&[("alpha", "alpha"), ("beta", "beta")], false);
+        type_text(&mut session, "a");
+        press(&mut session, KeyCode::Down);
+        let selected = session.app_state().matching_items[session.app_state().selected_index]
+            .label
+            .clone();
+        assert_eq!(
+            press(&mut session, KeyCode::Enter),
+            LauncherOutcome::Print(selected)
+        );
+
+        let mut session = new_session(&[("alpha", "alpha")], false);
+        assert_eq!(press(&mut session, KeyCode::Esc), LauncherOutcome::Exit(1));
+    }
+}
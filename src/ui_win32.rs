@@ -2,28 +2,30 @@ use crate::app_state::{
     ensure_selection_visible, AppState, LauncherItem, LauncherItemTone, RmodsInstallStatusView,
     RmodsPendingAction, RmodsUiItem, RtasksInputPriority, RtasksInputStatus, StartupUpdateNotice,
 };
+use crate::controller::{LauncherController, LauncherOutcome, LauncherPlatform};
 use crate::fuzzy::fuzzy_score;
-use crate::launcher::{
-    abbreviate_target, centered_text_y, compact_target_hint, launch_target,
-    truncate_with_ellipsis_end,
-};
+use crate::launcher::{centered_text_y, copy_text_to_clipboard, launch_target};
 use crate::modules::{
     input_accessory_text,
     types::{InputAccessoryKind, ModuleKeyEvent},
     ModuleRuntime,
 };
-use crate::ranking::update_matching_items_with_dataset;
 use crate::rmods_registry::{
     download_verify_and_install_rmod, fetch_default_registry, install_status_for,
     read_registry_cache, scan_installed_rmods, uninstall_rmod, RmodsInstallStatus,
     RmodsLocalModule, RmodsRegistryItem, DEFAULT_RMODS_REGISTRY_URL, RMODS_PACKAGE_KIND_COMPANION,
 };
+use crate::row_layout::{
+    compact_target_hint, compute_row_zones, match_highlight_positions, truncate_with_ellipsis_end,
+};
 use crate::rsnip_companion::install_rsnip_latest;
 use crate::rtasks_companion::{
     install_rtasks_latest, RtasksCompanion, RtasksIpcResponse, RtasksPriority, RtasksTaskStatus,
 };
-use crate::settings::{rmenu_data_dirs, CmdOptions, QuickSelectMode, RmenuConfig};
+use crate::schemes::SchemeRegistry;
+use crate::settings::{rmenu_data_dirs, CmdOptions, RgbColor, RmenuConfig};
 use crate::sources::persist_history_entry;
+use crate::streaming::StreamingRanker;
 use std::ffi::OsStr;
 use std::fs;
 use std::iter::once;
@@ -37,16 +39,16 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use windows::{
     core::PCWSTR,
     Win32::{
-        Foundation::{BOOL, COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Foundation::{BOOL, COLORREF, HWND, LPARAM, LRESULT, RECT, SIZE, WPARAM},
         Graphics::Gdi::{
-            BeginPaint, CreateFontW, CreateSolidBrush, EndPaint, FillRect, InvalidateRect,
-            SelectObject, SetBkColor, SetTextColor, TextOutW, PAINTSTRUCT,
+            BeginPaint, CreateFontW, CreateSolidBrush, EndPaint, FillRect, GetTextExtentPoint32W,
+            InvalidateRect, SelectObject, SetBkColor, SetTextColor, TextOutW, PAINTSTRUCT,
         },
         System::LibraryLoader::GetModuleHandleW,
         UI::{
             Input::KeyboardAndMouse::{
-                GetKeyState, VK_BACK, VK_CONTROL, VK_DOWN, VK_ESCAPE, VK_MENU, VK_RETURN, VK_SHIFT,
-                VK_SPACE, VK_TAB, VK_UP,
+                GetKeyState, VK_BACK, VK_CONTROL, VK_DOWN, VK_ESCAPE, VK_F1, VK_F24, VK_MENU,
+                VK_RETURN, VK_SHIFT, VK_SPACE, VK_TAB, VK_UP,
             },
             WindowsAndMessaging::{
                 CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetClientRect,
@@ -61,14 +63,11 @@ use windows::{
     },
 };
 
-static APP_STATE: Mutex<Option<AppState>> = Mutex::new(None);
-static CONFIG: Mutex<Option<RmenuConfig>> = Mutex::new(None);
-static MODULE_RUNTIME: Mutex<Option<ModuleRuntime>> = Mutex::new(None);
+static CONTROLLER: Mutex<Option<LauncherController>> = Mutex::new(None);
 static UI_MEASURE_STATE: Mutex<UiMeasureState> = Mutex::new(UiMeasureState::disabled());
 static UI_RUN_TIMING_TRACE: Mutex<Option<UiRunTimingTrace>> = Mutex::new(None);
 static UI_EMBEDDED_MODE: AtomicBool = AtomicBool::new(false);
 static UI_EXIT_CODE: AtomicI32 = AtomicI32::new(0);
-static SUPPRESS_NEXT_RMODS_SPACE_CHAR: AtomicBool = AtomicBool::new(false);
 const INSTALL_CLOSE_TIMER_ID: usize = 42;
 const INSTALL_START_TIMER_ID: usize = 43;
 const TIMER_COUNTDOWN_REFRESH_ID: usize = 44;
@@ -192,30 +191,75 @@ fn current_ui_exit_code() -> i32 {
     UI_EXIT_CODE.load(Ordering::SeqCst)
 }
 
-fn launch_target_and_persist(target: String, silent_mode: bool, history_max_items: usize) {
-    if let Err(error) = launch_target(&target) {
+fn launch_target_and_persist(
+    schemes: &SchemeRegistry,
+    target: String,
+    silent_mode: bool,
+    history_max_items: usize,
+    half_life_hours: f64,
+) {
+    if let Err(error) = launch_target(schemes, &target) {
         if !silent_mode {
             eprintln!("Error launching target '{}': {}", target, error);
         }
         return;
     }
 
-    persist_history_entry(&target, silent_mode, history_max_items);
+    persist_history_entry(&target, silent_mode, history_max_items, half_life_hours);
 }
 
 fn launch_target_after_hiding_window(
     hwnd: HWND,
+    schemes: SchemeRegistry,
     target: String,
     silent_mode: bool,
     history_max_items: usize,
+    half_life_hours: f64,
+) {
+    run_after_hiding_window(hwnd, move || {
+        launch_target_and_persist(
+            &schemes,
+            target,
+            silent_mode,
+            history_max_items,
+            half_life_hours,
+        );
+    });
+}
+
+fn launch_item_action_after_hiding_window(
+    hwnd: HWND,
+    schemes: SchemeRegistry,
+    target: String,
+    silent_mode: bool,
 ) {
+    run_after_hiding_window(hwnd, move || {
+        if let Err(error) = launch_target(&schemes, &target) {
+            if !silent_mode {
+                eprintln!("Error running item action '{}': {}", target, error);
+            }
+        }
+    });
+}
+
+fn copy_text_after_hiding_window(hwnd: HWND, text: String, silent_mode: bool) {
+    run_after_hiding_window(hwnd, move || {
+        if let Err(error) = copy_text_to_clipboard(&text) {
+            if !silent_mode {
+                eprintln!("Copy to clipboard failed: {}", error);
+            }
+        }
+    });
+}
+
+fn run_after_hiding_window(hwnd: HWND, job: impl FnOnce() + Send + 'static) {
     let hwnd_value = hwnd.0;
     unsafe {
         ShowWindow(hwnd, SW_HIDE);
     }
 
     thread::spawn(move || {
-        launch_target_and_persist(target, silent_mode, history_max_items);
+        job();
         unsafe {
             let _ = PostMessageW(HWND(hwnd_value), WM_LAUNCH_DONE, WPARAM(0), LPARAM(0));
         }
@@ -236,6 +280,52 @@ fn draw_text_w(hdc: windows::Win32::Graphics::Gdi::HDC, x: i32, y: i32, text: &s
     }
 }
 
+fn draw_text_with_highlights(
+    hdc: windows::Win32::Graphics::Gdi::HDC,
+    x: i32,
+    y: i32,
+    text: &str,
+    positions: &[usize],
+    highlight: COLORREF,
+) {
+    if positions.is_empty() {
+        draw_text_w(hdc, x, y, text);
+        return;
+    }
+
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut cursor_x = x;
+    let mut start = 0;
+    while start < chars.len() {
+        let highlighted = positions.contains(&start);
+        let mut end = start + 1;
+        while end < chars.len() && positions.contains(&end) == highlighted {
+            end += 1;
+        }
+
+        let utf16 = chars[start..end]
+            .iter()
+            .collect::<String>()
+            .encode_utf16()
+            .collect::<Vec<u16>>();
+        let mut size = SIZE::default();
+        unsafe {
+            let previous = highlighted.then(|| SetTextColor(hdc, highlight));
+            TextOutW(hdc, cursor_x, y, &utf16);
+            GetTextExtentPoint32W(hdc, &utf16, &mut size);
+            if let Some(previous) = previous {
+                SetTextColor(hdc, previous);
+            }
+        }
+        cursor_x += size.cx;
+        start = end;
+    }
+}
+
+fn colorref(color: RgbColor) -> COLORREF {
+    COLORREF((color.b as u32) << 16 | (color.g as u32) << 8 | (color.r as u32))
+}
+
 fn blend_color(left: COLORREF, right: COLORREF, right_weight: u32) -> COLORREF {
     let left_weight = 100u32.saturating_sub(right_weight);
     let left_value = left.0;
@@ -249,7 +339,11 @@ fn blend_color(left: COLORREF, right: COLORREF, right_weight: u32) -> COLORREF {
 }
 
 fn placeholder_text_color(config: &RmenuConfig) -> COLORREF {
-    blend_color(config.colors.background, config.colors.foreground, 45)
+    blend_color(
+        colorref(config.colors.background),
+        colorref(config.colors.foreground),
+        45,
+    )
 }
 
 fn launcher_item_tone_color(tone: LauncherItemTone) -> COLORREF {
@@ -262,8 +356,8 @@ fn launcher_item_tone_color(tone: LauncherItemTone) -> COLORREF {
 
 fn accessory_text_color(kind: InputAccessoryKind, config: &RmenuConfig) -> COLORREF {
     match kind {
-        InputAccessoryKind::Info => config.colors.foreground,
-        InputAccessoryKind::Hint => config.colors.foreground,
+        InputAccessoryKind::Info => colorref(config.colors.foreground),
+        InputAccessoryKind::Hint => colorref(config.colors.foreground),
         InputAccessoryKind::Success => COLORREF(0x0090D890),
         InputAccessoryKind::Warning => COLORREF(0x0030B0E0),
         InputAccessoryKind::Error => COLORREF(0x004050E8),
@@ -468,12 +562,7 @@ fn visible_item_count(app_state: &AppState, config: &RmenuConfig) -> usize {
         .min(config.behavior.max_items.max(0) as usize)
 }
 
-fn resize_window_to_state(hwnd: HWND, app_state: &AppState) {
-    let config_guard = CONFIG.lock().unwrap();
-    let Some(config) = config_guard.as_ref() else {
-        return;
-    };
-
+fn window_height(app_state: &AppState, config: &RmenuConfig) -> i32 {
     let item_count = visible_item_count(app_state, config);
     let input_bar_height = config.dimensions.height;
     let list_height = if item_count == 0 {
@@ -481,22 +570,75 @@ fn resize_window_to_state(hwnd: HWND, app_state: &AppState) {
     } else {
         (item_count as i32 * config.dimensions.item_height) + (2 * config.dimensions.padding)
     };
-    let height = input_bar_height + list_height + config.dimensions.border_width * 2;
+    input_bar_height + list_height + config.dimensions.border_width * 2
+}
 
+/// Resizes to `height` and repaints. Call it without holding `CONTROLLER`:
+/// `MoveWindow` paints synchronously.
+fn refresh_window(hwnd: HWND, height: i32) {
     let mut rect = windows::Win32::Foundation::RECT::default();
     if unsafe { GetWindowRect(hwnd, &mut rect) }.as_bool() {
         let width = rect.right - rect.left;
         let _ = unsafe { MoveWindow(hwnd, rect.left, rect.top, width, height, true) };
     }
-}
-
-fn refresh_window(hwnd: HWND, app_state: &AppState) {
-    resize_window_to_state(hwnd, app_state);
     unsafe {
         InvalidateRect(hwnd, None, true);
     }
 }
 
+/// Runs `handle` on the controller, then releases it before acting on the
+/// outcome, since closing the window re-enters `window_proc`.
+fn with_controller(hwnd: HWND, handle: impl FnOnce(&mut LauncherController) -> LauncherOutcome) {
+    let (outcome, schemes, height, silent_mode, history_max_items, half_life_hours) = {
+        let mut controller_guard = CONTROLLER.lock().unwrap();
+        let Some(controller) = controller_guard.as_mut() else {
+            return;
+        };
+        let outcome = handle(controller);
+        let schemes = matches!(outcome, LauncherOutcome::Launch { .. })
+            .then(|| controller.runtime().schemes().clone());
+        let app_state = controller.app_state();
+        (
+            outcome,
+            schemes,
+            window_height(app_state, controller.config()),
+            app_state.silent_mode,
+            app_state.history_max_items,
+            app_state.frecency_half_life_hours,
+        )
+    };
+
+    match outcome {
+        LauncherOutcome::Continue => refresh_window(hwnd, height),
+        LauncherOutcome::Exit(code) => request_ui_exit(hwnd, code),
+        LauncherOutcome::Print(text) => {
+            println!("{text}");
+            request_ui_exit(hwnd, 0);
+        }
+        LauncherOutcome::Copy(text) => copy_text_after_hiding_window(hwnd, text, silent_mode),
+        LauncherOutcome::Launch {
+            target,
+            remember: true,
+        } => launch_target_after_hiding_window(
+            hwnd,
+            schemes.unwrap_or_default(),
+            target,
+            silent_mode,
+            history_max_items,
+            half_life_hours,
+        ),
+        LauncherOutcome::Launch {
+            target,
+            remember: false,
+        } => launch_item_action_after_hiding_window(
+            hwnd,
+            schemes.unwrap_or_default(),
+            target,
+            silent_mode,
+        ),
+    }
+}
+
 fn is_rtasks_input(input: &str) -> bool {
     input == "t" || input.starts_with("t ")
 }
@@ -795,8 +937,11 @@ fn rmods_status_tone(status: RmodsInstallStatusView) -> Option<LauncherItemTone>
     }
 }
 
-fn launch_update_changelog(notice: &StartupUpdateNotice) -> Result<(), String> {
-    launch_target(&notice.release_url).map_err(|error| error.to_string())
+fn launch_update_changelog(
+    schemes: &SchemeRegistry,
+    notice: &StartupUpdateNotice,
+) -> Result<(), String> {
+    launch_target(schemes, &notice.release_url).map_err(|error| error.to_string())
 }
 
 fn launch_update_installer(notice: &StartupUpdateNotice) -> Result<(), String> {
@@ -835,11 +980,216 @@ fn launch_update_installer(notice: &StartupUpdateNotice) -> Result<(), String> {
         .map_err(|error| format!("failed to launch updater: {error}"))
 }
 
-fn set_runtime_feedback(message: impl Into<String>, kind: InputAccessoryKind) {
-    let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-    if let Some(runtime) = runtime_guard.as_mut() {
-        runtime.set_runtime_feedback(message, kind);
+fn win32_platform() -> LauncherPlatform {
+    LauncherPlatform {
+        copy_text: copy_text_to_clipboard,
+        claim_input: claim_win32_input,
+        handle_key: handle_win32_key,
+    }
+}
+
+/// The update notice, `/rmods` list, and RTasks capture replace ranked results.
+fn claim_win32_input(app_state: &mut AppState) -> bool {
+    if render_startup_update_notice(app_state) {
+        return true;
+    }
+
+    if !is_rtasks_input(&app_state.current_input) {
+        app_state.rtasks_status = None;
+        app_state.rtasks_priority = None;
+    }
+
+    if is_rmods_input(&app_state.current_input) {
+        update_rmods_items(app_state, false);
+        return true;
+    } else {
+        app_state.rmods.loaded = false;
+        app_state.rmods.items.clear();
+        app_state.rmods.error = None;
+    }
+
+    if is_rtasks_input(&app_state.current_input) {
+        app_state.matching_items.clear();
+        app_state.selected_index = 0;
+        app_state.scroll_offset = 0;
+        return true;
+    }
+    false
+}
+
+/// RTasks capture and the `/rmods` list own some keys before the launcher's
+/// defaults.
+fn handle_win32_key(
+    controller: &mut LauncherController,
+    key: &ModuleKeyEvent,
+) -> Option<LauncherOutcome> {
+    let input = controller.app_state().current_input.clone();
+    if is_rtasks_input(&input) {
+        if key.alt {
+            let app_state = controller.app_state_mut();
+            match key.key.as_str() {
+                "1" => toggle_rtasks_status(app_state, RtasksInputStatus::Todo),
+                "2" => toggle_rtasks_status(app_state, RtasksInputStatus::Doing),
+                "3" => toggle_rtasks_status(app_state, RtasksInputStatus::Done),
+                "q" => toggle_rtasks_priority(app_state, RtasksInputPriority::High),
+                "w" => toggle_rtasks_priority(app_state, RtasksInputPriority::Medium),
+                "e" => toggle_rtasks_priority(app_state, RtasksInputPriority::Low),
+                _ => {}
+            }
+            return Some(LauncherOutcome::Continue);
+        }
+        if key.key == "enter" {
+            add_rtasks_task(controller);
+            return Some(LauncherOutcome::Exit(0));
+        }
+    }
+
+    if is_rmods_input(&input) {
+        match key.key.as_str() {
+            "space" => {
+                toggle_selected_rmod(controller.app_state_mut());
+                controller.swallow_next_char();
+            }
+            "f5" => update_rmods_items(controller.app_state_mut(), true),
+            "r" if key.ctrl => update_rmods_items(controller.app_state_mut(), true),
+            "u" if key.ctrl => select_rmods_updates(controller.app_state_mut()),
+            "enter" => apply_rmods_changes_with_feedback(controller),
+            _ => return None,
+        }
+        return Some(LauncherOutcome::Continue);
     }
+    None
+}
+
+fn add_rtasks_task(controller: &mut LauncherController) {
+    let app_state = controller.app_state();
+    let task_input = rtasks_task_text(&app_state.current_input).to_string();
+    if task_input.is_empty() {
+        return;
+    }
+    let result = RtasksCompanion::discover().and_then(|companion| {
+        companion.ensure_and_add_task(
+            task_input,
+            rtasks_status_for_ipc(app_state.rtasks_status),
+            rtasks_priority_for_ipc(app_state.rtasks_priority),
+        )
+    });
+    let runtime = controller.runtime_mut();
+    match result {
+        Ok(RtasksIpcResponse::Ok { .. }) => {
+            runtime.set_runtime_feedback("RTasks task added", InputAccessoryKind::Success)
+        }
+        Ok(RtasksIpcResponse::Error { message }) => runtime.set_runtime_feedback(
+            format!("RTasks add failed: {message}"),
+            InputAccessoryKind::Error,
+        ),
+        Err(err) => runtime.set_runtime_feedback(
+            format!("RTasks add failed: {err:?}"),
+            InputAccessoryKind::Error,
+        ),
+    }
+}
+
+fn apply_rmods_changes_with_feedback(controller: &mut LauncherController) {
+    let silent_mode = controller.app_state().silent_mode;
+    let result = apply_rmods_changes(controller.app_state_mut());
+    let runtime = controller.runtime_mut();
+    match result {
+        Ok(summary)
+            if summary.installed == 0 && summary.updated == 0 && summary.uninstalled == 0 =>
+        {
+            runtime.set_runtime_feedback("No rMods changes", InputAccessoryKind::Hint);
+        }
+        Ok(summary) => {
+            runtime.reload_external_descriptors(silent_mode);
+            runtime.set_runtime_feedback(
+                format!(
+                    "rMods: installed {}, updated {}, removed {}",
+                    summary.installed, summary.updated, summary.uninstalled
+                ),
+                InputAccessoryKind::Success,
+            );
+        }
+        Err(error) => {
+            runtime.reload_external_descriptors(silent_mode);
+            runtime.set_runtime_feedback(
+                format!("rMods changes failed: {error}"),
+                InputAccessoryKind::Error,
+            );
+        }
+    }
+}
+
+/// Enter installs the update, Ctrl+Enter opens the changelog, and any other
+/// key dismisses the notice and carries on.
+fn handle_startup_notice_key(
+    controller: &mut LauncherController,
+    key: &ModuleKeyEvent,
+) -> Option<LauncherOutcome> {
+    let notice = controller.app_state().startup_update_notice.clone()?;
+    let result = (key.key == "enter").then(|| {
+        if key.ctrl {
+            launch_update_changelog(controller.runtime().schemes(), &notice)
+                .map_err(|error| format!("Failed to open changelog: {error}"))
+        } else {
+            launch_update_installer(&notice)
+        }
+    });
+    dismiss_startup_update_notice(controller.app_state_mut());
+    controller.refresh_matches();
+    match result {
+        Some(Ok(())) if !key.ctrl => Some(LauncherOutcome::Exit(0)),
+        Some(Err(error)) => {
+            controller
+                .runtime_mut()
+                .set_runtime_feedback(error, InputAccessoryKind::Error);
+            Some(LauncherOutcome::Continue)
+        }
+        _ => Some(LauncherOutcome::Continue),
+    }
+}
+
+/// `/install rsnip` and `/install rtasks` download on a timer so the window
+/// can show progress first.
+fn start_companion_install(
+    hwnd: HWND,
+    controller: &mut LauncherController,
+    key: &ModuleKeyEvent,
+) -> bool {
+    if key.key != "enter" || key.ctrl {
+        return false;
+    }
+    let input = &controller.app_state().current_input;
+    let mut parts = input
+        .strip_prefix('/')
+        .unwrap_or_default()
+        .split_whitespace();
+    let is_install = parts
+        .next()
+        .is_some_and(|command| command.eq_ignore_ascii_case("install"));
+    let Some(companion_name) = parts
+        .next()
+        .map(str::to_ascii_lowercase)
+        .filter(|name| is_install && (name == "rsnip" || name == "rtasks"))
+    else {
+        return false;
+    };
+
+    controller.runtime_mut().set_runtime_feedback(
+        format!(
+            "Fetching {} from GitHub latest release",
+            if companion_name == "rtasks" {
+                "RTasks"
+            } else {
+                "rSnip"
+            }
+        ),
+        InputAccessoryKind::Info,
+    );
+    unsafe {
+        SetTimer(hwnd, INSTALL_START_TIMER_ID, 350, None);
+    }
+    true
 }
 
 fn rmods_status_label(status: RmodsInstallStatusView) -> &'static str {
@@ -1048,84 +1398,9 @@ fn dismiss_startup_update_notice(app_state: &mut AppState) -> bool {
     }
     app_state.startup_update_notice = None;
     app_state.selected_index = 0;
-    update_matching_items_from_config(app_state);
     true
 }
 
-fn update_matching_items_from_config(app_state: &mut AppState) {
-    if render_startup_update_notice(app_state) {
-        return;
-    }
-
-    if !is_rtasks_input(&app_state.current_input) {
-        app_state.rtasks_status = None;
-        app_state.rtasks_priority = None;
-    }
-
-    if is_rmods_input(&app_state.current_input) {
-        update_rmods_items(app_state, false);
-        return;
-    } else {
-        app_state.rmods.loaded = false;
-        app_state.rmods.items.clear();
-        app_state.rmods.error = None;
-    }
-
-    if is_rtasks_input(&app_state.current_input) {
-        app_state.matching_items.clear();
-        app_state.selected_index = 0;
-        app_state.scroll_offset = 0;
-        return;
-    }
-
-    let mut provider_items = Vec::new();
-
-    {
-        let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-        if let Some(runtime) = runtime_guard.as_mut() {
-            runtime.run_on_query_change(app_state);
-            if runtime.items_replaced_in_cycle() {
-                normalize_quick_select_items(app_state);
-                return;
-            }
-            provider_items = runtime.collect_provider_items(app_state);
-        }
-    }
-
-    let config_guard = CONFIG.lock().unwrap();
-    let case_sensitive = config_guard
-        .as_ref()
-        .map_or(false, |c| c.behavior.case_sensitive);
-    let max_visible_items = config_guard
-        .as_ref()
-        .map_or(10usize, |c| c.behavior.max_items.max(1) as usize);
-    drop(config_guard);
-
-    let mut dataset = app_state.all_items.clone();
-
-    {
-        let runtime_guard = MODULE_RUNTIME.lock().unwrap();
-        if let Some(runtime) = runtime_guard.as_ref() {
-            dataset = runtime.merge_rank_dataset(app_state.all_items.clone(), provider_items);
-        } else {
-            dataset.extend(provider_items);
-        }
-    }
-
-    update_matching_items_with_dataset(app_state, dataset, case_sensitive, max_visible_items);
-
-    {
-        let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-        if let Some(runtime) = runtime_guard.as_mut() {
-            let decorated = runtime.decorate_items(app_state, app_state.matching_items.clone());
-            app_state.matching_items = decorated;
-        }
-    }
-
-    normalize_quick_select_items(app_state);
-    ensure_selection_visible(app_state, max_visible_items);
-}
-
 fn resolve_digit_from_key(key_code: i32) -> Option<char> {
     if (0x30..=0x39).contains(&key_code) {
         return char::from_u32(key_code as u32);
@@ -1150,6 +1425,10 @@ fn resolve_key_name(key_code: i32) -> String {
         code if code == VK_BACK.0 as i32 => "backspace".to_string(),
         code if code == VK_UP.0 as i32 => "up".to_string(),
         code if code == VK_DOWN.0 as i32 => "down".to_string(),
+        code if code == VK_SPACE.0 as i32 => "space".to_string(),
+        code if (VK_F1.0 as i32..=VK_F24.0 as i32).contains(&code) => {
+            format!("f{}", code - VK_F1.0 as i32 + 1)
+        }
         code if (0x30..=0x39).contains(&code) => {
             char::from_u32(code as u32).unwrap_or('?').to_string()
         }
@@ -1174,148 +1453,6 @@ fn build_module_key_event(key_code: i32) -> ModuleKeyEvent {
     }
 }
 
-fn find_quick_select_index(
-    app_state: &AppState,
-    key: char,
-    max_visible_items: usize,
-) -> Option<usize> {
-    let visible_end =
-        (app_state.scroll_offset + max_visible_items).min(app_state.matching_items.len());
-    let key_str = key.to_string();
-
-    for item_index in app_state.scroll_offset..visible_end {
-        let item = &app_state.matching_items[item_index];
-        if item.quick_select_key.as_deref() == Some(key_str.as_str()) {
-            return Some(item_index);
-        }
-    }
-
-    None
-}
-
-fn normalize_quick_select_items(app_state: &mut AppState) {
-    use std::collections::BTreeSet;
-
-    let mut seen: BTreeSet<String> = BTreeSet::new();
-
-    for item in &mut app_state.matching_items {
-        let Some(key) = item.quick_select_key.clone() else {
-            continue;
-        };
-
-        if key.is_empty() {
-            item.quick_select_key = None;
-            continue;
-        }
-
-        if seen.insert(key.clone()) {
-            continue;
-        }
-
-        if !app_state.silent_mode {
-            eprintln!(
-                "modules quick-select conflict: key '{}' duplicated, keeping first item and clearing duplicate on '{}'",
-                key, item.label
-            );
-        }
-
-        item.quick_select_key = None;
-        if item.trailing_badge.as_deref() == Some(key.as_str()) {
-            item.trailing_badge = None;
-            item.trailing_badge_tone = None;
-        }
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct RowZones {
-    left_text: String,
-    right_text: String,
-    chip_text: Option<String>,
-    right_x: i32,
-    chip_x: i32,
-}
-
-fn compute_row_zones(
-    label: &str,
-    hint: &str,
-    chip: Option<String>,
-    left_x: i32,
-    row_right_bound: i32,
-    char_w: i32,
-) -> RowZones {
-    if row_right_bound <= left_x || char_w <= 0 {
-        return RowZones {
-            left_text: String::new(),
-            right_text: String::new(),
-            chip_text: None,
-            right_x: left_x,
-            chip_x: left_x,
-        };
-    }
-
-    let total_w = row_right_bound - left_x;
-    let min_gap = char_w * 2;
-    let zone_gap = char_w;
-    let min_label_w = char_w * 4;
-    let min_hint_chars = 4;
-
-    let mut chip_text = chip.filter(|value| !value.is_empty());
-    let mut chip_w = chip_text
-        .as_ref()
-        .map(|text| text.chars().count() as i32 * char_w)
-        .unwrap_or(0);
-
-    if chip_text.is_some() && total_w < min_label_w + zone_gap + chip_w {
-        chip_text = None;
-        chip_w = 0;
-    }
-
-    let hint_right_bound = if chip_text.is_some() {
-        row_right_bound - chip_w - zone_gap
-    } else {
-        row_right_bound
-    };
-
-    let mut right_text = String::new();
-    let mut right_w = 0;
-    let available_for_hint = hint_right_bound - left_x - min_label_w - min_gap;
-    if !hint.is_empty() && available_for_hint >= char_w * min_hint_chars {
-        let max_hint_chars = (available_for_hint / char_w).max(1) as usize;
-        right_text = abbreviate_target(hint, max_hint_chars);
-        right_w = right_text.chars().count() as i32 * char_w;
-    }
-
-    let right_x = if right_text.is_empty() {
-        hint_right_bound
-    } else {
-        hint_right_bound - right_w
-    };
-
-    let label_right_limit = if right_text.is_empty() {
-        hint_right_bound
-    } else {
-        right_x - min_gap
-    };
-    let left_max_w = (label_right_limit - left_x).max(char_w);
-    let left_max_chars = (left_max_w / char_w).max(1) as usize;
-    let left_text = truncate_with_ellipsis_end(label, left_max_chars);
-
-    let chip_x = if chip_text.is_some() {
-        row_right_bound - chip_w
-    } else {
-        hint_right_bound
-    };
-
-    RowZones {
-        left_text,
-        right_text,
-        chip_text,
-        right_x,
-        chip_x,
-    }
-}
-
 unsafe extern "system" fn window_proc(
     hwnd: HWND,
     msg: u32,
@@ -1341,28 +1478,28 @@ unsafe extern "system" fn window_proc(
             let mut ps = PAINTSTRUCT::default();
             let hdc = BeginPaint(hwnd, &mut ps);
 
-            let config_guard = CONFIG.lock().unwrap();
-            let app_state_guard = APP_STATE.lock().unwrap();
-
-            if let (Some(config), Some(app_state)) = (&*config_guard, &*app_state_guard) {
-                let config = config.clone();
-                let app_state = app_state.clone();
-                drop(config_guard);
-                drop(app_state_guard);
+            let snapshot = CONTROLLER.lock().unwrap().as_ref().map(|controller| {
+                (
+                    controller.config().clone(),
+                    controller.app_state().clone(),
+                    controller.input_accessory(),
+                )
+            });
 
+            if let Some((config, app_state, accessory)) = snapshot {
                 let mut rect = RECT::default();
                 GetClientRect(hwnd, &mut rect);
 
-                SetBkColor(hdc, config.colors.background);
-                SetTextColor(hdc, config.colors.foreground);
+                SetBkColor(hdc, colorref(config.colors.background));
+                SetTextColor(hdc, colorref(config.colors.foreground));
 
-                let bg_brush = CreateSolidBrush(config.colors.background);
+                let bg_brush = CreateSolidBrush(colorref(config.colors.background));
                 FillRect(hdc, &rect, bg_brush);
 
                 let final_border_width = config.dimensions.border_width;
 
                 if final_border_width > 0 {
-                    let border_brush = CreateSolidBrush(config.colors.border);
+                    let border_brush = CreateSolidBrush(colorref(config.colors.border));
                     let bw = final_border_width;
                     FillRect(
                         hdc,
@@ -1444,18 +1581,11 @@ unsafe extern "system" fn window_proc(
                 if app_state.current_input.is_empty() {
                     SetTextColor(hdc, placeholder_text_color(&config));
                     draw_text_w(hdc, x_offset, input_text_y, INPUT_PLACEHOLDER_TEXT);
-                    SetTextColor(hdc, config.colors.foreground);
+                    SetTextColor(hdc, colorref(config.colors.foreground));
                 } else {
                     draw_text_w(hdc, x_offset, input_text_y, &app_state.current_input);
                 }
 
-                let accessory = {
-                    let runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                    runtime_guard
-                        .as_ref()
-                        .and_then(|runtime| runtime.active_input_accessory())
-                };
-
                 if is_rtasks_input(&app_state.current_input) {
                     let status_text = rtasks_status_label(app_state.rtasks_status);
                     let priority_text = rtasks_priority_label(app_state.rtasks_priority);
@@ -1469,7 +1599,7 @@ unsafe extern "system" fn window_proc(
                         if app_state.rtasks_status.is_some() {
                             COLORREF(0x0079C398)
                         } else {
-                            config.colors.foreground
+                            colorref(config.colors.foreground)
                         },
                     );
                     draw_text_w(
@@ -1483,7 +1613,7 @@ unsafe extern "system" fn window_proc(
                         if app_state.rtasks_priority.is_some() {
                             COLORREF(0x0079C398)
                         } else {
-                            config.colors.foreground
+                            colorref(config.colors.foreground)
                         },
                     );
                     draw_text_w(
@@ -1492,7 +1622,7 @@ unsafe extern "system" fn window_proc(
                         input_text_y,
                         priority_text,
                     );
-                    SetTextColor(hdc, config.colors.foreground);
+                    SetTextColor(hdc, colorref(config.colors.foreground));
                 } else if let Some(accessory) = accessory {
                     let accessory_text = input_accessory_text(&accessory);
                     let max_chars =
@@ -1505,7 +1635,7 @@ unsafe extern "system" fn window_proc(
                         let old_color = accessory_text_color(accessory.kind, &config);
                         SetTextColor(hdc, old_color);
                         draw_text_w(hdc, accessory_x, input_text_y, &accessory_draw);
-                        SetTextColor(hdc, config.colors.foreground);
+                        SetTextColor(hdc, colorref(config.colors.foreground));
                     }
                 }
 
@@ -1521,19 +1651,20 @@ unsafe extern "system" fn window_proc(
                     let y = input_bar_actual_height + (current_item_height * visible_row as i32);
 
                     if item_index == app_state.selected_index {
-                        SetBkColor(hdc, config.colors.selected_background);
-                        SetTextColor(hdc, config.colors.selected_foreground);
+                        SetBkColor(hdc, colorref(config.colors.selected_background));
+                        SetTextColor(hdc, colorref(config.colors.selected_foreground));
                         let select_rect = RECT {
                             left: 0,
                             top: y,
                             right: rect.right,
                             bottom: y + current_item_height,
                         };
-                        let select_brush = CreateSolidBrush(config.colors.selected_background);
+                        let select_brush =
+                            CreateSolidBrush(colorref(config.colors.selected_background));
                         FillRect(hdc, &select_rect, select_brush);
                     } else {
-                        SetBkColor(hdc, config.colors.background);
-                        SetTextColor(hdc, config.colors.foreground);
+                        SetBkColor(hdc, colorref(config.colors.background));
+                        SetTextColor(hdc, colorref(config.colors.foreground));
                     }
 
                     let left_x = current_padding;
@@ -1557,7 +1688,20 @@ unsafe extern "system" fn window_proc(
                     );
 
                     let item_text_y = centered_text_y(y, current_item_height, config.font.size);
-                    draw_text_w(hdc, left_x, item_text_y, &row.left_text);
+                    let highlights = match_highlight_positions(
+                        &app_state,
+                        &row.left_text,
+                        config.behavior.case_sensitive,
+                        &config.scopes,
+                    );
+                    draw_text_with_highlights(
+                        hdc,
+                        left_x,
+                        item_text_y,
+                        &row.left_text,
+                        &highlights,
+                        colorref(config.colors.match_highlight),
+                    );
                     if !row.right_text.is_empty() {
                         draw_text_w(hdc, row.right_x, item_text_y, &row.right_text);
                     }
@@ -1587,389 +1731,49 @@ unsafe extern "system" fn window_proc(
             LRESULT(0)
         }
         WM_KEYDOWN | WM_SYSKEYDOWN => {
-            let key_code = w_param.0 as i32;
-            let mut app_state_guard = APP_STATE.lock().unwrap();
-            if let Some(app_state) = app_state_guard.as_mut() {
-                let ctrl_down = unsafe { (GetKeyState(VK_CONTROL.0 as i32) as u16 & 0x8000) != 0 };
-                if let Some(notice) = app_state.startup_update_notice.clone() {
-                    if key_code == VK_RETURN.0 as i32 && ctrl_down {
-                        let result = launch_update_changelog(&notice);
-                        dismiss_startup_update_notice(app_state);
-                        if let Err(error) = result {
-                            set_runtime_feedback(
-                                format!("Failed to open changelog: {error}"),
-                                InputAccessoryKind::Error,
-                            );
-                        }
-                        refresh_window(hwnd, app_state);
-                        return LRESULT(0);
-                    }
-                    if key_code == VK_RETURN.0 as i32 {
-                        let result = launch_update_installer(&notice);
-                        dismiss_startup_update_notice(app_state);
-                        match result {
-                            Ok(()) => request_ui_exit(hwnd, 0),
-                            Err(error) => {
-                                set_runtime_feedback(error, InputAccessoryKind::Error);
-                                refresh_window(hwnd, app_state);
-                            }
-                        }
-                        return LRESULT(0);
-                    }
-                    dismiss_startup_update_notice(app_state);
-                    InvalidateRect(hwnd, None, true);
-                    return LRESULT(0);
-                }
-
-                let module_key_event = build_module_key_event(key_code);
-                let input_before_modules = app_state.current_input.clone();
-                {
-                    let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                    if let Some(runtime) = runtime_guard.as_mut() {
-                        runtime.run_on_key(app_state, &module_key_event);
-                    }
-                }
-                if app_state.current_input != input_before_modules {
-                    update_matching_items_from_config(app_state);
-                    InvalidateRect(hwnd, None, true);
+            let key = build_module_key_event(w_param.0 as i32);
+            with_controller(hwnd, |controller| {
+                if let Some(outcome) = handle_startup_notice_key(controller, &key) {
+                    return outcome;
                 }
-
-                let alt_down = unsafe { (GetKeyState(VK_MENU.0 as i32) as u16 & 0x8000) != 0 };
-                if is_rtasks_input(&app_state.current_input) && alt_down {
-                    match key_code {
-                        code if code == '1' as i32 => {
-                            toggle_rtasks_status(app_state, RtasksInputStatus::Todo)
-                        }
-                        code if code == '2' as i32 => {
-                            toggle_rtasks_status(app_state, RtasksInputStatus::Doing)
-                        }
-                        code if code == '3' as i32 => {
-                            toggle_rtasks_status(app_state, RtasksInputStatus::Done)
-                        }
-                        code if code == 'Q' as i32 => {
-                            toggle_rtasks_priority(app_state, RtasksInputPriority::High)
-                        }
-                        code if code == 'W' as i32 => {
-                            toggle_rtasks_priority(app_state, RtasksInputPriority::Medium)
-                        }
-                        code if code == 'E' as i32 => {
-                            toggle_rtasks_priority(app_state, RtasksInputPriority::Low)
-                        }
-                        _ => {}
-                    }
-                    refresh_window(hwnd, app_state);
-                    return LRESULT(0);
-                }
-
-                if is_rmods_input(&app_state.current_input) {
-                    match key_code {
-                        code if code == VK_SPACE.0 as i32 => {
-                            toggle_selected_rmod(app_state);
-                            SUPPRESS_NEXT_RMODS_SPACE_CHAR.store(true, Ordering::Relaxed);
-                            refresh_window(hwnd, app_state);
-                            return LRESULT(0);
-                        }
-                        code if code == 0x74 || (ctrl_down && code == 'R' as i32) => {
-                            update_rmods_items(app_state, true);
-                            refresh_window(hwnd, app_state);
-                            return LRESULT(0);
-                        }
-                        code if ctrl_down && code == 'U' as i32 => {
-                            select_rmods_updates(app_state);
-                            refresh_window(hwnd, app_state);
-                            return LRESULT(0);
-                        }
-                        _ => {}
-                    }
-                }
-
-                if key_code == VK_ESCAPE.0 as i32 {
-                    request_ui_exit(hwnd, 1);
-                } else if let Some(digit_key) = resolve_digit_from_key(key_code) {
-                    let (max_visible, quick_select_mode) = {
-                        let config_guard = CONFIG.lock().unwrap();
-                        let max_visible = config_guard
-                            .as_ref()
-                            .map_or(10usize, |c| c.behavior.max_items.max(1) as usize);
-                        let mode = config_guard
-                            .as_ref()
-                            .map_or(QuickSelectMode::Submit, |c| c.behavior.quick_select_mode);
-                        (max_visible, mode)
-                    };
-
-                    if let Some(item_index) =
-                        find_quick_select_index(app_state, digit_key, max_visible)
-                    {
-                        app_state.selected_index = item_index;
-                        ensure_selection_visible(app_state, max_visible);
-
-                        if quick_select_mode == QuickSelectMode::Submit {
-                            let selected =
-                                app_state.matching_items[app_state.selected_index].clone();
-                            if app_state.launcher_mode {
-                                launch_target_after_hiding_window(
-                                    hwnd,
-                                    selected.target,
-                                    app_state.silent_mode,
-                                    app_state.history_max_items,
-                                );
-                                return LRESULT(0);
-                            } else {
-                                println!("{}", selected.label);
-                                request_ui_exit(hwnd, 0);
-                            }
-                        } else {
-                            InvalidateRect(hwnd, None, true);
-                        }
-                    }
-                } else if key_code == VK_RETURN.0 as i32 {
-                    if is_rmods_input(&app_state.current_input) {
-                        let result = apply_rmods_changes(app_state);
-                        let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                        if let Some(runtime) = runtime_guard.as_mut() {
-                            match result {
-                                Ok(summary)
-                                    if summary.installed == 0
-                                        && summary.updated == 0
-                                        && summary.uninstalled == 0 =>
-                                {
-                                    runtime.set_runtime_feedback(
-                                        "No rMods changes",
-                                        InputAccessoryKind::Hint,
-                                    );
-                                }
-                                Ok(summary) => {
-                                    runtime.reload_external_descriptors(app_state.silent_mode);
-                                    runtime.set_runtime_feedback(
-                                        format!(
-                                            "rMods: installed {}, updated {}, removed {}",
-                                            summary.installed, summary.updated, summary.uninstalled
-                                        ),
-                                        InputAccessoryKind::Success,
-                                    );
-                                }
-                                Err(error) => {
-                                    runtime.reload_external_descriptors(app_state.silent_mode);
-                                    runtime.set_runtime_feedback(
-                                        format!("rMods changes failed: {error}"),
-                                        InputAccessoryKind::Error,
-                                    );
-                                }
-                            }
-                        }
-                        refresh_window(hwnd, app_state);
-                        return LRESULT(0);
-                    } else if is_rtasks_input(&app_state.current_input) {
-                        let task_input = rtasks_task_text(&app_state.current_input).to_string();
-                        if !task_input.is_empty() {
-                            let result = RtasksCompanion::discover().and_then(|companion| {
-                                companion.ensure_and_add_task(
-                                    task_input,
-                                    rtasks_status_for_ipc(app_state.rtasks_status),
-                                    rtasks_priority_for_ipc(app_state.rtasks_priority),
-                                )
-                            });
-                            let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                            if let Some(runtime) = runtime_guard.as_mut() {
-                                match result {
-                                    Ok(RtasksIpcResponse::Ok { .. }) => runtime
-                                        .set_runtime_feedback(
-                                            "RTasks task added",
-                                            InputAccessoryKind::Success,
-                                        ),
-                                    Ok(RtasksIpcResponse::Error { message }) => runtime
-                                        .set_runtime_feedback(
-                                            format!("RTasks add failed: {message}"),
-                                            InputAccessoryKind::Error,
-                                        ),
-                                    Err(err) => runtime.set_runtime_feedback(
-                                        format!("RTasks add failed: {err:?}"),
-                                        InputAccessoryKind::Error,
-                                    ),
-                                }
-                            }
-                        }
-                        request_ui_exit(hwnd, 0);
-                    } else if !app_state.current_input.is_empty() {
-                        let current_input = app_state.current_input.clone();
-                        if let Some(raw_command) = current_input.strip_prefix('/') {
-                            let parts = raw_command.split_whitespace().collect::<Vec<_>>();
-                            if let Some((command, rest)) = parts.split_first() {
-                                let args =
-                                    rest.iter().map(|v| (*v).to_string()).collect::<Vec<_>>();
-                                if command.eq_ignore_ascii_case("install")
-                                    && args.first().is_some_and(|value| {
-                                        value.eq_ignore_ascii_case("rsnip")
-                                            || value.eq_ignore_ascii_case("rtasks")
-                                    })
-                                {
-                                    let companion_name = args
-                                        .first()
-                                        .map(|value| value.to_ascii_lowercase())
-                                        .unwrap_or_default();
-                                    {
-                                        let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                                        if let Some(runtime) = runtime_guard.as_mut() {
-                                            runtime.set_runtime_feedback(
-                                                format!(
-                                                    "Fetching {} from GitHub latest release",
-                                                    if companion_name == "rtasks" {
-                                                        "RTasks"
-                                                    } else {
-                                                        "rSnip"
-                                                    }
-                                                ),
-                                                InputAccessoryKind::Info,
-                                            );
-                                        }
-                                    }
-                                    refresh_window(hwnd, app_state);
-                                    unsafe {
-                                        SetTimer(hwnd, INSTALL_START_TIMER_ID, 350, None);
-                                    }
-                                    return LRESULT(0);
-                                }
-
-                                let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                                if let Some(runtime) = runtime_guard.as_mut() {
-                                    if runtime.dispatch_command(
-                                        app_state,
-                                        command,
-                                        &args,
-                                        app_state.silent_mode,
-                                    ) {
-                                        refresh_window(hwnd, app_state);
-                                        return LRESULT(0);
-                                    }
-                                }
-                            }
-                        } else if !app_state.matching_items.is_empty()
-                            && app_state.selected_index < app_state.matching_items.len()
-                        {
-                            let selected =
-                                app_state.matching_items[app_state.selected_index].clone();
-                            if app_state.launcher_mode {
-                                launch_target_after_hiding_window(
-                                    hwnd,
-                                    selected.target,
-                                    app_state.silent_mode,
-                                    app_state.history_max_items,
-                                );
-                                return LRESULT(0);
-                            } else {
-                                println!("{}", selected.label);
-                            }
-                        } else if app_state.launcher_mode {
-                            launch_target_after_hiding_window(
-                                hwnd,
-                                app_state.current_input.clone(),
-                                app_state.silent_mode,
-                                app_state.history_max_items,
-                            );
-                            return LRESULT(0);
-                        } else if app_state.all_items.is_empty() {
-                            println!("{}", app_state.current_input);
-                        }
-                    }
-                    request_ui_exit(hwnd, 0);
-                } else if key_code == VK_DOWN.0 as i32 {
-                    if !app_state.matching_items.is_empty() {
-                        app_state.selected_index =
-                            (app_state.selected_index + 1) % app_state.matching_items.len();
-                        let max_visible = {
-                            let config_guard = CONFIG.lock().unwrap();
-                            config_guard
-                                .as_ref()
-                                .map_or(10usize, |c| c.behavior.max_items.max(1) as usize)
-                        };
-                        ensure_selection_visible(app_state, max_visible);
-                        InvalidateRect(hwnd, None, true);
-                    }
-                } else if key_code == VK_UP.0 as i32 {
-                    if !app_state.matching_items.is_empty() {
-                        app_state.selected_index =
-                            (app_state.selected_index + app_state.matching_items.len() - 1)
-                                % app_state.matching_items.len();
-                        let max_visible = {
-                            let config_guard = CONFIG.lock().unwrap();
-                            config_guard
-                                .as_ref()
-                                .map_or(10usize, |c| c.behavior.max_items.max(1) as usize)
-                        };
-                        ensure_selection_visible(app_state, max_visible);
-                        InvalidateRect(hwnd, None, true);
-                    }
-                } else if key_code == VK_BACK.0 as i32 {
-                    if !app_state.current_input.is_empty() {
-                        app_state.current_input.pop();
-                        app_state.selected_index = 0;
-                        update_matching_items_from_config(app_state);
-                        refresh_window(hwnd, app_state);
-                    }
-                } else if key_code == VK_TAB.0 as i32 {
-                    if !app_state.matching_items.is_empty()
-                        && app_state.selected_index < app_state.matching_items.len()
-                    {
-                        app_state.current_input = app_state.matching_items
-                            [app_state.selected_index]
-                            .label
-                            .clone();
-                        update_matching_items_from_config(app_state);
-                        refresh_window(hwnd, app_state);
-                    }
+                if start_companion_install(hwnd, controller, &key) {
+                    return LauncherOutcome::Continue;
                 }
-            }
-            drop(app_state_guard);
+                controller.handle_key(&key)
+            });
             LRESULT(0)
         }
         WM_CHAR => {
-            let char_code = w_param.0 as u16;
-            if char_code >= ' ' as u16 {
-                if char_code == ' ' as u16
-                    && SUPPRESS_NEXT_RMODS_SPACE_CHAR.swap(false, Ordering::Relaxed)
+            let Some(char_val) = char::from_u32(w_param.0 as u32) else {
+                return LRESULT(0);
+            };
+            with_controller(hwnd, |controller| {
+                let app_state = controller.app_state_mut();
+                let selected_rmod_id = if is_rmods_input(&app_state.current_input) {
+                    selected_rmods_item_id(app_state)
+                } else {
+                    None
+                };
+                if app_state.current_input.eq_ignore_ascii_case("/rmods")
+                    && !char_val.is_whitespace()
+                    && !char_val.is_control()
                 {
-                    return LRESULT(0);
+                    app_state.current_input.push(' ');
                 }
-                if let Ok(mut app_state_guard) = APP_STATE.lock() {
-                    if let Some(app_state) = app_state_guard.as_mut() {
-                        let selected_rmod_id = if is_rmods_input(&app_state.current_input) {
-                            selected_rmods_item_id(app_state)
-                        } else {
-                            None
-                        };
-                        if let Some(char_val) = std::char::from_u32(char_code as u32) {
-                            if app_state.current_input.eq_ignore_ascii_case("/rmods")
-                                && !char_val.is_whitespace()
-                            {
-                                app_state.current_input.push(' ');
-                            }
-                            app_state.current_input.push(char_val);
-                        }
-                        app_state.selected_index = 0;
-                        update_matching_items_from_config(app_state);
-                        if let Some(selected_id) = selected_rmod_id {
-                            restore_rmods_selection(app_state, &selected_id);
-                        }
-                        refresh_window(hwnd, app_state);
-                    }
+                let outcome = controller.insert_char(char_val);
+                if let Some(selected_id) = selected_rmod_id {
+                    restore_rmods_selection(controller.app_state_mut(), &selected_id);
                 }
-            }
+                outcome
+            });
             LRESULT(0)
         }
         WM_INSTALL_PROGRESS => {
-            if let Ok(app_state_guard) = APP_STATE.lock() {
-                if let Some(app_state) = app_state_guard.as_ref() {
-                    refresh_window(hwnd, app_state);
-                }
-            }
+            with_controller(hwnd, |_| LauncherOutcome::Continue);
             LRESULT(0)
         }
         WM_INSTALL_DONE => {
-            if let Ok(app_state_guard) = APP_STATE.lock() {
-                if let Some(app_state) = app_state_guard.as_ref() {
-                    refresh_window(hwnd, app_state);
-                }
-            }
+            with_controller(hwnd, |_| LauncherOutcome::Continue);
             unsafe {
                 SetTimer(hwnd, INSTALL_CLOSE_TIMER_ID, 1_000, None);
             }
@@ -1980,36 +1784,26 @@ unsafe extern "system" fn window_proc(
                 unsafe {
                     KillTimer(hwnd, INSTALL_START_TIMER_ID);
                 }
-                let companion_name = if let Ok(app_state_guard) = APP_STATE.lock() {
-                    if let Some(app_state) = app_state_guard.as_ref() {
-                        let companion_name = app_state
-                            .current_input
-                            .split_whitespace()
-                            .nth(1)
-                            .map(|value| value.to_ascii_lowercase())
-                            .unwrap_or_else(|| "rsnip".to_string());
-                        {
-                            let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                            if let Some(runtime) = runtime_guard.as_mut() {
-                                let installing_text = if companion_name == "rtasks" {
-                                    "Installing RTasks"
-                                } else {
-                                    "Installing rSnip"
-                                };
-                                runtime.set_runtime_feedback(
-                                    installing_text,
-                                    InputAccessoryKind::Info,
-                                );
-                            }
-                        }
-                        refresh_window(hwnd, app_state);
-                        companion_name
-                    } else {
-                        "rsnip".to_string()
+                let mut companion_name = "rsnip".to_string();
+                with_controller(hwnd, |controller| {
+                    if let Some(name) = controller
+                        .app_state()
+                        .current_input
+                        .split_whitespace()
+                        .nth(1)
+                    {
+                        companion_name = name.to_ascii_lowercase();
                     }
-                } else {
-                    "rsnip".to_string()
-                };
+                    let installing_text = if companion_name == "rtasks" {
+                        "Installing RTasks"
+                    } else {
+                        "Installing rSnip"
+                    };
+                    controller
+                        .runtime_mut()
+                        .set_runtime_feedback(installing_text, InputAccessoryKind::Info);
+                    LauncherOutcome::Continue
+                });
 
                 let hwnd_raw = hwnd.0;
                 std::thread::spawn(move || {
@@ -2023,15 +1817,14 @@ unsafe extern "system" fn window_proc(
                             .map(|_| "rSnip installed as rMenu companion")
                             .map_err(|err| format!("rSnip install failed: {err:?}"))
                     };
-                    {
-                        let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                        if let Some(runtime) = runtime_guard.as_mut() {
-                            match install_result {
-                                Ok(message) => runtime
-                                    .set_runtime_feedback(message, InputAccessoryKind::Success),
-                                Err(message) => {
-                                    runtime.set_runtime_feedback(message, InputAccessoryKind::Error)
-                                }
+                    if let Some(controller) = CONTROLLER.lock().unwrap().as_mut() {
+                        let runtime = controller.runtime_mut();
+                        match install_result {
+                            Ok(message) => {
+                                runtime.set_runtime_feedback(message, InputAccessoryKind::Success)
+                            }
+                            Err(message) => {
+                                runtime.set_runtime_feedback(message, InputAccessoryKind::Error)
                             }
                         }
                     }
@@ -2040,35 +1833,7 @@ unsafe extern "system" fn window_proc(
             } else if w_param.0 == INSTALL_CLOSE_TIMER_ID {
                 request_ui_exit(hwnd, 0);
             } else if w_param.0 == TIMER_COUNTDOWN_REFRESH_ID {
-                if let Ok(config_guard) = CONFIG.lock() {
-                    if config_guard.is_some() {
-                        update_timer_countdown_accessory(
-                            hwnd,
-                            &CmdOptions {
-                                elements_str: None,
-                                prompt: None,
-                                config_path: None,
-                                modules_dir: None,
-                                data_dir: None,
-                                install_companion: None,
-                                silent: false,
-                                debug_ranking: None,
-                                metrics: false,
-                                modules_debug: false,
-                                reindex: false,
-                                layout: None,
-                                cli_width_percent: None,
-                                cli_max_width: None,
-                                cli_height: None,
-                                cli_item_height: None,
-                                cli_x_pos: None,
-                                cli_y_pos: None,
-                                cli_padding: None,
-                                cli_border_width: None,
-                            },
-                        );
-                    }
-                }
+                update_timer_countdown_accessory(hwnd, &CmdOptions::default());
             }
             LRESULT(0)
         }
@@ -2077,14 +1842,9 @@ unsafe extern "system" fn window_proc(
                 return LRESULT(0);
             }
 
-            let mut app_state_guard = APP_STATE.lock().unwrap();
-            let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-            if let (Some(app_state), Some(runtime)) =
-                (app_state_guard.as_mut(), runtime_guard.as_mut())
-            {
-                runtime.run_on_unload(app_state);
+            if let Some(mut controller) = CONTROLLER.lock().unwrap().take() {
+                controller.shutdown();
             }
-            *runtime_guard = None;
             PostQuitMessage(current_ui_exit_code());
             LRESULT(0)
         }
@@ -2174,23 +1934,19 @@ fn active_accessory_is_timer(runtime: &ModuleRuntime) -> bool {
 }
 
 fn update_timer_countdown_accessory(hwnd: HWND, cmd_options: &CmdOptions) {
-    let input_is_empty = APP_STATE
-        .lock()
-        .ok()
-        .and_then(|guard| {
-            guard
-                .as_ref()
-                .map(|state| state.current_input.trim().is_empty())
-        })
-        .unwrap_or(false);
-    if !input_is_empty {
-        let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-        if let Some(runtime) = runtime_guard.as_mut() {
-            if active_accessory_is_timer(runtime) {
-                runtime.clear_runtime_feedback();
-                unsafe { InvalidateRect(hwnd, None, true) };
-            }
+    let input_is_empty = {
+        let mut controller_guard = CONTROLLER.lock().unwrap();
+        let Some(controller) = controller_guard.as_mut() else {
+            return;
+        };
+        let input_is_empty = controller.app_state().current_input.trim().is_empty();
+        if !input_is_empty && active_accessory_is_timer(controller.runtime()) {
+            controller.runtime_mut().clear_runtime_feedback();
+            unsafe { InvalidateRect(hwnd, None, true) };
         }
+        input_is_empty
+    };
+    if !input_is_empty {
         return;
     }
 
@@ -2219,10 +1975,11 @@ fn update_timer_countdown_accessory(hwnd: HWND, cmd_options: &CmdOptions) {
             Some(((deadline - now) + 999) / 1000)
         });
 
-    let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-    let Some(runtime) = runtime_guard.as_mut() else {
+    let mut controller_guard = CONTROLLER.lock().unwrap();
+    let Some(controller) = controller_guard.as_mut() else {
         return;
     };
+    let runtime = controller.runtime_mut();
 
     if let Some(seconds) = remaining {
         runtime.set_runtime_feedback(
@@ -2240,7 +1997,8 @@ fn run_ui_internal(
     cmd_options: &CmdOptions,
     config: &RmenuConfig,
     initial_app_state: AppState,
-    mut module_runtime: ModuleRuntime,
+    module_runtime: ModuleRuntime,
+    streaming_ranker: Option<StreamingRanker>,
     measure_mode: bool,
     embedded_mode: bool,
     mut run_timings: Option<&mut UiRunTimings>,
@@ -2258,43 +2016,27 @@ fn run_ui_internal(
 
     UI_EMBEDDED_MODE.store(embedded_mode, Ordering::SeqCst);
     UI_EXIT_CODE.store(0, Ordering::SeqCst);
-    {
-        let mut config_guard = CONFIG.lock().unwrap();
-        *config_guard = Some(config.clone());
-    }
-    {
-        let mut app_state_guard = APP_STATE.lock().unwrap();
-        *app_state_guard = Some(initial_app_state);
+    let mut controller = LauncherController::new(
+        config.clone(),
+        initial_app_state,
+        module_runtime,
+        win32_platform(),
+    );
+    if let Some(ranker) = streaming_ranker {
+        controller.attach_streaming_ranker(ranker);
     }
-    module_runtime.clear_runtime_feedback();
-    {
-        let mut app_state_guard = APP_STATE.lock().unwrap();
-        if let Some(app_state) = app_state_guard.as_mut() {
-            let on_load_started_at = Instant::now();
-            module_runtime.run_on_load(app_state);
-            if let Some(timings) = run_timings.as_deref_mut() {
-                timings.module_on_load_ms = on_load_started_at.elapsed().as_millis();
-            }
-
-            let matching_started_at = Instant::now();
-            if app_state.current_input.trim().is_empty() {
-                if !render_startup_update_notice(app_state) {
-                    app_state.matching_items.clear();
-                    app_state.selected_index = 0;
-                    app_state.scroll_offset = 0;
-                }
-            } else {
-                update_matching_items_from_config(app_state);
-            }
-            if let Some(timings) = run_timings.as_deref_mut() {
-                timings.initial_matching_update_ms = matching_started_at.elapsed().as_millis();
-            }
-        }
+    let on_load_started_at = Instant::now();
+    controller.run_on_load();
+    if let Some(timings) = run_timings.as_deref_mut() {
+        timings.module_on_load_ms = on_load_started_at.elapsed().as_millis();
     }
-    {
-        let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-        *runtime_guard = Some(module_runtime);
+    let matching_started_at = Instant::now();
+    controller.show_initial_results();
+    if let Some(timings) = run_timings.as_deref_mut() {
+        timings.initial_matching_update_ms = matching_started_at.elapsed().as_millis();
     }
+    let num_items_for_geom = visible_item_count(controller.app_state(), config);
+    *CONTROLLER.lock().unwrap() = Some(controller);
     if let Some(timings) = run_timings.as_deref_mut() {
         timings.pre_window_setup_ms = run_started_at.elapsed().as_millis();
     }
@@ -2325,13 +2067,6 @@ fn run_ui_internal(
             timings.register_class_ms = register_started_at.elapsed().as_millis();
         }
 
-        let num_items_for_geom = {
-            let app_state_guard = APP_STATE.lock().unwrap();
-            app_state_guard
-                .as_ref()
-                .map_or(0, |state| visible_item_count(state, config))
-        };
-
         let geometry = determine_window_geometry(cmd_options, config, num_items_for_geom);
 
         let ex_style = if measure_mode {
@@ -2393,22 +2128,13 @@ fn run_ui_internal(
                         return Ok(exit_code);
                     }
 
-                    let mut should_repaint = false;
-                    {
-                        let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-                        if let Some(runtime) = runtime_guard.as_mut() {
-                            if runtime.poll_hot_reload(cmd_options.silent) {
-                                should_repaint = true;
-                            }
-                        }
-                    }
-
-                    if should_repaint {
-                        let mut app_state_guard = APP_STATE.lock().unwrap();
-                        if let Some(app_state) = app_state_guard.as_mut() {
-                            update_matching_items_from_config(app_state);
-                        }
-                        InvalidateRect(hwnd, None, true);
+                    let changed_height =
+                        CONTROLLER.lock().unwrap().as_mut().and_then(|controller| {
+                            (controller.poll_hot_reload() || controller.poll_streaming())
+                                .then(|| window_height(controller.app_state(), controller.config()))
+                        });
+                    if let Some(height) = changed_height {
+                        refresh_window(hwnd, height);
                     }
 
                     std::thread::yield_now();
@@ -2446,12 +2172,14 @@ pub fn run_ui(
     config: &RmenuConfig,
     initial_app_state: AppState,
     module_runtime: ModuleRuntime,
+    streaming_ranker: Option<StreamingRanker>,
 ) -> windows::core::Result<i32> {
     run_ui_internal(
         cmd_options,
         config,
         initial_app_state,
         module_runtime,
+        streaming_ranker,
         false,
         false,
         None,
@@ -2470,6 +2198,7 @@ pub fn run_ui_embedded(
         config,
         initial_app_state,
         module_runtime,
+        None,
         false,
         true,
         None,
@@ -2489,6 +2218,7 @@ pub fn run_ui_embedded_timed(
         config,
         initial_app_state,
         module_runtime,
+        None,
         false,
         true,
         Some(&mut timings),
@@ -2498,8 +2228,11 @@ pub fn run_ui_embedded_timed(
 
 #[allow(dead_code)]
 pub fn take_module_runtime() -> Option<ModuleRuntime> {
-    let mut runtime_guard = MODULE_RUNTIME.lock().unwrap();
-    runtime_guard.take()
+    CONTROLLER
+        .lock()
+        .unwrap()
+        .take()
+        .map(LauncherController::into_runtime)
 }
 
 pub fn measure_ui_latencies(
@@ -2513,6 +2246,7 @@ pub fn measure_ui_latencies(
         config,
         initial_app_state,
         module_runtime,
+        None,
         true,
         false,
         None,
@@ -2532,8 +2266,7 @@ pub fn measure_ui_latencies(
 #[cfg(test)]
 mod tests {
     use super::{
-        compute_row_zones, dismiss_startup_update_notice, find_quick_select_index,
-        normalize_quick_select_items, render_startup_update_notice, rmods_badge_tone,
+        dismiss_startup_update_notice, render_startup_update_notice, rmods_badge_tone,
         rmods_item_hint, rmods_kind_badge, rmods_status_tone,
     };
     use crate::app_state::{
@@ -2568,41 +2301,6 @@ mod tests {
         assert_eq!(state.startup_update_notice, None);
     }
 
-    #[test]
-    fn quick_select_conflicts_keep_first_visible_item() {
-        let mut first = LauncherItem::new(
-            "First".to_string(),
-            "t1".to_string(),
-            LauncherSource::Direct,
-        );
-        first.quick_select_key = Some("1".to_string());
-        first.trailing_badge = Some("1".to_string());
-
-        let mut second = LauncherItem::new(
-            "Second".to_string(),
-            "t2".to_string(),
-            LauncherSource::Direct,
-        );
-        second.quick_select_key = Some("1".to_string());
-        second.trailing_badge = Some("1".to_string());
-        second.trailing_badge_tone = Some(LauncherItemTone::Warning);
-
-        let mut state = AppState {
-            matching_items: vec![first, second],
-            ..Default::default()
-        };
-
-        normalize_quick_select_items(&mut state);
-
-        assert_eq!(
-            state.matching_items[0].quick_select_key.as_deref(),
-            Some("1")
-        );
-        assert_eq!(state.matching_items[1].quick_select_key, None);
-        assert_eq!(state.matching_items[1].trailing_badge, None);
-        assert_eq!(state.matching_items[1].trailing_badge_tone, None);
-    }
-
     #[test]
     fn rmods_badge_tone_is_derived_from_rmods_badge_text_after_interaction() {
         let mut item = LauncherItem::new(
@@ -2658,66 +2356,4 @@ mod tests {
         assert_eq!(rmods_kind_badge(&item.kind), "[COMPANION] ");
         assert!(rmods_item_hint(&item).contains("<data_dir>\\companions\\rsnip"));
     }
-
-    #[test]
-    fn quick_select_index_is_resolved_from_visible_window() {
-        let mut items = Vec::new();
-        for idx in 0..6 {
-            let mut item = LauncherItem::new(
-                format!("Item {idx}"),
-                format!("target-{idx}"),
-                LauncherSource::Direct,
-            );
-            if idx == 4 {
-                item.quick_select_key = Some("5".to_string());
-            }
-            items.push(item);
-        }
-
-        let state = AppState {
-            matching_items: items,
-            scroll_offset: 2,
-            ..Default::default()
-        };
-
-        let index = find_quick_select_index(&state, '5', 3);
-        assert_eq!(index, Some(4));
-
-        let hidden = find_quick_select_index(&state, '5', 2);
-        assert_eq!(hidden, None);
-    }
-
-    #[test]
-    fn row_zones_hide_hint_and_chip_on_extreme_width() {
-        let row = compute_row_zones(
-            "Very long label value",
-            "C:/a/very/long/path/hint",
-            Some("[1]".to_string()),
-            0,
-            10,
-            2,
-        );
-
-        assert!(row.right_text.is_empty());
-        assert!(row.chip_text.is_none());
-        assert!(!row.left_text.is_empty());
-    }
-
-    #[test]
-    fn row_zones_keep_all_zones_when_width_is_enough() {
-        let row = compute_row_zones(
-            "Calculator",
-            "\\Windows\\System32\\calc.exe",
-            Some("[1]".to_string()),
-            0,
-            120,
-            2,
-        );
-
-        assert!(!row.left_text.is_empty());
-        assert!(!row.right_text.is_empty());
-        assert_eq!(row.chip_text.as_deref(), Some("[1]"));
-        assert!(row.right_x >= 0);
-        assert!(row.chip_x >= 0);
-    }
 }